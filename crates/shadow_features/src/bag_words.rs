@@ -30,6 +30,23 @@ pub struct BagOfWordsFeatureGroupNGramEntry {
 }
 
 impl BagOfWordsFeatureGroup {
+    pub fn feature_names(&self) -> Vec<String> {
+        self.ngrams
+            .keys()
+            .map(|ngram| match self.strategy {
+                BagOfWordsFeatureGroupStrategy::Present => {
+                    format!("{} contains \"{}\"", self.source_column_name, ngram)
+                }
+                BagOfWordsFeatureGroupStrategy::Count => {
+                    format!("count of \"{}\" in {}", ngram, self.source_column_name)
+                }
+                BagOfWordsFeatureGroupStrategy::TfIdf => {
+                    format!("tf-idf of \"{}\" in {}", ngram, self.source_column_name)
+                }
+            })
+            .collect()
+    }
+
     pub fn compute_table(
         &self,
         column: TableColumnView,
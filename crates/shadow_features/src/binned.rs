@@ -0,0 +1,157 @@
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{
+    NumberTableColumn, NumberTableColumnView, TableColumn, TableColumnView, TableValue,
+};
+use shadow_zip::zip;
+
+/// Assigns values to equal frequency bins and one hot encodes the bin. The first feature is for invalid values and each of the remaining `thresholds.len() + 1` features is for one bin.
+#[derive(Clone, Debug)]
+pub struct BinnedFeatureGroup {
+    pub source_column_name: String,
+    pub thresholds: Vec<f32>,
+}
+
+impl BinnedFeatureGroup {
+    /// `n_bins` must be at least 1. A column without any finite values gets no thresholds, so it has a single bin for valid values.
+    pub fn compute_for_column(column: TableColumnView, n_bins: usize) -> BinnedFeatureGroup {
+        assert!(n_bins >= 1);
+        match column {
+            TableColumnView::Number(column) => Self::compute_for_number_column(column, n_bins),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_number_column(column: NumberTableColumnView, n_bins: usize) -> Self {
+        let mut thresholds =
+            shadow_tree::compute_binning_instructions::compute_thresholds_for_number_feature(
+                column.as_slice(),
+                n_bins,
+                column.len(),
+            );
+        // Columns with many repeated values produce repeated quantiles, which would result in bins that are always empty.
+        thresholds.dedup();
+        Self {
+            source_column_name: column.name().unwrap().to_owned(),
+            thresholds,
+        }
+    }
+
+    pub fn n_features(&self) -> usize {
+        self.thresholds.len() + 2
+    }
+
+    pub fn feature_names(&self) -> Vec<String> {
+        let name = &self.source_column_name;
+        let mut feature_names = vec![format!("{} is invalid", name)];
+        if self.thresholds.is_empty() {
+            feature_names.push(format!("{} is valid", name));
+            return feature_names;
+        }
+        feature_names.push(format!("{} <= {}", name, self.thresholds[0]));
+        for thresholds in self.thresholds.windows(2) {
+            feature_names.push(format!("{} < {} <= {}", thresholds[0], name, thresholds[1]));
+        }
+        feature_names.push(format!("{} > {}", name, self.thresholds.last().unwrap()));
+        feature_names
+    }
+}
+
+impl BinnedFeatureGroup {
+    pub fn compute_table(
+        &self,
+        column: TableColumnView,
+        progress: &impl Fn(u64),
+    ) -> Vec<TableColumn> {
+        match column {
+            TableColumnView::Number(column) => {
+                let n_examples = column.len().to_u64().unwrap();
+                let columns = self.compute_table_for_number_column(column);
+                progress(n_examples);
+                columns
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_f32_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        features: ArrayViewMut2<TableValue>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_value_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_bin(&self, value: f32) -> usize {
+        if !value.is_finite() {
+            0
+        } else {
+            1 + self
+                .thresholds
+                .partition_point(|threshold| value > *threshold)
+        }
+    }
+
+    fn compute_table_for_number_column(&self, column: NumberTableColumnView) -> Vec<TableColumn> {
+        let mut feature_columns = vec![vec![0.0; column.len()]; self.n_features()];
+        for (example_index, value) in column.iter().enumerate() {
+            feature_columns[self.compute_bin(*value)][example_index] = 1.0;
+        }
+        feature_columns
+            .into_iter()
+            .map(|feature_column| TableColumn::Number(NumberTableColumn::new(None, feature_column)))
+            .collect()
+    }
+
+    fn compute_array_f32_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        features.fill(0.0);
+        for (mut features, value) in zip!(features.axis_iter_mut(Axis(0)), column.iter()) {
+            features[self.compute_bin(*value)] = 1.0;
+            progress();
+        }
+    }
+
+    fn compute_array_value_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        features.fill(TableValue::Number(0.0));
+        for (mut features, value) in zip!(features.axis_iter_mut(Axis(0)), column.iter()) {
+            features[self.compute_bin(*value)] = TableValue::Number(1.0);
+            progress();
+        }
+    }
+}
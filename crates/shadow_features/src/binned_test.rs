@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod test {
+    use crate::{BinnedFeatureGroup, FeatureGroup};
+    use shadow_table::{NumberTableColumn, TableColumnView};
+
+    #[test]
+    fn test_binned() {
+        let values = (0..1000).map(|i| i as f32).collect::<Vec<_>>();
+        let column = NumberTableColumn::new(Some("x".to_owned()), values);
+        let feature_group =
+            BinnedFeatureGroup::compute_for_column(TableColumnView::Number(column.view()), 4);
+        assert_eq!(feature_group.thresholds.len(), 3);
+        assert_eq!(feature_group.compute_bin(f32::NAN), 0);
+        assert_eq!(feature_group.compute_bin(-10.0), 1);
+        assert_eq!(feature_group.compute_bin(600.0), 3);
+        assert_eq!(feature_group.compute_bin(5000.0), 4);
+    }
+
+    #[test]
+    fn test_binned_all_missing() {
+        let column = NumberTableColumn::new(Some("x".to_owned()), vec![f32::NAN; 10]);
+        let feature_group =
+            BinnedFeatureGroup::compute_for_column(TableColumnView::Number(column.view()), 4);
+        assert!(feature_group.thresholds.is_empty());
+        assert_eq!(feature_group.n_features(), 2);
+        assert_eq!(feature_group.compute_bin(f32::NAN), 0);
+        assert_eq!(feature_group.compute_bin(1.0), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_binned_zero_bins() {
+        let column = NumberTableColumn::new(Some("x".to_owned()), vec![1.0, 2.0]);
+        BinnedFeatureGroup::compute_for_column(TableColumnView::Number(column.view()), 0);
+    }
+
+    #[test]
+    fn test_binned_feature_names() {
+        let feature_group = FeatureGroup::Binned(BinnedFeatureGroup {
+            source_column_name: "x".to_owned(),
+            thresholds: vec![1.0, 2.0],
+        });
+        assert_eq!(
+            feature_group.feature_names(),
+            vec!["x is invalid", "x <= 1", "1 < x <= 2", "x > 2"]
+        );
+        assert_eq!(
+            feature_group.feature_names().len(),
+            feature_group.n_features()
+        );
+    }
+}
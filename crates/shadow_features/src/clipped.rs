@@ -0,0 +1,140 @@
+use crate::quantile_transform::{quantile, sorted_finite_values};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{
+    NumberTableColumn, NumberTableColumnView, TableColumn, TableColumnView, TableValue,
+};
+use shadow_zip::zip;
+
+/// Clamps values to the `[min, max]` range fitted from the lower and upper percentiles of the training values.
+#[derive(Clone, Debug)]
+pub struct ClippedFeatureGroup {
+    pub source_column_name: String,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl ClippedFeatureGroup {
+    /// `lower_percentile` and `upper_percentile` are in the range `[0, 100]`.
+    pub fn compute_for_column(
+        column: TableColumnView,
+        lower_percentile: f32,
+        upper_percentile: f32,
+    ) -> ClippedFeatureGroup {
+        match column {
+            TableColumnView::Number(column) => {
+                Self::compute_for_number_column(column, lower_percentile, upper_percentile)
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_number_column(
+        column: NumberTableColumnView,
+        lower_percentile: f32,
+        upper_percentile: f32,
+    ) -> Self {
+        assert!((0.0..=upper_percentile).contains(&lower_percentile) && upper_percentile <= 100.0);
+        let values = sorted_finite_values(column.as_slice());
+        let (min, max) = if values.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (
+                quantile(&values, lower_percentile / 100.0),
+                quantile(&values, upper_percentile / 100.0),
+            )
+        };
+        Self {
+            source_column_name: column.name().unwrap().to_owned(),
+            min,
+            max,
+        }
+    }
+}
+
+impl ClippedFeatureGroup {
+    pub fn compute_table(&self, column: TableColumnView, progress: &impl Fn(u64)) -> TableColumn {
+        let column = match column {
+            TableColumnView::Number(column) => {
+                TableColumn::Number(self.compute_table_for_number_column(column))
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        };
+        progress(column.len().to_u64().unwrap());
+        column
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_f32_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        features: ArrayViewMut2<TableValue>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_value_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    fn compute_feature(&self, value: f32) -> f32 {
+        if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(self.min, self.max)
+        }
+    }
+
+    fn compute_table_for_number_column(&self, column: NumberTableColumnView) -> NumberTableColumn {
+        let feature_values = column
+            .iter()
+            .map(|value| self.compute_feature(*value))
+            .collect();
+        NumberTableColumn::new(None, feature_values)
+    }
+
+    fn compute_array_f32_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.iter_mut(), column.iter()) {
+            *feature = self.compute_feature(*value);
+            progress()
+        }
+    }
+
+    fn compute_array_value_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.column_mut(0), column.iter()) {
+            *feature = TableValue::Number(self.compute_feature(*value));
+            progress()
+        }
+    }
+}
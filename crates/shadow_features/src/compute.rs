@@ -1,9 +1,12 @@
 use crate::{
     bag_words::BagOfWordsFeatureGroup,
     bag_words_cosine_similarity::BagOfWordsCosineSimilarityFeatureGroup,
-    identity::IdentityFeatureGroup, normalized::NormalizedFeatureGroup,
-    one_hot_encoded::OneHotEncodedFeatureGroup, word_embedding::WordEmbeddingFeatureGroup,
-    FeatureGroup,
+    binned::BinnedFeatureGroup, clipped::ClippedFeatureGroup, identity::IdentityFeatureGroup,
+    imputed::ImputedFeatureGroup, interaction::InteractionFeatureGroup, log::LogFeatureGroup,
+    normalized::NormalizedFeatureGroup, one_hot_encoded::OneHotEncodedFeatureGroup,
+    polynomial::PolynomialFeatureGroup, power_transform::PowerTransformFeatureGroup,
+    quantile_transform::QuantileTransformFeatureGroup, target_encoded::TargetEncodedFeatureGroup,
+    word_embedding::WordEmbeddingFeatureGroup, FeatureGroup,
 };
 use ndarray::prelude::*;
 use shadow_table::prelude::*;
@@ -29,6 +32,51 @@ pub fn compute_features_array_f32(
     features
 }
 
+/// Computes the features for the examples a model is trained on. Target encoded feature groups are computed out of fold with `n_folds` folds using `target_column`, so no example's features include its own target. The other feature groups are computed as in `compute_features_array_f32`.
+pub fn compute_train_features_array_f32(
+    table: &TableView,
+    feature_groups: &[FeatureGroup],
+    target_column: TableColumnView,
+    n_folds: usize,
+    progress: &impl Fn(),
+) -> Array2<f32> {
+    let n_features = feature_groups
+        .iter()
+        .map(|feature_group| feature_group.n_features())
+        .sum::<usize>();
+    let mut features = Array::zeros((table.nrows(), n_features));
+    let mut feature_index = 0;
+    for feature_group in feature_groups.iter() {
+        let n_features_in_group = feature_group.n_features();
+        let slice = s![.., feature_index..feature_index + n_features_in_group];
+        let features = features.slice_mut(slice);
+        match feature_group {
+            FeatureGroup::TargetEncoded(feature_group) => {
+                let source_column = table
+                    .columns()
+                    .iter()
+                    .find(|column| column.name() == Some(&feature_group.source_column_name))
+                    .unwrap();
+                feature_group.compute_out_of_fold_array_f32(
+                    features,
+                    source_column.view(),
+                    target_column.clone(),
+                    n_folds,
+                    progress,
+                );
+            }
+            _ => compute_features_array_f32_for_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            ),
+        }
+        feature_index += n_features_in_group;
+    }
+    features
+}
+
 fn compute_features_array_f32_for_feature_group(
     table: &TableView,
     feature_group: &FeatureGroup,
@@ -52,6 +100,74 @@ fn compute_features_array_f32_for_feature_group(
                 progress,
             )
         }
+        FeatureGroup::Log(feature_group) => compute_features_array_f32_for_log_feature_group(
+            table,
+            feature_group,
+            features,
+            progress,
+        ),
+        FeatureGroup::PowerTransform(feature_group) => {
+            compute_features_array_f32_for_power_transform_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::QuantileTransform(feature_group) => {
+            compute_features_array_f32_for_quantile_transform_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Binned(feature_group) => compute_features_array_f32_for_binned_feature_group(
+            table,
+            feature_group,
+            features,
+            progress,
+        ),
+        FeatureGroup::Clipped(feature_group) => {
+            compute_features_array_f32_for_clipped_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Imputed(feature_group) => {
+            compute_features_array_f32_for_imputed_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::TargetEncoded(feature_group) => {
+            compute_features_array_f32_for_target_encoded_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Interaction(feature_group) => {
+            compute_features_array_f32_for_interaction_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Polynomial(feature_group) => {
+            compute_features_array_f32_for_polynomial_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
         FeatureGroup::OneHotEncoded(feature_group) => {
             compute_features_array_f32_for_one_hot_encoded_feature_group(
                 table,
@@ -115,6 +231,144 @@ fn compute_features_array_f32_for_normalized_feature_group(
     feature_group.compute_array_f32(features, source_column.view(), progress)
 }
 
+fn compute_features_array_f32_for_log_feature_group(
+    table: &TableView,
+    feature_group: &LogFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name))
+        .unwrap();
+    feature_group.compute_array_f32(features, source_column.view(), progress);
+}
+
+fn compute_features_array_f32_for_power_transform_feature_group(
+    table: &TableView,
+    feature_group: &PowerTransformFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name))
+        .unwrap();
+    feature_group.compute_array_f32(features, source_column.view(), progress);
+}
+
+fn compute_features_array_f32_for_quantile_transform_feature_group(
+    table: &TableView,
+    feature_group: &QuantileTransformFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name))
+        .unwrap();
+    feature_group.compute_array_f32(features, source_column.view(), progress);
+}
+
+fn compute_features_array_f32_for_binned_feature_group(
+    table: &TableView,
+    feature_group: &BinnedFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name))
+        .unwrap();
+    feature_group.compute_array_f32(features, source_column.view(), progress);
+}
+
+fn compute_features_array_f32_for_clipped_feature_group(
+    table: &TableView,
+    feature_group: &ClippedFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name))
+        .unwrap();
+    feature_group.compute_array_f32(features, source_column.view(), progress);
+}
+
+fn compute_features_array_f32_for_imputed_feature_group(
+    table: &TableView,
+    feature_group: &ImputedFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name))
+        .unwrap();
+    feature_group.compute_array_f32(features, source_column.view(), progress);
+}
+
+fn compute_features_array_f32_for_target_encoded_feature_group(
+    table: &TableView,
+    feature_group: &TargetEncodedFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name))
+        .unwrap();
+    feature_group.compute_array_f32(features, source_column.view(), progress);
+}
+
+fn compute_features_array_f32_for_interaction_feature_group(
+    table: &TableView,
+    feature_group: &InteractionFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let column_a = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name_a))
+        .unwrap();
+    let column_b = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name_b))
+        .unwrap();
+    feature_group.compute_array_f32(features, column_a.view(), column_b.view(), progress);
+}
+
+fn compute_features_array_f32_for_polynomial_feature_group(
+    table: &TableView,
+    feature_group: &PolynomialFeatureGroup,
+    features: ArrayViewMut2<f32>,
+    progress: &impl Fn(),
+) {
+    let source_columns: Vec<_> = feature_group
+        .source_column_names
+        .iter()
+        .map(|source_column_name| {
+            table
+                .columns()
+                .iter()
+                .find(|column| column.name() == Some(source_column_name))
+                .unwrap()
+                .view()
+        })
+        .collect();
+    feature_group.compute_array_f32(features, &source_columns, progress);
+}
+
 fn compute_features_array_f32_for_one_hot_encoded_feature_group(
     table: &TableView,
     feature_group: &OneHotEncodedFeatureGroup,
@@ -214,6 +468,67 @@ fn compute_features_table_for_feature_group(
                 progress,
             )
         }
+        FeatureGroup::Log(feature_group) => {
+            compute_features_table_for_log_feature_group(table, feature_group, features, progress)
+        }
+        FeatureGroup::PowerTransform(feature_group) => {
+            compute_features_table_for_power_transform_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::QuantileTransform(feature_group) => {
+            compute_features_table_for_quantile_transform_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Binned(feature_group) => compute_features_table_for_binned_feature_group(
+            table,
+            feature_group,
+            features,
+            progress,
+        ),
+        FeatureGroup::Clipped(feature_group) => compute_features_table_for_clipped_feature_group(
+            table,
+            feature_group,
+            features,
+            progress,
+        ),
+        FeatureGroup::Imputed(feature_group) => compute_features_table_for_imputed_feature_group(
+            table,
+            feature_group,
+            features,
+            progress,
+        ),
+        FeatureGroup::TargetEncoded(feature_group) => {
+            compute_features_table_for_target_encoded_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Interaction(feature_group) => {
+            compute_features_table_for_interaction_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Polynomial(feature_group) => {
+            compute_features_table_for_polynomial_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
         FeatureGroup::OneHotEncoded(_) => unimplemented!(),
         FeatureGroup::BagOfWords(feature_group) => {
             compute_features_table_for_bag_of_words_feature_group(
@@ -272,6 +587,163 @@ fn compute_features_table_for_normalized_feature_group(
     features.columns_mut().push(feature_column);
 }
 
+fn compute_features_table_for_log_feature_group(
+    table: &TableView,
+    feature_group: &LogFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    let feature_column = feature_group.compute_table(column.view(), progress);
+    features.columns_mut().push(feature_column);
+}
+
+fn compute_features_table_for_power_transform_feature_group(
+    table: &TableView,
+    feature_group: &PowerTransformFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    let feature_column = feature_group.compute_table(column.view(), progress);
+    features.columns_mut().push(feature_column);
+}
+
+fn compute_features_table_for_quantile_transform_feature_group(
+    table: &TableView,
+    feature_group: &QuantileTransformFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    let feature_column = feature_group.compute_table(column.view(), progress);
+    features.columns_mut().push(feature_column);
+}
+
+fn compute_features_table_for_binned_feature_group(
+    table: &TableView,
+    feature_group: &BinnedFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    let columns = feature_group.compute_table(source_column.view(), progress);
+    for column in columns {
+        features.columns_mut().push(column);
+    }
+}
+
+fn compute_features_table_for_clipped_feature_group(
+    table: &TableView,
+    feature_group: &ClippedFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    let feature_column = feature_group.compute_table(column.view(), progress);
+    features.columns_mut().push(feature_column);
+}
+
+fn compute_features_table_for_imputed_feature_group(
+    table: &TableView,
+    feature_group: &ImputedFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    let columns = feature_group.compute_table(source_column.view(), progress);
+    for column in columns {
+        features.columns_mut().push(column);
+    }
+}
+
+fn compute_features_table_for_target_encoded_feature_group(
+    table: &TableView,
+    feature_group: &TargetEncodedFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    let columns = feature_group.compute_table(source_column.view(), progress);
+    for column in columns {
+        features.columns_mut().push(column);
+    }
+}
+
+fn compute_features_table_for_interaction_feature_group(
+    table: &TableView,
+    feature_group: &InteractionFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let column_a = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name_a))
+        .unwrap();
+    let column_b = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name_b))
+        .unwrap();
+    let columns = feature_group.compute_table(column_a.view(), column_b.view(), progress);
+    for column in columns {
+        features.columns_mut().push(column);
+    }
+}
+
+fn compute_features_table_for_polynomial_feature_group(
+    table: &TableView,
+    feature_group: &PolynomialFeatureGroup,
+    features: &mut Table,
+    progress: &impl Fn(u64),
+) {
+    let source_columns: Vec<_> = feature_group
+        .source_column_names
+        .iter()
+        .map(|source_column_name| {
+            table
+                .columns()
+                .iter()
+                .find(|column| column.name() == Some(source_column_name))
+                .unwrap()
+                .view()
+        })
+        .collect();
+    let columns = feature_group.compute_table(&source_columns, progress);
+    for column in columns {
+        features.columns_mut().push(column);
+    }
+}
+
 fn compute_features_table_for_bag_of_words_feature_group(
     table: &TableView,
     feature_group: &BagOfWordsFeatureGroup,
@@ -368,6 +840,76 @@ fn compute_features_array_value_for_feature_group(
                 progress,
             )
         }
+        FeatureGroup::Log(feature_group) => compute_features_array_value_for_log_feature_group(
+            table,
+            feature_group,
+            features,
+            progress,
+        ),
+        FeatureGroup::PowerTransform(feature_group) => {
+            compute_features_array_value_for_power_transform_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::QuantileTransform(feature_group) => {
+            compute_features_array_value_for_quantile_transform_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Binned(feature_group) => {
+            compute_features_array_value_for_binned_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Clipped(feature_group) => {
+            compute_features_array_value_for_clipped_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Imputed(feature_group) => {
+            compute_features_array_value_for_imputed_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::TargetEncoded(feature_group) => {
+            compute_features_array_value_for_target_encoded_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Interaction(feature_group) => {
+            compute_features_array_value_for_interaction_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
+        FeatureGroup::Polynomial(feature_group) => {
+            compute_features_array_value_for_polynomial_feature_group(
+                table,
+                feature_group,
+                features,
+                progress,
+            )
+        }
         FeatureGroup::OneHotEncoded(_) => unimplemented!(),
         FeatureGroup::BagOfWords(feature_group) => {
             compute_features_array_value_for_bag_of_words_feature_group(
@@ -424,6 +966,144 @@ fn compute_features_array_value_for_normalized_feature_group(
     feature_group.compute_array_value(features, source_column.view(), progress);
 }
 
+fn compute_features_array_value_for_log_feature_group(
+    table: &TableView,
+    feature_group: &LogFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    feature_group.compute_array_value(features, source_column.view(), progress);
+}
+
+fn compute_features_array_value_for_power_transform_feature_group(
+    table: &TableView,
+    feature_group: &PowerTransformFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    feature_group.compute_array_value(features, source_column.view(), progress);
+}
+
+fn compute_features_array_value_for_quantile_transform_feature_group(
+    table: &TableView,
+    feature_group: &QuantileTransformFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    feature_group.compute_array_value(features, source_column.view(), progress);
+}
+
+fn compute_features_array_value_for_binned_feature_group(
+    table: &TableView,
+    feature_group: &BinnedFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    feature_group.compute_array_value(features, source_column.view(), progress);
+}
+
+fn compute_features_array_value_for_clipped_feature_group(
+    table: &TableView,
+    feature_group: &ClippedFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    feature_group.compute_array_value(features, source_column.view(), progress);
+}
+
+fn compute_features_array_value_for_imputed_feature_group(
+    table: &TableView,
+    feature_group: &ImputedFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    feature_group.compute_array_value(features, source_column.view(), progress);
+}
+
+fn compute_features_array_value_for_target_encoded_feature_group(
+    table: &TableView,
+    feature_group: &TargetEncodedFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_column = table
+        .columns()
+        .iter()
+        .find(|column| column.name().unwrap() == feature_group.source_column_name)
+        .unwrap();
+    feature_group.compute_array_value(features, source_column.view(), progress);
+}
+
+fn compute_features_array_value_for_interaction_feature_group(
+    table: &TableView,
+    feature_group: &InteractionFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let column_a = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name_a))
+        .unwrap();
+    let column_b = table
+        .columns()
+        .iter()
+        .find(|column| column.name() == Some(&feature_group.source_column_name_b))
+        .unwrap();
+    feature_group.compute_array_value(features, column_a.view(), column_b.view(), progress);
+}
+
+fn compute_features_array_value_for_polynomial_feature_group(
+    table: &TableView,
+    feature_group: &PolynomialFeatureGroup,
+    features: ArrayViewMut2<shadow_table::TableValue>,
+    progress: &impl Fn(),
+) {
+    let source_columns: Vec<_> = feature_group
+        .source_column_names
+        .iter()
+        .map(|source_column_name| {
+            table
+                .columns()
+                .iter()
+                .find(|column| column.name() == Some(source_column_name))
+                .unwrap()
+                .view()
+        })
+        .collect();
+    feature_group.compute_array_value(features, &source_columns, progress);
+}
+
 fn compute_features_array_value_for_bag_of_words_feature_group(
     table: &TableView,
     feature_group: &BagOfWordsFeatureGroup,
@@ -0,0 +1,206 @@
+use crate::quantile_transform::{quantile, sorted_finite_values};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{
+    NumberTableColumn, NumberTableColumnView, TableColumn, TableColumnView, TableValue,
+};
+use shadow_zip::zip;
+
+/// Replaces missing values with `fill_value`, which is fitted with `strategy` on the training values. If `missing_indicator` is true, a second feature is 1 when the value was missing and 0 otherwise.
+#[derive(Clone, Debug)]
+pub struct ImputedFeatureGroup {
+    pub source_column_name: String,
+    pub strategy: ImputationStrategy,
+    pub fill_value: f32,
+    pub missing_indicator: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImputationStrategy {
+    Mean,
+    Median,
+    /// If more than one value is the most frequent, the smallest of them is used.
+    Mode,
+    Constant(f32),
+}
+
+impl ImputedFeatureGroup {
+    pub fn compute_for_column(
+        column: TableColumnView,
+        strategy: ImputationStrategy,
+        missing_indicator: bool,
+    ) -> ImputedFeatureGroup {
+        match column {
+            TableColumnView::Number(column) => {
+                Self::compute_for_number_column(column, strategy, missing_indicator)
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_number_column(
+        column: NumberTableColumnView,
+        strategy: ImputationStrategy,
+        missing_indicator: bool,
+    ) -> Self {
+        let values = sorted_finite_values(column.as_slice());
+        let fill_value = match strategy {
+            ImputationStrategy::Constant(value) => value,
+            _ if values.is_empty() => 0.0,
+            ImputationStrategy::Mean => {
+                shadow_metrics::MeanVariance::compute(values.iter().cloned()).mean
+            }
+            ImputationStrategy::Median => quantile(&values, 0.5),
+            ImputationStrategy::Mode => compute_mode(&values),
+        };
+        Self {
+            source_column_name: column.name().unwrap().to_owned(),
+            strategy,
+            fill_value,
+            missing_indicator,
+        }
+    }
+
+    pub fn n_features(&self) -> usize {
+        if self.missing_indicator {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn feature_names(&self) -> Vec<String> {
+        let mut feature_names = vec![self.source_column_name.clone()];
+        if self.missing_indicator {
+            feature_names.push(format!("{} is missing", self.source_column_name));
+        }
+        feature_names
+    }
+}
+
+impl ImputedFeatureGroup {
+    pub fn compute_table(
+        &self,
+        column: TableColumnView,
+        progress: &impl Fn(u64),
+    ) -> Vec<TableColumn> {
+        match column {
+            TableColumnView::Number(column) => {
+                let n_examples = column.len().to_u64().unwrap();
+                let columns = self.compute_table_for_number_column(column);
+                progress(n_examples);
+                columns
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_f32_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        features: ArrayViewMut2<TableValue>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_value_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    fn compute_features(&self, value: f32) -> (f32, f32) {
+        if value.is_finite() {
+            (value, 0.0)
+        } else {
+            (self.fill_value, 1.0)
+        }
+    }
+
+    fn compute_table_for_number_column(&self, column: NumberTableColumnView) -> Vec<TableColumn> {
+        let (feature_values, indicator_values): (Vec<f32>, Vec<f32>) = column
+            .iter()
+            .map(|value| self.compute_features(*value))
+            .unzip();
+        let mut columns = vec![TableColumn::Number(NumberTableColumn::new(
+            None,
+            feature_values,
+        ))];
+        if self.missing_indicator {
+            columns.push(TableColumn::Number(NumberTableColumn::new(
+                None,
+                indicator_values,
+            )));
+        }
+        columns
+    }
+
+    fn compute_array_f32_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (mut features, value) in zip!(features.axis_iter_mut(Axis(0)), column.iter()) {
+            let (feature, indicator) = self.compute_features(*value);
+            features[0] = feature;
+            if self.missing_indicator {
+                features[1] = indicator;
+            }
+            progress();
+        }
+    }
+
+    fn compute_array_value_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (mut features, value) in zip!(features.axis_iter_mut(Axis(0)), column.iter()) {
+            let (feature, indicator) = self.compute_features(*value);
+            features[0] = TableValue::Number(feature);
+            if self.missing_indicator {
+                features[1] = TableValue::Number(indicator);
+            }
+            progress();
+        }
+    }
+}
+
+/// Finds the most frequent value in `sorted_values`, which must not be empty.
+fn compute_mode(sorted_values: &[f32]) -> f32 {
+    let mut mode = sorted_values[0];
+    let mut mode_count = 0;
+    let mut start = 0;
+    while start < sorted_values.len() {
+        let value = sorted_values[start];
+        let count = sorted_values[start..].partition_point(|v| *v == value);
+        if count > mode_count {
+            mode = value;
+            mode_count = count;
+        }
+        start += count;
+    }
+    mode
+}
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod test {
+    use crate::{ImputationStrategy, ImputedFeatureGroup};
+    use ndarray::prelude::*;
+    use shadow_table::{NumberTableColumn, TableColumnView};
+
+    fn fill_value(values: Vec<f32>, strategy: ImputationStrategy) -> f32 {
+        let column = NumberTableColumn::new(Some("x".to_owned()), values);
+        ImputedFeatureGroup::compute_for_column(
+            TableColumnView::Number(column.view()),
+            strategy,
+            false,
+        )
+        .fill_value
+    }
+
+    #[test]
+    fn test_fill_values() {
+        let values = vec![1.0, 2.0, 2.0, f32::NAN, 7.0];
+        assert_eq!(fill_value(values.clone(), ImputationStrategy::Mean), 3.0);
+        assert_eq!(fill_value(values.clone(), ImputationStrategy::Median), 2.0);
+        assert_eq!(fill_value(values.clone(), ImputationStrategy::Mode), 2.0);
+        assert_eq!(fill_value(values, ImputationStrategy::Constant(-1.0)), -1.0);
+        assert_eq!(fill_value(vec![f32::NAN], ImputationStrategy::Mean), 0.0);
+    }
+
+    #[test]
+    fn test_missing_indicator() {
+        let column = NumberTableColumn::new(Some("x".to_owned()), vec![1.0, f32::NAN, 3.0]);
+        let feature_group = ImputedFeatureGroup::compute_for_column(
+            TableColumnView::Number(column.view()),
+            ImputationStrategy::Median,
+            true,
+        );
+        assert_eq!(feature_group.n_features(), 2);
+        let mut features = Array::zeros((3, 2));
+        feature_group.compute_array_f32(
+            features.view_mut(),
+            TableColumnView::Number(column.view()),
+            &|| {},
+        );
+        assert_eq!(features, array![[1.0, 0.0], [2.0, 1.0], [3.0, 0.0]]);
+    }
+}
@@ -0,0 +1,311 @@
+use fnv::FnvHashMap;
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{
+    EnumTableColumnView, NumberTableColumn, NumberTableColumnView, TableColumn, TableColumnView,
+    TableValue,
+};
+use shadow_zip::zip;
+
+/// Crosses two source columns so that linear models can learn how their effects combine.
+#[derive(Clone, Debug)]
+pub struct InteractionFeatureGroup {
+    pub source_column_name_a: String,
+    pub source_column_name_b: String,
+    pub interaction: Interaction,
+}
+
+#[derive(Clone, Debug)]
+pub enum Interaction {
+    /// The product of the two standardized numbers.
+    NumberNumber {
+        mean_a: f32,
+        variance_a: f32,
+        mean_b: f32,
+        variance_b: f32,
+    },
+    /// A one hot encoding of the most frequent combinations of the variants of `a` and `b`. The first feature is for other combinations and missing values.
+    EnumEnum { variants: Vec<(String, String)> },
+    /// The standardized number `a` multiplied by a one hot encoding of the most frequent variants of `b`. The first feature is for other variants and missing values.
+    NumberEnum {
+        mean: f32,
+        variance: f32,
+        variants: Vec<String>,
+    },
+}
+
+impl InteractionFeatureGroup {
+    /// `max_features` caps the number of features produced by crosses with enum columns, which would otherwise grow with the product of the numbers of variants. If `column_a` is an enum column and `column_b` is a number column, they are swapped.
+    pub fn compute_for_columns(
+        column_a: TableColumnView,
+        column_b: TableColumnView,
+        max_features: usize,
+    ) -> InteractionFeatureGroup {
+        assert!(max_features >= 2);
+        match (column_a, column_b) {
+            (TableColumnView::Number(column_a), TableColumnView::Number(column_b)) => {
+                Self::compute_for_number_number_columns(column_a, column_b)
+            }
+            (TableColumnView::Enum(column_a), TableColumnView::Enum(column_b)) => {
+                Self::compute_for_enum_enum_columns(column_a, column_b, max_features)
+            }
+            (TableColumnView::Number(column_a), TableColumnView::Enum(column_b))
+            | (TableColumnView::Enum(column_b), TableColumnView::Number(column_a)) => {
+                Self::compute_for_number_enum_columns(column_a, column_b, max_features)
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_number_number_columns(
+        column_a: NumberTableColumnView,
+        column_b: NumberTableColumnView,
+    ) -> Self {
+        let (mean_a, variance_a) = compute_mean_variance(column_a.as_slice());
+        let (mean_b, variance_b) = compute_mean_variance(column_b.as_slice());
+        Self {
+            source_column_name_a: column_a.name().unwrap().to_owned(),
+            source_column_name_b: column_b.name().unwrap().to_owned(),
+            interaction: Interaction::NumberNumber {
+                mean_a,
+                variance_a,
+                mean_b,
+                variance_b,
+            },
+        }
+    }
+
+    fn compute_for_enum_enum_columns(
+        column_a: EnumTableColumnView,
+        column_b: EnumTableColumnView,
+        max_features: usize,
+    ) -> Self {
+        let mut counts: FnvHashMap<(usize, usize), usize> = FnvHashMap::default();
+        for (value_a, value_b) in zip!(column_a.iter(), column_b.iter()) {
+            if let (Some(value_a), Some(value_b)) = (value_a, value_b) {
+                *counts.entry((value_a.get(), value_b.get())).or_insert(0) += 1;
+            }
+        }
+        let mut counts: Vec<((usize, usize), usize)> = counts.into_iter().collect();
+        counts.sort_by(|(pair_a, count_a), (pair_b, count_b)| {
+            count_b.cmp(count_a).then(pair_a.cmp(pair_b))
+        });
+        let variants = counts
+            .into_iter()
+            .take(max_features - 1)
+            .map(|((value_a, value_b), _)| {
+                (
+                    column_a.variants()[value_a - 1].clone(),
+                    column_b.variants()[value_b - 1].clone(),
+                )
+            })
+            .collect();
+        Self {
+            source_column_name_a: column_a.name().unwrap().to_owned(),
+            source_column_name_b: column_b.name().unwrap().to_owned(),
+            interaction: Interaction::EnumEnum { variants },
+        }
+    }
+
+    fn compute_for_number_enum_columns(
+        column_a: NumberTableColumnView,
+        column_b: EnumTableColumnView,
+        max_features: usize,
+    ) -> Self {
+        let (mean, variance) = compute_mean_variance(column_a.as_slice());
+        let mut counts = vec![0usize; column_b.variants().len()];
+        for value in column_b.iter().flatten() {
+            counts[value.get() - 1] += 1;
+        }
+        let mut variant_indices: Vec<usize> = (0..counts.len()).collect();
+        variant_indices.sort_by(|a, b| counts[*b].cmp(&counts[*a]).then(a.cmp(b)));
+        let variants = variant_indices
+            .into_iter()
+            .take(max_features - 1)
+            .map(|variant_index| column_b.variants()[variant_index].clone())
+            .collect();
+        Self {
+            source_column_name_a: column_a.name().unwrap().to_owned(),
+            source_column_name_b: column_b.name().unwrap().to_owned(),
+            interaction: Interaction::NumberEnum {
+                mean,
+                variance,
+                variants,
+            },
+        }
+    }
+
+    pub fn n_features(&self) -> usize {
+        match &self.interaction {
+            Interaction::NumberNumber { .. } => 1,
+            Interaction::EnumEnum { variants } => variants.len() + 1,
+            Interaction::NumberEnum { variants, .. } => variants.len() + 1,
+        }
+    }
+
+    pub fn feature_names(&self) -> Vec<String> {
+        let a = &self.source_column_name_a;
+        let b = &self.source_column_name_b;
+        match &self.interaction {
+            Interaction::NumberNumber { .. } => vec![format!("{} * {}", a, b)],
+            Interaction::EnumEnum { variants } => std::iter::once(format!("{}, {} = other", a, b))
+                .chain(variants.iter().map(|(variant_a, variant_b)| {
+                    format!("{} = {}, {} = {}", a, variant_a, b, variant_b)
+                }))
+                .collect(),
+            Interaction::NumberEnum { variants, .. } => {
+                std::iter::once(format!("{} * ({} = other)", a, b))
+                    .chain(
+                        variants
+                            .iter()
+                            .map(|variant| format!("{} * ({} = {})", a, b, variant)),
+                    )
+                    .collect()
+            }
+        }
+    }
+}
+
+impl InteractionFeatureGroup {
+    pub fn compute_table(
+        &self,
+        column_a: TableColumnView,
+        column_b: TableColumnView,
+        progress: &impl Fn(u64),
+    ) -> Vec<TableColumn> {
+        let mut features = Array::zeros((column_a.len(), self.n_features()));
+        self.compute_array_f32(features.view_mut(), column_a, column_b, &|| {});
+        progress(features.nrows().to_u64().unwrap());
+        features
+            .axis_iter(Axis(1))
+            .map(|feature_column| {
+                TableColumn::Number(NumberTableColumn::new(None, feature_column.to_vec()))
+            })
+            .collect()
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column_a: TableColumnView,
+        column_b: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        features.fill(0.0);
+        match (&self.interaction, column_a, column_b) {
+            (
+                Interaction::NumberNumber {
+                    mean_a,
+                    variance_a,
+                    mean_b,
+                    variance_b,
+                },
+                TableColumnView::Number(column_a),
+                TableColumnView::Number(column_b),
+            ) => {
+                for (mut features, value_a, value_b) in zip!(
+                    features.axis_iter_mut(Axis(0)),
+                    column_a.iter(),
+                    column_b.iter()
+                ) {
+                    features[0] = standardize(*value_a, *mean_a, *variance_a)
+                        * standardize(*value_b, *mean_b, *variance_b);
+                    progress();
+                }
+            }
+            (
+                Interaction::EnumEnum { variants },
+                TableColumnView::Enum(column_a),
+                TableColumnView::Enum(column_b),
+            ) => {
+                let feature_indices: FnvHashMap<(usize, usize), usize> = variants
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, (variant_a, variant_b))| {
+                        let value_a = variant_position(column_a.variants(), variant_a)?;
+                        let value_b = variant_position(column_b.variants(), variant_b)?;
+                        Some(((value_a, value_b), i + 1))
+                    })
+                    .collect();
+                for (mut features, value_a, value_b) in zip!(
+                    features.axis_iter_mut(Axis(0)),
+                    column_a.iter(),
+                    column_b.iter()
+                ) {
+                    let feature_index = match (value_a, value_b) {
+                        (Some(value_a), Some(value_b)) => feature_indices
+                            .get(&(value_a.get(), value_b.get()))
+                            .cloned()
+                            .unwrap_or(0),
+                        _ => 0,
+                    };
+                    features[feature_index] = 1.0;
+                    progress();
+                }
+            }
+            (
+                Interaction::NumberEnum {
+                    mean,
+                    variance,
+                    variants,
+                },
+                TableColumnView::Number(column_a),
+                TableColumnView::Enum(column_b),
+            ) => {
+                let mut feature_indices = vec![0; column_b.variants().len() + 1];
+                for (i, variant) in variants.iter().enumerate() {
+                    if let Some(value) = variant_position(column_b.variants(), variant) {
+                        feature_indices[value] = i + 1;
+                    }
+                }
+                for (mut features, value_a, value_b) in zip!(
+                    features.axis_iter_mut(Axis(0)),
+                    column_a.iter(),
+                    column_b.iter()
+                ) {
+                    let feature_index = feature_indices[value_b.map(|v| v.get()).unwrap_or(0)];
+                    features[feature_index] = standardize(*value_a, *mean, *variance);
+                    progress();
+                }
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column_a: TableColumnView,
+        column_b: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        let mut features_f32 = Array::zeros(features.raw_dim());
+        self.compute_array_f32(features_f32.view_mut(), column_a, column_b, progress);
+        for (feature, feature_f32) in zip!(features.iter_mut(), features_f32.iter()) {
+            *feature = TableValue::Number(*feature_f32);
+        }
+    }
+}
+
+/// Returns the mean and variance of the finite values.
+pub(crate) fn compute_mean_variance(values: &[f32]) -> (f32, f32) {
+    let mean_variance =
+        shadow_metrics::MeanVariance::compute(values.iter().cloned().filter(|v| v.is_finite()));
+    (mean_variance.mean, mean_variance.variance)
+}
+
+/// Standardizes `value`, mapping invalid values to zero, which is the standardized mean.
+pub(crate) fn standardize(value: f32, mean: f32, variance: f32) -> f32 {
+    if !value.is_finite() || variance == 0.0 || !variance.is_finite() {
+        0.0
+    } else {
+        (value - mean) / f32::sqrt(variance)
+    }
+}
+
+fn variant_position(variants: &[String], variant: &str) -> Option<usize> {
+    variants
+        .iter()
+        .position(|v| v == variant)
+        .map(|position| position + 1)
+}
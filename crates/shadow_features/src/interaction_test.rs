@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod test {
+    use crate::{InteractionFeatureGroup, PolynomialFeatureGroup};
+    use ndarray::prelude::*;
+    use shadow_table::{EnumTableColumn, NumberTableColumn, TableColumnView};
+    use std::num::NonZeroUsize;
+
+    fn enum_column(name: &str, variants: &[&str], values: &[usize]) -> EnumTableColumn {
+        EnumTableColumn::new(
+            Some(name.to_owned()),
+            variants.iter().map(|variant| variant.to_string()).collect(),
+            values
+                .iter()
+                .map(|value| NonZeroUsize::new(*value))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_enum_enum_interaction_keeps_most_frequent_combinations() {
+        let color = enum_column("color", &["red", "blue"], &[1, 1, 1, 2, 2, 0]);
+        let size = enum_column("size", &["s", "l"], &[1, 1, 2, 2, 2, 1]);
+        let feature_group = InteractionFeatureGroup::compute_for_columns(
+            TableColumnView::Enum(color.view()),
+            TableColumnView::Enum(size.view()),
+            3,
+        );
+        assert_eq!(
+            feature_group.feature_names(),
+            vec![
+                "color, size = other",
+                "color = red, size = s",
+                "color = blue, size = l",
+            ]
+        );
+        let mut features = Array::zeros((6, 3));
+        feature_group.compute_array_f32(
+            features.view_mut(),
+            TableColumnView::Enum(color.view()),
+            TableColumnView::Enum(size.view()),
+            &|| {},
+        );
+        assert_eq!(
+            features.map_axis(Axis(1), |row| row.iter().position(|v| *v == 1.0).unwrap()),
+            array![1, 1, 0, 2, 2, 0]
+        );
+    }
+
+    #[test]
+    fn test_number_enum_interaction() {
+        let x = NumberTableColumn::new(Some("x".to_owned()), vec![1.0, 3.0, f32::NAN]);
+        let color = enum_column("color", &["red", "blue"], &[1, 2, 1]);
+        // The enum column is given first, so the columns are swapped.
+        let feature_group = InteractionFeatureGroup::compute_for_columns(
+            TableColumnView::Enum(color.view()),
+            TableColumnView::Number(x.view()),
+            10,
+        );
+        assert_eq!(feature_group.source_column_name_a, "x");
+        assert_eq!(
+            feature_group.feature_names(),
+            vec![
+                "x * (color = other)",
+                "x * (color = red)",
+                "x * (color = blue)"
+            ]
+        );
+        let mut features = Array::zeros((3, 3));
+        feature_group.compute_array_f32(
+            features.view_mut(),
+            TableColumnView::Number(x.view()),
+            TableColumnView::Enum(color.view()),
+            &|| {},
+        );
+        assert_eq!(
+            features,
+            array![[0.0, -1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let x = NumberTableColumn::new(Some("x".to_owned()), vec![1.0, 3.0]);
+        let y = NumberTableColumn::new(Some("y".to_owned()), vec![4.0, 2.0]);
+        let columns = [
+            TableColumnView::Number(x.view()),
+            TableColumnView::Number(y.view()),
+        ];
+        let feature_group = PolynomialFeatureGroup::compute_for_columns(&columns, 2);
+        assert_eq!(feature_group.feature_names(), vec!["x^2", "x * y"]);
+        let mut features = Array::zeros((2, 2));
+        feature_group.compute_array_f32(features.view_mut(), &columns, &|| {});
+        assert_eq!(features, array![[1.0, -1.0], [1.0, -1.0]]);
+    }
+}
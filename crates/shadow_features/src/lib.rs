@@ -1,26 +1,68 @@
 pub use self::{
     bag_words::BagOfWordsFeatureGroup,
     bag_words_cosine_similarity::BagOfWordsCosineSimilarityFeatureGroup,
-    compute::{compute_features_array_f32, compute_features_array_value, compute_features_table},
+    binned::BinnedFeatureGroup,
+    clipped::ClippedFeatureGroup,
+    compute::{
+        compute_features_array_f32, compute_features_array_value, compute_features_table,
+        compute_train_features_array_f32,
+    },
     identity::IdentityFeatureGroup,
+    imputed::{ImputationStrategy, ImputedFeatureGroup},
+    interaction::{Interaction, InteractionFeatureGroup},
+    log::LogFeatureGroup,
     normalized::NormalizedFeatureGroup,
     one_hot_encoded::OneHotEncodedFeatureGroup,
+    polynomial::PolynomialFeatureGroup,
+    power_transform::{PowerTransformFeatureGroup, PowerTransformMethod},
+    quantile_transform::QuantileTransformFeatureGroup,
+    target_encoded::TargetEncodedFeatureGroup,
     word_embedding::WordEmbeddingFeatureGroup,
 };
 
 pub mod bag_words;
 pub mod bag_words_cosine_similarity;
+pub mod binned;
+#[cfg(test)]
+mod binned_test;
+pub mod clipped;
 pub mod compute;
 pub mod identity;
+pub mod imputed;
+#[cfg(test)]
+mod imputed_test;
+pub mod interaction;
+#[cfg(test)]
+mod interaction_test;
+pub mod log;
 pub mod normalized;
 pub mod one_hot_encoded;
+pub mod polynomial;
+pub mod power_transform;
+#[cfg(test)]
+mod power_transform_test;
+pub mod quantile_transform;
+#[cfg(test)]
+mod quantile_transform_test;
+pub mod target_encoded;
+#[cfg(test)]
+mod target_encoded_test;
 pub mod word_embedding;
 
 #[derive(Clone, Debug)]
 pub enum FeatureGroup {
     Identity(IdentityFeatureGroup),
     Normalized(NormalizedFeatureGroup),
+    Log(LogFeatureGroup),
+    PowerTransform(PowerTransformFeatureGroup),
+    QuantileTransform(QuantileTransformFeatureGroup),
+    Binned(BinnedFeatureGroup),
+    Clipped(ClippedFeatureGroup),
+    Imputed(ImputedFeatureGroup),
     OneHotEncoded(OneHotEncodedFeatureGroup),
+    TargetEncoded(TargetEncodedFeatureGroup),
+    Interaction(InteractionFeatureGroup),
+    Polynomial(PolynomialFeatureGroup),
     BagOfWords(BagOfWordsFeatureGroup),
     WordEmbedding(WordEmbeddingFeatureGroup),
     BagOfWordsCosineSimilarity(BagOfWordsCosineSimilarityFeatureGroup),
@@ -31,10 +73,49 @@ impl FeatureGroup {
         match self {
             FeatureGroup::Identity(_) => 1,
             FeatureGroup::Normalized(_) => 1,
+            FeatureGroup::Log(_) => 1,
+            FeatureGroup::PowerTransform(_) => 1,
+            FeatureGroup::QuantileTransform(_) => 1,
+            FeatureGroup::Binned(s) => s.n_features(),
+            FeatureGroup::Clipped(_) => 1,
+            FeatureGroup::Imputed(s) => s.n_features(),
             FeatureGroup::OneHotEncoded(s) => s.variants.len() + 1,
+            FeatureGroup::TargetEncoded(s) => s.n_features(),
+            FeatureGroup::Interaction(s) => s.n_features(),
+            FeatureGroup::Polynomial(s) => s.n_features(),
             FeatureGroup::BagOfWords(s) => s.ngrams.len(),
             FeatureGroup::BagOfWordsCosineSimilarity(_) => 1,
             FeatureGroup::WordEmbedding(s) => s.model.size,
         }
     }
+
+    pub fn feature_names(&self) -> Vec<String> {
+        match self {
+            FeatureGroup::Identity(s) => vec![s.source_column_name.clone()],
+            FeatureGroup::Normalized(s) => vec![s.source_column_name.clone()],
+            FeatureGroup::Log(s) => vec![format!("log({})", s.source_column_name)],
+            FeatureGroup::PowerTransform(s) => match s.method {
+                PowerTransformMethod::YeoJohnson => {
+                    vec![format!("yeo_johnson({})", s.source_column_name)]
+                }
+                PowerTransformMethod::BoxCox => vec![format!("box_cox({})", s.source_column_name)],
+            },
+            FeatureGroup::QuantileTransform(s) => {
+                vec![format!("quantile({})", s.source_column_name)]
+            }
+            FeatureGroup::Binned(s) => s.feature_names(),
+            FeatureGroup::Clipped(s) => vec![format!("clip({})", s.source_column_name)],
+            FeatureGroup::Imputed(s) => s.feature_names(),
+            FeatureGroup::OneHotEncoded(s) => s.feature_names(),
+            FeatureGroup::TargetEncoded(s) => s.feature_names(),
+            FeatureGroup::Interaction(s) => s.feature_names(),
+            FeatureGroup::Polynomial(s) => s.feature_names(),
+            FeatureGroup::BagOfWords(s) => s.feature_names(),
+            FeatureGroup::BagOfWordsCosineSimilarity(s) => vec![format!(
+                "cosine_similarity({}, {})",
+                s.source_column_name_a, s.source_column_name_b
+            )],
+            FeatureGroup::WordEmbedding(s) => s.feature_names(),
+        }
+    }
 }
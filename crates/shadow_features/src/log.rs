@@ -0,0 +1,121 @@
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{
+    NumberTableColumn, NumberTableColumnView, TableColumn, TableColumnView, TableValue,
+};
+use shadow_zip::zip;
+
+/// Computes `ln(1 + x + offset)`, where `offset` shifts the training minimum to zero.
+#[derive(Clone, Debug)]
+pub struct LogFeatureGroup {
+    pub source_column_name: String,
+    pub offset: f32,
+}
+
+impl LogFeatureGroup {
+    pub fn compute_for_column(column: TableColumnView) -> LogFeatureGroup {
+        match column {
+            TableColumnView::Number(column) => Self::compute_for_number_column(column),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_number_column(column: NumberTableColumnView) -> Self {
+        let min = column
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold(0.0f32, |a, &b| f32::min(a, b));
+        Self {
+            source_column_name: column.name().unwrap().to_owned(),
+            offset: -min,
+        }
+    }
+}
+
+impl LogFeatureGroup {
+    pub fn compute_table(&self, column: TableColumnView, progress: &impl Fn(u64)) -> TableColumn {
+        let column = match column {
+            TableColumnView::Number(column) => {
+                TableColumn::Number(self.compute_table_for_number_column(column))
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        };
+        progress(column.len().to_u64().unwrap());
+        column
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_f32_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        features: ArrayViewMut2<TableValue>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_value_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    fn compute_feature(&self, value: f32) -> f32 {
+        if !value.is_finite() {
+            0.0
+        } else {
+            // Values below the training minimum are clamped so the logarithm is always defined.
+            f32::max(value + self.offset, 0.0).ln_1p()
+        }
+    }
+
+    fn compute_table_for_number_column(&self, column: NumberTableColumnView) -> NumberTableColumn {
+        let feature_values = column
+            .iter()
+            .map(|value| self.compute_feature(*value))
+            .collect();
+        NumberTableColumn::new(None, feature_values)
+    }
+
+    fn compute_array_f32_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.iter_mut(), column.iter()) {
+            *feature = self.compute_feature(*value);
+            progress()
+        }
+    }
+
+    fn compute_array_value_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.column_mut(0), column.iter()) {
+            *feature = TableValue::Number(self.compute_feature(*value));
+            progress()
+        }
+    }
+}
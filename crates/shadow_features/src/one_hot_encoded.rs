@@ -22,6 +22,16 @@ impl OneHotEncodedFeatureGroup {
 			variants: column.variants().to_owned(),
 		}
 	}
+
+	pub fn feature_names(&self) -> Vec<String> {
+		std::iter::once(format!("{} is missing", self.source_column_name))
+			.chain(
+				self.variants
+					.iter()
+					.map(|variant| format!("{} = {}", self.source_column_name, variant)),
+			)
+			.collect()
+	}
 }
 
 impl OneHotEncodedFeatureGroup {
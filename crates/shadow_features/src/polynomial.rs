@@ -0,0 +1,130 @@
+use crate::interaction::{compute_mean_variance, standardize};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{NumberTableColumn, TableColumn, TableColumnView, TableValue};
+use shadow_zip::zip;
+
+/// Computes the degree 2 polynomial terms of the standardized source columns. Each term `(i, j)` is the product of the source columns at indexes `i` and `j`, so `(i, i)` is a square.
+#[derive(Clone, Debug)]
+pub struct PolynomialFeatureGroup {
+    pub source_column_names: Vec<String>,
+    pub means: Vec<f32>,
+    pub variances: Vec<f32>,
+    pub terms: Vec<(usize, usize)>,
+}
+
+impl PolynomialFeatureGroup {
+    /// The terms are generated in order, `(0, 0)`, `(0, 1)`, ..., `(1, 1)`, ..., and only the first `max_features` are kept.
+    pub fn compute_for_columns(
+        columns: &[TableColumnView],
+        max_features: usize,
+    ) -> PolynomialFeatureGroup {
+        let columns: Vec<_> = columns
+            .iter()
+            .map(|column| match column {
+                TableColumnView::Number(column) => column.view(),
+                _ => unimplemented!(),
+            })
+            .collect();
+        let (means, variances) = columns
+            .iter()
+            .map(|column| compute_mean_variance(column.as_slice()))
+            .unzip();
+        let n_columns = columns.len();
+        let terms = (0..n_columns)
+            .flat_map(|i| (i..n_columns).map(move |j| (i, j)))
+            .take(max_features)
+            .collect();
+        Self {
+            source_column_names: columns
+                .iter()
+                .map(|column| column.name().unwrap().to_owned())
+                .collect(),
+            means,
+            variances,
+            terms,
+        }
+    }
+
+    pub fn n_features(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn feature_names(&self) -> Vec<String> {
+        self.terms
+            .iter()
+            .map(|(i, j)| {
+                if i == j {
+                    format!("{}^2", self.source_column_names[*i])
+                } else {
+                    format!(
+                        "{} * {}",
+                        self.source_column_names[*i], self.source_column_names[*j]
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+impl PolynomialFeatureGroup {
+    pub fn compute_table(
+        &self,
+        columns: &[TableColumnView],
+        progress: &impl Fn(u64),
+    ) -> Vec<TableColumn> {
+        let n_examples = columns.first().map(|column| column.len()).unwrap_or(0);
+        let mut features = Array::zeros((n_examples, self.n_features()));
+        self.compute_array_f32(features.view_mut(), columns, &|| {});
+        progress(n_examples.to_u64().unwrap());
+        features
+            .axis_iter(Axis(1))
+            .map(|feature_column| {
+                TableColumn::Number(NumberTableColumn::new(None, feature_column.to_vec()))
+            })
+            .collect()
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        columns: &[TableColumnView],
+        progress: &impl Fn(),
+    ) {
+        let columns: Vec<_> = columns
+            .iter()
+            .map(|column| match column {
+                TableColumnView::Number(column) => column.as_slice(),
+                _ => unimplemented!(),
+            })
+            .collect();
+        let mut standardized = vec![0.0; columns.len()];
+        for (example_index, mut features) in features.axis_iter_mut(Axis(0)).enumerate() {
+            for (standardized, column, mean, variance) in zip!(
+                standardized.iter_mut(),
+                columns.iter(),
+                self.means.iter(),
+                self.variances.iter()
+            ) {
+                *standardized = standardize(column[example_index], *mean, *variance);
+            }
+            for (feature, (i, j)) in zip!(features.iter_mut(), self.terms.iter()) {
+                *feature = standardized[*i] * standardized[*j];
+            }
+            progress();
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        columns: &[TableColumnView],
+        progress: &impl Fn(),
+    ) {
+        let mut features_f32 = Array::zeros(features.raw_dim());
+        self.compute_array_f32(features_f32.view_mut(), columns, progress);
+        for (feature, feature_f32) in zip!(features.iter_mut(), features_f32.iter()) {
+            *feature = TableValue::Number(*feature_f32);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{
+    NumberTableColumn, NumberTableColumnView, TableColumn, TableColumnView, TableValue,
+};
+use shadow_zip::zip;
+
+/// Applies a Yeo-Johnson or Box-Cox power transform with a fitted `lambda`, then standardizes the result with the fitted `mean` and `variance`.
+#[derive(Clone, Debug)]
+pub struct PowerTransformFeatureGroup {
+    pub source_column_name: String,
+    pub method: PowerTransformMethod,
+    pub lambda: f32,
+    pub mean: f32,
+    pub variance: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerTransformMethod {
+    YeoJohnson,
+    /// Box-Cox is only defined for positive values. Other values produce a feature value of zero.
+    BoxCox,
+}
+
+const MIN_LAMBDA: f64 = -5.0;
+const MAX_LAMBDA: f64 = 5.0;
+const LAMBDA_TOLERANCE: f64 = 1e-6;
+
+impl PowerTransformFeatureGroup {
+    pub fn compute_for_column(
+        column: TableColumnView,
+        method: PowerTransformMethod,
+    ) -> PowerTransformFeatureGroup {
+        match column {
+            TableColumnView::Number(column) => Self::compute_for_number_column(column, method),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_number_column(
+        column: NumberTableColumnView,
+        method: PowerTransformMethod,
+    ) -> Self {
+        let values: Vec<f64> = column
+            .iter()
+            .filter(|value| is_valid(method, **value))
+            .map(|value| value.to_f64().unwrap())
+            .collect();
+        let lambda = if values.is_empty() {
+            1.0
+        } else {
+            // Choose the lambda that maximizes the log likelihood of the transformed values with a golden section search.
+            let log_likelihood = |lambda: f64| compute_log_likelihood(method, &values, lambda);
+            let inverse_golden_ratio = (5.0f64.sqrt() - 1.0) / 2.0;
+            let mut a = MIN_LAMBDA;
+            let mut b = MAX_LAMBDA;
+            while b - a > LAMBDA_TOLERANCE {
+                let c = b - (b - a) * inverse_golden_ratio;
+                let d = a + (b - a) * inverse_golden_ratio;
+                if log_likelihood(c) > log_likelihood(d) {
+                    b = d;
+                } else {
+                    a = c;
+                }
+            }
+            (a + b) / 2.0
+        };
+        let mean_variance = shadow_metrics::MeanVariance::compute(
+            values
+                .iter()
+                .map(|value| transform(method, lambda, *value).to_f32().unwrap()),
+        );
+        Self {
+            source_column_name: column.name().unwrap().to_owned(),
+            method,
+            lambda: lambda.to_f32().unwrap(),
+            mean: mean_variance.mean,
+            variance: mean_variance.variance,
+        }
+    }
+}
+
+impl PowerTransformFeatureGroup {
+    pub fn compute_table(&self, column: TableColumnView, progress: &impl Fn(u64)) -> TableColumn {
+        let column = match column {
+            TableColumnView::Number(column) => {
+                TableColumn::Number(self.compute_table_for_number_column(column))
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        };
+        progress(column.len().to_u64().unwrap());
+        column
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_f32_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        features: ArrayViewMut2<TableValue>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_value_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    fn compute_feature(&self, value: f32) -> f32 {
+        if !is_valid(self.method, value) || self.variance == 0.0 || self.variance.is_nan() {
+            return 0.0;
+        }
+        let value = transform(
+            self.method,
+            self.lambda.to_f64().unwrap(),
+            value.to_f64().unwrap(),
+        );
+        (value.to_f32().unwrap() - self.mean) / f32::sqrt(self.variance)
+    }
+
+    fn compute_table_for_number_column(&self, column: NumberTableColumnView) -> NumberTableColumn {
+        let feature_values = column
+            .iter()
+            .map(|value| self.compute_feature(*value))
+            .collect();
+        NumberTableColumn::new(None, feature_values)
+    }
+
+    fn compute_array_f32_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.iter_mut(), column.iter()) {
+            *feature = self.compute_feature(*value);
+            progress()
+        }
+    }
+
+    fn compute_array_value_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.column_mut(0), column.iter()) {
+            *feature = TableValue::Number(self.compute_feature(*value));
+            progress()
+        }
+    }
+}
+
+fn is_valid(method: PowerTransformMethod, value: f32) -> bool {
+    match method {
+        PowerTransformMethod::YeoJohnson => value.is_finite(),
+        PowerTransformMethod::BoxCox => value.is_finite() && value > 0.0,
+    }
+}
+
+fn transform(method: PowerTransformMethod, lambda: f64, value: f64) -> f64 {
+    match method {
+        PowerTransformMethod::YeoJohnson => {
+            if value >= 0.0 {
+                if lambda.abs() < f64::EPSILON {
+                    value.ln_1p()
+                } else {
+                    ((value + 1.0).powf(lambda) - 1.0) / lambda
+                }
+            } else if (lambda - 2.0).abs() < f64::EPSILON {
+                -(-value).ln_1p()
+            } else {
+                -((-value + 1.0).powf(2.0 - lambda) - 1.0) / (2.0 - lambda)
+            }
+        }
+        PowerTransformMethod::BoxCox => {
+            if lambda.abs() < f64::EPSILON {
+                value.ln()
+            } else {
+                (value.powf(lambda) - 1.0) / lambda
+            }
+        }
+    }
+}
+
+fn compute_log_likelihood(method: PowerTransformMethod, values: &[f64], lambda: f64) -> f64 {
+    let n = values.len().to_f64().unwrap();
+    let mut mean = 0.0;
+    let mut m2 = 0.0;
+    let mut jacobian = 0.0;
+    for (i, value) in values.iter().enumerate() {
+        let transformed = transform(method, lambda, *value);
+        let delta = transformed - mean;
+        mean += delta / (i + 1).to_f64().unwrap();
+        m2 += delta * (transformed - mean);
+        jacobian += match method {
+            PowerTransformMethod::YeoJohnson => value.signum() * value.abs().ln_1p(),
+            PowerTransformMethod::BoxCox => value.ln(),
+        };
+    }
+    let variance = m2 / n;
+    if variance <= 0.0 || !variance.is_finite() {
+        return f64::NEG_INFINITY;
+    }
+    -n / 2.0 * variance.ln() + (lambda - 1.0) * jacobian
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod test {
+    use crate::{PowerTransformFeatureGroup, PowerTransformMethod};
+    use shadow_table::{NumberTableColumn, TableColumnView};
+
+    #[test]
+    fn test_box_cox_lambda_for_log_normal_values() {
+        // Values that are evenly spaced on a log scale are made symmetric by a log transform, which is the Box-Cox transform with a lambda of zero.
+        let values = (-50..=50)
+            .map(|i| (i as f32 / 10.0).exp())
+            .collect::<Vec<_>>();
+        let column = NumberTableColumn::new(Some("x".to_owned()), values);
+        let feature_group = PowerTransformFeatureGroup::compute_for_column(
+            TableColumnView::Number(column.view()),
+            PowerTransformMethod::BoxCox,
+        );
+        assert!(feature_group.lambda.abs() < 1e-3);
+        assert!(feature_group.mean.abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_yeo_johnson_lambda_for_symmetric_values() {
+        let values = (-50..=50).map(|i| i as f32).collect::<Vec<_>>();
+        let column = NumberTableColumn::new(Some("x".to_owned()), values);
+        let feature_group = PowerTransformFeatureGroup::compute_for_column(
+            TableColumnView::Number(column.view()),
+            PowerTransformMethod::YeoJohnson,
+        );
+        assert!((feature_group.lambda - 1.0).abs() < 1e-2);
+    }
+}
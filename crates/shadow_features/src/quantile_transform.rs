@@ -0,0 +1,171 @@
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_table::{
+    NumberTableColumn, NumberTableColumnView, TableColumn, TableColumnView, TableValue,
+};
+use shadow_zip::zip;
+
+/// Maps values to `[0, 1]` by interpolating between `quantiles`, which are the training values at evenly spaced probabilities.
+#[derive(Clone, Debug)]
+pub struct QuantileTransformFeatureGroup {
+    pub source_column_name: String,
+    pub quantiles: Vec<f32>,
+}
+
+impl QuantileTransformFeatureGroup {
+    pub fn compute_for_column(
+        column: TableColumnView,
+        n_quantiles: usize,
+    ) -> QuantileTransformFeatureGroup {
+        match column {
+            TableColumnView::Number(column) => Self::compute_for_number_column(column, n_quantiles),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_number_column(column: NumberTableColumnView, n_quantiles: usize) -> Self {
+        assert!(n_quantiles >= 2);
+        let values = sorted_finite_values(column.as_slice());
+        let quantiles = if values.is_empty() {
+            Vec::new()
+        } else {
+            (0..n_quantiles)
+                .map(|i| {
+                    let q = i.to_f32().unwrap() / (n_quantiles - 1).to_f32().unwrap();
+                    quantile(&values, q)
+                })
+                .collect()
+        };
+        Self {
+            source_column_name: column.name().unwrap().to_owned(),
+            quantiles,
+        }
+    }
+}
+
+impl QuantileTransformFeatureGroup {
+    pub fn compute_table(&self, column: TableColumnView, progress: &impl Fn(u64)) -> TableColumn {
+        let column = match column {
+            TableColumnView::Number(column) => {
+                TableColumn::Number(self.compute_table_for_number_column(column))
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        };
+        progress(column.len().to_u64().unwrap());
+        column
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_f32_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        features: ArrayViewMut2<TableValue>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Number(column) => {
+                self.compute_array_value_for_number_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Enum(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    /// Values below the first quantile, including negative infinity, map to 0 and values above the last, including positive infinity, map to 1. NaN maps to 0.
+    fn compute_feature(&self, value: f32) -> f32 {
+        let n_quantiles = self.quantiles.len();
+        if value.is_nan() || n_quantiles < 2 {
+            return 0.0;
+        }
+        let lower = self.quantiles.partition_point(|quantile| *quantile < value);
+        let upper = self
+            .quantiles
+            .partition_point(|quantile| *quantile <= value);
+        let position = if lower == n_quantiles {
+            (n_quantiles - 1).to_f32().unwrap()
+        } else if upper == 0 {
+            0.0
+        } else if lower < upper {
+            // The value is equal to one or more quantiles, so use the middle of their positions.
+            (lower + upper - 1).to_f32().unwrap() / 2.0
+        } else {
+            let left = self.quantiles[lower - 1];
+            let right = self.quantiles[lower];
+            (lower - 1).to_f32().unwrap() + (value - left) / (right - left)
+        };
+        position / (n_quantiles - 1).to_f32().unwrap()
+    }
+
+    fn compute_table_for_number_column(&self, column: NumberTableColumnView) -> NumberTableColumn {
+        let feature_values = column
+            .iter()
+            .map(|value| self.compute_feature(*value))
+            .collect();
+        NumberTableColumn::new(None, feature_values)
+    }
+
+    fn compute_array_f32_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.iter_mut(), column.iter()) {
+            *feature = self.compute_feature(*value);
+            progress()
+        }
+    }
+
+    fn compute_array_value_for_number_column(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column: NumberTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (feature, value) in zip!(features.column_mut(0), column.iter()) {
+            *feature = TableValue::Number(self.compute_feature(*value));
+            progress()
+        }
+    }
+}
+
+pub(crate) fn sorted_finite_values(values: &[f32]) -> Vec<f32> {
+    let mut values: Vec<f32> = values
+        .iter()
+        .cloned()
+        .filter(|value| value.is_finite())
+        .collect();
+    values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    values
+}
+
+/// Computes the `q` quantile of `sorted_values` with linear interpolation. `sorted_values` must not be empty.
+pub(crate) fn quantile(sorted_values: &[f32], q: f32) -> f32 {
+    let position = (sorted_values.len() - 1).to_f32().unwrap() * q;
+    let index = position.trunc().to_usize().unwrap();
+    let fract = position.fract();
+    match sorted_values.get(index + 1) {
+        Some(next_value) if fract > 0.0 => {
+            sorted_values[index] * (1.0 - fract) + next_value * fract
+        }
+        _ => sorted_values[index],
+    }
+}
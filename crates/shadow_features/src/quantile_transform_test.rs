@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod test {
+    use crate::QuantileTransformFeatureGroup;
+    use shadow_table::{NumberTableColumn, TableColumnView};
+
+    #[test]
+    fn test_quantile_transform() {
+        let values = (0..=100).map(|i| (i * i) as f32).collect::<Vec<_>>();
+        let column = NumberTableColumn::new(Some("x".to_owned()), values);
+        let feature_group = QuantileTransformFeatureGroup::compute_for_column(
+            TableColumnView::Number(column.view()),
+            11,
+        );
+        assert_eq!(feature_group.quantiles.len(), 11);
+        let mut features = ndarray::Array2::zeros((6, 1));
+        let input = NumberTableColumn::new(
+            Some("x".to_owned()),
+            vec![
+                -1.0,
+                2500.0,
+                20_000.0,
+                f32::NAN,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            ],
+        );
+        feature_group.compute_array_f32(
+            features.view_mut(),
+            TableColumnView::Number(input.view()),
+            &|| {},
+        );
+        assert_eq!(features[(0, 0)], 0.0);
+        assert!((features[(1, 0)] - 0.5).abs() < f32::EPSILON);
+        assert_eq!(features[(2, 0)], 1.0);
+        assert_eq!(features[(3, 0)], 0.0);
+        assert_eq!(features[(4, 0)], 0.0);
+        assert_eq!(features[(5, 0)], 1.0);
+    }
+}
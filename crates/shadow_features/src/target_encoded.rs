@@ -0,0 +1,321 @@
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_metrics::Mean;
+use shadow_table::{
+    EnumTableColumnView, NumberTableColumn, TableColumn, TableColumnView, TableValue,
+};
+use shadow_zip::zip;
+use std::num::NonZeroUsize;
+
+/// Replaces each enum variant with the mean of the target over the training examples with that variant, smoothed toward the mean over all training examples, `prior`. Regression and binary classification targets produce one feature. Multiclass classification targets produce one feature per class, the smoothed fraction of examples with that class.
+#[derive(Clone, Debug)]
+pub struct TargetEncodedFeatureGroup {
+    pub source_column_name: String,
+    /// The number of examples at which a variant's own mean and the prior are weighted equally.
+    pub smoothing: f32,
+    pub prior: Vec<f32>,
+    /// `encodings[0]` is for missing values and `encodings[i]` is for the variant at index `i - 1`.
+    pub encodings: Vec<Vec<f32>>,
+}
+
+impl TargetEncodedFeatureGroup {
+    pub fn compute_for_column(
+        column: TableColumnView,
+        target_column: TableColumnView,
+        smoothing: f32,
+    ) -> TargetEncodedFeatureGroup {
+        match column {
+            TableColumnView::Enum(column) => {
+                Self::compute_for_enum_column(column, target_column, smoothing)
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    fn compute_for_enum_column(
+        column: EnumTableColumnView,
+        target_column: TableColumnView,
+        smoothing: f32,
+    ) -> Self {
+        let targets = compute_targets(target_column);
+        let statistics = TargetStatistics::compute(column.view(), targets.view(), |_| true);
+        let (prior, encodings) = statistics.compute_encodings(smoothing);
+        Self {
+            source_column_name: column.name().unwrap().to_owned(),
+            smoothing,
+            prior,
+            encodings,
+        }
+    }
+
+    pub fn n_features(&self) -> usize {
+        self.prior.len()
+    }
+
+    pub fn feature_names(&self) -> Vec<String> {
+        if self.n_features() == 1 {
+            vec![format!("target_encoded({})", self.source_column_name)]
+        } else {
+            (0..self.n_features())
+                .map(|class_index| {
+                    format!(
+                        "target_encoded({})[{}]",
+                        self.source_column_name, class_index
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+impl TargetEncodedFeatureGroup {
+    pub fn compute_table(
+        &self,
+        column: TableColumnView,
+        progress: &impl Fn(u64),
+    ) -> Vec<TableColumn> {
+        match column {
+            TableColumnView::Enum(column) => {
+                let n_examples = column.len().to_u64().unwrap();
+                let columns = self.compute_table_for_enum_column(column);
+                progress(n_examples);
+                columns
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Number(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_f32(
+        &self,
+        features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Enum(column) => {
+                self.compute_array_f32_for_enum_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Number(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    pub fn compute_array_value(
+        &self,
+        features: ArrayViewMut2<TableValue>,
+        column: TableColumnView,
+        progress: &impl Fn(),
+    ) {
+        match column {
+            TableColumnView::Enum(column) => {
+                self.compute_array_value_for_enum_column(features, column, progress)
+            }
+            TableColumnView::Unknown(_) => unimplemented!(),
+            TableColumnView::Number(_) => unimplemented!(),
+            TableColumnView::Text(_) => unimplemented!(),
+        }
+    }
+
+    /// Computes the features for the training examples without leaking each example's own target into its features. The examples are split into `n_folds` folds by `example_index % n_folds`, and the features for each fold are computed from encodings fitted on the other folds.
+    pub fn compute_out_of_fold_array_f32(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: TableColumnView,
+        target_column: TableColumnView,
+        n_folds: usize,
+        progress: &impl Fn(),
+    ) {
+        assert!(n_folds >= 2);
+        let column = match column {
+            TableColumnView::Enum(column) => column,
+            _ => unimplemented!(),
+        };
+        let targets = compute_targets(target_column);
+        let fold_statistics: Vec<TargetStatistics> = (0..n_folds)
+            .map(|fold| {
+                TargetStatistics::compute(column.view(), targets.view(), |example_index| {
+                    example_index % n_folds == fold
+                })
+            })
+            .collect();
+        for fold in 0..n_folds {
+            let mut statistics = TargetStatistics::new(self.encodings.len(), self.prior.len());
+            for (other_fold, other_fold_statistics) in fold_statistics.iter().enumerate() {
+                if other_fold != fold {
+                    statistics.merge(other_fold_statistics);
+                }
+            }
+            let (prior, encodings) = statistics.compute_encodings(self.smoothing);
+            for (example_index, (mut features, value)) in
+                zip!(features.axis_iter_mut(Axis(0)), column.iter()).enumerate()
+            {
+                if example_index % n_folds != fold {
+                    continue;
+                }
+                let encoding = encodings.get(variant_index(value)).unwrap_or(&prior);
+                for (feature, encoding) in zip!(features.iter_mut(), encoding.iter()) {
+                    *feature = *encoding;
+                }
+                progress();
+            }
+        }
+    }
+
+    fn encoding(&self, value: &Option<NonZeroUsize>) -> &[f32] {
+        // Variants that were not present when the feature group was computed use the prior.
+        self.encodings
+            .get(variant_index(value))
+            .unwrap_or(&self.prior)
+    }
+
+    fn compute_table_for_enum_column(&self, column: EnumTableColumnView) -> Vec<TableColumn> {
+        (0..self.n_features())
+            .map(|feature_index| {
+                let feature_values = column
+                    .iter()
+                    .map(|value| self.encoding(value)[feature_index])
+                    .collect();
+                TableColumn::Number(NumberTableColumn::new(None, feature_values))
+            })
+            .collect()
+    }
+
+    fn compute_array_f32_for_enum_column(
+        &self,
+        mut features: ArrayViewMut2<f32>,
+        column: EnumTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (mut features, value) in zip!(features.axis_iter_mut(Axis(0)), column.iter()) {
+            for (feature, encoding) in zip!(features.iter_mut(), self.encoding(value).iter()) {
+                *feature = *encoding;
+            }
+            progress();
+        }
+    }
+
+    fn compute_array_value_for_enum_column(
+        &self,
+        mut features: ArrayViewMut2<TableValue>,
+        column: EnumTableColumnView,
+        progress: &impl Fn(),
+    ) {
+        for (mut features, value) in zip!(features.axis_iter_mut(Axis(0)), column.iter()) {
+            for (feature, encoding) in zip!(features.iter_mut(), self.encoding(value).iter()) {
+                *feature = TableValue::Number(*encoding);
+            }
+            progress();
+        }
+    }
+}
+
+fn variant_index(value: &Option<NonZeroUsize>) -> usize {
+    value.map(|value| value.get()).unwrap_or(0)
+}
+
+/// Converts the target column to an array with one column per feature. Rows for examples with a missing target are NaN.
+fn compute_targets(target_column: TableColumnView) -> Array2<f32> {
+    match target_column {
+        TableColumnView::Number(target_column) => Array::from_shape_vec(
+            (target_column.len(), 1),
+            target_column.as_slice().to_owned(),
+        )
+        .unwrap(),
+        TableColumnView::Enum(target_column) => {
+            let n_classes = target_column.variants().len();
+            // Binary classification targets are encoded as the fraction of examples with the second class, matching the labels used by the binary classifier.
+            let n_outputs = if n_classes == 2 { 1 } else { n_classes };
+            let mut targets = Array::zeros((target_column.len(), n_outputs));
+            for (mut targets, value) in zip!(targets.axis_iter_mut(Axis(0)), target_column.iter()) {
+                match value {
+                    None => targets.fill(f32::NAN),
+                    Some(value) if n_classes == 2 => {
+                        targets[0] = (value.get() - 1).to_f32().unwrap()
+                    }
+                    Some(value) => targets[value.get() - 1] = 1.0,
+                }
+            }
+            targets
+        }
+        _ => unimplemented!(),
+    }
+}
+
+struct TargetStatistics {
+    prior: Vec<Mean>,
+    variants: Vec<Vec<Mean>>,
+}
+
+impl TargetStatistics {
+    fn new(n_variants: usize, n_outputs: usize) -> Self {
+        Self {
+            prior: vec![Mean::new(); n_outputs],
+            variants: vec![vec![Mean::new(); n_outputs]; n_variants],
+        }
+    }
+
+    fn compute(
+        column: EnumTableColumnView,
+        targets: ArrayView2<f32>,
+        include: impl Fn(usize) -> bool,
+    ) -> Self {
+        let mut statistics = Self::new(column.variants().len() + 1, targets.ncols());
+        for (example_index, (value, targets)) in
+            zip!(column.iter(), targets.axis_iter(Axis(0))).enumerate()
+        {
+            if !include(example_index) || targets.iter().any(|target| !target.is_finite()) {
+                continue;
+            }
+            let variant_means = &mut statistics.variants[variant_index(value)];
+            for (prior, variant_mean, target) in zip!(
+                statistics.prior.iter_mut(),
+                variant_means.iter_mut(),
+                targets.iter()
+            ) {
+                prior.update(*target);
+                variant_mean.update(*target);
+            }
+        }
+        statistics
+    }
+
+    fn merge(&mut self, other: &TargetStatistics) {
+        for (prior, other_prior) in zip!(self.prior.iter_mut(), other.prior.iter()) {
+            prior.merge(other_prior.clone());
+        }
+        for (variant_means, other_variant_means) in
+            zip!(self.variants.iter_mut(), other.variants.iter())
+        {
+            for (mean, other_mean) in zip!(variant_means.iter_mut(), other_variant_means.iter()) {
+                mean.merge(other_mean.clone());
+            }
+        }
+    }
+
+    fn compute_encodings(&self, smoothing: f32) -> (Vec<f32>, Vec<Vec<f32>>) {
+        let prior: Vec<f32> = self
+            .prior
+            .iter()
+            .map(|mean| mean.clone().finalize().unwrap_or(0.0))
+            .collect();
+        let encodings = self
+            .variants
+            .iter()
+            .map(|variant_means| {
+                zip!(variant_means.iter(), prior.iter())
+                    .map(|(mean, prior)| {
+                        let n = mean.n().to_f32().unwrap();
+                        match mean.clone().finalize() {
+                            Some(mean) => (n * mean + smoothing * prior) / (n + smoothing),
+                            None => *prior,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        (prior, encodings)
+    }
+}
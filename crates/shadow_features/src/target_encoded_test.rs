@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        compute_features_array_f32, compute_train_features_array_f32, FeatureGroup,
+        IdentityFeatureGroup, TargetEncodedFeatureGroup,
+    };
+    use ndarray::prelude::*;
+    use shadow_table::{EnumTableColumn, NumberTableColumn, Table, TableColumn, TableColumnView};
+    use std::num::NonZeroUsize;
+
+    fn enum_column(n_variants: usize, values: &[usize]) -> EnumTableColumn {
+        EnumTableColumn::new(
+            Some("x".to_owned()),
+            (0..n_variants).map(|i| i.to_string()).collect(),
+            values
+                .iter()
+                .map(|value| NonZeroUsize::new(*value))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_regression_smoothing() {
+        let column = enum_column(2, &[1, 1, 2, 2, 0]);
+        let target = NumberTableColumn::new(None, vec![1.0, 3.0, 5.0, 7.0, 9.0]);
+        let feature_group = TargetEncodedFeatureGroup::compute_for_column(
+            TableColumnView::Enum(column.view()),
+            TableColumnView::Number(target.view()),
+            1.0,
+        );
+        assert_eq!(feature_group.prior, vec![5.0]);
+        assert_eq!(
+            feature_group.encodings,
+            vec![vec![7.0], vec![3.0], vec![17.0 / 3.0]]
+        );
+    }
+
+    #[test]
+    fn test_multiclass() {
+        let column = enum_column(2, &[1, 1, 2]);
+        let target = enum_column(3, &[1, 2, 3]);
+        let feature_group = TargetEncodedFeatureGroup::compute_for_column(
+            TableColumnView::Enum(column.view()),
+            TableColumnView::Enum(target.view()),
+            0.0,
+        );
+        assert_eq!(feature_group.n_features(), 3);
+        assert_eq!(feature_group.encodings[1], vec![0.5, 0.5, 0.0]);
+        assert_eq!(feature_group.encodings[2], vec![0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_out_of_fold() {
+        let column = enum_column(1, &[1, 1, 1, 1]);
+        let target = enum_column(2, &[1, 2, 1, 2]);
+        let feature_group = TargetEncodedFeatureGroup::compute_for_column(
+            TableColumnView::Enum(column.view()),
+            TableColumnView::Enum(target.view()),
+            0.0,
+        );
+        assert_eq!(feature_group.encodings[1], vec![0.5]);
+        // Each example is encoded using only the examples in the other fold, which all have the opposite label.
+        let mut features = Array::zeros((4, 1));
+        feature_group.compute_out_of_fold_array_f32(
+            features.view_mut(),
+            TableColumnView::Enum(column.view()),
+            TableColumnView::Enum(target.view()),
+            2,
+            &|| {},
+        );
+        assert_eq!(features, array![[1.0], [0.0], [1.0], [0.0]]);
+    }
+
+    #[test]
+    fn test_train_features_are_out_of_fold() {
+        let column = enum_column(1, &[1, 1, 1, 1]);
+        let target = enum_column(2, &[1, 2, 1, 2]);
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = vec![
+            TableColumn::Number(NumberTableColumn::new(
+                Some("n".to_owned()),
+                vec![1.0, 2.0, 3.0, 4.0],
+            )),
+            TableColumn::Enum(column.clone()),
+        ];
+        let feature_groups = vec![
+            FeatureGroup::Identity(IdentityFeatureGroup {
+                source_column_name: "n".to_owned(),
+            }),
+            FeatureGroup::TargetEncoded(TargetEncodedFeatureGroup::compute_for_column(
+                TableColumnView::Enum(column.view()),
+                TableColumnView::Enum(target.view()),
+                0.0,
+            )),
+        ];
+        let features = compute_train_features_array_f32(
+            &table.view(),
+            &feature_groups,
+            TableColumnView::Enum(target.view()),
+            2,
+            &|| {},
+        );
+        assert_eq!(
+            features,
+            array![[1.0, 1.0], [2.0, 0.0], [3.0, 1.0], [4.0, 0.0]]
+        );
+        // Examples seen at inference use the encodings fitted on every training example.
+        let features = compute_features_array_f32(&table.view(), &feature_groups, &|| {});
+        assert_eq!(
+            features,
+            array![[1.0, 0.5], [2.0, 0.5], [3.0, 0.5], [4.0, 0.5]]
+        );
+    }
+
+    #[test]
+    fn test_feature_names() {
+        let column = enum_column(2, &[1, 2]);
+        let target = NumberTableColumn::new(None, vec![1.0, 2.0]);
+        let feature_group =
+            FeatureGroup::TargetEncoded(TargetEncodedFeatureGroup::compute_for_column(
+                TableColumnView::Enum(column.view()),
+                TableColumnView::Number(target.view()),
+                1.0,
+            ));
+        assert_eq!(feature_group.feature_names(), vec!["target_encoded(x)"]);
+        let target = enum_column(3, &[1, 3]);
+        let feature_group =
+            FeatureGroup::TargetEncoded(TargetEncodedFeatureGroup::compute_for_column(
+                TableColumnView::Enum(column.view()),
+                TableColumnView::Enum(target.view()),
+                1.0,
+            ));
+        assert_eq!(
+            feature_group.feature_names(),
+            vec![
+                "target_encoded(x)[0]",
+                "target_encoded(x)[1]",
+                "target_encoded(x)[2]"
+            ]
+        );
+        assert_eq!(
+            feature_group.feature_names().len(),
+            feature_group.n_features()
+        );
+    }
+}
@@ -12,6 +12,12 @@ pub struct WordEmbeddingFeatureGroup {
 }
 
 impl WordEmbeddingFeatureGroup {
+	pub fn feature_names(&self) -> Vec<String> {
+		(0..self.model.size)
+			.map(|index| format!("{} embedding[{}]", self.source_column_name, index))
+			.collect()
+	}
+
 	pub fn compute_table(
 		&self,
 		column: shadow_table::TableColumnView,
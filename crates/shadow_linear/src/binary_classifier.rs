@@ -1,13 +1,17 @@
 use super::{
+    calibration::{BinaryCalibrationMethod, BinaryCalibrator},
+    compute_class_weighted_example_weights, compute_example_weights,
+    early_stopping::{EarlyStoppingMetricInput, Task},
+    n_examples_per_early_stopping_chunk,
+    optimizer::OptimizerState,
     shap::{compute_shap_values_for_example, ComputeShapValuesForExampleOutput},
-    train_early_stopping_split, EarlyStoppingMonitor, Progress, TrainOptions, TrainProgressEvent,
+    train_linear_model, EarlyStoppingMetric, EarlyStoppingValidation, Examples, LinearModel,
+    LinearModelTrainOutput, Progress, TrainOptions,
 };
 use ndarray::{self, prelude::*};
 use num::{clamp, ToPrimitive};
 use rayon::{self, prelude::*};
-use shadow_kill_chip;
 use shadow_metrics::{BinaryCrossEntropy, BinaryCrossEntropyInput};
-use shadow_progress_counter::ProgressCounter;
 use shadow_table::prelude::*;
 use shadow_zip::{pzip, zip};
 use std::{num::NonZeroUsize, ops::Neg};
@@ -17,114 +21,138 @@ pub struct BinaryClassifier {
     pub bias: f32,
     pub weights: Array1<f32>,
     pub means: Vec<f32>,
+    /// Applied to the probabilities by `predict`. See `BinaryClassifier::calibrate`.
+    pub calibrator: Option<BinaryCalibrator>,
 }
 
 pub struct BinaryClassifierTrainOutput {
     pub model: BinaryClassifier,
     pub losses: Option<Vec<f32>>,
     pub feature_importances: Option<Vec<f32>>,
+    pub n_zero_weights: usize,
+    /// The epoch whose model was returned, if early stopping was enabled.
+    pub best_epoch: Option<usize>,
 }
 
 impl BinaryClassifier {
+    /// If `weights` is given, each example's contribution to the loss is multiplied by its weight and the weight of its class in `train_options.class_weights`.
     pub fn train(
         features: ArrayView2<f32>,
         labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
         train_options: &TrainOptions,
         progress: Progress,
-    ) -> BinaryClassifierTrainOutput {
+    ) -> anyhow::Result<BinaryClassifierTrainOutput> {
+        BinaryClassifier::train_with_initial_model(
+            None,
+            features,
+            labels,
+            weights,
+            validation,
+            train_options,
+            progress,
+        )
+    }
+
+    /// Continues training `model`, starting from its weights and bias instead of zeros. The model keeps its means, but its calibrator is discarded because it no longer matches the trained model.
+    pub fn train_from(
+        model: BinaryClassifier,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<BinaryClassifierTrainOutput> {
+        assert_eq!(model.weights.len(), features.ncols());
+        BinaryClassifier::train_with_initial_model(
+            Some(model),
+            features,
+            labels,
+            weights,
+            validation,
+            train_options,
+            progress,
+        )
+    }
+
+    fn train_with_initial_model(
+        initial_model: Option<BinaryClassifier>,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<BinaryClassifierTrainOutput> {
+        let n_classes = labels.variants().len();
         let n_features = features.ncols();
-        let (features_train, labels_train, features_early_stopping, labels_early_stopping) =
-            train_early_stopping_split(
-                features,
-                labels.as_slice().into(),
-                train_options
-                    .early_stopping_options
-                    .as_ref()
-                    .map(|o| o.early_stopping_fraction)
-                    .unwrap_or(0.0),
-            );
-        let means = features_train
-            .axis_iter(Axis(1))
-            .map(|column| column.mean().unwrap())
-            .collect();
-        let mut model = BinaryClassifier {
-            bias: 0.0,
-            weights: <Array1<f32>>::zeros(n_features),
-            means,
-        };
-        let mut early_stopping_monitor =
-            train_options
-                .early_stopping_options
-                .as_ref()
-                .map(|early_stopping_options| {
-                    EarlyStoppingMonitor::new(
-                        early_stopping_options.min_decrease_in_loss_for_significant_change,
-                        early_stopping_options.n_rounds_without_improvement_to_stop,
-                    )
-                });
-        let progress_counter = ProgressCounter::new(train_options.max_epochs.to_u64().unwrap());
-        (progress.handle_progress_event)(TrainProgressEvent::Train(progress_counter.clone()));
-        let mut probabilities_buffer: Array1<f32> = Array1::zeros(labels.len());
-        let mut losses = if train_options.compute_losses {
-            Some(Vec::new())
-        } else {
-            None
-        };
-        let kill_chip = progress.kill_chip;
-        for _ in 0..train_options.max_epochs {
-            progress_counter.inc(1);
-            let n_examples_per_batch = train_options.n_examples_per_batch;
-            struct BinaryClassifierPtr(*mut BinaryClassifier);
-            unsafe impl Send for BinaryClassifierPtr {}
-            unsafe impl Sync for BinaryClassifierPtr {}
-            let model_ptr = BinaryClassifierPtr(&mut model);
-            pzip!(
-                features_train.axis_chunks_iter(Axis(0), n_examples_per_batch),
-                labels_train.axis_chunks_iter(Axis(0), n_examples_per_batch),
-                probabilities_buffer.axis_chunks_iter_mut(Axis(0), n_examples_per_batch),
-            )
-            .for_each(|(features, labels, probabilities)| {
-                let model_ptr = &model_ptr;
-                let model = unsafe { &mut *model_ptr.0 };
-                BinaryClassifier::train_batch(
-                    model,
-                    features,
-                    labels,
-                    probabilities,
-                    train_options,
-                    kill_chip,
+        let example_weights = compute_class_weighted_example_weights(
+            weights,
+            labels.as_slice().into(),
+            n_classes,
+            &train_options.class_weights,
+        );
+        let LinearModelTrainOutput {
+            model,
+            losses,
+            best_epoch,
+        } = train_linear_model(
+            Task::BinaryClassification,
+            Examples {
+                features: features.view(),
+                labels: labels.as_slice().into(),
+                weights: example_weights.view(),
+            },
+            validation,
+            |validation| {
+                let validation_examples = validation.compute_examples();
+                let labels = validation_examples.enum_labels().to_owned();
+                let weights = compute_class_weighted_example_weights(
+                    None,
+                    labels.view(),
+                    n_classes,
+                    &train_options.class_weights,
                 );
-            });
-            if let Some(losses) = &mut losses {
-                let loss =
-                    BinaryClassifier::compute_loss(probabilities_buffer.view(), labels_train);
-                losses.push(loss);
-            }
-            if let Some(early_stopping_monitor) = early_stopping_monitor.as_mut() {
-                let early_stopping_metric_value =
-                    BinaryClassifier::compute_early_stopping_metric_value(
-                        &model,
-                        features_early_stopping,
-                        labels_early_stopping,
-                        train_options,
-                    );
-                let should_stop = early_stopping_monitor.update(early_stopping_metric_value);
-                if should_stop {
-                    break;
-                }
-            }
-
-            if progress.kill_chip.is_activate() {
-                break;
-            }
-        }
-        (progress.handle_progress_event)(TrainProgressEvent::TrainDone);
+                Ok((validation_examples.features, labels, weights))
+            },
+            |train_examples| {
+                initial_model
+                    .map(|model| BinaryClassifier {
+                        calibrator: None,
+                        ..model
+                    })
+                    .unwrap_or_else(|| {
+                        let means = train_examples
+                            .features
+                            .axis_iter(Axis(1))
+                            .map(|column| column.mean().unwrap())
+                            .collect();
+                        BinaryClassifier {
+                            bias: 0.0,
+                            weights: <Array1<f32>>::zeros(n_features),
+                            means,
+                            calibrator: None,
+                        }
+                    })
+            },
+            train_options,
+            progress,
+        )?;
         let feature_importances = BinaryClassifier::compute_feature_importances(&model);
-        BinaryClassifierTrainOutput {
+        let n_zero_weights = model
+            .weights
+            .iter()
+            .filter(|weight| **weight == 0.0)
+            .count();
+        Ok(BinaryClassifierTrainOutput {
             model,
             losses,
             feature_importances: Some(feature_importances),
-        }
+            n_zero_weights,
+            best_epoch,
+        })
     }
 
     fn compute_feature_importances(model: &BinaryClassifier) -> Vec<f32> {
@@ -140,93 +168,21 @@ impl BinaryClassifier {
         feature_importances
     }
 
-    fn train_batch(
-        &mut self,
-        features: ArrayView2<f32>,
-        labels: ArrayView1<Option<NonZeroUsize>>,
-        mut probabilities: ArrayViewMut1<f32>,
-        train_options: &TrainOptions,
-        kill_chip: &shadow_kill_chip::KillChip,
-    ) {
-        if kill_chip.is_activate() {
-            return;
-        }
-        let learning_rate = train_options.learning_rate;
-        let mut py = features.dot(&self.weights) + self.bias;
-        for (probability, py) in zip!(probabilities.iter_mut(), py.iter_mut()) {
-            *probability = 1.0 / (py.neg().exp() + 1.0);
-            *py = *probability;
-        }
-        for (py, label) in zip!(py.view_mut(), labels) {
-            let label = match label.map(|l| l.get()) {
-                Some(1) => 0.0,
-                Some(2) => 1.0,
-                _ => unreachable!(),
-            };
-            *py -= label
-        }
-        let py = py.insert_axis(Axis(1));
-        let weight_gradients = (&features * &py).mean_axis(Axis(0)).unwrap();
-        let bias_gradient = py.mean_axis(Axis(0)).unwrap()[0];
-        for (weight, weight_gradient) in zip!(self.weights.view_mut(), weight_gradients.view()) {
-            *weight += -learning_rate * weight_gradient;
-        }
-        self.bias += -learning_rate * bias_gradient;
-    }
-
     pub fn compute_loss(
         probabilities: ArrayView1<f32>,
         labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
     ) -> f32 {
         let mut total = 0.0;
-        for (label, probability) in zip!(labels.iter(), probabilities) {
+        for (label, probability, weight) in zip!(labels.iter(), probabilities, weights) {
             let label = (label.unwrap().get() - 1).to_f32().unwrap();
             let probability_clamped =
                 clamp(*probability, std::f32::EPSILON, 1.0 - std::f32::EPSILON);
-            total += -1.0 * label * probability_clamped.ln()
-                + -1.0 * (1.0 - label) * (1.0 - probability_clamped).ln()
+            total += weight
+                * (-1.0 * label * probability_clamped.ln()
+                    + -1.0 * (1.0 - label) * (1.0 - probability_clamped).ln())
         }
-        total / labels.len().to_f32().unwrap()
-    }
-
-    fn compute_early_stopping_metric_value(
-        &self,
-        features: ArrayView2<f32>,
-        labels: ArrayView1<Option<NonZeroUsize>>,
-        train_options: &TrainOptions,
-    ) -> f32 {
-        pzip!(
-            features.axis_chunks_iter(Axis(0), train_options.n_examples_per_batch),
-            labels.axis_chunks_iter(Axis(0), train_options.n_examples_per_batch),
-        )
-        .fold(
-            || {
-                let predictions = unsafe {
-                    <Array1<f32>>::uninit(train_options.n_examples_per_batch).assume_init()
-                };
-                let metric = BinaryCrossEntropy::new();
-                (predictions, metric)
-            },
-            |(mut predictions, mut metric), (features, labels)| {
-                let slice = s![0..features.nrows()];
-                let mut predictions_slice = predictions.slice_mut(slice);
-                self.predict(features, predictions_slice.view_mut());
-                for (prediction, label) in zip!(predictions_slice.iter(), labels.iter()) {
-                    metric.update(BinaryCrossEntropyInput {
-                        probability: *prediction,
-                        label: *label,
-                    });
-                }
-                (predictions, metric)
-            },
-        )
-        .map(|(_, metric)| metric)
-        .reduce(BinaryCrossEntropy::new, |mut a, b| {
-            a.merge(b);
-            a
-        })
-        .finalize()
-        .unwrap()
+        total / weights.sum()
     }
 
     pub fn predict(&self, features: ArrayView2<f32>, mut probabilities: ArrayViewMut1<f32>) {
@@ -241,6 +197,31 @@ impl BinaryClassifier {
         for probability in probabilities.iter_mut() {
             *probability = 1.0 / (probability.neg().exp() + 1.0);
         }
+        if let Some(calibrator) = &self.calibrator {
+            for probability in probabilities.iter_mut() {
+                *probability = calibrator.compute_calibrated_probability(*probability);
+            }
+        }
+    }
+
+    /// Fits a calibrator to held-out examples that were not used for training, replacing any existing calibrator. If `weights` is given, each example's contribution is multiplied by its weight.
+    pub fn calibrate(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        method: BinaryCalibrationMethod,
+    ) {
+        self.calibrator = None;
+        let mut probabilities = Array1::zeros(features.nrows());
+        self.predict(features, probabilities.view_mut());
+        let weights = compute_example_weights(weights, features.nrows());
+        self.calibrator = Some(BinaryCalibrator::fit(
+            method,
+            probabilities.view(),
+            labels.as_slice().into(),
+            weights.view(),
+        ));
     }
 
     pub fn compute_feature_contributions(
@@ -261,7 +242,9 @@ impl BinaryClassifier {
     }
 
     pub fn from_reader(
-        binary_classifier: crate::serialize::BinaryClassifierReader,
+        binary_classifier: crate::serialize::VersionedReader<
+            crate::serialize::BinaryClassifierReader,
+        >,
     ) -> BinaryClassifier {
         crate::serialize::deserialize_binary_classifier(binary_classifier)
     }
@@ -275,13 +258,131 @@ impl BinaryClassifier {
 
     #[must_use]
     pub fn from_bytes(&self, bytes: &[u8]) -> BinaryClassifier {
-        let reader = buffalo::read::<crate::serialize::BinaryClassifierReader>(bytes);
+        let reader = buffalo::read::<
+            crate::serialize::Versioned<crate::serialize::BinaryClassifierReader>,
+        >(bytes);
         Self::from_reader(reader)
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut writer = buffalo::Writer::new();
-        self.to_writer(&mut writer);
+        let position = self.to_writer(&mut writer);
+        writer.write(&position);
         writer.into_bytes()
     }
 }
+
+impl LinearModel for BinaryClassifier {
+    type Label = Option<NonZeroUsize>;
+    type LabelDim = Ix1;
+
+    const TRAIN_BATCHES_IN_PARALLEL: bool = true;
+
+    fn new_optimizer_state(&self, train_options: &TrainOptions) -> OptimizerState {
+        OptimizerState::new(train_options, self.weights.len(), 1)
+    }
+
+    fn train_batch(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+        optimizer_state: &mut OptimizerState,
+    ) {
+        let mut py = features.dot(&self.weights) + self.bias;
+        for py in py.iter_mut() {
+            *py = 1.0 / (py.neg().exp() + 1.0);
+        }
+        for (py, label, weight) in zip!(py.view_mut(), labels, weights) {
+            let label = match label.map(|l| l.get()) {
+                Some(1) => 0.0,
+                Some(2) => 1.0,
+                _ => unreachable!(),
+            };
+            *py = weight * (*py - label)
+        }
+        let py = py.insert_axis(Axis(1));
+        let weight_gradients = (&features * &py).mean_axis(Axis(0)).unwrap();
+        let bias_gradient = py.mean_axis(Axis(0)).unwrap()[0];
+        optimizer_state.update_weights(self.weights.iter_mut(), weight_gradients.iter());
+        optimizer_state.update_biases(
+            std::iter::once(&mut self.bias),
+            std::iter::once(&bias_gradient),
+        );
+    }
+
+    fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        let mut probabilities = Array1::zeros(features.nrows());
+        self.predict(features, probabilities.view_mut());
+        BinaryClassifier::compute_loss(probabilities.view(), labels, weights)
+    }
+
+    fn compute_early_stopping_metric_value(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+        train_options: &TrainOptions,
+    ) -> f32 {
+        let metric = &train_options
+            .early_stopping_options
+            .as_ref()
+            .unwrap()
+            .metric;
+        if !matches!(metric, EarlyStoppingMetric::Loss) {
+            let mut predictions = Array1::zeros(features.nrows());
+            self.predict(features, predictions.view_mut());
+            return metric.compute_value_to_minimize(
+                EarlyStoppingMetricInput::BinaryClassification {
+                    probabilities: predictions.view(),
+                    labels: labels.view(),
+                    weights: weights.view(),
+                },
+            );
+        }
+        let n_examples_per_chunk =
+            n_examples_per_early_stopping_chunk(features.nrows(), train_options);
+        pzip!(
+            features.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+            labels.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+            weights.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+        )
+        .fold(
+            || {
+                let predictions =
+                    unsafe { <Array1<f32>>::uninit(n_examples_per_chunk).assume_init() };
+                let metric = BinaryCrossEntropy::new();
+                (predictions, metric)
+            },
+            |(mut predictions, mut metric), (features, labels, weights)| {
+                let slice = s![0..features.nrows()];
+                let mut predictions_slice = predictions.slice_mut(slice);
+                self.predict(features, predictions_slice.view_mut());
+                for (prediction, label, weight) in
+                    zip!(predictions_slice.iter(), labels.iter(), weights.iter())
+                {
+                    metric.update_weighted(
+                        BinaryCrossEntropyInput {
+                            probability: *prediction,
+                            label: *label,
+                        },
+                        *weight,
+                    );
+                }
+                (predictions, metric)
+            },
+        )
+        .map(|(_, metric)| metric)
+        .reduce(BinaryCrossEntropy::new, |mut a, b| {
+            a.merge(b);
+            a
+        })
+        .finalize()
+        .unwrap()
+    }
+}
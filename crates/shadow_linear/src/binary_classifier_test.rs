@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod test {
+    use crate::{BinaryClassifier, ClassWeights, Progress, TrainOptions};
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::EnumTableColumn;
+    use std::num::NonZeroUsize;
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    #[test]
+    fn test_balanced_class_weights() {
+        // One in ten labels is positive and the features carry no information, so the model can only learn the prior.
+        let (features, _) = features_and_labels(|_| 0.0);
+        let labels = (0..features.nrows())
+            .map(|i| NonZeroUsize::new(if i % 10 == 0 { 2 } else { 1 }))
+            .collect();
+        let labels =
+            EnumTableColumn::new(None, vec!["false".to_owned(), "true".to_owned()], labels);
+        let mean_probability = |class_weights: ClassWeights| {
+            let kill_chip = shadow_kill_chip::KillChip::new();
+            let model = BinaryClassifier::train(
+                features.view(),
+                labels.view(),
+                None,
+                None,
+                &TrainOptions {
+                    class_weights,
+                    deterministic: true,
+                    learning_rate: 0.01,
+                    ..Default::default()
+                },
+                Progress {
+                    kill_chip: &kill_chip,
+                    handle_progress_event: &mut |_| {},
+                },
+            )
+            .unwrap()
+            .model;
+            let mut probabilities = Array::zeros(features.nrows());
+            model.predict(features.view(), probabilities.view_mut());
+            probabilities.mean().unwrap()
+        };
+        assert!((mean_probability(ClassWeights::Uniform) - 0.1).abs() < 0.05);
+        assert!((mean_probability(ClassWeights::Balanced) - 0.5).abs() < 0.05);
+        assert!((mean_probability(ClassWeights::Custom(vec![1.0, 3.0])) - 0.25).abs() < 0.05);
+    }
+}
@@ -0,0 +1,293 @@
+use ndarray::prelude::*;
+use num::{clamp, ToPrimitive};
+use shadow_zip::zip;
+use std::num::NonZeroUsize;
+
+/// Maps the probabilities of a `BinaryClassifier` to calibrated probabilities, so that of the examples predicted positive with probability `p`, a fraction `p` are positive.
+#[derive(Clone, Debug)]
+pub enum BinaryCalibrator {
+    Platt(PlattScaling),
+    Isotonic(IsotonicRegression),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum BinaryCalibrationMethod {
+    Platt,
+    /// Isotonic regression is more flexible than Platt scaling, but needs more examples to avoid overfitting.
+    Isotonic,
+}
+
+/// Platt scaling fits a logistic regression to the logits of the probabilities, and predicts `sigmoid(a * logit(probability) + b)`.
+#[derive(Clone, Debug)]
+pub struct PlattScaling {
+    pub a: f32,
+    pub b: f32,
+}
+
+/// Isotonic regression fits a nondecreasing function from the probabilities to the fraction of positive labels. The function interpolates linearly between the points `(probabilities[i], calibrated_probabilities[i])`.
+#[derive(Clone, Debug)]
+pub struct IsotonicRegression {
+    pub probabilities: Vec<f32>,
+    pub calibrated_probabilities: Vec<f32>,
+}
+
+/// Temperature scaling divides the logits of a `MulticlassClassifier` by `temperature` before the softmax. A temperature above 1 makes the probabilities less confident without changing the predicted class.
+#[derive(Clone, Debug)]
+pub struct TemperatureScaling {
+    pub temperature: f32,
+}
+
+impl BinaryCalibrator {
+    /// Fits a calibrator to the uncalibrated probabilities of held-out examples, which must not have been used to train the model.
+    pub fn fit(
+        method: BinaryCalibrationMethod,
+        probabilities: ArrayView1<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+    ) -> BinaryCalibrator {
+        let labels = labels.map(|label| match label.map(|label| label.get()) {
+            Some(1) => 0.0,
+            Some(2) => 1.0,
+            _ => unreachable!(),
+        });
+        match method {
+            BinaryCalibrationMethod::Platt => {
+                BinaryCalibrator::Platt(PlattScaling::fit(probabilities, labels.view(), weights))
+            }
+            BinaryCalibrationMethod::Isotonic => BinaryCalibrator::Isotonic(
+                IsotonicRegression::fit(probabilities, labels.view(), weights),
+            ),
+        }
+    }
+
+    pub fn compute_calibrated_probability(&self, probability: f32) -> f32 {
+        match self {
+            BinaryCalibrator::Platt(platt_scaling) => {
+                let logit = compute_logit(probability);
+                1.0 / (1.0 + (-(platt_scaling.a * logit + platt_scaling.b)).exp())
+            }
+            BinaryCalibrator::Isotonic(isotonic_regression) => {
+                isotonic_regression.compute_calibrated_probability(probability)
+            }
+        }
+    }
+}
+
+impl PlattScaling {
+    fn fit(
+        probabilities: ArrayView1<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+    ) -> PlattScaling {
+        // Platt's targets move the labels slightly away from 0 and 1, so the parameters stay finite when the probabilities separate the classes.
+        let mut positive_weight = 0.0;
+        let mut negative_weight = 0.0;
+        for (label, weight) in zip!(labels.iter(), weights.iter()) {
+            if *label > 0.5 {
+                positive_weight += weight.to_f64().unwrap();
+            } else {
+                negative_weight += weight.to_f64().unwrap();
+            }
+        }
+        let positive_target = (positive_weight + 1.0) / (positive_weight + 2.0);
+        let negative_target = 1.0 / (negative_weight + 2.0);
+        let examples: Vec<(f64, f64, f64)> =
+            zip!(probabilities.iter(), labels.iter(), weights.iter())
+                .map(|(probability, label, weight)| {
+                    let logit = compute_logit(*probability).to_f64().unwrap();
+                    let target = if *label > 0.5 {
+                        positive_target
+                    } else {
+                        negative_target
+                    };
+                    (logit, target, weight.to_f64().unwrap())
+                })
+                .collect();
+        let compute_loss = |a: f64, b: f64| {
+            examples
+                .iter()
+                .map(|(logit, target, weight)| {
+                    // The cross entropy written in terms of the logit, which is stable for large logits.
+                    let z = a * logit + b;
+                    weight * (z.max(0.0) - z * target + (-z.abs()).exp().ln_1p())
+                })
+                .sum::<f64>()
+        };
+        // Newton's method on the cross entropy, which is convex in `a` and `b`. Each step is halved until it decreases the loss, because full steps can diverge when the probabilities are very overconfident.
+        let (mut a, mut b) = (1.0f64, 0.0f64);
+        let mut loss = compute_loss(a, b);
+        for _ in 0..100 {
+            let (mut gradient_a, mut gradient_b) = (0.0, 0.0);
+            let (mut hessian_aa, mut hessian_ab, mut hessian_bb) = (1e-9, 0.0, 1e-9);
+            for (logit, target, weight) in examples.iter() {
+                let probability = 1.0 / (1.0 + (-(a * logit + b)).exp());
+                let residual = weight * (probability - target);
+                let curvature = weight * probability * (1.0 - probability);
+                gradient_a += residual * logit;
+                gradient_b += residual;
+                hessian_aa += curvature * logit * logit;
+                hessian_ab += curvature * logit;
+                hessian_bb += curvature;
+            }
+            let determinant = hessian_aa * hessian_bb - hessian_ab * hessian_ab;
+            if determinant <= 0.0 {
+                break;
+            }
+            let mut step_a = (hessian_bb * gradient_a - hessian_ab * gradient_b) / determinant;
+            let mut step_b = (hessian_aa * gradient_b - hessian_ab * gradient_a) / determinant;
+            let mut new_loss = compute_loss(a - step_a, b - step_b);
+            while new_loss > loss && step_a.abs() + step_b.abs() > 1e-12 {
+                step_a /= 2.0;
+                step_b /= 2.0;
+                new_loss = compute_loss(a - step_a, b - step_b);
+            }
+            if new_loss > loss {
+                break;
+            }
+            a -= step_a;
+            b -= step_b;
+            loss = new_loss;
+            if step_a.abs() + step_b.abs() < 1e-9 {
+                break;
+            }
+        }
+        PlattScaling {
+            a: a.to_f32().unwrap(),
+            b: b.to_f32().unwrap(),
+        }
+    }
+}
+
+impl IsotonicRegression {
+    /// Fits the function with the pool adjacent violators algorithm. Examples with a probability that is not finite are skipped.
+    fn fit(
+        probabilities: ArrayView1<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+    ) -> IsotonicRegression {
+        let mut examples: Vec<(f32, f32, f32)> =
+            zip!(probabilities.iter(), labels.iter(), weights.iter())
+                .filter(|(probability, _, weight)| probability.is_finite() && **weight > 0.0)
+                .map(|(probability, label, weight)| (*probability, *label, *weight))
+                .collect();
+        examples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        // Each block is the sums of the weights, the weighted probabilities and the weighted labels of consecutive examples that share a calibrated probability.
+        let mut blocks: Vec<(f64, f64, f64)> = Vec::new();
+        for (probability, label, weight) in examples {
+            let weight = weight.to_f64().unwrap();
+            blocks.push((
+                weight,
+                weight * probability.to_f64().unwrap(),
+                weight * label.to_f64().unwrap(),
+            ));
+            while blocks.len() > 1 {
+                let last = blocks[blocks.len() - 1];
+                let previous = blocks[blocks.len() - 2];
+                if previous.2 / previous.0 < last.2 / last.0 {
+                    break;
+                }
+                blocks.pop();
+                let merged = blocks.last_mut().unwrap();
+                merged.0 += last.0;
+                merged.1 += last.1;
+                merged.2 += last.2;
+            }
+        }
+        IsotonicRegression {
+            probabilities: blocks
+                .iter()
+                .map(|block| (block.1 / block.0).to_f32().unwrap())
+                .collect(),
+            calibrated_probabilities: blocks
+                .iter()
+                .map(|block| (block.2 / block.0).to_f32().unwrap())
+                .collect(),
+        }
+    }
+
+    fn compute_calibrated_probability(&self, probability: f32) -> f32 {
+        let index = self
+            .probabilities
+            .partition_point(|block_probability| *block_probability < probability);
+        if index == 0 {
+            return self
+                .calibrated_probabilities
+                .first()
+                .copied()
+                .unwrap_or(probability);
+        }
+        if index == self.probabilities.len() {
+            return *self.calibrated_probabilities.last().unwrap();
+        }
+        let (x0, x1) = (self.probabilities[index - 1], self.probabilities[index]);
+        let (y0, y1) = (
+            self.calibrated_probabilities[index - 1],
+            self.calibrated_probabilities[index],
+        );
+        if x1 <= x0 {
+            return y1;
+        }
+        y0 + (y1 - y0) * (probability - x0) / (x1 - x0)
+    }
+}
+
+impl TemperatureScaling {
+    /// Fits the temperature to the uncalibrated probabilities of held-out examples by minimizing the cross entropy.
+    pub fn fit(
+        probabilities: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+    ) -> TemperatureScaling {
+        let log_probabilities =
+            probabilities.map(|probability| probability.max(f32::MIN_POSITIVE).ln());
+        let compute_loss = |temperature: f32| {
+            let mut loss = 0.0f64;
+            for (log_probabilities, label, weight) in zip!(
+                log_probabilities.axis_iter(Axis(0)),
+                labels.iter(),
+                weights.iter()
+            ) {
+                let logits = log_probabilities.map(|log_probability| log_probability / temperature);
+                let max = logits.fold(f32::MIN, |a, b| a.max(*b));
+                let log_sum = logits
+                    .iter()
+                    .map(|logit| (logit - max).exp())
+                    .sum::<f32>()
+                    .ln()
+                    + max;
+                let label_logit = logits[label.unwrap().get() - 1];
+                loss += (weight * (log_sum - label_logit)).to_f64().unwrap();
+            }
+            loss
+        };
+        // The cross entropy is unimodal in the log of the temperature, so a golden section search finds its minimum.
+        let golden_ratio = (5.0f32.sqrt() - 1.0) / 2.0;
+        let (mut low, mut high) = (0.01f32.ln(), 100.0f32.ln());
+        let mut c = high - golden_ratio * (high - low);
+        let mut d = low + golden_ratio * (high - low);
+        let (mut loss_c, mut loss_d) = (compute_loss(c.exp()), compute_loss(d.exp()));
+        for _ in 0..60 {
+            if loss_c < loss_d {
+                high = d;
+                d = c;
+                loss_d = loss_c;
+                c = high - golden_ratio * (high - low);
+                loss_c = compute_loss(c.exp());
+            } else {
+                low = c;
+                c = d;
+                loss_c = loss_d;
+                d = low + golden_ratio * (high - low);
+                loss_d = compute_loss(d.exp());
+            }
+        }
+        TemperatureScaling {
+            temperature: ((low + high) / 2.0).exp(),
+        }
+    }
+}
+
+fn compute_logit(probability: f32) -> f32 {
+    let probability = clamp(probability, f32::EPSILON, 1.0 - f32::EPSILON);
+    (probability / (1.0 - probability)).ln()
+}
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        BinaryCalibrationMethod, BinaryCalibrator, BinaryClassifier, MulticlassClassifier,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_metrics::{CalibrationMetrics, CalibrationMetricsInput};
+    use shadow_table::EnumTableColumn;
+    use std::num::NonZeroUsize;
+
+    /// Samples 4000 labels from `probability`, and returns a model that is four times as confident, split into calibration and evaluation examples.
+    fn overconfident_binary_classifier() -> (BinaryClassifier, Array2<f32>, EnumTableColumn) {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((4000, 1), |_| rng.gen::<f32>());
+        let labels = features
+            .iter()
+            .map(|feature| {
+                let probability = 1.0 / (1.0 + (-4.0 * (feature - 0.5)).exp());
+                NonZeroUsize::new(if rng.gen::<f32>() < probability { 2 } else { 1 })
+            })
+            .collect();
+        let labels =
+            EnumTableColumn::new(None, vec!["false".to_owned(), "true".to_owned()], labels);
+        let model = BinaryClassifier {
+            bias: -8.0,
+            weights: arr1(&[16.0]),
+            means: vec![0.5],
+            calibrator: None,
+        };
+        (model, features, labels)
+    }
+
+    fn expected_calibration_error(
+        model: &BinaryClassifier,
+        features: ArrayView2<f32>,
+        labels: &[Option<NonZeroUsize>],
+    ) -> f32 {
+        let mut probabilities = Array::zeros(features.nrows());
+        model.predict(features, probabilities.view_mut());
+        let labels: Array1<bool> = labels
+            .iter()
+            .map(|label| label.unwrap().get() == 2)
+            .collect();
+        let mut metrics = CalibrationMetrics::new(10);
+        metrics.update(CalibrationMetricsInput {
+            probabilities: probabilities.view(),
+            labels: labels.view(),
+        });
+        metrics.finalize().expected_calibration_error
+    }
+
+    #[test]
+    fn test_binary_calibration() {
+        let (model, features, labels) = overconfident_binary_classifier();
+        let (features_calibration, features_evaluation) = features.view().split_at(Axis(0), 2000);
+        let labels_view = labels.view();
+        let (labels_calibration, labels_evaluation) = labels_view.as_slice().split_at(2000);
+        let labels_calibration = EnumTableColumn::new(
+            None,
+            vec!["false".to_owned(), "true".to_owned()],
+            labels_calibration.to_vec(),
+        );
+        let uncalibrated_error =
+            expected_calibration_error(&model, features_evaluation, labels_evaluation);
+        for method in [
+            BinaryCalibrationMethod::Platt,
+            BinaryCalibrationMethod::Isotonic,
+        ] {
+            let mut calibrated_model = model.clone();
+            calibrated_model.calibrate(
+                features_calibration,
+                labels_calibration.view(),
+                None,
+                method,
+            );
+            let calibrated_error = expected_calibration_error(
+                &calibrated_model,
+                features_evaluation,
+                labels_evaluation,
+            );
+            assert!(
+                calibrated_error < uncalibrated_error / 2.0,
+                "{:?}: {} >= {}",
+                method,
+                calibrated_error,
+                uncalibrated_error,
+            );
+            if let Some(BinaryCalibrator::Platt(platt_scaling)) = &calibrated_model.calibrator {
+                // The model is four times too confident, so the logits are scaled back by a quarter.
+                assert!((platt_scaling.a - 0.25).abs() < 0.05, "{:?}", platt_scaling);
+            }
+            let deserialized = calibrated_model.from_bytes(&calibrated_model.to_bytes());
+            let mut probabilities = Array::zeros(features_evaluation.nrows());
+            let mut deserialized_probabilities = Array::zeros(features_evaluation.nrows());
+            calibrated_model.predict(features_evaluation, probabilities.view_mut());
+            deserialized.predict(features_evaluation, deserialized_probabilities.view_mut());
+            assert_eq!(probabilities, deserialized_probabilities);
+        }
+    }
+
+    #[test]
+    fn test_isotonic_calibration_skips_non_finite_probabilities() {
+        let probabilities = arr1(&[0.1, f32::NAN, 0.9, f32::INFINITY]);
+        let labels = [1, 2, 2, 1].map(NonZeroUsize::new);
+        let calibrator = BinaryCalibrator::fit(
+            BinaryCalibrationMethod::Isotonic,
+            probabilities.view(),
+            ArrayView1::from(labels.as_slice()),
+            Array::ones(4).view(),
+        );
+        assert_eq!(calibrator.compute_calibrated_probability(0.1), 0.0);
+        assert_eq!(calibrator.compute_calibrated_probability(0.9), 1.0);
+    }
+
+    #[test]
+    fn test_temperature_scaling() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((4000, 3), |_| rng.gen::<f32>());
+        let labels = features
+            .axis_iter(Axis(0))
+            .map(|features| {
+                // The true logits are twice the features.
+                let exps = features.map(|feature| (2.0 * feature).exp());
+                let mut sample = rng.gen::<f32>() * exps.sum();
+                let mut label = exps.len();
+                for (class, exp) in exps.iter().enumerate() {
+                    if sample < *exp {
+                        label = class + 1;
+                        break;
+                    }
+                    sample -= exp;
+                }
+                NonZeroUsize::new(label)
+            })
+            .collect();
+        let labels = EnumTableColumn::new(
+            None,
+            vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+            labels,
+        );
+        let mut model = MulticlassClassifier {
+            biases: Array::zeros(3),
+            weights: Array::eye(3) * 6.0,
+            means: vec![0.5; 3],
+            calibrator: None,
+        };
+        model.calibrate(features.view(), labels.view(), None);
+        let temperature = model.calibrator.as_ref().unwrap().temperature;
+        assert!((temperature - 3.0).abs() < 0.5, "{}", temperature);
+        let deserialized = model.from_bytes(&model.to_bytes());
+        assert_eq!(deserialized.calibrator.unwrap().temperature, temperature);
+    }
+}
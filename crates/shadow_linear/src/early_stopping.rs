@@ -0,0 +1,396 @@
+use anyhow::{anyhow, bail, Result};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_features::FeatureGroup;
+use shadow_metrics::{
+    AucRoc, BinaryClassificationMetrics, BinaryClassificationMetricsInput,
+    MultiLabelClassificationMetrics, MultiLabelClassificationMetricsInput,
+    MulticlassClassificationMetrics, MulticlassClassificationMetricsInput, RegressionMetrics,
+    RegressionMetricsInput,
+};
+use shadow_table::prelude::*;
+use shadow_zip::zip;
+use std::{num::NonZeroUsize, sync::Arc};
+
+/// The metric computed on the early stopping examples after each epoch.
+#[derive(Clone, Debug)]
+pub enum EarlyStoppingMetric {
+    /// The loss the model is trained to minimize. For regressors trained with the squared error, this is the mean squared error, without the factor of one half in the loss, as it was before the loss was configurable.
+    Loss,
+    MeanSquaredError,
+    MeanAbsoluteError,
+    /// For binary classifiers, the probability threshold is 0.5. For multi-label classifiers, this is the fraction of examples with every label predicted correctly.
+    Accuracy,
+    /// For binary classifiers, the probability threshold is 0.5. For multiclass and multi-label classifiers, this is the mean of the F1 scores of the classes or labels.
+    F1Score,
+    /// The area under the ROC curve of a binary classifier. The weights of the examples are ignored.
+    AucRoc,
+    Custom(CustomEarlyStoppingMetric),
+}
+
+#[derive(Clone)]
+pub struct CustomEarlyStoppingMetric {
+    pub compute: Arc<dyn Fn(EarlyStoppingMetricInput) -> f32 + Send + Sync>,
+    pub higher_is_better: bool,
+}
+
+impl std::fmt::Debug for CustomEarlyStoppingMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CustomEarlyStoppingMetric")
+            .field("higher_is_better", &self.higher_is_better)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The predictions of the model on the early stopping examples. Regressors produce predictions after the link function is applied.
+pub enum EarlyStoppingMetricInput<'a> {
+    Regression {
+        predictions: ArrayView1<'a, f32>,
+        labels: ArrayView1<'a, f32>,
+        weights: ArrayView1<'a, f32>,
+    },
+    BinaryClassification {
+        probabilities: ArrayView1<'a, f32>,
+        labels: ArrayView1<'a, Option<NonZeroUsize>>,
+        weights: ArrayView1<'a, f32>,
+    },
+    MulticlassClassification {
+        probabilities: ArrayView2<'a, f32>,
+        labels: ArrayView1<'a, Option<NonZeroUsize>>,
+        weights: ArrayView1<'a, f32>,
+    },
+    MultiLabelClassification {
+        probabilities: ArrayView2<'a, f32>,
+        labels: ArrayView2<'a, bool>,
+        weights: ArrayView1<'a, f32>,
+    },
+}
+
+/// The kind of predictions a model makes, which determines the early stopping metrics it supports. `Glm` is a regression model and `OrdinalClassifier` is a multiclass classification model.
+#[derive(Clone, Copy, Debug)]
+pub enum Task {
+    Regression,
+    BinaryClassification,
+    MulticlassClassification,
+    MultiLabelClassification,
+}
+
+impl EarlyStoppingMetric {
+    /// Returns an error if the metric cannot be computed for models of `task`.
+    pub(crate) fn validate(&self, task: Task) -> Result<()> {
+        let supported = match self {
+            EarlyStoppingMetric::Loss | EarlyStoppingMetric::Custom(_) => true,
+            EarlyStoppingMetric::MeanSquaredError | EarlyStoppingMetric::MeanAbsoluteError => {
+                matches!(task, Task::Regression)
+            }
+            EarlyStoppingMetric::Accuracy | EarlyStoppingMetric::F1Score => {
+                !matches!(task, Task::Regression)
+            }
+            EarlyStoppingMetric::AucRoc => matches!(task, Task::BinaryClassification),
+        };
+        if !supported {
+            bail!(
+                "the {:?} early stopping metric is not supported for {:?}",
+                self,
+                task
+            );
+        }
+        Ok(())
+    }
+
+    /// Computes the metric, negated if higher values are better, so that smaller values are always better.
+    pub(crate) fn compute_value_to_minimize(&self, input: EarlyStoppingMetricInput) -> f32 {
+        match (self, input) {
+            (EarlyStoppingMetric::Loss, _) => unreachable!(),
+            (EarlyStoppingMetric::Custom(metric), input) => {
+                let value = (metric.compute)(input);
+                if metric.higher_is_better {
+                    -value
+                } else {
+                    value
+                }
+            }
+            (
+                EarlyStoppingMetric::MeanSquaredError | EarlyStoppingMetric::MeanAbsoluteError,
+                EarlyStoppingMetricInput::Regression {
+                    predictions,
+                    labels,
+                    weights,
+                },
+            ) => {
+                let mut metrics = RegressionMetrics::new();
+                metrics.update_weighted(
+                    RegressionMetricsInput {
+                        predictions: predictions.as_slice().unwrap(),
+                        labels: labels.as_slice().unwrap(),
+                    },
+                    weights.as_slice().unwrap(),
+                );
+                let metrics = metrics.finalize();
+                match self {
+                    EarlyStoppingMetric::MeanSquaredError => metrics.mse,
+                    _ => metrics.mae,
+                }
+            }
+            (
+                EarlyStoppingMetric::Accuracy | EarlyStoppingMetric::F1Score,
+                EarlyStoppingMetricInput::BinaryClassification {
+                    probabilities,
+                    labels,
+                    weights,
+                },
+            ) => {
+                let mut metrics = BinaryClassificationMetrics::new(1);
+                metrics.update_weighted(
+                    BinaryClassificationMetricsInput {
+                        probabilities: probabilities.as_slice().unwrap(),
+                        labels: labels.as_slice().unwrap(),
+                    },
+                    weights.as_slice().unwrap(),
+                );
+                let metrics = metrics.finalize();
+                let metrics = &metrics.thresholds[0];
+                match self {
+                    EarlyStoppingMetric::Accuracy => -metrics.accuracy,
+                    _ => -metrics.f1_score.unwrap_or(0.0),
+                }
+            }
+            (
+                EarlyStoppingMetric::AucRoc,
+                EarlyStoppingMetricInput::BinaryClassification {
+                    probabilities,
+                    labels,
+                    ..
+                },
+            ) => {
+                let input = zip!(probabilities.iter(), labels.iter())
+                    .map(|(probability, label)| (*probability, label.unwrap()))
+                    .collect();
+                -AucRoc::compute(input)
+            }
+            (
+                EarlyStoppingMetric::Accuracy | EarlyStoppingMetric::F1Score,
+                EarlyStoppingMetricInput::MulticlassClassification {
+                    probabilities,
+                    labels,
+                    weights,
+                },
+            ) => {
+                let mut metrics = MulticlassClassificationMetrics::new(probabilities.ncols());
+                metrics.update_weighted(
+                    MulticlassClassificationMetricsInput {
+                        probabilities,
+                        labels,
+                    },
+                    weights,
+                );
+                let metrics = metrics.finalize();
+                match self {
+                    EarlyStoppingMetric::Accuracy => -metrics.accuracy,
+                    _ => {
+                        // Classes that never occur and are never predicted have an undefined F1 score, which counts as zero.
+                        let f1_score_sum = metrics
+                            .class_metrics
+                            .iter()
+                            .map(|class_metrics| class_metrics.f1_score)
+                            .filter(|f1_score| f1_score.is_finite())
+                            .sum::<f32>();
+                        -f1_score_sum / metrics.class_metrics.len().to_f32().unwrap()
+                    }
+                }
+            }
+            (
+                EarlyStoppingMetric::Accuracy | EarlyStoppingMetric::F1Score,
+                EarlyStoppingMetricInput::MultiLabelClassification {
+                    probabilities,
+                    labels,
+                    weights,
+                },
+            ) => {
+                let mut metrics = MultiLabelClassificationMetrics::new(probabilities.ncols());
+                metrics.update_weighted(
+                    MultiLabelClassificationMetricsInput {
+                        probabilities,
+                        labels,
+                    },
+                    weights,
+                );
+                let metrics = metrics.finalize();
+                match self {
+                    EarlyStoppingMetric::Accuracy => -metrics.subset_accuracy,
+                    _ => -metrics.macro_f1_score,
+                }
+            }
+            (metric, _) => unreachable!(
+                "the {:?} early stopping metric is validated before training",
+                metric
+            ),
+        }
+    }
+}
+
+/// A table of examples to compute the early stopping metric on, in place of the last `early_stopping_fraction` of the training examples.
+pub struct EarlyStoppingValidation<'a> {
+    pub table: &'a TableView<'a>,
+    /// The feature groups used to compute the training features, which are computed from `table` the same way.
+    pub feature_groups: &'a [FeatureGroup],
+    /// Multi-label classifiers ignore this and read a column for each label, with the name of the training label column.
+    pub target_column_name: &'a str,
+}
+
+pub(crate) struct EarlyStoppingValidationExamples<'a> {
+    pub features: Array2<f32>,
+    labels: TableColumnView<'a>,
+}
+
+impl<'a> EarlyStoppingValidation<'a> {
+    /// Returns an error if the table does not have a target column of the type the labels of models of `task` have. The label columns of multi-label classifiers are checked by `compute_multi_label_examples`.
+    pub(crate) fn validate(&self, task: Task) -> Result<()> {
+        if let Task::MultiLabelClassification = task {
+            return Ok(());
+        }
+        let column = self
+            .table
+            .columns()
+            .iter()
+            .find(|column| column.name() == Some(self.target_column_name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "the validation table does not have the target column {}",
+                    self.target_column_name
+                )
+            })?;
+        match (task, column) {
+            (Task::Regression, TableColumnView::Number(_)) => Ok(()),
+            (Task::Regression, _) => bail!("the target column must be a number column"),
+            (
+                Task::BinaryClassification | Task::MulticlassClassification,
+                TableColumnView::Enum(_),
+            ) => Ok(()),
+            (Task::BinaryClassification | Task::MulticlassClassification, _) => {
+                bail!("the target column must be an enum column")
+            }
+            (Task::MultiLabelClassification, _) => unreachable!(),
+        }
+    }
+
+    /// Computes the features and, for each of `labels`, whether the label is true in the column of the table with the same name.
+    pub(crate) fn compute_multi_label_examples(
+        &self,
+        labels: &[EnumTableColumnView],
+    ) -> Result<(Array2<f32>, Array2<bool>)> {
+        let mut label_values = Array2::from_elem((self.table.nrows(), labels.len()), false);
+        for (mut label_values, labels) in zip!(label_values.axis_iter_mut(Axis(1)), labels.iter()) {
+            let name = labels.name().ok_or_else(|| {
+                anyhow!("the label columns must have names to find them in the validation table")
+            })?;
+            let column = self
+                .table
+                .columns()
+                .iter()
+                .find(|column| column.name() == Some(name))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "the validation table does not have the label column {}",
+                        name
+                    )
+                })?;
+            let column = match column {
+                TableColumnView::Enum(column) if column.variants().len() == 2 => column,
+                _ => bail!(
+                    "the label column {} must be an enum column with two variants",
+                    name
+                ),
+            };
+            for (label_value, label) in zip!(label_values.iter_mut(), column.as_slice().iter()) {
+                *label_value = match label.map(|label| label.get()) {
+                    Some(1) => false,
+                    Some(2) => true,
+                    _ => bail!("the label column {} has missing values", name),
+                };
+            }
+        }
+        let features =
+            shadow_features::compute_features_array_f32(self.table, self.feature_groups, &|| {});
+        Ok((features, label_values))
+    }
+
+    pub(crate) fn compute_examples(&self) -> EarlyStoppingValidationExamples<'a> {
+        let features =
+            shadow_features::compute_features_array_f32(self.table, self.feature_groups, &|| {});
+        // `validate` checked that the target column exists.
+        let labels = self
+            .table
+            .columns()
+            .iter()
+            .find(|column| column.name() == Some(self.target_column_name))
+            .unwrap()
+            .clone();
+        EarlyStoppingValidationExamples { features, labels }
+    }
+}
+
+impl<'a> EarlyStoppingValidationExamples<'a> {
+    pub fn number_labels(&self) -> ArrayView1<'_, f32> {
+        match &self.labels {
+            TableColumnView::Number(labels) => labels.as_slice().into(),
+            _ => panic!("the target column must be a number column"),
+        }
+    }
+
+    pub fn enum_labels(&self) -> ArrayView1<'_, Option<NonZeroUsize>> {
+        match &self.labels {
+            TableColumnView::Enum(labels) => labels.as_slice().into(),
+            _ => panic!("the target column must be an enum column"),
+        }
+    }
+}
+
+/// Tracks the early stopping metric, which must be smaller when the model is better, across epochs.
+pub(crate) struct EarlyStoppingMonitor {
+    threshold: f32,
+    epochs: usize,
+    n_epochs_without_observed_improvement: usize,
+    best_metric_value: Option<f32>,
+    best_epoch: Option<usize>,
+}
+
+impl EarlyStoppingMonitor {
+    pub fn new(threshold: f32, epochs: usize) -> EarlyStoppingMonitor {
+        EarlyStoppingMonitor {
+            threshold,
+            epochs,
+            n_epochs_without_observed_improvement: 0,
+            best_metric_value: None,
+            best_epoch: None,
+        }
+    }
+
+    /// The epoch with the smallest metric value so far.
+    pub fn best_epoch(&self) -> Option<usize> {
+        self.best_epoch
+    }
+
+    /// Records the metric value for `epoch` and returns true if training should stop because the metric has not decreased by at least `threshold` from the best value in `epochs` epochs.
+    pub fn update(&mut self, epoch: usize, early_stopping_metric_value: f32) -> bool {
+        let best_metric_value = match self.best_metric_value {
+            Some(best_metric_value) => best_metric_value,
+            None => {
+                self.best_metric_value = Some(early_stopping_metric_value);
+                self.best_epoch = Some(epoch);
+                return false;
+            }
+        };
+        if early_stopping_metric_value < best_metric_value {
+            self.best_metric_value = Some(early_stopping_metric_value);
+            self.best_epoch = Some(epoch);
+        }
+        if early_stopping_metric_value <= best_metric_value - self.threshold {
+            self.n_epochs_without_observed_improvement = 0;
+            false
+        } else {
+            self.n_epochs_without_observed_improvement += 1;
+            self.n_epochs_without_observed_improvement >= self.epochs
+        }
+    }
+}
@@ -0,0 +1,215 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        CustomEarlyStoppingMetric, EarlyStoppingMetric, EarlyStoppingMetricInput,
+        EarlyStoppingOptions, EarlyStoppingValidation, Progress, Regressor, RegressorTrainOutput,
+        Task, TrainOptions,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_features::{FeatureGroup, IdentityFeatureGroup};
+    use shadow_table::{NumberTableColumn, Table, TableColumn};
+    use std::sync::{Arc, Mutex};
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    fn train_output_with_examples(
+        train_options: &TrainOptions,
+        features: ArrayView2<f32>,
+        labels: Vec<f32>,
+    ) -> RegressorTrainOutput {
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        Regressor::train(
+            features,
+            labels.view(),
+            None,
+            None,
+            train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_early_stopping_restores_the_best_epoch() {
+        // The custom metric ignores the predictions and is smallest after the third epoch, so training stops three epochs later.
+        let values = [5.0, 3.0, 1.0, 2.0, 4.0, 6.0, 0.0];
+        let recorded_predictions = Arc::new(Mutex::new(Vec::new()));
+        let metric = CustomEarlyStoppingMetric {
+            compute: Arc::new({
+                let recorded_predictions = recorded_predictions.clone();
+                move |input| match input {
+                    EarlyStoppingMetricInput::Regression { predictions, .. } => {
+                        let mut recorded_predictions = recorded_predictions.lock().unwrap();
+                        recorded_predictions.push(predictions.to_owned());
+                        values[recorded_predictions.len() - 1]
+                    }
+                    _ => unreachable!(),
+                }
+            }),
+            higher_is_better: false,
+        };
+        let (features, labels) = features_and_labels(|features| {
+            2.0 * features[0] - features[1] + 0.5 * features[2] + 1.0
+        });
+        let output = train_output_with_examples(
+            &TrainOptions {
+                deterministic: true,
+                early_stopping_options: Some(EarlyStoppingOptions {
+                    early_stopping_fraction: 0.1,
+                    n_rounds_without_improvement_to_stop: 3,
+                    min_decrease_in_loss_for_significant_change: 0.0,
+                    metric: EarlyStoppingMetric::Custom(metric),
+                }),
+                learning_rate: 0.01,
+                ..Default::default()
+            },
+            features.view(),
+            labels,
+        );
+        assert_eq!(output.best_epoch, Some(2));
+        let recorded_predictions = recorded_predictions.lock().unwrap();
+        assert_eq!(recorded_predictions.len(), 6);
+        // The early stopping examples are the last 10% of the examples.
+        let mut predictions = Array::zeros(100);
+        output
+            .model
+            .predict(features.slice(s![900.., ..]), predictions.view_mut());
+        assert_eq!(predictions, recorded_predictions[2]);
+    }
+
+    #[test]
+    fn test_early_stopping_with_validation_table() {
+        // Every validation label is zero, which the untrained model predicts, so the metric only gets worse after the first epoch.
+        let (features, labels) = features_and_labels(|features| 2.0 * features[0] + 1.0);
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = vec![
+            TableColumn::Number(NumberTableColumn::new(
+                Some("x".to_owned()),
+                features.column(0).to_vec(),
+            )),
+            TableColumn::Number(NumberTableColumn::new(
+                Some("y".to_owned()),
+                vec![0.0; features.nrows()],
+            )),
+        ];
+        let feature_groups = vec![FeatureGroup::Identity(IdentityFeatureGroup {
+            source_column_name: "x".to_owned(),
+        })];
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let output = Regressor::train(
+            features.slice(s![.., 0..1]),
+            labels.view(),
+            None,
+            Some(EarlyStoppingValidation {
+                table: &table.view(),
+                feature_groups: &feature_groups,
+                target_column_name: "y",
+            }),
+            &TrainOptions {
+                deterministic: true,
+                early_stopping_options: Some(EarlyStoppingOptions {
+                    early_stopping_fraction: 0.1,
+                    n_rounds_without_improvement_to_stop: 3,
+                    min_decrease_in_loss_for_significant_change: 0.0,
+                    metric: EarlyStoppingMetric::MeanSquaredError,
+                }),
+                learning_rate: 0.001,
+                ..Default::default()
+            },
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap();
+        assert_eq!(output.best_epoch, Some(0));
+        assert!(output.model.bias.abs() < 0.5, "{}", output.model.bias);
+    }
+
+    #[test]
+    fn test_validate_early_stopping_options() {
+        let train_options = |metric| TrainOptions {
+            early_stopping_options: Some(EarlyStoppingOptions {
+                early_stopping_fraction: 0.1,
+                n_rounds_without_improvement_to_stop: 3,
+                min_decrease_in_loss_for_significant_change: 0.0,
+                metric,
+            }),
+            ..Default::default()
+        };
+        assert!(train_options(EarlyStoppingMetric::AucRoc)
+            .validate(Task::BinaryClassification, None)
+            .is_ok());
+        assert!(train_options(EarlyStoppingMetric::AucRoc)
+            .validate(Task::Regression, None)
+            .is_err());
+        assert!(train_options(EarlyStoppingMetric::MeanSquaredError)
+            .validate(Task::MulticlassClassification, None)
+            .is_err());
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = vec![TableColumn::Number(NumberTableColumn::new(
+            Some("y".to_owned()),
+            vec![0.0],
+        ))];
+        let table = table.view();
+        let validation = |target_column_name| EarlyStoppingValidation {
+            table: &table,
+            feature_groups: &[],
+            target_column_name,
+        };
+        let train_options = train_options(EarlyStoppingMetric::Loss);
+        assert!(train_options
+            .validate(Task::Regression, Some(&validation("y")))
+            .is_ok());
+        assert!(train_options
+            .validate(Task::Regression, Some(&validation("z")))
+            .is_err());
+        assert!(train_options
+            .validate(Task::BinaryClassification, Some(&validation("y")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_train_returns_validation_error() {
+        let (features, labels) = features_and_labels(|features| features[0]);
+        let labels = NumberTableColumn::new(None, labels);
+        let train_options = TrainOptions {
+            early_stopping_options: Some(EarlyStoppingOptions {
+                early_stopping_fraction: 0.1,
+                n_rounds_without_improvement_to_stop: 3,
+                min_decrease_in_loss_for_significant_change: 0.0,
+                metric: EarlyStoppingMetric::AucRoc,
+            }),
+            ..Default::default()
+        };
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let output = Regressor::train(
+            features.view(),
+            labels.view(),
+            None,
+            None,
+            &train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        );
+        assert!(output.is_err());
+    }
+}
@@ -0,0 +1,379 @@
+use super::{
+    compute_example_weights,
+    early_stopping::{EarlyStoppingMetricInput, Task},
+    optimizer::OptimizerState,
+    shap::{compute_shap_values_for_example, ComputeShapValuesForExampleOutput},
+    train_linear_model, EarlyStoppingMetric, EarlyStoppingValidation, Examples, LinearModel,
+    LinearModelTrainOutput, Progress, TrainOptions,
+};
+use ndarray::prelude::*;
+use num::clamp;
+use shadow_metrics::{DevianceFamily, Mean};
+use shadow_table::prelude::*;
+use shadow_zip::zip;
+
+/// A generalized linear model, which predicts the mean of the label as `link.compute_mean(bias + weights · features)` and is trained to maximize the likelihood of the label under `family`.
+#[derive(Clone, Debug)]
+pub struct Glm {
+    pub bias: f32,
+    pub weights: Array1<f32>,
+    pub means: Vec<f32>,
+    pub family: GlmFamily,
+    pub link: LinkFunction,
+}
+
+pub struct GlmTrainOutput {
+    pub model: Glm,
+    pub losses: Option<Vec<f32>>,
+    pub feature_importances: Option<Vec<f32>>,
+    pub n_zero_weights: usize,
+    /// The epoch whose model was returned, if early stopping was enabled.
+    pub best_epoch: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GlmOptions {
+    pub family: GlmFamily,
+    /// The canonical link of `family` is used if this is `None`.
+    pub link: Option<LinkFunction>,
+}
+
+impl Default for GlmOptions {
+    fn default() -> GlmOptions {
+        GlmOptions {
+            family: GlmFamily::Gaussian,
+            link: None,
+        }
+    }
+}
+
+/// The distribution of the label given the features.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlmFamily {
+    Gaussian,
+    /// For counts.
+    Poisson,
+    /// For positive labels whose standard deviation grows with their mean.
+    Gamma,
+    /// For labels that are 0 or 1.
+    Bernoulli,
+}
+
+/// The link function maps the mean of the label to the output of the linear model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkFunction {
+    Identity,
+    Log,
+    Logit,
+    Inverse,
+    Sqrt,
+}
+
+impl GlmFamily {
+    pub fn canonical_link(self) -> LinkFunction {
+        match self {
+            GlmFamily::Gaussian => LinkFunction::Identity,
+            GlmFamily::Poisson => LinkFunction::Log,
+            GlmFamily::Gamma => LinkFunction::Inverse,
+            GlmFamily::Bernoulli => LinkFunction::Logit,
+        }
+    }
+
+    /// The family of the deviance that evaluates the model in `shadow_metrics`.
+    pub fn deviance_family(self) -> DevianceFamily {
+        match self {
+            GlmFamily::Gaussian => DevianceFamily::Gaussian,
+            GlmFamily::Poisson => DevianceFamily::Poisson,
+            GlmFamily::Gamma => DevianceFamily::Gamma,
+            GlmFamily::Bernoulli => DevianceFamily::Bernoulli,
+        }
+    }
+
+    /// Restricts a mean to the support of the distribution, so the loss and its gradient are finite.
+    fn clamp_mean(self, mean: f32) -> f32 {
+        match self {
+            GlmFamily::Gaussian => mean,
+            GlmFamily::Poisson | GlmFamily::Gamma => mean.max(f32::EPSILON),
+            GlmFamily::Bernoulli => clamp(mean, f32::EPSILON, 1.0 - f32::EPSILON),
+        }
+    }
+
+    /// The variance of the label as a function of its mean, up to the dispersion.
+    fn compute_variance(self, mean: f32) -> f32 {
+        match self {
+            GlmFamily::Gaussian => 1.0,
+            GlmFamily::Poisson => mean,
+            GlmFamily::Gamma => mean * mean,
+            GlmFamily::Bernoulli => mean * (1.0 - mean),
+        }
+    }
+
+    /// The negative log likelihood of the label, without the terms that do not depend on the mean.
+    pub fn compute_loss(self, mean: f32, label: f32) -> f32 {
+        let mean = self.clamp_mean(mean);
+        match self {
+            GlmFamily::Gaussian => 0.5 * (label - mean) * (label - mean),
+            GlmFamily::Poisson => mean - label * mean.ln(),
+            GlmFamily::Gamma => label / mean + mean.ln(),
+            GlmFamily::Bernoulli => -(label * mean.ln() + (1.0 - label) * (1.0 - mean).ln()),
+        }
+    }
+}
+
+impl LinkFunction {
+    /// Applies the inverse of the link function to an output to produce the mean of the label.
+    pub fn compute_mean(self, output: f32) -> f32 {
+        match self {
+            LinkFunction::Identity => output,
+            LinkFunction::Log => output.exp(),
+            LinkFunction::Logit => 1.0 / (1.0 + (-output).exp()),
+            LinkFunction::Inverse => 1.0 / output,
+            LinkFunction::Sqrt => output * output,
+        }
+    }
+
+    pub fn compute_output(self, mean: f32) -> f32 {
+        match self {
+            LinkFunction::Identity => mean,
+            LinkFunction::Log => mean.ln(),
+            LinkFunction::Logit => (mean / (1.0 - mean)).ln(),
+            LinkFunction::Inverse => 1.0 / mean,
+            LinkFunction::Sqrt => mean.sqrt(),
+        }
+    }
+
+    /// The derivative of the mean with respect to the output.
+    fn compute_mean_derivative(self, output: f32) -> f32 {
+        match self {
+            LinkFunction::Identity => 1.0,
+            LinkFunction::Log => output.exp(),
+            LinkFunction::Logit => {
+                let mean = self.compute_mean(output);
+                mean * (1.0 - mean)
+            }
+            LinkFunction::Inverse => -1.0 / (output * output),
+            LinkFunction::Sqrt => 2.0 * output,
+        }
+    }
+}
+
+impl Glm {
+    /// Trains with the family and link in `train_options.glm_options`. If `weights` is given, each example's contribution to the loss is multiplied by its weight.
+    pub fn train(
+        features: ArrayView2<f32>,
+        labels: NumberTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<GlmTrainOutput> {
+        let n_features = features.ncols();
+        let example_weights = compute_example_weights(weights, labels.len());
+        let family = train_options.glm_options.family;
+        let link = train_options
+            .glm_options
+            .link
+            .unwrap_or_else(|| family.canonical_link());
+        let LinearModelTrainOutput {
+            model,
+            losses,
+            best_epoch,
+        } = train_linear_model(
+            Task::Regression,
+            Examples {
+                features: features.view(),
+                labels: labels.as_slice().into(),
+                weights: example_weights.view(),
+            },
+            validation,
+            |validation| {
+                let validation_examples = validation.compute_examples();
+                let labels = validation_examples.number_labels().to_owned();
+                let weights = compute_example_weights(None, labels.len());
+                Ok((validation_examples.features, labels, weights))
+            },
+            |train_examples| {
+                let means = train_examples
+                    .features
+                    .axis_iter(Axis(1))
+                    .map(|column| column.mean().unwrap())
+                    .collect();
+                // Starting from the output that predicts the mean label for every example keeps the first steps inside the domain of the link.
+                let mut mean_label = Mean::new();
+                for (label, weight) in
+                    zip!(train_examples.labels.iter(), train_examples.weights.iter())
+                {
+                    mean_label.update_weighted(*label, *weight);
+                }
+                let mean_label = family.clamp_mean(mean_label.finalize().unwrap_or(0.0));
+                Glm {
+                    bias: link.compute_output(mean_label),
+                    weights: <Array1<f32>>::zeros(n_features),
+                    means,
+                    family,
+                    link,
+                }
+            },
+            train_options,
+            progress,
+        )?;
+        let mut feature_importances = model
+            .weights
+            .iter()
+            .map(|weight| weight.abs())
+            .collect::<Vec<_>>();
+        let feature_importances_sum = feature_importances.iter().sum::<f32>();
+        feature_importances
+            .iter_mut()
+            .for_each(|feature_importance| *feature_importance /= feature_importances_sum);
+        let n_zero_weights = model
+            .weights
+            .iter()
+            .filter(|weight| **weight == 0.0)
+            .count();
+        Ok(GlmTrainOutput {
+            model,
+            losses,
+            feature_importances: Some(feature_importances),
+            n_zero_weights,
+            best_epoch,
+        })
+    }
+
+    /// Computes the weighted mean of `family.compute_loss` over the examples.
+    pub fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        let mut predictions = Array1::zeros(features.nrows());
+        self.predict(features, predictions.view_mut());
+        let mut loss = Mean::new();
+        for (prediction, label, weight) in zip!(predictions.iter(), labels.iter(), weights.iter()) {
+            loss.update_weighted(self.family.compute_loss(*prediction, *label), *weight);
+        }
+        loss.finalize().unwrap_or(0.0)
+    }
+
+    /// Predicts the mean of the label for each example.
+    pub fn predict(&self, features: ArrayView2<f32>, mut predictions: ArrayViewMut1<f32>) {
+        predictions.fill(self.bias);
+        ndarray::linalg::general_mat_vec_mul(1.0, &features, &self.weights, 1.0, &mut predictions);
+        for prediction in predictions.iter_mut() {
+            *prediction = self.link.compute_mean(*prediction);
+        }
+    }
+
+    /// The contributions are to the output of the linear model, before the inverse of the link function is applied.
+    pub fn compute_feature_contributions(
+        &self,
+        features: ArrayView2<f32>,
+    ) -> Vec<ComputeShapValuesForExampleOutput> {
+        features
+            .axis_iter(Axis(0))
+            .map(|features| {
+                compute_shap_values_for_example(
+                    features.as_slice().unwrap(),
+                    self.bias,
+                    self.weights.view(),
+                    &self.means,
+                )
+            })
+            .collect()
+    }
+
+    pub fn from_reader(glm: crate::serialize::GlmReader) -> Glm {
+        crate::serialize::deserialize_glm(glm)
+    }
+
+    pub fn to_writer(
+        &self,
+        writer: &mut buffalo::Writer,
+    ) -> buffalo::Position<crate::serialize::GlmWriter> {
+        crate::serialize::serialize_glm(self, writer)
+    }
+
+    #[must_use]
+    pub fn from_bytes(&self, bytes: &[u8]) -> Glm {
+        let reader = buffalo::read::<crate::serialize::GlmReader>(bytes);
+        Self::from_reader(reader)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = buffalo::Writer::new();
+        let position = self.to_writer(&mut writer);
+        writer.write(&position);
+        writer.into_bytes()
+    }
+}
+
+impl LinearModel for Glm {
+    type Label = f32;
+    type LabelDim = Ix1;
+
+    const TRAIN_BATCHES_IN_PARALLEL: bool = false;
+
+    fn new_optimizer_state(&self, train_options: &TrainOptions) -> OptimizerState {
+        OptimizerState::new(train_options, self.weights.len(), 1)
+    }
+
+    fn train_batch(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+        optimizer_state: &mut OptimizerState,
+    ) {
+        let outputs = features.dot(&self.weights) + self.bias;
+        // The derivative of the negative log likelihood with respect to the output is (mean - label) / (variance * link'(mean)), which is mean - label for the canonical link.
+        let py = zip!(outputs.iter(), labels.iter(), weights.iter())
+            .map(|(output, label, weight)| {
+                let mean = self.family.clamp_mean(self.link.compute_mean(*output));
+                weight * (mean - label) * self.link.compute_mean_derivative(*output)
+                    / self.family.compute_variance(mean).max(f32::EPSILON)
+            })
+            .collect::<Array1<f32>>()
+            .insert_axis(Axis(1));
+        let weight_gradients = (&features * &py).mean_axis(Axis(0)).unwrap();
+        let bias_gradient = py.mean_axis(Axis(0)).unwrap()[0];
+        optimizer_state.update_weights(self.weights.iter_mut(), weight_gradients.iter());
+        optimizer_state.update_biases(
+            std::iter::once(&mut self.bias),
+            std::iter::once(&bias_gradient),
+        );
+    }
+
+    fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        Glm::compute_loss(self, features, labels, weights)
+    }
+
+    fn compute_early_stopping_metric_value(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+        train_options: &TrainOptions,
+    ) -> f32 {
+        let metric = &train_options
+            .early_stopping_options
+            .as_ref()
+            .unwrap()
+            .metric;
+        if let EarlyStoppingMetric::Loss = metric {
+            return Glm::compute_loss(self, features, labels, weights);
+        }
+        let mut predictions = Array1::zeros(features.nrows());
+        self.predict(features, predictions.view_mut());
+        metric.compute_value_to_minimize(EarlyStoppingMetricInput::Regression {
+            predictions: predictions.view(),
+            labels: labels.view(),
+            weights: weights.view(),
+        })
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod test {
+    use crate::{Glm, GlmFamily, GlmOptions, LinkFunction, Progress, TrainOptions};
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_metrics::{MeanDeviance, MeanDevianceInput};
+    use shadow_table::NumberTableColumn;
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    #[test]
+    fn test_glm() {
+        // The labels are the means of a log link model, which both families recover.
+        let (features, labels) = features_and_labels(|features| (features[0] + 0.5).exp());
+        let labels = NumberTableColumn::new(None, labels);
+        for family in [GlmFamily::Poisson, GlmFamily::Gamma] {
+            let kill_chip = shadow_kill_chip::KillChip::new();
+            let model = Glm::train(
+                features.view(),
+                labels.view(),
+                None,
+                None,
+                &TrainOptions {
+                    deterministic: true,
+                    glm_options: GlmOptions {
+                        family,
+                        link: Some(LinkFunction::Log),
+                    },
+                    learning_rate: 0.05,
+                    max_epochs: 200,
+                    ..Default::default()
+                },
+                Progress {
+                    kill_chip: &kill_chip,
+                    handle_progress_event: &mut |_| {},
+                },
+            )
+            .unwrap()
+            .model;
+            assert!((model.weights[0] - 1.0).abs() < 0.1, "{:?}", model);
+            assert!((model.bias - 0.5).abs() < 0.1, "{:?}", model);
+            let mut predictions = Array::zeros(features.nrows());
+            model.predict(features.view(), predictions.view_mut());
+            let mut deviance = MeanDeviance::new(family.deviance_family());
+            for (prediction, label) in predictions.iter().zip(labels.view().as_slice()) {
+                deviance.update(MeanDevianceInput {
+                    prediction: *prediction,
+                    label: *label,
+                });
+            }
+            assert!(deviance.finalize().unwrap() < 0.01);
+            let deserialized = model.from_bytes(&model.to_bytes());
+            assert_eq!(deserialized.family, family);
+            assert_eq!(deserialized.link, LinkFunction::Log);
+            assert_eq!(deserialized.weights, model.weights);
+        }
+        assert_eq!(GlmFamily::Gamma.canonical_link(), LinkFunction::Inverse);
+    }
+}
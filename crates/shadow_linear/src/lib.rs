@@ -1,45 +1,155 @@
 pub use self::{
-    binary_classifier::BinaryClassifier, multiclass_classifier::MulticlassClassifier,
-    regressor::Regressor,
+    binary_classifier::{BinaryClassifier, BinaryClassifierTrainOutput},
+    calibration::{
+        BinaryCalibrationMethod, BinaryCalibrator, IsotonicRegression, PlattScaling,
+        TemperatureScaling,
+    },
+    early_stopping::{
+        CustomEarlyStoppingMetric, EarlyStoppingMetric, EarlyStoppingMetricInput,
+        EarlyStoppingValidation, Task,
+    },
+    glm::{Glm, GlmFamily, GlmOptions, GlmTrainOutput, LinkFunction},
+    loss::{HuberLossOptions, QuantileLossOptions, RegressionLoss, TweedieLossOptions},
+    multi_label_classifier::{MultiLabelClassifier, MultiLabelClassifierTrainOutput},
+    multiclass_classifier::{MulticlassClassifier, MulticlassClassifierTrainOutput},
+    online::OnlineTrainer,
+    optimizer::{
+        AdaGradOptions, AdamOptions, CosineLearningRateScheduleOptions,
+        ExponentialLearningRateScheduleOptions, FtrlOptions, LearningRateSchedule, MomentumOptions,
+        Optimizer, StepLearningRateScheduleOptions, WarmupLearningRateScheduleOptions,
+    },
+    ordinal_classifier::{OrdinalClassifier, OrdinalClassifierTrainOutput},
+    regressor::{Regressor, RegressorTrainOutput},
+    shap::ComputeShapValuesForExampleOutput,
 };
-use ndarray::prelude::*;
+use self::{early_stopping::EarlyStoppingMonitor, optimizer::OptimizerState};
+use ndarray::{prelude::*, RemoveAxis, Slice};
 use num::ToPrimitive;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_xoshiro::Xoshiro256Plus;
+use rayon::prelude::*;
 use shadow_progress_counter::ProgressCounter;
+use shadow_zip::zip;
+use std::{num::NonZeroUsize, ops::Range};
 
 mod binary_classifier;
+#[cfg(test)]
+mod binary_classifier_test;
+mod calibration;
+#[cfg(test)]
+mod calibration_test;
+mod early_stopping;
+#[cfg(test)]
+mod early_stopping_test;
+mod glm;
+#[cfg(test)]
+mod glm_test;
+mod loss;
+#[cfg(test)]
+mod loss_test;
+mod multi_label_classifier;
+#[cfg(test)]
+mod multi_label_classifier_test;
 mod multiclass_classifier;
+mod online;
+#[cfg(test)]
+mod online_test;
+mod optimizer;
+#[cfg(test)]
+mod optimizer_test;
+mod ordinal_classifier;
+#[cfg(test)]
+mod ordinal_classifier_test;
 mod regressor;
+#[cfg(test)]
+mod regressor_test;
 pub mod serialize;
+#[cfg(test)]
+mod serialize_test;
 mod shap;
 
 #[derive(Clone, Debug)]
 pub struct TrainOptions {
+    /// Weights for the classes of the labels, which the classifiers multiply with the weights of the examples. `Regressor::train` ignores them.
+    pub class_weights: ClassWeights,
+    /// If true, the loss on the training examples is computed after each epoch.
     pub compute_losses: bool,
     pub early_stopping_options: Option<EarlyStoppingOptions>,
+    /// The family and link function of `Glm::train`.
+    pub glm_options: GlmOptions,
+    /// Setting both `l1_regularization` and `l2_regularization` trains with an elastic net penalty. Neither is applied to the biases.
+    pub l1_regularization: f32,
     pub l2_regularization: f32,
     pub learning_rate: f32,
+    pub learning_rate_schedule: LearningRateSchedule,
     pub max_epochs: usize,
     pub n_examples_per_batch: usize,
+    /// Only `Optimizer::Sgd` trains batches in parallel.
+    pub optimizer: Optimizer,
+    /// The loss minimized by `Regressor::train`. The classifiers always minimize the cross entropy.
+    pub regression_loss: RegressionLoss,
+    pub seed: u64,
+    /// If true, the training examples are shuffled at the start of each epoch with a random number generator seeded from `seed` and the epoch.
+    pub shuffle: bool,
+    /// If true, batches are trained one after another instead of in parallel and metrics are accumulated in a fixed order, so training on the same data with the same options always produces the same model.
+    pub deterministic: bool,
 }
 
 impl Default for TrainOptions {
     fn default() -> TrainOptions {
         TrainOptions {
+            class_weights: ClassWeights::Uniform,
             compute_losses: false,
             early_stopping_options: None,
+            glm_options: GlmOptions::default(),
+            l1_regularization: 0.0,
             l2_regularization: 0.0,
             learning_rate: 0.1,
+            learning_rate_schedule: LearningRateSchedule::Constant,
             max_epochs: 100,
             n_examples_per_batch: 32,
+            optimizer: Optimizer::Sgd,
+            regression_loss: RegressionLoss::SquaredError,
+            seed: 0,
+            shuffle: false,
+            deterministic: false,
         }
     }
 }
 
+impl TrainOptions {
+    /// Returns an error if the early stopping options cannot be used to train a model of `task` with `validation`. Every `train` function returns this error before it starts training.
+    pub fn validate(
+        &self,
+        task: Task,
+        validation: Option<&EarlyStoppingValidation>,
+    ) -> anyhow::Result<()> {
+        if let Some(early_stopping_options) = &self.early_stopping_options {
+            early_stopping_options.metric.validate(task)?;
+            if let Some(validation) = validation {
+                validation.validate(task)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ClassWeights {
+    Uniform,
+    /// Weights each class by `n_examples / (n_classes * n_examples_in_class)`, so that every class contributes equally to the loss.
+    Balanced,
+    /// The weight for each class, in the order of the label's variants.
+    Custom(Vec<f32>),
+}
+
 #[derive(Clone, Debug)]
 pub struct EarlyStoppingOptions {
+    /// The fraction of the training examples held out to compute the early stopping metric, unless an `EarlyStoppingValidation` is given.
     pub early_stopping_fraction: f32,
     pub n_rounds_without_improvement_to_stop: usize,
     pub min_decrease_in_loss_for_significant_change: f32,
+    pub metric: EarlyStoppingMetric,
 }
 
 pub struct Progress<'a> {
@@ -53,61 +163,338 @@ pub enum TrainProgressEvent {
     TrainDone,
 }
 
-fn train_early_stopping_split<'features, 'labels, Label>(
-    features: ArrayView2<'features, f32>,
-    labels: ArrayView1<'labels, Label>,
+/// The parts of training that differ between the linear models. `train_linear_model` runs the rest.
+trait LinearModel: Clone {
+    type Label: Clone + Sync + 'static;
+    /// `Ix1` for a label per example, or `Ix2` for a row of labels per example.
+    type LabelDim: RemoveAxis + Copy + 'static;
+
+    /// Whether SGD may train the batches of an epoch on several threads, which update the model without locking.
+    const TRAIN_BATCHES_IN_PARALLEL: bool;
+
+    fn new_optimizer_state(&self, train_options: &TrainOptions) -> OptimizerState;
+
+    /// Takes one gradient step on a batch of examples.
+    fn train_batch(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: ArrayView<Self::Label, Self::LabelDim>,
+        weights: ArrayView1<f32>,
+        optimizer_state: &mut OptimizerState,
+    );
+
+    /// The mean of the examples' losses, weighted by `weights`.
+    fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView<Self::Label, Self::LabelDim>,
+        weights: ArrayView1<f32>,
+    ) -> f32;
+
+    /// Computes `train_options.early_stopping_options.metric`, which is smaller when the model is better.
+    fn compute_early_stopping_metric_value(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView<Self::Label, Self::LabelDim>,
+        weights: ArrayView1<f32>,
+        train_options: &TrainOptions,
+    ) -> f32;
+}
+
+/// The features of some examples with a label and a weight for each.
+struct Examples<'a, Label, D> {
+    features: ArrayView2<'a, f32>,
+    labels: ArrayView<'a, Label, D>,
+    weights: ArrayView1<'a, f32>,
+}
+
+impl<'a, Label, D: Copy> Clone for Examples<'a, Label, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, Label, D: Copy> Copy for Examples<'a, Label, D> {}
+
+/// The features, labels and weights of the examples from the validation table, which replace the early stopping split of the training examples.
+type ValidationExamples<Label, D> = (Array2<f32>, Array<Label, D>, Array1<f32>);
+
+struct LinearModelTrainOutput<Model> {
+    model: Model,
+    losses: Option<Vec<f32>>,
+    best_epoch: Option<usize>,
+}
+
+/// Trains a linear model for `train_options.max_epochs` epochs, or until early stopping stops it, and returns the model from the epoch with the best early stopping metric. `initialize` creates the model from the training examples, which exclude the early stopping examples. `compute_validation_examples` is only called if `validation` is given and early stopping is enabled.
+fn train_linear_model<Model: LinearModel>(
+    task: Task,
+    examples: Examples<Model::Label, Model::LabelDim>,
+    validation: Option<EarlyStoppingValidation>,
+    compute_validation_examples: impl FnOnce(
+        &EarlyStoppingValidation,
+    ) -> anyhow::Result<
+        ValidationExamples<Model::Label, Model::LabelDim>,
+    >,
+    initialize: impl FnOnce(Examples<Model::Label, Model::LabelDim>) -> Model,
+    train_options: &TrainOptions,
+    progress: Progress,
+) -> anyhow::Result<LinearModelTrainOutput<Model>> {
+    train_options.validate(task, validation.as_ref())?;
+    let validation_examples = validation
+        .filter(|_| train_options.early_stopping_options.is_some())
+        .map(|validation| compute_validation_examples(&validation))
+        .transpose()?;
+    let (train_examples, early_stopping_examples) = train_early_stopping_split(
+        examples,
+        if validation_examples.is_some() {
+            0.0
+        } else {
+            train_options
+                .early_stopping_options
+                .as_ref()
+                .map(|o| o.early_stopping_fraction)
+                .unwrap_or(0.0)
+        },
+    );
+    let Examples {
+        features: features_train,
+        labels: labels_train,
+        weights: weights_train,
+    } = train_examples;
+    let (features_early_stopping, labels_early_stopping, weights_early_stopping) =
+        match &validation_examples {
+            Some((features, labels, weights)) => (features.view(), labels.view(), weights.view()),
+            None => (
+                early_stopping_examples.features.view(),
+                early_stopping_examples.labels.view(),
+                early_stopping_examples.weights.view(),
+            ),
+        };
+    let mut model = initialize(train_examples);
+    let mut early_stopping_monitor =
+        train_options
+            .early_stopping_options
+            .as_ref()
+            .map(|early_stopping_options| {
+                EarlyStoppingMonitor::new(
+                    early_stopping_options.min_decrease_in_loss_for_significant_change,
+                    early_stopping_options.n_rounds_without_improvement_to_stop,
+                )
+            });
+    let progress_counter = ProgressCounter::new(train_options.max_epochs.to_u64().unwrap());
+    (progress.handle_progress_event)(TrainProgressEvent::Train(progress_counter.clone()));
+    let mut losses = if train_options.compute_losses {
+        Some(Vec::new())
+    } else {
+        None
+    };
+    let kill_chip = progress.kill_chip;
+    let mut best_model = None;
+    let mut optimizer_state = model.new_optimizer_state(train_options);
+    for epoch in 0..train_options.max_epochs {
+        progress_counter.inc(1);
+        optimizer_state.learning_rate = train_options.learning_rate_schedule.compute_learning_rate(
+            train_options.learning_rate,
+            epoch,
+            train_options.max_epochs,
+        );
+        let permutation = shuffle_train_examples(features_train.nrows(), train_options, epoch);
+        let permutation = permutation.as_deref();
+        let batch_ranges =
+            compute_batch_ranges(features_train.nrows(), train_options.n_examples_per_batch);
+        if !(Model::TRAIN_BATCHES_IN_PARALLEL && train_batches_in_parallel(train_options)) {
+            for batch_range in batch_ranges {
+                if kill_chip.is_activate() {
+                    break;
+                }
+                model.train_batch(
+                    select_examples(features_train, permutation, batch_range.clone()).view(),
+                    select_examples(labels_train, permutation, batch_range.clone()).view(),
+                    select_examples(weights_train, permutation, batch_range).view(),
+                    &mut optimizer_state,
+                );
+            }
+        } else {
+            struct ModelPtr<Model>(*mut Model);
+            unsafe impl<Model> Send for ModelPtr<Model> {}
+            unsafe impl<Model> Sync for ModelPtr<Model> {}
+            let model_ptr = ModelPtr(&mut model);
+            batch_ranges.into_par_iter().for_each(|batch_range| {
+                if kill_chip.is_activate() {
+                    return;
+                }
+                let model_ptr = &model_ptr;
+                let model = unsafe { &mut *model_ptr.0 };
+                // Each batch steps with its own copy of the optimizer state, which SGD does not update.
+                let mut optimizer_state = optimizer_state.clone();
+                model.train_batch(
+                    select_examples(features_train, permutation, batch_range.clone()).view(),
+                    select_examples(labels_train, permutation, batch_range.clone()).view(),
+                    select_examples(weights_train, permutation, batch_range).view(),
+                    &mut optimizer_state,
+                );
+            });
+        }
+        if let Some(losses) = &mut losses {
+            losses.push(model.compute_loss(features_train, labels_train, weights_train));
+        }
+        if let Some(early_stopping_monitor) = early_stopping_monitor.as_mut() {
+            let early_stopping_metric_value = model.compute_early_stopping_metric_value(
+                features_early_stopping,
+                labels_early_stopping,
+                weights_early_stopping,
+                train_options,
+            );
+            let should_stop = early_stopping_monitor.update(epoch, early_stopping_metric_value);
+            if early_stopping_monitor.best_epoch() == Some(epoch) {
+                best_model = Some(model.clone());
+            }
+            if should_stop {
+                break;
+            }
+        }
+        if kill_chip.is_activate() {
+            break;
+        }
+    }
+    (progress.handle_progress_event)(TrainProgressEvent::TrainDone);
+    if let Some(best_model) = best_model {
+        model = best_model;
+    }
+    let best_epoch = early_stopping_monitor
+        .as_ref()
+        .and_then(|early_stopping_monitor| early_stopping_monitor.best_epoch());
+    Ok(LinearModelTrainOutput {
+        model,
+        losses,
+        best_epoch,
+    })
+}
+
+fn train_early_stopping_split<Label, D: RemoveAxis>(
+    examples: Examples<Label, D>,
     early_stopping_fraction: f32,
-) -> (
-    ArrayView2<'features, f32>,
-    ArrayView1<'labels, Label>,
-    ArrayView2<'features, f32>,
-    ArrayView1<'labels, Label>,
-) {
-    let split_index = ((1.0 - early_stopping_fraction) * features.nrows().to_f32().unwrap())
-        .to_usize()
-        .unwrap();
-    let (features_train, features_early_stopping) = features.split_at(Axis(0), split_index);
-    let (labels_train, labels_early_stopping) = labels.split_at(Axis(0), split_index);
+) -> (Examples<Label, D>, Examples<Label, D>) {
+    let split_index = ((1.0 - early_stopping_fraction)
+        * examples.features.nrows().to_f32().unwrap())
+    .to_usize()
+    .unwrap();
+    let (features_train, features_early_stopping) =
+        examples.features.split_at(Axis(0), split_index);
+    let (labels_train, labels_early_stopping) = examples.labels.split_at(Axis(0), split_index);
+    let (weights_train, weights_early_stopping) = examples.weights.split_at(Axis(0), split_index);
     (
-        features_train,
-        labels_train,
-        features_early_stopping,
-        labels_early_stopping,
+        Examples {
+            features: features_train,
+            labels: labels_train,
+            weights: weights_train,
+        },
+        Examples {
+            features: features_early_stopping,
+            labels: labels_early_stopping,
+            weights: weights_early_stopping,
+        },
     )
 }
 
-struct EarlyStoppingMonitor {
-    threshold: f32,
-    epochs: usize,
-    n_epochs_without_observed_improvement: usize,
-    previous_epoch_metric_value: Option<f32>,
+/// Returns a weight for each example, which is 1 if `weights` is `None`.
+fn compute_example_weights(weights: Option<ArrayView1<f32>>, n_examples: usize) -> Array1<f32> {
+    match weights {
+        Some(weights) => {
+            assert_eq!(weights.len(), n_examples);
+            weights.to_owned()
+        }
+        None => Array1::ones(n_examples),
+    }
+}
+
+/// Returns a weight for each example, which is the product of its weight in `weights` and the weight of its class.
+fn compute_class_weighted_example_weights(
+    weights: Option<ArrayView1<f32>>,
+    labels: ArrayView1<Option<NonZeroUsize>>,
+    n_classes: usize,
+    class_weights: &ClassWeights,
+) -> Array1<f32> {
+    let mut example_weights = compute_example_weights(weights, labels.len());
+    let class_weights = match class_weights {
+        ClassWeights::Uniform => return example_weights,
+        ClassWeights::Balanced => {
+            let mut n_examples_per_class = vec![0usize; n_classes];
+            for label in labels.iter() {
+                n_examples_per_class[label.unwrap().get() - 1] += 1;
+            }
+            n_examples_per_class
+                .iter()
+                .map(|n_examples_in_class| {
+                    if *n_examples_in_class == 0 {
+                        0.0
+                    } else {
+                        labels.len().to_f32().unwrap()
+                            / (n_classes * n_examples_in_class).to_f32().unwrap()
+                    }
+                })
+                .collect()
+        }
+        ClassWeights::Custom(class_weights) => {
+            assert_eq!(class_weights.len(), n_classes);
+            class_weights.clone()
+        }
+    };
+    for (example_weight, label) in zip!(example_weights.iter_mut(), labels.iter()) {
+        *example_weight *= class_weights[label.unwrap().get() - 1];
+    }
+    example_weights
+}
+
+/// Returns the order to visit the training examples in during `epoch`, or `None` to visit them in their original order. The examples themselves are never copied. Each batch gathers its rows with `select_examples`.
+fn shuffle_train_examples(
+    n_examples: usize,
+    train_options: &TrainOptions,
+    epoch: usize,
+) -> Option<Vec<usize>> {
+    if !train_options.shuffle {
+        return None;
+    }
+    let mut rng =
+        Xoshiro256Plus::seed_from_u64(train_options.seed.wrapping_add(epoch.to_u64().unwrap()));
+    let mut permutation: Vec<usize> = (0..n_examples).collect();
+    permutation.shuffle(&mut rng);
+    Some(permutation)
+}
+
+/// Splits the positions of an epoch's examples into batches.
+fn compute_batch_ranges(n_examples: usize, n_examples_per_batch: usize) -> Vec<Range<usize>> {
+    (0..n_examples)
+        .step_by(n_examples_per_batch)
+        .map(|start| start..(start + n_examples_per_batch).min(n_examples))
+        .collect()
 }
 
-impl EarlyStoppingMonitor {
-    pub fn new(threshold: f32, epochs: usize) -> EarlyStoppingMonitor {
-        EarlyStoppingMonitor {
-            threshold,
-            epochs,
-            previous_epoch_metric_value: None,
-            n_epochs_without_observed_improvement: 0,
+/// Returns the rows of `array` at the positions `range` of an epoch visited in the order of `permutation`. Without a permutation the rows are borrowed.
+fn select_examples<'a, T: Clone, D: RemoveAxis>(
+    mut array: ArrayView<'a, T, D>,
+    permutation: Option<&[usize]>,
+    range: Range<usize>,
+) -> CowArray<'a, T, D> {
+    match permutation {
+        Some(permutation) => array.select(Axis(0), &permutation[range]).into(),
+        None => {
+            array.slice_axis_inplace(Axis(0), Slice::from(range));
+            array.into()
         }
     }
+}
 
-    pub fn update(&mut self, early_stopping_metric_value: f32) -> bool {
-        let result = if let Some(previous_stopping_metric) = self.previous_epoch_metric_value {
-            if early_stopping_metric_value > previous_stopping_metric
-                || f32::abs(early_stopping_metric_value - previous_stopping_metric) < self.threshold
-            {
-                self.n_epochs_without_observed_improvement += 1;
-                self.n_epochs_without_observed_improvement >= self.epochs
-            } else {
-                self.n_epochs_without_observed_improvement = 0;
-                false
-            }
-        } else {
-            false
-        };
-        self.previous_epoch_metric_value = Some(early_stopping_metric_value);
-        result
+/// With SGD, batches are trained in parallel, each updating the shared weights without locking, unless training must be deterministic. The other optimizers update their state from every batch's gradients in turn, so they always train batches one after another.
+fn train_batches_in_parallel(train_options: &TrainOptions) -> bool {
+    !train_options.deterministic && matches!(train_options.optimizer, Optimizer::Sgd)
+}
+
+fn n_examples_per_early_stopping_chunk(n_examples: usize, train_options: &TrainOptions) -> usize {
+    if train_options.deterministic {
+        // A single chunk is folded on one thread, so the metric is always accumulated in the same order.
+        n_examples.max(1)
+    } else {
+        train_options.n_examples_per_batch
     }
 }
@@ -0,0 +1,109 @@
+/// The loss a `Regressor` minimizes. Each loss is a function of the label and the model's output, which is the linear combination of the features plus the bias. The Poisson and Tweedie losses use a log link, so the prediction is the exponential of the output.
+#[derive(Clone, Debug)]
+pub enum RegressionLoss {
+    /// `0.5 * (label - output)^2`.
+    SquaredError,
+    /// The squared error for residuals smaller than `delta` and the absolute error beyond it, which makes the model robust to outliers.
+    Huber(HuberLossOptions),
+    /// The pinball loss, which is minimized by the `quantile` of the label conditioned on the features.
+    Quantile(QuantileLossOptions),
+    /// The negative log likelihood of a Poisson distribution, for count labels.
+    Poisson,
+    /// The negative log likelihood of a Tweedie distribution with `variance_power` between 1 and 2, for nonnegative labels with many zeros, such as insurance claim amounts.
+    Tweedie(TweedieLossOptions),
+}
+
+#[derive(Clone, Debug)]
+pub struct HuberLossOptions {
+    pub delta: f32,
+}
+
+impl Default for HuberLossOptions {
+    fn default() -> HuberLossOptions {
+        HuberLossOptions { delta: 1.0 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QuantileLossOptions {
+    pub quantile: f32,
+}
+
+impl Default for QuantileLossOptions {
+    fn default() -> QuantileLossOptions {
+        QuantileLossOptions { quantile: 0.5 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TweedieLossOptions {
+    pub variance_power: f32,
+}
+
+impl Default for TweedieLossOptions {
+    fn default() -> TweedieLossOptions {
+        TweedieLossOptions {
+            variance_power: 1.5,
+        }
+    }
+}
+
+impl RegressionLoss {
+    pub fn compute_loss(&self, output: f32, label: f32) -> f32 {
+        match self {
+            RegressionLoss::SquaredError => 0.5 * (label - output) * (label - output),
+            RegressionLoss::Huber(options) => {
+                let residual = (label - output).abs();
+                if residual <= options.delta {
+                    0.5 * residual * residual
+                } else {
+                    options.delta * (residual - 0.5 * options.delta)
+                }
+            }
+            RegressionLoss::Quantile(options) => {
+                let residual = label - output;
+                if residual >= 0.0 {
+                    options.quantile * residual
+                } else {
+                    (options.quantile - 1.0) * residual
+                }
+            }
+            RegressionLoss::Poisson => output.exp() - label * output,
+            RegressionLoss::Tweedie(options) => {
+                let p = options.variance_power;
+                -label * ((1.0 - p) * output).exp() / (1.0 - p)
+                    + ((2.0 - p) * output).exp() / (2.0 - p)
+            }
+        }
+    }
+
+    /// Computes the derivative of the loss with respect to the output.
+    pub fn compute_gradient(&self, output: f32, label: f32) -> f32 {
+        match self {
+            RegressionLoss::SquaredError => output - label,
+            RegressionLoss::Huber(options) => (output - label).clamp(-options.delta, options.delta),
+            RegressionLoss::Quantile(options) => {
+                if label > output {
+                    -options.quantile
+                } else {
+                    1.0 - options.quantile
+                }
+            }
+            RegressionLoss::Poisson => output.exp() - label,
+            RegressionLoss::Tweedie(options) => {
+                let p = options.variance_power;
+                -label * ((1.0 - p) * output).exp() + ((2.0 - p) * output).exp()
+            }
+        }
+    }
+
+    /// Applies the inverse of the link function to an output to produce a prediction.
+    pub fn compute_prediction(&self, output: f32) -> f32 {
+        match self {
+            RegressionLoss::SquaredError
+            | RegressionLoss::Huber(_)
+            | RegressionLoss::Quantile(_) => output,
+            RegressionLoss::Poisson | RegressionLoss::Tweedie(_) => output.exp(),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        HuberLossOptions, Progress, QuantileLossOptions, RegressionLoss, Regressor,
+        RegressorTrainOutput, TrainOptions, TweedieLossOptions,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::NumberTableColumn;
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    fn train_output_with_examples(
+        train_options: &TrainOptions,
+        features: ArrayView2<f32>,
+        labels: Vec<f32>,
+    ) -> RegressorTrainOutput {
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        Regressor::train(
+            features,
+            labels.view(),
+            None,
+            None,
+            train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_huber_loss_is_robust_to_outliers() {
+        // Every 50th label is an outlier.
+        let (features, mut labels) = features_and_labels(|features| 2.0 * features[0] + 1.0);
+        for label in labels.iter_mut().step_by(50) {
+            *label += 100.0;
+        }
+        let train_options = TrainOptions {
+            deterministic: true,
+            learning_rate: 0.01,
+            ..Default::default()
+        };
+        let squared_error =
+            train_output_with_examples(&train_options, features.view(), labels.clone()).model;
+        let huber = train_output_with_examples(
+            &TrainOptions {
+                regression_loss: RegressionLoss::Huber(HuberLossOptions::default()),
+                learning_rate: 0.1,
+                ..train_options
+            },
+            features.view(),
+            labels,
+        )
+        .model;
+        assert!((huber.bias - 1.0).abs() < 0.2, "{}", huber.bias);
+        assert!(
+            (squared_error.bias - 1.0).abs() > 1.0,
+            "{}",
+            squared_error.bias
+        );
+    }
+
+    #[test]
+    fn test_quantile_loss() {
+        // The noise is uniform in [0, 1), so the 0.9 quantile of each label is 0.9 above its mean.
+        let mut rng = Xoshiro256Plus::seed_from_u64(1);
+        let (features, labels) = features_and_labels(|features| features[0] + rng.gen::<f32>());
+        let model = train_output_with_examples(
+            &TrainOptions {
+                deterministic: true,
+                learning_rate: 0.1,
+                max_epochs: 200,
+                regression_loss: RegressionLoss::Quantile(QuantileLossOptions { quantile: 0.9 }),
+                ..Default::default()
+            },
+            features.view(),
+            labels.clone(),
+        )
+        .model;
+        let mut predictions = Array::zeros(labels.len());
+        model.predict(features.view(), predictions.view_mut());
+        let fraction_below = labels
+            .iter()
+            .zip(predictions.iter())
+            .filter(|(label, prediction)| label <= prediction)
+            .count() as f32
+            / labels.len() as f32;
+        assert!((fraction_below - 0.9).abs() < 0.05, "{}", fraction_below);
+    }
+
+    #[test]
+    fn test_log_link_losses() {
+        let (features, labels) = features_and_labels(|features| (features[0] + 0.5).exp());
+        for regression_loss in [
+            RegressionLoss::Poisson,
+            RegressionLoss::Tweedie(TweedieLossOptions::default()),
+        ] {
+            let model = train_output_with_examples(
+                &TrainOptions {
+                    deterministic: true,
+                    learning_rate: 0.05,
+                    max_epochs: 200,
+                    regression_loss: regression_loss.clone(),
+                    ..Default::default()
+                },
+                features.view(),
+                labels.clone(),
+            )
+            .model;
+            assert!((model.weights[0] - 1.0).abs() < 0.1, "{:?}", model);
+            assert!((model.bias - 0.5).abs() < 0.1, "{:?}", model);
+            // The prediction applies the inverse of the log link.
+            let mut predictions = Array::zeros(2);
+            model.predict(features.slice(s![0..2, ..]), predictions.view_mut());
+            for (prediction, label) in predictions.iter().zip(labels.iter()) {
+                assert!((prediction - label).abs() / label < 0.1);
+            }
+            // The loss survives serialization, so the deserialized model predicts the same values.
+            let deserialized = model.from_bytes(&model.to_bytes());
+            let mut deserialized_predictions = Array::zeros(2);
+            deserialized.predict(
+                features.slice(s![0..2, ..]),
+                deserialized_predictions.view_mut(),
+            );
+            assert_eq!(predictions, deserialized_predictions);
+        }
+    }
+}
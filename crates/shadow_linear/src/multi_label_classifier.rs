@@ -0,0 +1,269 @@
+use super::{
+    compute_example_weights,
+    early_stopping::{EarlyStoppingMetricInput, Task},
+    optimizer::OptimizerState,
+    shap::{compute_shap_values_for_example, ComputeShapValuesForExampleOutput},
+    train_linear_model, EarlyStoppingMetric, EarlyStoppingValidation, Examples, LinearModel,
+    LinearModelTrainOutput, Progress, TrainOptions,
+};
+use ndarray::prelude::*;
+use num::{clamp, ToPrimitive};
+use shadow_metrics::Mean;
+use shadow_table::prelude::*;
+use shadow_zip::zip;
+
+/// A classifier for several binary labels at once, with an independent logistic regression for each label.
+#[derive(Clone, Debug)]
+pub struct MultiLabelClassifier {
+    pub biases: Array1<f32>,
+    /// One column per label.
+    pub weights: Array2<f32>,
+    pub means: Vec<f32>,
+}
+
+pub struct MultiLabelClassifierTrainOutput {
+    pub model: MultiLabelClassifier,
+    pub losses: Option<Vec<f32>>,
+    pub feature_importances: Option<Vec<f32>>,
+    pub n_zero_weights: usize,
+    /// The epoch whose model was returned, if early stopping was enabled.
+    pub best_epoch: Option<usize>,
+}
+
+impl MultiLabelClassifier {
+    /// Each of `labels` is a column with two variants, where the second variant means the label is true, like the labels of a `BinaryClassifier`. If `weights` is given, each example's contribution to the loss is multiplied by its weight. `train_options.class_weights` is ignored. The early stopping examples in `validation` are labeled by the columns with the names of `labels`.
+    pub fn train(
+        features: ArrayView2<f32>,
+        labels: &[EnumTableColumnView],
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<MultiLabelClassifierTrainOutput> {
+        let n_examples = features.nrows();
+        let n_features = features.ncols();
+        let n_labels = labels.len();
+        let mut label_values = Array2::from_elem((n_examples, n_labels), false);
+        for (mut label_values, labels) in zip!(label_values.axis_iter_mut(Axis(1)), labels.iter()) {
+            assert_eq!(labels.variants().len(), 2);
+            for (label_value, label) in zip!(label_values.iter_mut(), labels.as_slice().iter()) {
+                *label_value = match label.map(|label| label.get()) {
+                    Some(1) => false,
+                    Some(2) => true,
+                    _ => unreachable!(),
+                };
+            }
+        }
+        let example_weights = compute_example_weights(weights, n_examples);
+        let LinearModelTrainOutput {
+            model,
+            losses,
+            best_epoch,
+        } = train_linear_model(
+            Task::MultiLabelClassification,
+            Examples {
+                features: features.view(),
+                labels: label_values.view(),
+                weights: example_weights.view(),
+            },
+            validation,
+            |validation| {
+                let (features, labels) = validation.compute_multi_label_examples(labels)?;
+                let weights = compute_example_weights(None, features.nrows());
+                Ok((features, labels, weights))
+            },
+            |train_examples| {
+                let means = train_examples
+                    .features
+                    .axis_iter(Axis(1))
+                    .map(|column| column.mean().unwrap())
+                    .collect();
+                MultiLabelClassifier {
+                    biases: <Array1<f32>>::zeros(n_labels),
+                    weights: <Array2<f32>>::zeros((n_features, n_labels)),
+                    means,
+                }
+            },
+            train_options,
+            progress,
+        )?;
+        let mut feature_importances = model
+            .weights
+            .axis_iter(Axis(0))
+            .map(|weights| weights.iter().map(|weight| weight.abs()).sum::<f32>())
+            .collect::<Vec<_>>();
+        let feature_importances_sum = feature_importances.iter().sum::<f32>();
+        feature_importances
+            .iter_mut()
+            .for_each(|feature_importance| *feature_importance /= feature_importances_sum);
+        let n_zero_weights = model
+            .weights
+            .iter()
+            .filter(|weight| **weight == 0.0)
+            .count();
+        Ok(MultiLabelClassifierTrainOutput {
+            model,
+            losses,
+            feature_importances: Some(feature_importances),
+            n_zero_weights,
+            best_epoch,
+        })
+    }
+
+    /// Computes the weighted mean over the examples of the binary cross entropy averaged over the labels.
+    pub fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView2<bool>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        let mut probabilities = Array2::zeros((features.nrows(), self.biases.len()));
+        self.predict(features, probabilities.view_mut());
+        let mut loss = Mean::new();
+        for (probabilities, labels, weight) in zip!(
+            probabilities.axis_iter(Axis(0)),
+            labels.axis_iter(Axis(0)),
+            weights.iter()
+        ) {
+            let mut example_loss = Mean::new();
+            for (probability, label) in zip!(probabilities.iter(), labels.iter()) {
+                let probability = clamp(*probability, f32::EPSILON, 1.0 - f32::EPSILON);
+                example_loss.update(if *label {
+                    -probability.ln()
+                } else {
+                    -(1.0 - probability).ln()
+                });
+            }
+            loss.update_weighted(example_loss.finalize().unwrap_or(0.0), *weight);
+        }
+        loss.finalize().unwrap_or(0.0)
+    }
+
+    /// Computes the probability that each label is true, with one column per label.
+    pub fn predict(&self, features: ArrayView2<f32>, mut probabilities: ArrayViewMut2<f32>) {
+        for mut row in probabilities.axis_iter_mut(Axis(0)) {
+            row.assign(&self.biases.view());
+        }
+        ndarray::linalg::general_mat_mul(1.0, &features, &self.weights, 1.0, &mut probabilities);
+        for probability in probabilities.iter_mut() {
+            *probability = 1.0 / ((-*probability).exp() + 1.0);
+        }
+    }
+
+    pub fn compute_feature_contributions(
+        &self,
+        features: ArrayView2<f32>,
+    ) -> Vec<Vec<ComputeShapValuesForExampleOutput>> {
+        features
+            .axis_iter(Axis(0))
+            .map(|features| {
+                zip!(self.weights.axis_iter(Axis(1)), self.biases.view())
+                    .map(|(weights, bias)| {
+                        compute_shap_values_for_example(
+                            features.as_slice().unwrap(),
+                            *bias,
+                            weights.view(),
+                            &self.means,
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn from_reader(
+        multi_label_classifier: crate::serialize::MultiLabelClassifierReader,
+    ) -> MultiLabelClassifier {
+        crate::serialize::deserialize_multi_label_classifier(multi_label_classifier)
+    }
+
+    pub fn to_writer(
+        &self,
+        writer: &mut buffalo::Writer,
+    ) -> buffalo::Position<crate::serialize::MultiLabelClassifierWriter> {
+        crate::serialize::serialize_multi_label_classifier(self, writer)
+    }
+
+    #[must_use]
+    pub fn from_bytes(&self, bytes: &[u8]) -> MultiLabelClassifier {
+        let reader = buffalo::read::<crate::serialize::MultiLabelClassifierReader>(bytes);
+        Self::from_reader(reader)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = buffalo::Writer::new();
+        let position = self.to_writer(&mut writer);
+        writer.write(&position);
+        writer.into_bytes()
+    }
+}
+
+impl LinearModel for MultiLabelClassifier {
+    type Label = bool;
+    type LabelDim = Ix2;
+
+    const TRAIN_BATCHES_IN_PARALLEL: bool = false;
+
+    fn new_optimizer_state(&self, train_options: &TrainOptions) -> OptimizerState {
+        OptimizerState::new(train_options, self.weights.len(), self.biases.len())
+    }
+
+    fn train_batch(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: ArrayView2<bool>,
+        weights: ArrayView1<f32>,
+        optimizer_state: &mut OptimizerState,
+    ) {
+        let mut py = Array2::zeros((features.nrows(), self.biases.len()));
+        self.predict(features, py.view_mut());
+        for (mut py, labels, weight) in zip!(
+            py.axis_iter_mut(Axis(0)),
+            labels.axis_iter(Axis(0)),
+            weights.iter()
+        ) {
+            for (py, label) in zip!(py.iter_mut(), labels.iter()) {
+                let label = if *label { 1.0 } else { 0.0 };
+                *py = weight * (*py - label);
+            }
+        }
+        let n_examples = features.nrows().to_f32().unwrap();
+        let weight_gradients = features.t().dot(&py) / n_examples;
+        let bias_gradients = py.mean_axis(Axis(0)).unwrap();
+        optimizer_state.update_weights(self.weights.iter_mut(), weight_gradients.iter());
+        optimizer_state.update_biases(self.biases.iter_mut(), bias_gradients.iter());
+    }
+
+    fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView2<bool>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        MultiLabelClassifier::compute_loss(self, features, labels, weights)
+    }
+
+    fn compute_early_stopping_metric_value(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView2<bool>,
+        weights: ArrayView1<f32>,
+        train_options: &TrainOptions,
+    ) -> f32 {
+        let metric = &train_options
+            .early_stopping_options
+            .as_ref()
+            .unwrap()
+            .metric;
+        if let EarlyStoppingMetric::Loss = metric {
+            return MultiLabelClassifier::compute_loss(self, features, labels, weights);
+        }
+        let mut probabilities = Array2::zeros((features.nrows(), self.biases.len()));
+        self.predict(features, probabilities.view_mut());
+        metric.compute_value_to_minimize(EarlyStoppingMetricInput::MultiLabelClassification {
+            probabilities: probabilities.view(),
+            labels: labels.view(),
+            weights: weights.view(),
+        })
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        EarlyStoppingMetric, EarlyStoppingOptions, EarlyStoppingValidation, MultiLabelClassifier,
+        Progress, TrainOptions,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_features::{FeatureGroup, IdentityFeatureGroup};
+    use shadow_metrics::{MultiLabelClassificationMetrics, MultiLabelClassificationMetricsInput};
+    use shadow_table::{EnumTableColumn, NumberTableColumn, Table, TableColumn};
+    use std::num::NonZeroUsize;
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    #[test]
+    fn test_multi_label_classifier() {
+        let (features, _) = features_and_labels(|_| 0.0);
+        let variants = vec!["false".to_owned(), "true".to_owned()];
+        let label = |compute_label: fn(ArrayView1<f32>) -> bool| {
+            let labels = features
+                .axis_iter(Axis(0))
+                .map(|features| NonZeroUsize::new(if compute_label(features) { 2 } else { 1 }))
+                .collect();
+            EnumTableColumn::new(None, variants.clone(), labels)
+        };
+        let labels = [
+            label(|features| features[0] > 0.5),
+            label(|features| features[1] < 0.5),
+        ];
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let model = MultiLabelClassifier::train(
+            features.view(),
+            &[labels[0].view(), labels[1].view()],
+            None,
+            None,
+            &TrainOptions {
+                deterministic: true,
+                learning_rate: 1.0,
+                ..Default::default()
+            },
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+        .model;
+        // Each label depends on a different feature, with opposite signs.
+        assert!(model.weights[(0, 0)] > 0.0 && model.weights[(1, 1)] < 0.0);
+        let mut probabilities = Array::zeros((features.nrows(), 2));
+        model.predict(features.view(), probabilities.view_mut());
+        let label_values = Array::from_shape_fn((features.nrows(), 2), |(example, label)| {
+            labels[label].view().as_slice()[example].unwrap().get() == 2
+        });
+        let mut metrics = MultiLabelClassificationMetrics::new(2);
+        metrics.update(MultiLabelClassificationMetricsInput {
+            probabilities: probabilities.view(),
+            labels: label_values.view(),
+        });
+        let metrics = metrics.finalize();
+        assert!(metrics.hamming_loss < 0.05, "{:?}", metrics);
+        let contributions = model.compute_feature_contributions(features.slice(s![0..1, ..]));
+        assert_eq!(contributions[0].len(), 2);
+        let deserialized = model.from_bytes(&model.to_bytes());
+        assert_eq!(deserialized.weights, model.weights);
+        assert_eq!(deserialized.biases, model.biases);
+    }
+
+    #[test]
+    fn test_multi_label_early_stopping_with_validation_table() {
+        // The validation labels are the opposite of the training labels, so the metric only gets worse after the first epoch.
+        let (features, _) = features_and_labels(|_| 0.0);
+        let variants = vec!["false".to_owned(), "true".to_owned()];
+        let label = |is_true: fn(f32) -> bool| {
+            let labels = features
+                .column(0)
+                .iter()
+                .map(|feature| NonZeroUsize::new(if is_true(*feature) { 2 } else { 1 }))
+                .collect();
+            EnumTableColumn::new(Some("a".to_owned()), variants.clone(), labels)
+        };
+        let labels = label(|feature| feature > 0.5);
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = vec![
+            TableColumn::Number(NumberTableColumn::new(
+                Some("x".to_owned()),
+                features.column(0).to_vec(),
+            )),
+            TableColumn::Enum(label(|feature| feature <= 0.5)),
+        ];
+        let table = table.view();
+        let feature_groups = vec![FeatureGroup::Identity(IdentityFeatureGroup {
+            source_column_name: "x".to_owned(),
+        })];
+        let validation = EarlyStoppingValidation {
+            table: &table,
+            feature_groups: &feature_groups,
+            target_column_name: "",
+        };
+        let (_, validation_labels) = validation
+            .compute_multi_label_examples(&[labels.view()])
+            .unwrap();
+        assert_eq!(validation_labels[(0, 0)], features[(0, 0)] <= 0.5);
+        let unnamed_labels = EnumTableColumn::new(None, variants.clone(), Vec::new());
+        assert!(validation
+            .compute_multi_label_examples(&[unnamed_labels.view()])
+            .is_err());
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let output = MultiLabelClassifier::train(
+            features.slice(s![.., 0..1]),
+            &[labels.view()],
+            None,
+            Some(validation),
+            &TrainOptions {
+                deterministic: true,
+                early_stopping_options: Some(EarlyStoppingOptions {
+                    early_stopping_fraction: 0.1,
+                    n_rounds_without_improvement_to_stop: 3,
+                    min_decrease_in_loss_for_significant_change: 0.0,
+                    metric: EarlyStoppingMetric::Loss,
+                }),
+                learning_rate: 1.0,
+                ..Default::default()
+            },
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap();
+        assert_eq!(output.best_epoch, Some(0));
+    }
+}
@@ -1,14 +1,17 @@
-use crate::Progress;
-
 use super::{
+    calibration::TemperatureScaling,
+    compute_class_weighted_example_weights, compute_example_weights,
+    early_stopping::{EarlyStoppingMetricInput, Task},
+    n_examples_per_early_stopping_chunk,
+    optimizer::OptimizerState,
     shap::{compute_shap_values_for_example, ComputeShapValuesForExampleOutput},
-    train_early_stopping_split, EarlyStoppingMonitor, TrainOptions, TrainProgressEvent,
+    train_linear_model, EarlyStoppingMetric, EarlyStoppingValidation, Examples, LinearModel,
+    LinearModelTrainOutput, Progress, TrainOptions,
 };
 use ndarray::{self, prelude::*};
 use num::{clamp, ToPrimitive};
 use rayon::{self, prelude::*};
 use shadow_metrics::{CrossEntropy, CrossEntropyInput};
-use shadow_progress_counter::ProgressCounter;
 use shadow_table::prelude::*;
 use shadow_zip::{pzip, zip};
 use std::num::NonZeroUsize;
@@ -18,115 +21,141 @@ pub struct MulticlassClassifier {
     pub biases: Array1<f32>,
     pub weights: Array2<f32>,
     pub means: Vec<f32>,
+    /// Applied to the logits by `predict`. See `MulticlassClassifier::calibrate`.
+    pub calibrator: Option<TemperatureScaling>,
 }
 
 pub struct MulticlassClassifierTrainOutput {
     pub model: MulticlassClassifier,
     pub losses: Option<Vec<f32>>,
     pub feature_importances: Option<Vec<f32>>,
+    pub n_zero_weights: usize,
+    /// The epoch whose model was returned, if early stopping was enabled.
+    pub best_epoch: Option<usize>,
 }
 
 impl MulticlassClassifier {
+    /// If `weights` is given, each example's contribution to the loss is multiplied by its weight and the weight of its class in `train_options.class_weights`.
     pub fn train(
         features: ArrayView2<f32>,
         labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
         train_options: &TrainOptions,
         progress: Progress,
-    ) -> MulticlassClassifierTrainOutput {
+    ) -> anyhow::Result<MulticlassClassifierTrainOutput> {
+        MulticlassClassifier::train_with_initial_model(
+            None,
+            features,
+            labels,
+            weights,
+            validation,
+            train_options,
+            progress,
+        )
+    }
+
+    /// Continues training `model`, starting from its weights and biases instead of zeros. The model keeps its means, but its calibrator is discarded because it no longer matches the trained model.
+    pub fn train_from(
+        model: MulticlassClassifier,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<MulticlassClassifierTrainOutput> {
+        assert_eq!(
+            model.weights.dim(),
+            (features.ncols(), labels.variants().len())
+        );
+        MulticlassClassifier::train_with_initial_model(
+            Some(model),
+            features,
+            labels,
+            weights,
+            validation,
+            train_options,
+            progress,
+        )
+    }
+
+    fn train_with_initial_model(
+        initial_model: Option<MulticlassClassifier>,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<MulticlassClassifierTrainOutput> {
         let n_classes = labels.variants().len();
         let n_features = features.ncols();
-        let (features_train, labels_train, features_early_stopping, labels_early_stopping) =
-            train_early_stopping_split(
-                features,
-                labels.as_slice().into(),
-                train_options
-                    .early_stopping_options
-                    .as_ref()
-                    .map(|o| o.early_stopping_fraction)
-                    .unwrap_or(0.0),
-            );
-        let means = features_train
-            .axis_iter(Axis(1))
-            .map(|column| column.mean().unwrap())
-            .collect();
-        let mut model = MulticlassClassifier {
-            biases: <Array1<f32>>::zeros(n_classes),
-            weights: <Array2<f32>>::zeros((n_features, n_classes)),
-            means,
-        };
-        let mut early_stopping_monitor =
-            train_options
-                .early_stopping_options
-                .as_ref()
-                .map(|early_stopping_options| {
-                    EarlyStoppingMonitor::new(
-                        early_stopping_options.min_decrease_in_loss_for_significant_change,
-                        early_stopping_options.n_rounds_without_improvement_to_stop,
-                    )
-                });
-        let progress_counter = ProgressCounter::new(train_options.max_epochs.to_u64().unwrap());
-        (progress.handle_progress_event)(TrainProgressEvent::Train(progress_counter.clone()));
-        let mut probabilities_buffer: Array2<f32> = Array2::zeros((labels.len(), n_classes));
-        let mut losses = if train_options.compute_losses {
-            Some(Vec::new())
-        } else {
-            None
-        };
-        let kill_chip = progress.kill_chip;
-        for _ in 0..train_options.max_epochs {
-            progress_counter.inc(1);
-            let n_examples_per_batch = train_options.n_examples_per_batch;
-            struct MulticlassClassifierPtr(*mut MulticlassClassifier);
-            unsafe impl Send for MulticlassClassifierPtr {}
-            unsafe impl Sync for MulticlassClassifierPtr {}
-            let model_ptr = MulticlassClassifierPtr(&mut model);
-            pzip!(
-                features_train.axis_chunks_iter(Axis(0), n_examples_per_batch),
-                labels_train.axis_chunks_iter(Axis(0), n_examples_per_batch),
-                probabilities_buffer.axis_chunks_iter_mut(Axis(0), n_examples_per_batch),
-            )
-            .for_each(|(features, labels, probabilities)| {
-                let model_ptr = &model_ptr;
-                let model = unsafe { &mut *model_ptr.0 };
-                MulticlassClassifier::train_batch(
-                    model,
-                    features,
-                    labels,
-                    probabilities,
-                    train_options,
-                    kill_chip,
+        let example_weights = compute_class_weighted_example_weights(
+            weights,
+            labels.as_slice().into(),
+            n_classes,
+            &train_options.class_weights,
+        );
+        let LinearModelTrainOutput {
+            model,
+            losses,
+            best_epoch,
+        } = train_linear_model(
+            Task::MulticlassClassification,
+            Examples {
+                features: features.view(),
+                labels: labels.as_slice().into(),
+                weights: example_weights.view(),
+            },
+            validation,
+            |validation| {
+                let validation_examples = validation.compute_examples();
+                let labels = validation_examples.enum_labels().to_owned();
+                let weights = compute_class_weighted_example_weights(
+                    None,
+                    labels.view(),
+                    n_classes,
+                    &train_options.class_weights,
                 );
-            });
-            if let Some(losses) = &mut losses {
-                let loss =
-                    MulticlassClassifier::compute_loss(probabilities_buffer.view(), labels_train);
-                losses.push(loss);
-            }
-            if let Some(early_stopping_monitor) = early_stopping_monitor.as_mut() {
-                let early_stopping_metric_value =
-                    MulticlassClassifier::compute_early_stopping_metric_value(
-                        &model,
-                        features_early_stopping,
-                        labels_early_stopping,
-                        train_options,
-                    );
-                let should_stop = early_stopping_monitor.update(early_stopping_metric_value);
-                if should_stop {
-                    break;
-                }
-            }
-
-            if progress.kill_chip.is_activate() {
-                break;
-            }
-        }
-        (progress.handle_progress_event)(TrainProgressEvent::TrainDone);
+                Ok((validation_examples.features, labels, weights))
+            },
+            |train_examples| {
+                initial_model
+                    .map(|model| MulticlassClassifier {
+                        calibrator: None,
+                        ..model
+                    })
+                    .unwrap_or_else(|| {
+                        let means = train_examples
+                            .features
+                            .axis_iter(Axis(1))
+                            .map(|column| column.mean().unwrap())
+                            .collect();
+                        MulticlassClassifier {
+                            biases: <Array1<f32>>::zeros(n_classes),
+                            weights: <Array2<f32>>::zeros((n_features, n_classes)),
+                            means,
+                            calibrator: None,
+                        }
+                    })
+            },
+            train_options,
+            progress,
+        )?;
         let feature_importances = MulticlassClassifier::compute_feature_importances(&model);
-        MulticlassClassifierTrainOutput {
+        let n_zero_weights = model
+            .weights
+            .iter()
+            .filter(|weight| **weight == 0.0)
+            .count();
+        Ok(MulticlassClassifierTrainOutput {
             model,
             losses,
             feature_importances: Some(feature_importances),
-        }
+            n_zero_weights,
+            best_epoch,
+        })
     }
 
     fn compute_feature_importances(model: &MulticlassClassifier) -> Vec<f32> {
@@ -148,112 +177,25 @@ impl MulticlassClassifier {
         feature_importances
     }
 
-    fn train_batch(
-        &mut self,
-        features: ArrayView2<f32>,
-        labels: ArrayView1<Option<NonZeroUsize>>,
-        mut probabilities: ArrayViewMut2<f32>,
-        train_options: &TrainOptions,
-        kill_chip: &shadow_kill_chip::KillChip,
-    ) {
-        if kill_chip.is_activate() {
-            return;
-        }
-        let learning_rate = train_options.learning_rate;
-        let n_classes = self.weights.ncols();
-        let mut logits = features.dot(&self.weights) + &self.biases;
-        softmax(logits.view_mut());
-        for (probability, logit) in zip!(probabilities.iter_mut(), logits.iter()) {
-            *probability = *logit;
-        }
-        let mut predictions = logits;
-        for (mut predictions, label) in zip!(predictions.axis_iter_mut(Axis(0)), labels) {
-            for (class_index, prediction) in predictions.iter_mut().enumerate() {
-                *prediction -= if class_index == label.unwrap().get() - 1 {
-                    1.0
-                } else {
-                    0.0
-                };
-            }
-        }
-        let py = predictions;
-        for class_index in 0..n_classes {
-            let weight_gradients = (&features * &py.column(class_index).insert_axis(Axis(1)))
-                .mean_axis(Axis(0))
-                .unwrap();
-            for (weight, weight_gradient) in zip!(
-                self.weights.column_mut(class_index),
-                weight_gradients.iter()
-            ) {
-                *weight += -learning_rate * weight_gradient
-            }
-            let bias_gradients = py
-                .column(class_index)
-                .insert_axis(Axis(1))
-                .mean_axis(Axis(0))
-                .unwrap();
-            self.biases[class_index] += -learning_rate * bias_gradients[0];
-        }
-    }
-
     pub fn compute_loss(
         probabilities: ArrayView2<f32>,
         labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
     ) -> f32 {
         let mut loss = 0.0;
-        for (label, probabilities) in zip!(labels.into_iter(), probabilities.axis_iter(Axis(0))) {
+        for (label, probabilities, weight) in zip!(
+            labels.into_iter(),
+            probabilities.axis_iter(Axis(0)),
+            weights
+        ) {
             for (index, &probability) in probabilities.indexed_iter() {
                 let probability = clamp(probability, std::f32::EPSILON, 1.0 - std::f32::EPSILON);
                 if index == (label.unwrap().get() - 1) {
-                    loss += -probability.ln();
+                    loss += -weight * probability.ln();
                 }
             }
         }
-        loss / labels.len().to_f32().unwrap()
-    }
-
-    fn compute_early_stopping_metric_value(
-        &self,
-        features: ArrayView2<f32>,
-        labels: ArrayView1<Option<NonZeroUsize>>,
-        train_options: &TrainOptions,
-    ) -> f32 {
-        let n_classes = self.biases.len();
-        pzip!(
-            features.axis_chunks_iter(Axis(0), train_options.n_examples_per_batch),
-            labels.axis_chunks_iter(Axis(0), train_options.n_examples_per_batch),
-        )
-        .fold(
-            || {
-                let predictions = unsafe {
-                    <Array2<f32>>::uninit((train_options.n_examples_per_batch, n_classes))
-                        .assume_init()
-                };
-                let metric = CrossEntropy::default();
-                (predictions, metric)
-            },
-            |(mut predictions, mut metric), (features, labels)| {
-                let slice = s![0..features.nrows(), ..];
-                let mut predictions_slice = predictions.slice_mut(slice);
-                self.predict(features, predictions_slice.view_mut());
-                for (prediction, label) in zip!(predictions_slice.axis_iter(Axis(0)), labels.iter())
-                {
-                    metric.update(CrossEntropyInput {
-                        probabilities: prediction,
-                        label: *label,
-                    });
-                }
-                (predictions, metric)
-            },
-        )
-        .map(|(_, metric)| metric)
-        .reduce(CrossEntropy::new, |mut a, b| {
-            a.merge(b);
-            a
-        })
-        .finalize()
-        .0
-        .unwrap()
+        loss / weights.sum()
     }
 
     pub fn predict(&self, features: ArrayView2<f32>, mut probabilities: ArrayViewMut2<f32>) {
@@ -261,9 +203,30 @@ impl MulticlassClassifier {
             row.assign(&self.biases.view());
         }
         ndarray::linalg::general_mat_mul(1.0, &features, &self.weights, 1.0, &mut probabilities);
+        if let Some(calibrator) = &self.calibrator {
+            probabilities /= calibrator.temperature;
+        }
         softmax(probabilities);
     }
 
+    /// Fits a temperature to held-out examples that were not used for training, replacing any existing calibrator. If `weights` is given, each example's contribution is multiplied by its weight.
+    pub fn calibrate(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+    ) {
+        self.calibrator = None;
+        let mut probabilities = Array2::zeros((features.nrows(), self.biases.len()));
+        self.predict(features, probabilities.view_mut());
+        let weights = compute_example_weights(weights, features.nrows());
+        self.calibrator = Some(TemperatureScaling::fit(
+            probabilities.view(),
+            labels.as_slice().into(),
+            weights.view(),
+        ));
+    }
+
     pub fn compute_feature_contributions(
         &self,
         features: ArrayView2<f32>,
@@ -286,7 +249,9 @@ impl MulticlassClassifier {
     }
 
     pub fn from_reader(
-        multiclass_classifier: crate::serialize::MulticlassClassifierReader,
+        multiclass_classifier: crate::serialize::VersionedReader<
+            crate::serialize::MulticlassClassifierReader,
+        >,
     ) -> MulticlassClassifier {
         crate::serialize::deserialize_multiclass_classifier(multiclass_classifier)
     }
@@ -300,17 +265,140 @@ impl MulticlassClassifier {
 
     #[must_use]
     pub fn from_bytes(&self, bytes: &[u8]) -> MulticlassClassifier {
-        let reader = buffalo::read::<crate::serialize::MulticlassClassifierReader>(bytes);
+        let reader = buffalo::read::<
+            crate::serialize::Versioned<crate::serialize::MulticlassClassifierReader>,
+        >(bytes);
         Self::from_reader(reader)
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut writer = buffalo::Writer::new();
-        self.to_writer(&mut writer);
+        let position = self.to_writer(&mut writer);
+        writer.write(&position);
         writer.into_bytes()
     }
 }
 
+impl LinearModel for MulticlassClassifier {
+    type Label = Option<NonZeroUsize>;
+    type LabelDim = Ix1;
+
+    const TRAIN_BATCHES_IN_PARALLEL: bool = true;
+
+    fn new_optimizer_state(&self, train_options: &TrainOptions) -> OptimizerState {
+        OptimizerState::new(train_options, self.weights.len(), self.biases.len())
+    }
+
+    fn train_batch(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+        optimizer_state: &mut OptimizerState,
+    ) {
+        let mut logits = features.dot(&self.weights) + &self.biases;
+        softmax(logits.view_mut());
+        let mut predictions = logits;
+        for (mut predictions, label, weight) in
+            zip!(predictions.axis_iter_mut(Axis(0)), labels, weights)
+        {
+            for (class_index, prediction) in predictions.iter_mut().enumerate() {
+                *prediction -= if class_index == label.unwrap().get() - 1 {
+                    1.0
+                } else {
+                    0.0
+                };
+                *prediction *= weight;
+            }
+        }
+        let py = predictions;
+        let weight_gradients = features.t().dot(&py) / features.nrows().to_f32().unwrap();
+        let bias_gradients = py.mean_axis(Axis(0)).unwrap();
+        optimizer_state.update_weights(self.weights.iter_mut(), weight_gradients.iter());
+        optimizer_state.update_biases(self.biases.iter_mut(), bias_gradients.iter());
+    }
+
+    fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        let mut probabilities = Array2::zeros((features.nrows(), self.biases.len()));
+        self.predict(features, probabilities.view_mut());
+        MulticlassClassifier::compute_loss(probabilities.view(), labels, weights)
+    }
+
+    fn compute_early_stopping_metric_value(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+        train_options: &TrainOptions,
+    ) -> f32 {
+        let metric = &train_options
+            .early_stopping_options
+            .as_ref()
+            .unwrap()
+            .metric;
+        if !matches!(metric, EarlyStoppingMetric::Loss) {
+            let mut predictions = Array2::zeros((features.nrows(), self.biases.len()));
+            self.predict(features, predictions.view_mut());
+            return metric.compute_value_to_minimize(
+                EarlyStoppingMetricInput::MulticlassClassification {
+                    probabilities: predictions.view(),
+                    labels: labels.view(),
+                    weights: weights.view(),
+                },
+            );
+        }
+        let n_classes = self.biases.len();
+        let n_examples_per_chunk =
+            n_examples_per_early_stopping_chunk(features.nrows(), train_options);
+        pzip!(
+            features.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+            labels.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+            weights.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+        )
+        .fold(
+            || {
+                let predictions = unsafe {
+                    <Array2<f32>>::uninit((n_examples_per_chunk, n_classes)).assume_init()
+                };
+                let metric = CrossEntropy::default();
+                (predictions, metric)
+            },
+            |(mut predictions, mut metric), (features, labels, weights)| {
+                let slice = s![0..features.nrows(), ..];
+                let mut predictions_slice = predictions.slice_mut(slice);
+                self.predict(features, predictions_slice.view_mut());
+                for (prediction, label, weight) in zip!(
+                    predictions_slice.axis_iter(Axis(0)),
+                    labels.iter(),
+                    weights.iter()
+                ) {
+                    metric.update_weighted(
+                        CrossEntropyInput {
+                            probabilities: prediction,
+                            label: *label,
+                        },
+                        *weight,
+                    );
+                }
+                (predictions, metric)
+            },
+        )
+        .map(|(_, metric)| metric)
+        .reduce(CrossEntropy::new, |mut a, b| {
+            a.merge(b);
+            a
+        })
+        .finalize()
+        .0
+        .unwrap()
+    }
+}
+
 fn softmax(mut logits: ArrayViewMut2<f32>) {
     for mut logits in logits.axis_iter_mut(Axis(0)) {
         let max = logits.iter().fold(std::f32::MIN, |a, &b| f32::max(a, b));
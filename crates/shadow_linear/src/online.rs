@@ -0,0 +1,184 @@
+use super::{
+    compute_class_weighted_example_weights, compute_example_weights, optimizer::OptimizerState,
+    BinaryClassifier, LinearModel, MulticlassClassifier, Regressor, TrainOptions,
+};
+use ndarray::prelude::*;
+use shadow_table::prelude::*;
+use shadow_zip::zip;
+
+/// Trains a model one mini-batch at a time, for streaming data. Unlike `train_from`, the optimizer state is kept between calls to `partial_fit`, so adaptive optimizers like Adam continue where they left off. Each call to `partial_fit` counts as one epoch of `train_options.learning_rate_schedule`. Early stopping and shuffling are not applied. If the kill chip passed to `partial_fit` is activated, the rest of that call's mini-batches are skipped.
+pub struct OnlineTrainer<Model> {
+    model: Model,
+    train_options: TrainOptions,
+    optimizer_state: OptimizerState,
+    n_batches: usize,
+}
+
+impl<Model> OnlineTrainer<Model> {
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    pub fn into_model(self) -> Model {
+        self.model
+    }
+
+    /// The number of calls to `partial_fit` so far.
+    pub fn n_batches(&self) -> usize {
+        self.n_batches
+    }
+
+    fn start_batch(&mut self) {
+        self.optimizer_state.learning_rate = self
+            .train_options
+            .learning_rate_schedule
+            .compute_learning_rate(
+                self.train_options.learning_rate,
+                self.n_batches,
+                self.train_options.max_epochs,
+            );
+        self.n_batches += 1;
+    }
+}
+
+impl OnlineTrainer<Regressor> {
+    /// The model is trained with `train_options.regression_loss`, like in `Regressor::train_from`.
+    pub fn new(model: Regressor, train_options: &TrainOptions) -> OnlineTrainer<Regressor> {
+        let optimizer_state = model.new_optimizer_state(train_options);
+        OnlineTrainer {
+            model: Regressor {
+                loss: train_options.regression_loss.clone(),
+                ..model
+            },
+            train_options: train_options.clone(),
+            optimizer_state,
+            n_batches: 0,
+        }
+    }
+
+    pub fn partial_fit(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: NumberTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        kill_chip: &shadow_kill_chip::KillChip,
+    ) {
+        assert_eq!(self.model.weights.len(), features.ncols());
+        self.start_batch();
+        let weights = compute_example_weights(weights, labels.len());
+        let labels: ArrayView1<f32> = labels.as_slice().into();
+        let n_examples_per_batch = self.train_options.n_examples_per_batch;
+        for (features, labels, weights) in zip!(
+            features.axis_chunks_iter(Axis(0), n_examples_per_batch),
+            labels.axis_chunks_iter(Axis(0), n_examples_per_batch),
+            weights.axis_chunks_iter(Axis(0), n_examples_per_batch),
+        ) {
+            if kill_chip.is_activate() {
+                break;
+            }
+            self.model
+                .train_batch(features, labels, weights, &mut self.optimizer_state);
+        }
+    }
+}
+
+impl OnlineTrainer<BinaryClassifier> {
+    /// The model's calibrator is discarded, like in `BinaryClassifier::train_from`.
+    pub fn new(
+        model: BinaryClassifier,
+        train_options: &TrainOptions,
+    ) -> OnlineTrainer<BinaryClassifier> {
+        let optimizer_state = model.new_optimizer_state(train_options);
+        OnlineTrainer {
+            model: BinaryClassifier {
+                calibrator: None,
+                ..model
+            },
+            train_options: train_options.clone(),
+            optimizer_state,
+            n_batches: 0,
+        }
+    }
+
+    /// `ClassWeights::Balanced` weighs the classes by their frequencies in this mini-batch.
+    pub fn partial_fit(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        kill_chip: &shadow_kill_chip::KillChip,
+    ) {
+        assert_eq!(self.model.weights.len(), features.ncols());
+        self.start_batch();
+        let weights = compute_class_weighted_example_weights(
+            weights,
+            labels.as_slice().into(),
+            labels.variants().len(),
+            &self.train_options.class_weights,
+        );
+        let labels: ArrayView1<Option<_>> = labels.as_slice().into();
+        let n_examples_per_batch = self.train_options.n_examples_per_batch;
+        for (features, labels, weights) in zip!(
+            features.axis_chunks_iter(Axis(0), n_examples_per_batch),
+            labels.axis_chunks_iter(Axis(0), n_examples_per_batch),
+            weights.axis_chunks_iter(Axis(0), n_examples_per_batch),
+        ) {
+            if kill_chip.is_activate() {
+                break;
+            }
+            self.model
+                .train_batch(features, labels, weights, &mut self.optimizer_state);
+        }
+    }
+}
+
+impl OnlineTrainer<MulticlassClassifier> {
+    /// The model's calibrator is discarded, like in `MulticlassClassifier::train_from`.
+    pub fn new(
+        model: MulticlassClassifier,
+        train_options: &TrainOptions,
+    ) -> OnlineTrainer<MulticlassClassifier> {
+        let optimizer_state = model.new_optimizer_state(train_options);
+        OnlineTrainer {
+            model: MulticlassClassifier {
+                calibrator: None,
+                ..model
+            },
+            train_options: train_options.clone(),
+            optimizer_state,
+            n_batches: 0,
+        }
+    }
+
+    /// `ClassWeights::Balanced` weighs the classes by their frequencies in this mini-batch.
+    pub fn partial_fit(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        kill_chip: &shadow_kill_chip::KillChip,
+    ) {
+        let n_classes = labels.variants().len();
+        assert_eq!(self.model.weights.dim(), (features.ncols(), n_classes));
+        self.start_batch();
+        let weights = compute_class_weighted_example_weights(
+            weights,
+            labels.as_slice().into(),
+            n_classes,
+            &self.train_options.class_weights,
+        );
+        let labels: ArrayView1<Option<_>> = labels.as_slice().into();
+        let n_examples_per_batch = self.train_options.n_examples_per_batch;
+        for (features, labels, weights) in zip!(
+            features.axis_chunks_iter(Axis(0), n_examples_per_batch),
+            labels.axis_chunks_iter(Axis(0), n_examples_per_batch),
+            weights.axis_chunks_iter(Axis(0), n_examples_per_batch),
+        ) {
+            if kill_chip.is_activate() {
+                break;
+            }
+            self.model
+                .train_batch(features, labels, weights, &mut self.optimizer_state);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        AdamOptions, OnlineTrainer, Optimizer, Progress, RegressionLoss, Regressor,
+        RegressorTrainOutput, TrainOptions,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::NumberTableColumn;
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    fn train_output_with_examples(
+        train_options: &TrainOptions,
+        features: ArrayView2<f32>,
+        labels: Vec<f32>,
+    ) -> RegressorTrainOutput {
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        Regressor::train(
+            features,
+            labels.view(),
+            None,
+            None,
+            train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_partial_fit() {
+        let (features, labels) = features_and_labels(|features| {
+            2.0 * features[0] - features[1] + 0.5 * features[2] + 1.0
+        });
+        let labels = NumberTableColumn::new(None, labels);
+        let train_options = TrainOptions {
+            deterministic: true,
+            max_epochs: 1,
+            shuffle: false,
+            ..Default::default()
+        };
+        let zeros = Regressor {
+            bias: 0.0,
+            weights: Array::zeros(3),
+            means: vec![0.0; 3],
+            loss: RegressionLoss::SquaredError,
+        };
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        // A single call to `partial_fit` on every example is one epoch of training.
+        let mut trainer = OnlineTrainer::<Regressor>::new(zeros.clone(), &train_options);
+        trainer.partial_fit(features.view(), labels.view(), None, &kill_chip);
+        let trained = train_output_with_examples(
+            &train_options,
+            features.view(),
+            labels.view().as_slice().to_vec(),
+        )
+        .model;
+        assert_eq!(trainer.model().weights, trained.weights);
+        // Streaming mini-batches through Adam converges because its moments are kept between batches.
+        let mut trainer = OnlineTrainer::<Regressor>::new(
+            zeros.clone(),
+            &TrainOptions {
+                learning_rate: 0.01,
+                optimizer: Optimizer::Adam(AdamOptions::default()),
+                ..train_options.clone()
+            },
+        );
+        for _ in 0..100 {
+            for (features, labels) in features
+                .axis_chunks_iter(Axis(0), 100)
+                .zip(labels.view().as_slice().chunks(100))
+            {
+                let labels = NumberTableColumn::new(None, labels.to_vec());
+                trainer.partial_fit(features, labels.view(), None, &kill_chip);
+            }
+        }
+        assert_eq!(trainer.n_batches(), 1000);
+        let model = trainer.into_model();
+        for (weight, expected) in model.weights.iter().zip([2.0, -1.0, 0.5]) {
+            assert!(
+                (weight - expected).abs() < 0.1,
+                "{} != {}",
+                weight,
+                expected
+            );
+        }
+        // Once the kill chip is activated, `partial_fit` leaves the model unchanged.
+        kill_chip.activate();
+        let mut trainer = OnlineTrainer::<Regressor>::new(zeros.clone(), &train_options);
+        trainer.partial_fit(features.view(), labels.view(), None, &kill_chip);
+        assert_eq!(trainer.model().weights, zeros.weights);
+        assert_eq!(trainer.model().bias, zeros.bias);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::TrainOptions;
+use num::ToPrimitive;
+use shadow_zip::zip;
+
+#[derive(Clone, Debug)]
+pub enum Optimizer {
+    Sgd,
+    Momentum(MomentumOptions),
+    Adam(AdamOptions),
+    AdaGrad(AdaGradOptions),
+    /// FTRL-Proximal, which uses the scheduled learning rate as its `alpha`.
+    Ftrl(FtrlOptions),
+}
+
+#[derive(Clone, Debug)]
+pub struct MomentumOptions {
+    pub momentum: f32,
+}
+
+impl Default for MomentumOptions {
+    fn default() -> MomentumOptions {
+        MomentumOptions { momentum: 0.9 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AdamOptions {
+    pub beta_1: f32,
+    pub beta_2: f32,
+    pub epsilon: f32,
+}
+
+impl Default for AdamOptions {
+    fn default() -> AdamOptions {
+        AdamOptions {
+            beta_1: 0.9,
+            beta_2: 0.999,
+            epsilon: 1e-8,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AdaGradOptions {
+    pub epsilon: f32,
+}
+
+impl Default for AdaGradOptions {
+    fn default() -> AdaGradOptions {
+        AdaGradOptions { epsilon: 1e-8 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FtrlOptions {
+    pub beta: f32,
+}
+
+impl Default for FtrlOptions {
+    fn default() -> FtrlOptions {
+        FtrlOptions { beta: 1.0 }
+    }
+}
+
+/// The learning rate for each epoch is `TrainOptions::learning_rate` scaled by the schedule.
+#[derive(Clone, Debug)]
+pub enum LearningRateSchedule {
+    Constant,
+    /// Multiplies the learning rate by `gamma` every `step_size` epochs.
+    Step(StepLearningRateScheduleOptions),
+    /// Multiplies the learning rate by `gamma` every epoch.
+    Exponential(ExponentialLearningRateScheduleOptions),
+    /// Anneals the learning rate to `min_learning_rate` along a half cosine over `max_epochs`.
+    Cosine(CosineLearningRateScheduleOptions),
+    /// Increases the learning rate linearly over the first `n_warmup_epochs` epochs, then keeps it constant.
+    Warmup(WarmupLearningRateScheduleOptions),
+}
+
+#[derive(Clone, Debug)]
+pub struct StepLearningRateScheduleOptions {
+    pub step_size: usize,
+    pub gamma: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExponentialLearningRateScheduleOptions {
+    pub gamma: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct CosineLearningRateScheduleOptions {
+    pub min_learning_rate: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct WarmupLearningRateScheduleOptions {
+    pub n_warmup_epochs: usize,
+}
+
+impl LearningRateSchedule {
+    pub fn compute_learning_rate(
+        &self,
+        learning_rate: f32,
+        epoch: usize,
+        max_epochs: usize,
+    ) -> f32 {
+        let epoch_f32 = epoch.to_f32().unwrap();
+        match self {
+            LearningRateSchedule::Constant => learning_rate,
+            LearningRateSchedule::Step(options) => {
+                let n_steps = (epoch / options.step_size.max(1)).to_i32().unwrap();
+                learning_rate * options.gamma.powi(n_steps)
+            }
+            LearningRateSchedule::Exponential(options) => {
+                learning_rate * options.gamma.powf(epoch_f32)
+            }
+            LearningRateSchedule::Cosine(options) => {
+                let progress = if max_epochs > 1 {
+                    epoch_f32 / (max_epochs - 1).to_f32().unwrap()
+                } else {
+                    0.0
+                };
+                options.min_learning_rate
+                    + 0.5
+                        * (learning_rate - options.min_learning_rate)
+                        * (1.0 + (std::f32::consts::PI * progress).cos())
+            }
+            LearningRateSchedule::Warmup(options) => {
+                if epoch < options.n_warmup_epochs {
+                    learning_rate * (epoch_f32 + 1.0) / options.n_warmup_epochs.to_f32().unwrap()
+                } else {
+                    learning_rate
+                }
+            }
+        }
+    }
+}
+
+/// The state an optimizer keeps for each weight and bias of a model, along with the learning rate for the current epoch.
+#[derive(Clone)]
+pub(crate) struct OptimizerState {
+    pub learning_rate: f32,
+    optimizer: Optimizer,
+    weights: ParameterOptimizerState,
+    biases: ParameterOptimizerState,
+}
+
+impl OptimizerState {
+    pub fn new(train_options: &TrainOptions, n_weights: usize, n_biases: usize) -> OptimizerState {
+        OptimizerState {
+            learning_rate: train_options.learning_rate,
+            optimizer: train_options.optimizer.clone(),
+            weights: ParameterOptimizerState::new(
+                &train_options.optimizer,
+                n_weights,
+                train_options.l1_regularization,
+                train_options.l2_regularization,
+            ),
+            // The biases are not regularized.
+            biases: ParameterOptimizerState::new(&train_options.optimizer, n_biases, 0.0, 0.0),
+        }
+    }
+
+    pub fn update_weights<'a>(
+        &mut self,
+        weights: impl IntoIterator<Item = &'a mut f32>,
+        gradients: impl IntoIterator<Item = &'a f32>,
+    ) {
+        self.weights
+            .update(&self.optimizer, self.learning_rate, weights, gradients);
+    }
+
+    pub fn update_biases<'a>(
+        &mut self,
+        biases: impl IntoIterator<Item = &'a mut f32>,
+        gradients: impl IntoIterator<Item = &'a f32>,
+    ) {
+        self.biases
+            .update(&self.optimizer, self.learning_rate, biases, gradients);
+    }
+}
+
+#[derive(Clone)]
+struct ParameterOptimizerState {
+    l1_regularization: f32,
+    l2_regularization: f32,
+    n_steps: i32,
+    /// The momentum velocity, Adam first moment, or FTRL `z`.
+    first: Vec<f32>,
+    /// The Adam second moment, AdaGrad or FTRL sum of squared gradients.
+    second: Vec<f32>,
+}
+
+impl ParameterOptimizerState {
+    fn new(
+        optimizer: &Optimizer,
+        n_parameters: usize,
+        l1_regularization: f32,
+        l2_regularization: f32,
+    ) -> Self {
+        let (n_first, n_second) = match optimizer {
+            Optimizer::Sgd => (0, 0),
+            Optimizer::Momentum(_) => (n_parameters, 0),
+            Optimizer::AdaGrad(_) => (0, n_parameters),
+            Optimizer::Adam(_) | Optimizer::Ftrl(_) => (n_parameters, n_parameters),
+        };
+        Self {
+            l1_regularization,
+            l2_regularization,
+            n_steps: 0,
+            first: vec![0.0; n_first],
+            second: vec![0.0; n_second],
+        }
+    }
+
+    /// Takes a step along the gradient of the loss plus the L2 penalty, then applies the L1 penalty with its proximal operator, which sets weights that would cross zero to exactly zero.
+    fn update<'a>(
+        &mut self,
+        optimizer: &Optimizer,
+        learning_rate: f32,
+        parameters: impl IntoIterator<Item = &'a mut f32>,
+        gradients: impl IntoIterator<Item = &'a f32>,
+    ) {
+        self.n_steps = self.n_steps.saturating_add(1);
+        let l1_regularization = self.l1_regularization;
+        let l2_regularization = self.l2_regularization;
+        match optimizer {
+            Optimizer::Sgd => {
+                for (parameter, gradient) in zip!(parameters, gradients) {
+                    let gradient = gradient + l2_regularization * *parameter;
+                    *parameter -= learning_rate * gradient;
+                    shrink(parameter, learning_rate * l1_regularization);
+                }
+            }
+            Optimizer::Momentum(options) => {
+                for (parameter, gradient, velocity) in
+                    zip!(parameters, gradients, self.first.iter_mut())
+                {
+                    let gradient = gradient + l2_regularization * *parameter;
+                    *velocity = options.momentum * *velocity + gradient;
+                    *parameter -= learning_rate * *velocity;
+                    shrink(parameter, learning_rate * l1_regularization);
+                }
+            }
+            Optimizer::Adam(options) => {
+                let bias_correction_1 = 1.0 - options.beta_1.powi(self.n_steps);
+                let bias_correction_2 = 1.0 - options.beta_2.powi(self.n_steps);
+                for (parameter, gradient, m, v) in zip!(
+                    parameters,
+                    gradients,
+                    self.first.iter_mut(),
+                    self.second.iter_mut()
+                ) {
+                    let gradient = gradient + l2_regularization * *parameter;
+                    *m = options.beta_1 * *m + (1.0 - options.beta_1) * gradient;
+                    *v = options.beta_2 * *v + (1.0 - options.beta_2) * gradient * gradient;
+                    let m_hat = *m / bias_correction_1;
+                    let v_hat = *v / bias_correction_2;
+                    let step_size = learning_rate / (v_hat.sqrt() + options.epsilon);
+                    *parameter -= step_size * m_hat;
+                    shrink(parameter, step_size * l1_regularization);
+                }
+            }
+            Optimizer::AdaGrad(options) => {
+                for (parameter, gradient, sum_squared_gradients) in
+                    zip!(parameters, gradients, self.second.iter_mut())
+                {
+                    let gradient = gradient + l2_regularization * *parameter;
+                    *sum_squared_gradients += gradient * gradient;
+                    let step_size =
+                        learning_rate / (sum_squared_gradients.sqrt() + options.epsilon);
+                    *parameter -= step_size * gradient;
+                    shrink(parameter, step_size * l1_regularization);
+                }
+            }
+            Optimizer::Ftrl(options) => {
+                // FTRL-Proximal applies the penalties in closed form when computing each weight from `z` and `n`. Because `z` accumulates the gradients of every step, the penalties are scaled by the number of steps so they have the same strength relative to the loss as with the other optimizers.
+                let n_steps = self.n_steps.to_f32().unwrap();
+                let l1_regularization = l1_regularization * n_steps;
+                let l2_regularization = l2_regularization * n_steps;
+                for (parameter, gradient, z, n) in zip!(
+                    parameters,
+                    gradients,
+                    self.first.iter_mut(),
+                    self.second.iter_mut()
+                ) {
+                    let sigma = ((*n + gradient * gradient).sqrt() - n.sqrt()) / learning_rate;
+                    *z += gradient - sigma * *parameter;
+                    *n += gradient * gradient;
+                    *parameter = if z.abs() <= l1_regularization {
+                        0.0
+                    } else {
+                        -(*z - z.signum() * l1_regularization)
+                            / ((options.beta + n.sqrt()) / learning_rate + l2_regularization)
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// The proximal operator of the L1 penalty, also known as soft thresholding.
+fn shrink(parameter: &mut f32, threshold: f32) {
+    *parameter = parameter.signum() * f32::max(parameter.abs() - threshold, 0.0);
+}
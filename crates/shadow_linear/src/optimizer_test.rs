@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        AdaGradOptions, AdamOptions, CosineLearningRateScheduleOptions, FtrlOptions,
+        LearningRateSchedule, MomentumOptions, Optimizer, Progress, Regressor,
+        RegressorTrainOutput, StepLearningRateScheduleOptions, TrainOptions,
+        WarmupLearningRateScheduleOptions,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::NumberTableColumn;
+
+    /// Trains a regressor on 1000 examples whose labels are a linear function of 3 features drawn uniformly from [0, 1).
+    fn train_output(train_options: &TrainOptions) -> RegressorTrainOutput {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((1000, 3), |_| rng.gen::<f32>());
+        let labels = features
+            .axis_iter(Axis(0))
+            .map(|features| 2.0 * features[0] - features[1] + 0.5 * features[2] + 1.0)
+            .collect();
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        Regressor::train(
+            features.view(),
+            labels.view(),
+            None,
+            None,
+            train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+    }
+
+    fn train(train_options: &TrainOptions) -> Regressor {
+        train_output(train_options).model
+    }
+
+    #[test]
+    fn test_optimizers_converge() {
+        let optimizers = vec![
+            (Optimizer::Sgd, 0.1),
+            (Optimizer::Momentum(MomentumOptions::default()), 0.01),
+            (Optimizer::Adam(AdamOptions::default()), 0.01),
+            (Optimizer::AdaGrad(AdaGradOptions::default()), 0.1),
+            (Optimizer::Ftrl(FtrlOptions::default()), 0.1),
+        ];
+        for (optimizer, learning_rate) in optimizers {
+            let model = train(&TrainOptions {
+                deterministic: true,
+                learning_rate,
+                optimizer: optimizer.clone(),
+                ..Default::default()
+            });
+            for (weight, expected) in model.weights.iter().zip([2.0, -1.0, 0.5]) {
+                assert!(
+                    (weight - expected).abs() < 0.1,
+                    "{:?}: {} != {}",
+                    optimizer,
+                    weight,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_learning_rate_schedules() {
+        let step = LearningRateSchedule::Step(StepLearningRateScheduleOptions {
+            step_size: 10,
+            gamma: 0.5,
+        });
+        assert_eq!(step.compute_learning_rate(1.0, 9, 100), 1.0);
+        assert_eq!(step.compute_learning_rate(1.0, 25, 100), 0.25);
+        let cosine = LearningRateSchedule::Cosine(CosineLearningRateScheduleOptions {
+            min_learning_rate: 0.0,
+        });
+        assert_eq!(cosine.compute_learning_rate(1.0, 0, 11), 1.0);
+        assert!((cosine.compute_learning_rate(1.0, 5, 11) - 0.5).abs() < 1e-6);
+        assert!(cosine.compute_learning_rate(1.0, 10, 11).abs() < 1e-6);
+        let warmup =
+            LearningRateSchedule::Warmup(WarmupLearningRateScheduleOptions { n_warmup_epochs: 4 });
+        assert_eq!(warmup.compute_learning_rate(1.0, 0, 100), 0.25);
+        assert_eq!(warmup.compute_learning_rate(1.0, 10, 100), 1.0);
+    }
+
+    #[test]
+    fn test_regularization() {
+        let unregularized = train(&TrainOptions {
+            deterministic: true,
+            ..Default::default()
+        });
+        let l2 = train(&TrainOptions {
+            deterministic: true,
+            l2_regularization: 0.1,
+            ..Default::default()
+        });
+        let norm = |model: &Regressor| model.weights.dot(&model.weights);
+        assert!(norm(&l2) < norm(&unregularized));
+        // A strong L1 penalty drives the weight with the smallest effect to exactly zero.
+        for optimizer in [Optimizer::Sgd, Optimizer::Ftrl(FtrlOptions::default())] {
+            let output = train_output(&TrainOptions {
+                deterministic: true,
+                l1_regularization: 0.06,
+                l2_regularization: 0.01,
+                optimizer,
+                ..Default::default()
+            });
+            assert_eq!(output.model.weights[2], 0.0);
+            assert_eq!(output.n_zero_weights, 1);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use super::{
+    compute_class_weighted_example_weights,
+    early_stopping::{EarlyStoppingMetricInput, Task},
+    optimizer::OptimizerState,
+    shap::{compute_shap_values_for_example, ComputeShapValuesForExampleOutput},
+    train_linear_model, EarlyStoppingMetric, EarlyStoppingValidation, Examples, LinearModel,
+    LinearModelTrainOutput, Progress, TrainOptions,
+};
+use ndarray::prelude::*;
+use num::{clamp, ToPrimitive};
+use shadow_metrics::Mean;
+use shadow_table::prelude::*;
+use shadow_zip::zip;
+use std::num::NonZeroUsize;
+
+/// An ordinal logistic regression model for labels whose variants are ordered, from lowest to highest. The model computes a score `weights · features` and predicts that the label is at most the `k`th variant with probability `sigmoid(thresholds[k] - score)`, so higher scores predict higher variants.
+#[derive(Clone, Debug)]
+pub struct OrdinalClassifier {
+    /// The increasing thresholds between consecutive classes, one fewer than the number of classes.
+    pub thresholds: Array1<f32>,
+    pub weights: Array1<f32>,
+    pub means: Vec<f32>,
+}
+
+pub struct OrdinalClassifierTrainOutput {
+    pub model: OrdinalClassifier,
+    pub losses: Option<Vec<f32>>,
+    pub feature_importances: Option<Vec<f32>>,
+    pub n_zero_weights: usize,
+    /// The epoch whose model was returned, if early stopping was enabled.
+    pub best_epoch: Option<usize>,
+}
+
+/// The smallest gap kept between consecutive thresholds, so every class has a nonzero probability.
+const MIN_THRESHOLD_GAP: f32 = 1e-3;
+
+impl OrdinalClassifier {
+    /// The order of the classes is the order of the label's variants. If `weights` is given, each example's contribution to the loss is multiplied by its weight and the weight of its class in `train_options.class_weights`.
+    pub fn train(
+        features: ArrayView2<f32>,
+        labels: EnumTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<OrdinalClassifierTrainOutput> {
+        let n_classes = labels.variants().len();
+        let n_features = features.ncols();
+        let example_weights = compute_class_weighted_example_weights(
+            weights,
+            labels.as_slice().into(),
+            n_classes,
+            &train_options.class_weights,
+        );
+        let LinearModelTrainOutput {
+            model,
+            losses,
+            best_epoch,
+        } = train_linear_model(
+            Task::MulticlassClassification,
+            Examples {
+                features: features.view(),
+                labels: labels.as_slice().into(),
+                weights: example_weights.view(),
+            },
+            validation,
+            |validation| {
+                let validation_examples = validation.compute_examples();
+                let labels = validation_examples.enum_labels().to_owned();
+                let weights = compute_class_weighted_example_weights(
+                    None,
+                    labels.view(),
+                    n_classes,
+                    &train_options.class_weights,
+                );
+                Ok((validation_examples.features, labels, weights))
+            },
+            |train_examples| {
+                let means = train_examples
+                    .features
+                    .axis_iter(Axis(1))
+                    .map(|column| column.mean().unwrap())
+                    .collect();
+                OrdinalClassifier {
+                    thresholds: compute_initial_thresholds(
+                        train_examples.labels,
+                        train_examples.weights,
+                        n_classes,
+                    ),
+                    weights: <Array1<f32>>::zeros(n_features),
+                    means,
+                }
+            },
+            train_options,
+            progress,
+        )?;
+        let mut feature_importances = model
+            .weights
+            .iter()
+            .map(|weight| weight.abs())
+            .collect::<Vec<_>>();
+        let feature_importances_sum = feature_importances.iter().sum::<f32>();
+        feature_importances
+            .iter_mut()
+            .for_each(|feature_importance| *feature_importance /= feature_importances_sum);
+        let n_zero_weights = model
+            .weights
+            .iter()
+            .filter(|weight| **weight == 0.0)
+            .count();
+        Ok(OrdinalClassifierTrainOutput {
+            model,
+            losses,
+            feature_importances: Some(feature_importances),
+            n_zero_weights,
+            best_epoch,
+        })
+    }
+
+    /// The probability that the label is at most the class at `class_index`.
+    fn compute_cumulative_probability(&self, class_index: usize, score: f32) -> f32 {
+        match self.thresholds.get(class_index) {
+            Some(threshold) => 1.0 / (1.0 + (score - threshold).exp()),
+            None => 1.0,
+        }
+    }
+
+    /// Computes the weighted mean negative log likelihood of the labels.
+    pub fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        let mut probabilities = Array2::zeros((features.nrows(), self.thresholds.len() + 1));
+        self.predict(features, probabilities.view_mut());
+        let mut loss = Mean::new();
+        for (probabilities, label, weight) in zip!(
+            probabilities.axis_iter(Axis(0)),
+            labels.iter(),
+            weights.iter()
+        ) {
+            let probability = probabilities[label.unwrap().get() - 1];
+            loss.update_weighted(-clamp(probability, f32::EPSILON, 1.0).ln(), *weight);
+        }
+        loss.finalize().unwrap_or(0.0)
+    }
+
+    pub fn predict(&self, features: ArrayView2<f32>, mut probabilities: ArrayViewMut2<f32>) {
+        let scores = features.dot(&self.weights);
+        for (mut probabilities, score) in zip!(probabilities.axis_iter_mut(Axis(0)), scores.iter())
+        {
+            let mut lower = 0.0;
+            for (class_index, probability) in probabilities.iter_mut().enumerate() {
+                let upper = self.compute_cumulative_probability(class_index, *score);
+                *probability = upper - lower;
+                lower = upper;
+            }
+        }
+    }
+
+    /// The contributions are to the score, so features with positive contributions push the prediction toward higher classes.
+    pub fn compute_feature_contributions(
+        &self,
+        features: ArrayView2<f32>,
+    ) -> Vec<ComputeShapValuesForExampleOutput> {
+        features
+            .axis_iter(Axis(0))
+            .map(|features| {
+                compute_shap_values_for_example(
+                    features.as_slice().unwrap(),
+                    0.0,
+                    self.weights.view(),
+                    &self.means,
+                )
+            })
+            .collect()
+    }
+
+    pub fn from_reader(
+        ordinal_classifier: crate::serialize::OrdinalClassifierReader,
+    ) -> OrdinalClassifier {
+        crate::serialize::deserialize_ordinal_classifier(ordinal_classifier)
+    }
+
+    pub fn to_writer(
+        &self,
+        writer: &mut buffalo::Writer,
+    ) -> buffalo::Position<crate::serialize::OrdinalClassifierWriter> {
+        crate::serialize::serialize_ordinal_classifier(self, writer)
+    }
+
+    #[must_use]
+    pub fn from_bytes(&self, bytes: &[u8]) -> OrdinalClassifier {
+        let reader = buffalo::read::<crate::serialize::OrdinalClassifierReader>(bytes);
+        Self::from_reader(reader)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = buffalo::Writer::new();
+        let position = self.to_writer(&mut writer);
+        writer.write(&position);
+        writer.into_bytes()
+    }
+}
+
+/// Places the thresholds where a model with zero weights predicts the frequency of each class in the training labels.
+impl LinearModel for OrdinalClassifier {
+    type Label = Option<NonZeroUsize>;
+    type LabelDim = Ix1;
+
+    const TRAIN_BATCHES_IN_PARALLEL: bool = false;
+
+    fn new_optimizer_state(&self, train_options: &TrainOptions) -> OptimizerState {
+        OptimizerState::new(train_options, self.weights.len(), self.thresholds.len())
+    }
+
+    fn train_batch(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+        optimizer_state: &mut OptimizerState,
+    ) {
+        let n_examples = features.nrows().to_f32().unwrap();
+        let scores = features.dot(&self.weights);
+        let mut score_gradients = Array1::<f32>::zeros(features.nrows());
+        let mut threshold_gradients = Array1::<f32>::zeros(self.thresholds.len());
+        for (score_gradient, score, label, weight) in zip!(
+            score_gradients.iter_mut(),
+            scores.iter(),
+            labels.iter(),
+            weights.iter()
+        ) {
+            let class_index = label.unwrap().get() - 1;
+            // The probability of the class is the difference of the cumulative probabilities at its upper and lower thresholds.
+            let upper = self.compute_cumulative_probability(class_index, *score);
+            let lower = class_index
+                .checked_sub(1)
+                .map(|index| self.compute_cumulative_probability(index, *score))
+                .unwrap_or(0.0);
+            let probability = (upper - lower).max(f32::EPSILON);
+            let upper_derivative = upper * (1.0 - upper);
+            let lower_derivative = lower * (1.0 - lower);
+            *score_gradient = weight * (upper_derivative - lower_derivative) / probability;
+            if class_index < self.thresholds.len() {
+                threshold_gradients[class_index] -=
+                    weight * upper_derivative / probability / n_examples;
+            }
+            if class_index > 0 {
+                threshold_gradients[class_index - 1] +=
+                    weight * lower_derivative / probability / n_examples;
+            }
+        }
+        let score_gradients = score_gradients.insert_axis(Axis(1));
+        let weight_gradients = (&features * &score_gradients).mean_axis(Axis(0)).unwrap();
+        optimizer_state.update_weights(self.weights.iter_mut(), weight_gradients.iter());
+        optimizer_state.update_biases(self.thresholds.iter_mut(), threshold_gradients.iter());
+        for index in 1..self.thresholds.len() {
+            self.thresholds[index] =
+                self.thresholds[index].max(self.thresholds[index - 1] + MIN_THRESHOLD_GAP);
+        }
+    }
+
+    fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        OrdinalClassifier::compute_loss(self, features, labels, weights)
+    }
+
+    fn compute_early_stopping_metric_value(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<Option<NonZeroUsize>>,
+        weights: ArrayView1<f32>,
+        train_options: &TrainOptions,
+    ) -> f32 {
+        let metric = &train_options
+            .early_stopping_options
+            .as_ref()
+            .unwrap()
+            .metric;
+        if let EarlyStoppingMetric::Loss = metric {
+            return OrdinalClassifier::compute_loss(self, features, labels, weights);
+        }
+        let mut probabilities = Array2::zeros((features.nrows(), self.thresholds.len() + 1));
+        self.predict(features, probabilities.view_mut());
+        metric.compute_value_to_minimize(EarlyStoppingMetricInput::MulticlassClassification {
+            probabilities: probabilities.view(),
+            labels: labels.view(),
+            weights: weights.view(),
+        })
+    }
+}
+
+fn compute_initial_thresholds(
+    labels: ArrayView1<Option<NonZeroUsize>>,
+    weights: ArrayView1<f32>,
+    n_classes: usize,
+) -> Array1<f32> {
+    let mut class_weights = vec![0.0; n_classes];
+    for (label, weight) in zip!(labels.iter(), weights.iter()) {
+        class_weights[label.unwrap().get() - 1] += weight;
+    }
+    let total_weight = class_weights.iter().sum::<f32>().max(f32::EPSILON);
+    let mut cumulative_weight = 0.0;
+    let mut thresholds = Array1::zeros(n_classes.saturating_sub(1));
+    for (threshold, class_weight) in zip!(thresholds.iter_mut(), class_weights.iter()) {
+        cumulative_weight += class_weight;
+        let cumulative_probability = clamp(cumulative_weight / total_weight, 1e-3, 1.0 - 1e-3);
+        *threshold = (cumulative_probability / (1.0 - cumulative_probability)).ln();
+    }
+    for index in 1..thresholds.len() {
+        thresholds[index] = f32::max(thresholds[index], thresholds[index - 1] + MIN_THRESHOLD_GAP);
+    }
+    thresholds
+}
@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod test {
+    use crate::{OrdinalClassifier, Progress, TrainOptions};
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_metrics::{OrdinalClassificationMetrics, OrdinalClassificationMetricsInput};
+    use shadow_table::EnumTableColumn;
+    use std::num::NonZeroUsize;
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    #[test]
+    fn test_ordinal_classifier() {
+        // The classes are ordered bins of the first feature.
+        let (features, _) = features_and_labels(|_| 0.0);
+        let labels: Vec<_> = features
+            .column(0)
+            .iter()
+            .map(|feature| NonZeroUsize::new(1 + (*feature * 3.0) as usize))
+            .collect();
+        let labels = EnumTableColumn::new(
+            None,
+            vec!["low".to_owned(), "medium".to_owned(), "high".to_owned()],
+            labels,
+        );
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let model = OrdinalClassifier::train(
+            features.view(),
+            labels.view(),
+            None,
+            None,
+            &TrainOptions {
+                deterministic: true,
+                learning_rate: 0.5,
+                ..Default::default()
+            },
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+        .model;
+        assert!(
+            model.weights[0] > 10.0 * model.weights[1].abs(),
+            "{:?}",
+            model
+        );
+        assert!(model.thresholds[0] < model.thresholds[1]);
+        let mut probabilities = Array::zeros((features.nrows(), 3));
+        model.predict(features.view(), probabilities.view_mut());
+        for probabilities in probabilities.axis_iter(Axis(0)) {
+            assert!((probabilities.sum() - 1.0).abs() < 1e-5);
+        }
+        let mut metrics = OrdinalClassificationMetrics::new(3);
+        metrics.update(OrdinalClassificationMetricsInput {
+            probabilities: probabilities.view(),
+            labels: labels.view().as_slice().into(),
+        });
+        let metrics = metrics.finalize();
+        assert!(metrics.accuracy > 0.9, "{:?}", metrics);
+        assert!(metrics.quadratic_weighted_kappa > 0.9, "{:?}", metrics);
+        let deserialized = model.from_bytes(&model.to_bytes());
+        assert_eq!(deserialized.thresholds, model.thresholds);
+        assert_eq!(deserialized.weights, model.weights);
+    }
+}
@@ -1,14 +1,15 @@
-use crate::Progress;
-
 use super::{
+    compute_example_weights,
+    early_stopping::{EarlyStoppingMetricInput, Task},
+    n_examples_per_early_stopping_chunk,
+    optimizer::OptimizerState,
     shap::{compute_shap_values_for_example, ComputeShapValuesForExampleOutput},
-    train_early_stopping_split, EarlyStoppingMonitor, TrainOptions, TrainProgressEvent,
+    train_linear_model, EarlyStoppingMetric, EarlyStoppingValidation, Examples, LinearModel,
+    LinearModelTrainOutput, Progress, RegressionLoss, TrainOptions,
 };
 use ndarray::{self, prelude::*};
-use num::ToPrimitive;
 use rayon::{self, prelude::*};
-use shadow_metrics::MeanSquaredError;
-use shadow_progress_counter::ProgressCounter;
+use shadow_metrics::Mean;
 use shadow_table::prelude::*;
 use shadow_zip::{pzip, zip};
 
@@ -17,112 +18,125 @@ pub struct Regressor {
     pub bias: f32,
     pub weights: Array1<f32>,
     pub means: Vec<f32>,
+    pub loss: RegressionLoss,
 }
 
 pub struct RegressorTrainOutput {
     pub model: Regressor,
     pub losses: Option<Vec<f32>>,
     pub feature_importances: Option<Vec<f32>>,
+    pub n_zero_weights: usize,
+    /// The epoch whose model was returned, if early stopping was enabled.
+    pub best_epoch: Option<usize>,
 }
 
 impl Regressor {
+    /// If `weights` is given, each example's contribution to the loss is multiplied by its weight.
     pub fn train(
         features: ArrayView2<f32>,
         labels: NumberTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
         train_options: &TrainOptions,
         progress: Progress,
-    ) -> RegressorTrainOutput {
-        let n_features = features.ncols();
-        let (features_train, labels_train, features_early_stopping, labels_early_stopping) =
-            train_early_stopping_split(
-                features,
-                labels.as_slice().into(),
-                train_options
-                    .early_stopping_options
-                    .as_ref()
-                    .map(|o| o.early_stopping_fraction)
-                    .unwrap_or(0.0),
-            );
-        let means = features_train
-            .axis_iter(Axis(1))
-            .map(|column| column.mean().unwrap())
-            .collect();
-        let mut model = Regressor {
-            bias: 0.0,
-            weights: <Array1<f32>>::zeros(n_features),
-            means,
-        };
-        let mut early_stopping_monitor =
-            train_options
-                .early_stopping_options
-                .as_ref()
-                .map(|early_stopping_options| {
-                    EarlyStoppingMonitor::new(
-                        early_stopping_options.min_decrease_in_loss_for_significant_change,
-                        early_stopping_options.n_rounds_without_improvement_to_stop,
-                    )
-                });
-        let progress_counter = ProgressCounter::new(train_options.max_epochs.to_u64().unwrap());
-        (progress.handle_progress_event)(TrainProgressEvent::Train(progress_counter.clone()));
-        let mut predictions_buffer: Array1<f32> = Array1::zeros(labels.len());
-        let mut losses = if train_options.compute_losses {
-            Some(Vec::new())
-        } else {
-            None
+    ) -> anyhow::Result<RegressorTrainOutput> {
+        Regressor::train_with_initial_model(
+            None,
+            features,
+            labels,
+            weights,
+            validation,
+            train_options,
+            progress,
+        )
+    }
+
+    /// Continues training `model`, starting from its weights and bias instead of zeros. The model keeps its means and is trained with `train_options.regression_loss`, which replaces its loss.
+    pub fn train_from(
+        model: Regressor,
+        features: ArrayView2<f32>,
+        labels: NumberTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<RegressorTrainOutput> {
+        assert_eq!(model.weights.len(), features.ncols());
+        let model = Regressor {
+            loss: train_options.regression_loss.clone(),
+            ..model
         };
-        let kill_chip = progress.kill_chip;
-        for _ in 0..train_options.max_epochs {
-            progress_counter.inc(1);
-            let n_examples_per_batch = train_options.n_examples_per_batch;
-            struct RegressorPtr(*mut Regressor);
-            unsafe impl Send for RegressorPtr {}
-            unsafe impl Sync for RegressorPtr {}
-            let model_ptr = RegressorPtr(&mut model);
-            pzip!(
-                features_train.axis_chunks_iter(Axis(0), n_examples_per_batch),
-                labels_train.axis_chunks_iter(Axis(0), n_examples_per_batch),
-                predictions_buffer.axis_chunks_iter_mut(Axis(0), n_examples_per_batch),
-            )
-            .for_each(|(features, labels, predictions)| {
-                let model_ptr = &model_ptr;
-                let model = unsafe { &mut *model_ptr.0 };
-                Regressor::train_batch(
-                    model,
-                    features,
-                    labels,
-                    predictions,
-                    train_options,
-                    kill_chip,
-                );
-            });
-            if let Some(losses) = &mut losses {
-                let loss = Regressor::compute_loss(predictions_buffer.view(), labels_train);
-                losses.push(loss);
-            }
-            if let Some(early_stopping_monitor) = early_stopping_monitor.as_mut() {
-                let early_stopping_metric_value = Regressor::compute_early_stopping_metric_value(
-                    &model,
-                    features_early_stopping,
-                    labels_early_stopping,
-                    train_options,
-                );
-                let should_stop = early_stopping_monitor.update(early_stopping_metric_value);
-                if should_stop {
-                    break;
-                }
-            }
+        Regressor::train_with_initial_model(
+            Some(model),
+            features,
+            labels,
+            weights,
+            validation,
+            train_options,
+            progress,
+        )
+    }
 
-            if progress.kill_chip.is_activate() {
-                break;
-            }
-        }
-        (progress.handle_progress_event)(TrainProgressEvent::TrainDone);
+    fn train_with_initial_model(
+        initial_model: Option<Regressor>,
+        features: ArrayView2<f32>,
+        labels: NumberTableColumnView,
+        weights: Option<ArrayView1<f32>>,
+        validation: Option<EarlyStoppingValidation>,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> anyhow::Result<RegressorTrainOutput> {
+        let n_features = features.ncols();
+        let example_weights = compute_example_weights(weights, labels.len());
+        let LinearModelTrainOutput {
+            model,
+            losses,
+            best_epoch,
+        } = train_linear_model(
+            Task::Regression,
+            Examples {
+                features: features.view(),
+                labels: labels.as_slice().into(),
+                weights: example_weights.view(),
+            },
+            validation,
+            |validation| {
+                let validation_examples = validation.compute_examples();
+                let labels = validation_examples.number_labels().to_owned();
+                let weights = compute_example_weights(None, labels.len());
+                Ok((validation_examples.features, labels, weights))
+            },
+            |train_examples| {
+                initial_model.unwrap_or_else(|| {
+                    let means = train_examples
+                        .features
+                        .axis_iter(Axis(1))
+                        .map(|column| column.mean().unwrap())
+                        .collect();
+                    Regressor {
+                        bias: 0.0,
+                        weights: <Array1<f32>>::zeros(n_features),
+                        means,
+                        loss: train_options.regression_loss.clone(),
+                    }
+                })
+            },
+            train_options,
+            progress,
+        )?;
         let feature_importances = Regressor::compute_feature_importances(&model);
-        RegressorTrainOutput {
+        let n_zero_weights = model
+            .weights
+            .iter()
+            .filter(|weight| **weight == 0.0)
+            .count();
+        Ok(RegressorTrainOutput {
             model,
             losses,
             feature_importances: Some(feature_importances),
-        }
+            n_zero_weights,
+            best_epoch,
+        })
     }
 
     fn compute_feature_importances(model: &Regressor) -> Vec<f32> {
@@ -139,81 +153,18 @@ impl Regressor {
         feature_importances
     }
 
-    fn train_batch(
-        &mut self,
-        features: ArrayView2<f32>,
-        labels: ArrayView1<f32>,
-        mut predictions: ArrayViewMut1<f32>,
-        train_options: &TrainOptions,
-        kill_chip: &shadow_kill_chip::KillChip,
-    ) {
-        if kill_chip.is_activate() {
-            return;
-        }
-        let learning_rate = train_options.learning_rate;
-        let p = features.dot(&self.weights) + self.bias;
-        for (prediction, p) in zip!(predictions.iter_mut(), p.iter()) {
-            *prediction = *p;
-        }
-        let py = (p - labels).insert_axis(Axis(1));
-        let weight_gradients = (&features * &py).mean_axis(Axis(0)).unwrap();
-        let bias_gradient = py.mean_axis(Axis(0)).unwrap()[0];
-        for (weight, weight_gradient) in zip!(self.weights.iter_mut(), weight_gradients.iter()) {
-            *weight += -learning_rate * weight_gradient;
-        }
-        self.bias += -learning_rate * bias_gradient;
-    }
-
-    fn compute_loss(predictions: ArrayView1<f32>, labels: ArrayView1<f32>) -> f32 {
-        let mut loss = 0.0;
-        for (label, prediction) in zip!(labels, predictions.iter()) {
-            loss += 0.5 * (label - prediction) * (label - prediction)
-        }
-        loss / labels.len().to_f32().unwrap()
-    }
-
-    fn compute_early_stopping_metric_value(
-        &self,
-        features: ArrayView2<f32>,
-        labels: ArrayView1<f32>,
-        train_options: &TrainOptions,
-    ) -> f32 {
-        pzip!(
-            features.axis_chunks_iter(Axis(0), train_options.n_examples_per_batch),
-            labels.axis_chunks_iter(Axis(0), train_options.n_examples_per_batch),
-        )
-        .fold(
-            || {
-                let predictions = unsafe {
-                    <Array1<f32>>::uninit(train_options.n_examples_per_batch).assume_init()
-                };
-                let metric = MeanSquaredError::new();
-                (predictions, metric)
-            },
-            |(mut predictions, mut metric), (features, labels)| {
-                let slice = s![0..features.nrows()];
-                let mut predictions_slice = predictions.slice_mut(slice);
-                self.predict(features, predictions_slice.view_mut());
-                for (prediction, label) in zip!(predictions_slice.iter(), labels.iter()) {
-                    metric.update((*prediction, *label));
-                }
-                (predictions, metric)
-            },
-        )
-        .map(|(_, metric)| metric)
-        .reduce(MeanSquaredError::new, |mut a, b| {
-            a.merge(b);
-            a
-        })
-        .finalize()
-        .unwrap()
+    pub fn predict(&self, features: ArrayView2<f32>, mut predictions: ArrayViewMut1<f32>) {
+        self.compute_outputs(features, predictions.view_mut());
+        predictions.mapv_inplace(|output| self.loss.compute_prediction(output));
     }
 
-    pub fn predict(&self, features: ArrayView2<f32>, mut predictions: ArrayViewMut1<f32>) {
-        predictions.fill(self.bias);
-        ndarray::linalg::general_mat_vec_mul(1.0, &features, &self.weights, 1.0, &mut predictions);
+    /// Computes the linear combination of the features plus the bias, before the link function is applied.
+    fn compute_outputs(&self, features: ArrayView2<f32>, mut outputs: ArrayViewMut1<f32>) {
+        outputs.fill(self.bias);
+        ndarray::linalg::general_mat_vec_mul(1.0, &features, &self.weights, 1.0, &mut outputs);
     }
 
+    /// With the Poisson and Tweedie losses, the contributions sum to the log of the prediction.
     pub fn compute_feature_contributions(
         &self,
         features: ArrayView2<f32>,
@@ -231,7 +182,9 @@ impl Regressor {
             .collect()
     }
 
-    pub fn from_reader(regressor: crate::serialize::RegressorReader) -> Regressor {
+    pub fn from_reader(
+        regressor: crate::serialize::VersionedReader<crate::serialize::RegressorReader>,
+    ) -> Regressor {
         crate::serialize::deserialize_regressor(regressor)
     }
 
@@ -244,13 +197,121 @@ impl Regressor {
 
     #[must_use]
     pub fn from_bytes(&self, bytes: &[u8]) -> Regressor {
-        let reader = buffalo::read::<crate::serialize::RegressorReader>(bytes);
+        let reader =
+            buffalo::read::<crate::serialize::Versioned<crate::serialize::RegressorReader>>(bytes);
         Self::from_reader(reader)
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut writer = buffalo::Writer::new();
-        self.to_writer(&mut writer);
+        let position = self.to_writer(&mut writer);
+        writer.write(&position);
         writer.into_bytes()
     }
 }
+
+impl LinearModel for Regressor {
+    type Label = f32;
+    type LabelDim = Ix1;
+
+    const TRAIN_BATCHES_IN_PARALLEL: bool = true;
+
+    fn new_optimizer_state(&self, train_options: &TrainOptions) -> OptimizerState {
+        OptimizerState::new(train_options, self.weights.len(), 1)
+    }
+
+    fn train_batch(
+        &mut self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+        optimizer_state: &mut OptimizerState,
+    ) {
+        let outputs = features.dot(&self.weights) + self.bias;
+        let py = zip!(outputs.iter(), labels.iter(), weights.iter())
+            .map(|(output, label, weight)| weight * self.loss.compute_gradient(*output, *label))
+            .collect::<Array1<f32>>()
+            .insert_axis(Axis(1));
+        let weight_gradients = (&features * &py).mean_axis(Axis(0)).unwrap();
+        let bias_gradient = py.mean_axis(Axis(0)).unwrap()[0];
+        optimizer_state.update_weights(self.weights.iter_mut(), weight_gradients.iter());
+        optimizer_state.update_biases(
+            std::iter::once(&mut self.bias),
+            std::iter::once(&bias_gradient),
+        );
+    }
+
+    fn compute_loss(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+    ) -> f32 {
+        let mut outputs = Array1::zeros(features.nrows());
+        self.compute_outputs(features, outputs.view_mut());
+        let mut loss = 0.0;
+        for (label, output, weight) in zip!(labels, outputs.iter(), weights.iter()) {
+            loss += weight * self.loss.compute_loss(*output, *label);
+        }
+        loss / weights.sum()
+    }
+
+    fn compute_early_stopping_metric_value(
+        &self,
+        features: ArrayView2<f32>,
+        labels: ArrayView1<f32>,
+        weights: ArrayView1<f32>,
+        train_options: &TrainOptions,
+    ) -> f32 {
+        let metric = &train_options
+            .early_stopping_options
+            .as_ref()
+            .unwrap()
+            .metric;
+        if !matches!(metric, EarlyStoppingMetric::Loss) {
+            let mut predictions = Array1::zeros(features.nrows());
+            self.predict(features, predictions.view_mut());
+            return metric.compute_value_to_minimize(EarlyStoppingMetricInput::Regression {
+                predictions: predictions.view(),
+                labels: labels.view(),
+                weights: weights.view(),
+            });
+        }
+        let n_examples_per_chunk =
+            n_examples_per_early_stopping_chunk(features.nrows(), train_options);
+        pzip!(
+            features.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+            labels.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+            weights.axis_chunks_iter(Axis(0), n_examples_per_chunk),
+        )
+        .fold(
+            || {
+                let outputs = unsafe { <Array1<f32>>::uninit(n_examples_per_chunk).assume_init() };
+                let metric = Mean::new();
+                (outputs, metric)
+            },
+            |(mut outputs, mut metric), (features, labels, weights)| {
+                let slice = s![0..features.nrows()];
+                let mut outputs_slice = outputs.slice_mut(slice);
+                self.compute_outputs(features, outputs_slice.view_mut());
+                for (output, label, weight) in
+                    zip!(outputs_slice.iter(), labels.iter(), weights.iter())
+                {
+                    let value = match self.loss {
+                        RegressionLoss::SquaredError => (output - label) * (output - label),
+                        _ => self.loss.compute_loss(*output, *label),
+                    };
+                    metric.update_weighted(value, *weight);
+                }
+                (outputs, metric)
+            },
+        )
+        .map(|(_, metric)| metric)
+        .reduce(Mean::new, |mut a, b| {
+            a.merge(b);
+            a
+        })
+        .finalize()
+        .unwrap()
+    }
+}
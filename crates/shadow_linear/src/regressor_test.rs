@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        HuberLossOptions, OnlineTrainer, Progress, RegressionLoss, Regressor, RegressorTrainOutput,
+        TrainOptions,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::NumberTableColumn;
+
+    /// Generates 1000 examples with 3 features drawn uniformly from [0, 1).
+    fn features_and_labels(
+        compute_label: impl FnMut(ArrayView1<f32>) -> f32,
+    ) -> (Array2<f32>, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let features = Array::from_shape_fn((n_examples, 3), |_| rng.gen::<f32>());
+        let labels = features.axis_iter(Axis(0)).map(compute_label).collect();
+        (features, labels)
+    }
+
+    fn train_output_with_examples(
+        train_options: &TrainOptions,
+        features: ArrayView2<f32>,
+        labels: Vec<f32>,
+    ) -> RegressorTrainOutput {
+        train_output_with_weighted_examples(train_options, features, labels, None)
+    }
+
+    fn train_output_with_weighted_examples(
+        train_options: &TrainOptions,
+        features: ArrayView2<f32>,
+        labels: Vec<f32>,
+        weights: Option<ArrayView1<f32>>,
+    ) -> RegressorTrainOutput {
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        Regressor::train(
+            features,
+            labels.view(),
+            weights,
+            None,
+            train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+    }
+
+    fn train(train_options: &TrainOptions) -> Regressor {
+        let (features, labels) = features_and_labels(|features| {
+            2.0 * features[0] - features[1] + 0.5 * features[2] + 1.0
+        });
+        train_output_with_examples(train_options, features.view(), labels).model
+    }
+
+    #[test]
+    fn test_deterministic_training_is_reproducible() {
+        let train_options = TrainOptions {
+            deterministic: true,
+            shuffle: true,
+            max_epochs: 10,
+            ..Default::default()
+        };
+        let model_a = train(&train_options);
+        let model_b = train(&train_options);
+        assert_eq!(model_a.weights, model_b.weights);
+        assert_eq!(model_a.bias.to_bits(), model_b.bias.to_bits());
+        let model_c = train(&TrainOptions {
+            seed: 1,
+            ..train_options
+        });
+        assert_ne!(model_a.weights, model_c.weights);
+    }
+
+    #[test]
+    fn test_examples_with_zero_weight_are_ignored() {
+        let (features, mut labels) = features_and_labels(|features| 2.0 * features[0] + 1.0);
+        let n_examples = labels.len();
+        // The second half of the labels are corrupted, but they have zero weight.
+        for label in labels[n_examples / 2..].iter_mut() {
+            *label += 10.0;
+        }
+        let weights =
+            Array::from_shape_fn(n_examples, |i| if i < n_examples / 2 { 1.0 } else { 0.0 });
+        let model = train_output_with_weighted_examples(
+            &TrainOptions {
+                deterministic: true,
+                ..Default::default()
+            },
+            features.view(),
+            labels,
+            Some(weights.view()),
+        )
+        .model;
+        assert!((model.bias - 1.0).abs() < 0.1, "{}", model.bias);
+        assert!((model.weights[0] - 2.0).abs() < 0.1, "{}", model.weights[0]);
+    }
+
+    #[test]
+    fn test_train_from_continues_training() {
+        // Without shuffling, stateless SGD trained for 5 epochs and then 5 more reaches the same model as 10 epochs at once.
+        let (features, labels) = features_and_labels(|features| {
+            2.0 * features[0] - features[1] + 0.5 * features[2] + 1.0
+        });
+        let train_options = TrainOptions {
+            deterministic: true,
+            max_epochs: 5,
+            shuffle: false,
+            ..Default::default()
+        };
+        let model =
+            train_output_with_examples(&train_options, features.view(), labels.clone()).model;
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let continued = Regressor::train_from(
+            model,
+            features.view(),
+            labels.view(),
+            None,
+            None,
+            &train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+        .model;
+        let trained_at_once = train_output_with_examples(
+            &TrainOptions {
+                max_epochs: 10,
+                ..train_options
+            },
+            features.view(),
+            labels.view().as_slice().to_vec(),
+        )
+        .model;
+        assert_eq!(continued.weights, trained_at_once.weights);
+        assert_eq!(continued.bias, trained_at_once.bias);
+    }
+
+    #[test]
+    fn test_train_from_uses_regression_loss() {
+        let (features, labels) = features_and_labels(|features| {
+            2.0 * features[0] - features[1] + 0.5 * features[2] + 1.0
+        });
+        let train_options = TrainOptions {
+            max_epochs: 1,
+            ..Default::default()
+        };
+        let model =
+            train_output_with_examples(&train_options, features.view(), labels.clone()).model;
+        assert!(matches!(model.loss, RegressionLoss::SquaredError));
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let train_options = TrainOptions {
+            regression_loss: RegressionLoss::Huber(HuberLossOptions { delta: 1.0 }),
+            ..train_options
+        };
+        let continued = Regressor::train_from(
+            model.clone(),
+            features.view(),
+            labels.view(),
+            None,
+            None,
+            &train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .unwrap()
+        .model;
+        assert!(matches!(continued.loss, RegressionLoss::Huber(_)));
+        let trainer = OnlineTrainer::<Regressor>::new(model, &train_options);
+        assert!(matches!(trainer.model().loss, RegressionLoss::Huber(_)));
+    }
+}
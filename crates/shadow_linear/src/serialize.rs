@@ -1,4 +1,69 @@
 use ndarray::prelude::*;
+use std::marker::PhantomData;
+
+/// A dynamic struct that gained fields after it was first saved. buffalo reads a field's offset from the struct's index without checking the field's id against the number of fields in the index, so reading a field that was added later from a struct saved earlier reads past the end of its index. Reading a `Versioned` struct also reads the number of fields in its index, so `VersionedReader::get` can check it first.
+#[derive(Debug)]
+pub struct Versioned<T>(PhantomData<T>);
+
+impl<T> buffalo::WriteType for Versioned<T>
+where
+    T: buffalo::WriteType,
+{
+    type WriteType = T::WriteType;
+}
+
+impl<'a, T, R> buffalo::ReadType<'a> for Versioned<T>
+where
+    T: buffalo::ReadType<'a, ReadType = buffalo::Pointer<R>>,
+    R: buffalo::Read<'a>,
+{
+    type ReadType = buffalo::Pointer<Versioned<R>>;
+}
+
+impl<'a, R> buffalo::Read<'a> for Versioned<R>
+where
+    R: buffalo::Read<'a>,
+{
+    type Output = VersionedReader<R::Output>;
+    fn read(bytes: &'a [u8], position: buffalo::Position<Self>) -> Self::Output {
+        let index_pointer = buffalo::DynamicStructIndexPointer::read(bytes, position.cast());
+        let index_position = buffalo::Position::new(*position - *index_pointer);
+        let n_fields = buffalo::DynamicStructIndexReader::<u16>::new(bytes, index_position)
+            .field_count()
+            .0;
+        VersionedReader {
+            reader: R::read(bytes, position.cast()),
+            n_fields,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct VersionedReader<R> {
+    reader: R,
+    n_fields: u16,
+}
+
+impl<R> VersionedReader<R>
+where
+    R: Copy,
+{
+    /// Reads the field with `id` with `read`, or returns `None` if the struct was saved before the field was added.
+    pub fn get<T>(&self, id: u16, read: impl FnOnce(R) -> Option<T>) -> Option<T> {
+        if id < self.n_fields {
+            read(self.reader)
+        } else {
+            None
+        }
+    }
+}
+
+impl<R> std::ops::Deref for VersionedReader<R> {
+    type Target = R;
+    fn deref(&self) -> &R {
+        &self.reader
+    }
+}
 
 #[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
 #[buffalo(size = "dynamic")]
@@ -9,6 +74,45 @@ pub struct Regressor {
     pub weights: Array1<f32>,
     #[buffalo(id = 2, required)]
     pub means: Vec<f32>,
+    /// Models saved before the loss was configurable do not have this field and were trained with the squared error.
+    #[buffalo(id = 3)]
+    pub loss: RegressionLoss,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 8)]
+pub enum RegressionLoss {
+    #[buffalo(id = 0)]
+    SquaredError,
+    #[buffalo(id = 1, required)]
+    Huber(HuberLossOptions),
+    #[buffalo(id = 2, required)]
+    Quantile(QuantileLossOptions),
+    #[buffalo(id = 3)]
+    Poisson,
+    #[buffalo(id = 4, required)]
+    Tweedie(TweedieLossOptions),
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct HuberLossOptions {
+    #[buffalo(id = 0, required)]
+    pub delta: f32,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct QuantileLossOptions {
+    #[buffalo(id = 0, required)]
+    pub quantile: f32,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct TweedieLossOptions {
+    #[buffalo(id = 0, required)]
+    pub variance_power: f32,
 }
 
 #[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
@@ -20,6 +124,36 @@ pub struct BinaryClassifier {
     pub weights: Array1<f32>,
     #[buffalo(id = 2, required)]
     pub means: Vec<f32>,
+    /// Models saved before calibration was added do not have this field and are uncalibrated.
+    #[buffalo(id = 3)]
+    pub calibrator: Option<BinaryCalibrator>,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 8)]
+pub enum BinaryCalibrator {
+    #[buffalo(id = 0, required)]
+    Platt(PlattScaling),
+    #[buffalo(id = 1, required)]
+    Isotonic(IsotonicRegression),
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct PlattScaling {
+    #[buffalo(id = 0, required)]
+    pub a: f32,
+    #[buffalo(id = 1, required)]
+    pub b: f32,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct IsotonicRegression {
+    #[buffalo(id = 0, required)]
+    pub probabilities: Vec<f32>,
+    #[buffalo(id = 1, required)]
+    pub calibrated_probabilities: Vec<f32>,
 }
 
 #[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
@@ -31,6 +165,81 @@ pub struct MulticlassClassifier {
     pub weights: Array2<f32>,
     #[buffalo(id = 2, required)]
     pub means: Vec<f32>,
+    /// Models saved before calibration was added do not have this field and are uncalibrated.
+    #[buffalo(id = 3)]
+    pub calibrator: Option<TemperatureScaling>,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct TemperatureScaling {
+    #[buffalo(id = 0, required)]
+    pub temperature: f32,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct MultiLabelClassifier {
+    #[buffalo(id = 0, required)]
+    pub biases: Array1<f32>,
+    #[buffalo(id = 1, required)]
+    pub weights: Array2<f32>,
+    #[buffalo(id = 2, required)]
+    pub means: Vec<f32>,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct OrdinalClassifier {
+    #[buffalo(id = 0, required)]
+    pub thresholds: Array1<f32>,
+    #[buffalo(id = 1, required)]
+    pub weights: Array1<f32>,
+    #[buffalo(id = 2, required)]
+    pub means: Vec<f32>,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct Glm {
+    #[buffalo(id = 0, required)]
+    pub bias: f32,
+    #[buffalo(id = 1, required)]
+    pub weights: Array1<f32>,
+    #[buffalo(id = 2, required)]
+    pub means: Vec<f32>,
+    #[buffalo(id = 3, required)]
+    pub family: GlmFamily,
+    #[buffalo(id = 4, required)]
+    pub link: LinkFunction,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 0)]
+pub enum GlmFamily {
+    #[buffalo(id = 0)]
+    Gaussian,
+    #[buffalo(id = 1)]
+    Poisson,
+    #[buffalo(id = 2)]
+    Gamma,
+    #[buffalo(id = 3)]
+    Bernoulli,
+}
+
+#[derive(Clone, Debug, buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 0)]
+pub enum LinkFunction {
+    #[buffalo(id = 0)]
+    Identity,
+    #[buffalo(id = 1)]
+    Log,
+    #[buffalo(id = 2)]
+    Logit,
+    #[buffalo(id = 3)]
+    Inverse,
+    #[buffalo(id = 4)]
+    Sqrt,
 }
 
 pub(crate) fn serialize_regressor(
@@ -39,14 +248,43 @@ pub(crate) fn serialize_regressor(
 ) -> buffalo::Position<RegressorWriter> {
     let weights = writer.write(&regressor.weights);
     let means = writer.write(regressor.means.as_slice());
+    let loss = serialize_regression_loss(&regressor.loss, writer);
     writer.write(&RegressorWriter {
         bias: regressor.bias,
         weights,
         means,
+        loss,
     })
 }
 
-pub(crate) fn deserialize_regressor(regressor: RegressorReader) -> crate::Regressor {
+fn serialize_regression_loss(
+    loss: &crate::RegressionLoss,
+    writer: &mut buffalo::Writer,
+) -> RegressionLossWriter {
+    match loss {
+        crate::RegressionLoss::SquaredError => RegressionLossWriter::SquaredError,
+        crate::RegressionLoss::Huber(options) => {
+            RegressionLossWriter::Huber(writer.write(&HuberLossOptionsWriter {
+                delta: options.delta,
+            }))
+        }
+        crate::RegressionLoss::Quantile(options) => {
+            RegressionLossWriter::Quantile(writer.write(&QuantileLossOptionsWriter {
+                quantile: options.quantile,
+            }))
+        }
+        crate::RegressionLoss::Poisson => RegressionLossWriter::Poisson,
+        crate::RegressionLoss::Tweedie(options) => {
+            RegressionLossWriter::Tweedie(writer.write(&TweedieLossOptionsWriter {
+                variance_power: options.variance_power,
+            }))
+        }
+    }
+}
+
+pub(crate) fn deserialize_regressor(
+    regressor: VersionedReader<RegressorReader>,
+) -> crate::Regressor {
     let bias = regressor.bias();
     let weights = regressor
         .weights()
@@ -59,10 +297,37 @@ pub(crate) fn deserialize_regressor(regressor: RegressorReader) -> crate::Regres
         .iter()
         .map(|mean| mean.to_owned())
         .collect::<Vec<_>>();
+    let loss = regressor
+        .get(3, RegressorReader::loss)
+        .map(deserialize_regression_loss)
+        .unwrap_or(crate::RegressionLoss::SquaredError);
     crate::Regressor {
         bias,
         weights,
         means,
+        loss,
+    }
+}
+
+fn deserialize_regression_loss(loss: RegressionLossReader) -> crate::RegressionLoss {
+    match loss {
+        RegressionLossReader::SquaredError(_) => crate::RegressionLoss::SquaredError,
+        RegressionLossReader::Huber(options) => {
+            crate::RegressionLoss::Huber(crate::HuberLossOptions {
+                delta: options.read().delta(),
+            })
+        }
+        RegressionLossReader::Quantile(options) => {
+            crate::RegressionLoss::Quantile(crate::QuantileLossOptions {
+                quantile: options.read().quantile(),
+            })
+        }
+        RegressionLossReader::Poisson(_) => crate::RegressionLoss::Poisson,
+        RegressionLossReader::Tweedie(options) => {
+            crate::RegressionLoss::Tweedie(crate::TweedieLossOptions {
+                variance_power: options.read().variance_power(),
+            })
+        }
     }
 }
 
@@ -72,15 +337,43 @@ pub(crate) fn serialize_binary_classifier(
 ) -> buffalo::Position<BinaryClassifierWriter> {
     let weights = writer.write(&binary_classifier.weights);
     let means = writer.write(binary_classifier.means.as_slice());
+    let calibrator = binary_classifier
+        .calibrator
+        .as_ref()
+        .map(|calibrator| serialize_binary_calibrator(calibrator, writer));
     writer.write(&BinaryClassifierWriter {
         bias: binary_classifier.bias,
         weights,
         means,
+        calibrator,
     })
 }
 
+fn serialize_binary_calibrator(
+    calibrator: &crate::BinaryCalibrator,
+    writer: &mut buffalo::Writer,
+) -> BinaryCalibratorWriter {
+    match calibrator {
+        crate::BinaryCalibrator::Platt(platt_scaling) => {
+            BinaryCalibratorWriter::Platt(writer.write(&PlattScalingWriter {
+                a: platt_scaling.a,
+                b: platt_scaling.b,
+            }))
+        }
+        crate::BinaryCalibrator::Isotonic(isotonic_regression) => {
+            let probabilities = writer.write(isotonic_regression.probabilities.as_slice());
+            let calibrated_probabilities =
+                writer.write(isotonic_regression.calibrated_probabilities.as_slice());
+            BinaryCalibratorWriter::Isotonic(writer.write(&IsotonicRegressionWriter {
+                probabilities,
+                calibrated_probabilities,
+            }))
+        }
+    }
+}
+
 pub(crate) fn deserialize_binary_classifier(
-    binary_classifier: BinaryClassifierReader,
+    binary_classifier: VersionedReader<BinaryClassifierReader>,
 ) -> crate::BinaryClassifier {
     let bias = binary_classifier.bias();
     let weights = binary_classifier
@@ -94,10 +387,37 @@ pub(crate) fn deserialize_binary_classifier(
         .iter()
         .map(|mean| mean.to_owned())
         .collect::<Vec<_>>();
+    let calibrator = binary_classifier
+        .get(3, BinaryClassifierReader::calibrator)
+        .flatten()
+        .map(deserialize_binary_calibrator);
     crate::BinaryClassifier {
         bias,
         weights,
         means,
+        calibrator,
+    }
+}
+
+fn deserialize_binary_calibrator(calibrator: BinaryCalibratorReader) -> crate::BinaryCalibrator {
+    match calibrator {
+        BinaryCalibratorReader::Platt(platt_scaling) => {
+            let platt_scaling = platt_scaling.read();
+            crate::BinaryCalibrator::Platt(crate::PlattScaling {
+                a: platt_scaling.a(),
+                b: platt_scaling.b(),
+            })
+        }
+        BinaryCalibratorReader::Isotonic(isotonic_regression) => {
+            let isotonic_regression = isotonic_regression.read();
+            crate::BinaryCalibrator::Isotonic(crate::IsotonicRegression {
+                probabilities: isotonic_regression.probabilities().iter().collect(),
+                calibrated_probabilities: isotonic_regression
+                    .calibrated_probabilities()
+                    .iter()
+                    .collect(),
+            })
+        }
     }
 }
 
@@ -108,15 +428,21 @@ pub(crate) fn serialize_multiclass_classifier(
     let weights = writer.write(&multiclass_classifier.weights);
     let biases = writer.write(&multiclass_classifier.biases);
     let means = writer.write(multiclass_classifier.means.as_slice());
+    let calibrator = multiclass_classifier.calibrator.as_ref().map(|calibrator| {
+        writer.write(&TemperatureScalingWriter {
+            temperature: calibrator.temperature,
+        })
+    });
     writer.write(&MulticlassClassifierWriter {
         biases,
         weights,
         means,
+        calibrator,
     })
 }
 
 pub(crate) fn deserialize_multiclass_classifier(
-    multiclass_classifier: MulticlassClassifierReader,
+    multiclass_classifier: VersionedReader<MulticlassClassifierReader>,
 ) -> crate::MulticlassClassifier {
     let biases = multiclass_classifier
         .biases()
@@ -129,9 +455,155 @@ pub(crate) fn deserialize_multiclass_classifier(
         .iter()
         .map(|mean| mean.to_owned())
         .collect::<Vec<_>>();
+    let calibrator = multiclass_classifier
+        .get(3, MulticlassClassifierReader::calibrator)
+        .flatten()
+        .map(|calibrator| crate::TemperatureScaling {
+            temperature: calibrator.temperature(),
+        });
     crate::MulticlassClassifier {
         biases: biases.into(),
         weights,
         means,
+        calibrator,
+    }
+}
+
+pub(crate) fn serialize_multi_label_classifier(
+    multi_label_classifier: &crate::MultiLabelClassifier,
+    writer: &mut buffalo::Writer,
+) -> buffalo::Position<MultiLabelClassifierWriter> {
+    let weights = writer.write(&multi_label_classifier.weights);
+    let biases = writer.write(&multi_label_classifier.biases);
+    let means = writer.write(multi_label_classifier.means.as_slice());
+    writer.write(&MultiLabelClassifierWriter {
+        biases,
+        weights,
+        means,
+    })
+}
+
+pub(crate) fn deserialize_multi_label_classifier(
+    multi_label_classifier: MultiLabelClassifierReader,
+) -> crate::MultiLabelClassifier {
+    let biases = multi_label_classifier
+        .biases()
+        .iter()
+        .map(|bias| bias.to_owned())
+        .collect::<Vec<_>>();
+    let weights = multi_label_classifier.weights();
+    let means = multi_label_classifier
+        .means()
+        .iter()
+        .map(|mean| mean.to_owned())
+        .collect::<Vec<_>>();
+    crate::MultiLabelClassifier {
+        biases: biases.into(),
+        weights,
+        means,
+    }
+}
+
+pub(crate) fn serialize_ordinal_classifier(
+    ordinal_classifier: &crate::OrdinalClassifier,
+    writer: &mut buffalo::Writer,
+) -> buffalo::Position<OrdinalClassifierWriter> {
+    let thresholds = writer.write(&ordinal_classifier.thresholds);
+    let weights = writer.write(&ordinal_classifier.weights);
+    let means = writer.write(ordinal_classifier.means.as_slice());
+    writer.write(&OrdinalClassifierWriter {
+        thresholds,
+        weights,
+        means,
+    })
+}
+
+pub(crate) fn deserialize_ordinal_classifier(
+    ordinal_classifier: OrdinalClassifierReader,
+) -> crate::OrdinalClassifier {
+    let thresholds = ordinal_classifier
+        .thresholds()
+        .iter()
+        .map(|threshold| threshold.to_owned())
+        .collect::<Vec<_>>()
+        .into();
+    let weights = ordinal_classifier
+        .weights()
+        .iter()
+        .map(|weights| weights.to_owned())
+        .collect::<Vec<_>>()
+        .into();
+    let means = ordinal_classifier
+        .means()
+        .iter()
+        .map(|mean| mean.to_owned())
+        .collect::<Vec<_>>();
+    crate::OrdinalClassifier {
+        thresholds,
+        weights,
+        means,
+    }
+}
+
+pub(crate) fn serialize_glm(
+    glm: &crate::Glm,
+    writer: &mut buffalo::Writer,
+) -> buffalo::Position<GlmWriter> {
+    let weights = writer.write(&glm.weights);
+    let means = writer.write(glm.means.as_slice());
+    let family = match glm.family {
+        crate::GlmFamily::Gaussian => GlmFamilyWriter::Gaussian,
+        crate::GlmFamily::Poisson => GlmFamilyWriter::Poisson,
+        crate::GlmFamily::Gamma => GlmFamilyWriter::Gamma,
+        crate::GlmFamily::Bernoulli => GlmFamilyWriter::Bernoulli,
+    };
+    let link = match glm.link {
+        crate::LinkFunction::Identity => LinkFunctionWriter::Identity,
+        crate::LinkFunction::Log => LinkFunctionWriter::Log,
+        crate::LinkFunction::Logit => LinkFunctionWriter::Logit,
+        crate::LinkFunction::Inverse => LinkFunctionWriter::Inverse,
+        crate::LinkFunction::Sqrt => LinkFunctionWriter::Sqrt,
+    };
+    writer.write(&GlmWriter {
+        bias: glm.bias,
+        weights,
+        means,
+        family,
+        link,
+    })
+}
+
+pub(crate) fn deserialize_glm(glm: GlmReader) -> crate::Glm {
+    let bias = glm.bias();
+    let weights = glm
+        .weights()
+        .iter()
+        .map(|weights| weights.to_owned())
+        .collect::<Vec<_>>()
+        .into();
+    let means = glm
+        .means()
+        .iter()
+        .map(|mean| mean.to_owned())
+        .collect::<Vec<_>>();
+    let family = match glm.family() {
+        GlmFamilyReader::Gaussian(_) => crate::GlmFamily::Gaussian,
+        GlmFamilyReader::Poisson(_) => crate::GlmFamily::Poisson,
+        GlmFamilyReader::Gamma(_) => crate::GlmFamily::Gamma,
+        GlmFamilyReader::Bernoulli(_) => crate::GlmFamily::Bernoulli,
+    };
+    let link = match glm.link() {
+        LinkFunctionReader::Identity(_) => crate::LinkFunction::Identity,
+        LinkFunctionReader::Log(_) => crate::LinkFunction::Log,
+        LinkFunctionReader::Logit(_) => crate::LinkFunction::Logit,
+        LinkFunctionReader::Inverse(_) => crate::LinkFunction::Inverse,
+        LinkFunctionReader::Sqrt(_) => crate::LinkFunction::Sqrt,
+    };
+    crate::Glm {
+        bias,
+        weights,
+        means,
+        family,
+        link,
     }
 }
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod test {
+    use crate::{BinaryClassifier, RegressionLoss, Regressor};
+    use ndarray::prelude::*;
+
+    /// A regressor as it was saved before the loss was configurable.
+    #[allow(unused)]
+    #[derive(buffalo::Write)]
+    #[buffalo(size = "dynamic")]
+    struct OldRegressor {
+        #[buffalo(id = 0, required)]
+        bias: f32,
+        #[buffalo(id = 1, required)]
+        weights: Array1<f32>,
+        #[buffalo(id = 2, required)]
+        means: Vec<f32>,
+    }
+
+    #[test]
+    fn test_read_regressor_saved_without_loss() {
+        let mut writer = buffalo::Writer::new();
+        let weights = writer.write(&arr1(&[1.0f32, 2.0]));
+        let means = writer.write([0.5f32, 0.25].as_slice());
+        let position = writer.write(&OldRegressorWriter {
+            bias: 0.1,
+            weights,
+            means,
+        });
+        writer.write(&position);
+        let bytes = writer.into_bytes();
+        let model = Regressor {
+            bias: 0.0,
+            weights: Array1::zeros(2),
+            means: Vec::new(),
+            loss: RegressionLoss::Poisson,
+        }
+        .from_bytes(&bytes);
+        assert_eq!(model.bias, 0.1);
+        assert_eq!(model.weights, arr1(&[1.0, 2.0]));
+        assert_eq!(model.means, vec![0.5, 0.25]);
+        assert!(matches!(model.loss, RegressionLoss::SquaredError));
+    }
+
+    #[test]
+    fn test_read_binary_classifier_saved_without_calibrator() {
+        // Binary classifiers saved before calibration was added have the same fields as `OldRegressor`.
+        let mut writer = buffalo::Writer::new();
+        let weights = writer.write(&arr1(&[1.0f32, 2.0]));
+        let means = writer.write([0.5f32, 0.25].as_slice());
+        let position = writer.write(&OldRegressorWriter {
+            bias: 0.1,
+            weights,
+            means,
+        });
+        writer.write(&position);
+        let bytes = writer.into_bytes();
+        let model = BinaryClassifier {
+            bias: 0.0,
+            weights: Array1::zeros(2),
+            means: Vec::new(),
+            calibrator: None,
+        }
+        .from_bytes(&bytes);
+        assert_eq!(model.bias, 0.1);
+        assert_eq!(model.weights, arr1(&[1.0, 2.0]));
+        assert!(model.calibrator.is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use ndarray::prelude::*;
 use shadow_zip::zip;
 
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct ComputeShapValuesForExampleOutput {
     pub baseline_value: f32,
     pub output_value: f32,
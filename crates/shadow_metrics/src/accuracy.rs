@@ -11,7 +11,12 @@ impl Accuracy {
 
 impl Accuracy {
     pub fn update(&mut self, value: (usize, usize)) {
-        self.0.update(if value.0 == value.1 { 1.0 } else { 0.0 })
+        self.update_weighted(value, 1.0)
+    }
+
+    pub fn update_weighted(&mut self, value: (usize, usize), weight: f32) {
+        self.0
+            .update_weighted(if value.0 == value.1 { 1.0 } else { 0.0 }, weight)
     }
 
     pub fn merge(&mut self, other: Accuracy) {
@@ -13,6 +13,11 @@ struct BinaryConfusionMatrix {
     false_positives: u64,
     true_negatives: u64,
     true_positives: u64,
+    /// The sums of the weights of the examples counted in each cell.
+    weighted_false_negatives: f64,
+    weighted_false_positives: f64,
+    weighted_true_negatives: f64,
+    weighted_true_positives: f64,
 }
 
 impl BinaryConfusionMatrix {
@@ -22,11 +27,18 @@ impl BinaryConfusionMatrix {
             false_positives: 0,
             true_negatives: 0,
             true_positives: 0,
+            weighted_false_negatives: 0.0,
+            weighted_false_positives: 0.0,
+            weighted_true_negatives: 0.0,
+            weighted_true_positives: 0.0,
         }
     }
 
-    fn total(&self) -> u64 {
-        self.false_negatives + self.false_positives + self.true_negatives + self.true_positives
+    fn weighted_total(&self) -> f64 {
+        self.weighted_false_negatives
+            + self.weighted_false_positives
+            + self.weighted_true_negatives
+            + self.weighted_true_positives
     }
 }
 
@@ -48,6 +60,11 @@ pub struct BinaryClassificationMetricsOutputForThreshold {
     pub false_positives: u64,
     pub true_negatives: u64,
     pub false_negatives: u64,
+    /// The sums of the weights of the examples in each cell of the confusion matrix, which equal the counts when every weight is 1. The other metrics are computed from these.
+    pub weighted_true_positives: f32,
+    pub weighted_false_positives: f32,
+    pub weighted_true_negatives: f32,
+    pub weighted_false_negatives: f32,
     pub accuracy: f32,
     pub precision: Option<f32>,
     pub recall: Option<f32>,
@@ -69,17 +86,45 @@ impl BinaryClassificationMetrics {
     }
 
     pub fn update(&mut self, input: BinaryClassificationMetricsInput) {
+        for (probability, label) in zip!(input.probabilities.iter(), input.labels.iter()) {
+            self.update_example(*probability, *label, 1.0);
+        }
+    }
+
+    /// Updates the metrics with each example counted `weights[i]` times.
+    pub fn update_weighted(&mut self, input: BinaryClassificationMetricsInput, weights: &[f32]) {
+        for (probability, label, weight) in zip!(
+            input.probabilities.iter(),
+            input.labels.iter(),
+            weights.iter()
+        ) {
+            self.update_example(*probability, *label, *weight);
+        }
+    }
+
+    fn update_example(&mut self, probability: f32, label: Option<NonZeroUsize>, weight: f32) {
+        let weight = weight.to_f64().unwrap();
         for (threshold, confusion_matrix) in self.confusion_matrices_for_thresholds.iter_mut() {
-            for (probability, label) in zip!(input.probabilities.iter(), input.labels.iter()) {
-                let predicted = *probability >= *threshold;
-                let actual = label.unwrap().get() == 2;
-                match (predicted, actual) {
-                    (false, false) => confusion_matrix.true_negatives += 1,
-                    (false, true) => confusion_matrix.false_negatives += 1,
-                    (true, false) => confusion_matrix.false_positives += 1,
-                    (true, true) => confusion_matrix.true_positives += 1,
-                };
-            }
+            let predicted = probability >= *threshold;
+            let actual = label.unwrap().get() == 2;
+            match (predicted, actual) {
+                (false, false) => {
+                    confusion_matrix.true_negatives += 1;
+                    confusion_matrix.weighted_true_negatives += weight;
+                }
+                (false, true) => {
+                    confusion_matrix.false_negatives += 1;
+                    confusion_matrix.weighted_false_negatives += weight;
+                }
+                (true, false) => {
+                    confusion_matrix.false_positives += 1;
+                    confusion_matrix.weighted_false_positives += weight;
+                }
+                (true, true) => {
+                    confusion_matrix.true_positives += 1;
+                    confusion_matrix.weighted_true_positives += weight;
+                }
+            };
         }
     }
 
@@ -92,6 +137,14 @@ impl BinaryClassificationMetrics {
             confusion_matrix_a.false_negatives += confusion_matrix_b.false_negatives;
             confusion_matrix_a.true_negatives += confusion_matrix_b.true_negatives;
             confusion_matrix_a.false_positives += confusion_matrix_b.false_positives;
+            confusion_matrix_a.weighted_true_positives +=
+                confusion_matrix_b.weighted_true_positives;
+            confusion_matrix_a.weighted_false_negatives +=
+                confusion_matrix_b.weighted_false_negatives;
+            confusion_matrix_a.weighted_true_negatives +=
+                confusion_matrix_b.weighted_true_negatives;
+            confusion_matrix_a.weighted_false_positives +=
+                confusion_matrix_b.weighted_false_positives;
         }
     }
 
@@ -100,16 +153,16 @@ impl BinaryClassificationMetrics {
             .confusion_matrices_for_thresholds
             .iter()
             .map(|(threshold, confusion_matrix)| {
-                let n_examples = confusion_matrix.total();
-                let true_positives = confusion_matrix.true_positives;
-                let false_positives = confusion_matrix.false_positives;
-                let false_negatives = confusion_matrix.false_negatives;
-                let true_negatives = confusion_matrix.true_negatives;
+                let n_examples = confusion_matrix.weighted_total();
+                let true_positives = confusion_matrix.weighted_true_positives;
+                let false_positives = confusion_matrix.weighted_false_positives;
+                let false_negatives = confusion_matrix.weighted_false_negatives;
+                let true_negatives = confusion_matrix.weighted_true_negatives;
                 let accuracy = (true_positives + true_negatives).to_f32().unwrap()
                     / n_examples.to_f32().unwrap();
 
                 let predicted_positive = true_positives + false_negatives;
-                let precision = if predicted_positive > 0 {
+                let precision = if predicted_positive > 0.0 {
                     Some(
                         true_positives.to_f32().unwrap()
                             / (true_positives + false_positives).to_f32().unwrap(),
@@ -119,7 +172,7 @@ impl BinaryClassificationMetrics {
                 };
 
                 let actual_positive = true_positives + false_negatives;
-                let recall = if actual_positive > 0 {
+                let recall = if actual_positive > 0.0 {
                     Some(
                         true_positives.to_f32().unwrap()
                             / (true_positives + false_negatives).to_f32().unwrap(),
@@ -141,10 +194,14 @@ impl BinaryClassificationMetrics {
                     / (true_negatives.to_f32().unwrap() + false_positives.to_f32().unwrap());
                 BinaryClassificationMetricsOutputForThreshold {
                     threshold: *threshold,
-                    false_negatives,
-                    false_positives,
-                    true_negatives,
-                    true_positives,
+                    false_negatives: confusion_matrix.false_negatives,
+                    false_positives: confusion_matrix.false_positives,
+                    true_negatives: confusion_matrix.true_negatives,
+                    true_positives: confusion_matrix.true_positives,
+                    weighted_false_negatives: false_negatives.to_f32().unwrap(),
+                    weighted_false_positives: false_positives.to_f32().unwrap(),
+                    weighted_true_negatives: true_negatives.to_f32().unwrap(),
+                    weighted_true_positives: true_positives.to_f32().unwrap(),
                     accuracy,
                     precision,
                     recall,
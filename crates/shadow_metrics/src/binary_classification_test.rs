@@ -27,6 +27,10 @@ mod tests {
              false_positives: 0,
              true_negatives: 2,
              false_negatives: 1,
+             weighted_true_positives: 2.0,
+             weighted_false_positives: 0.0,
+             weighted_true_negatives: 2.0,
+             weighted_false_negatives: 1.0,
              accuracy: 0.8,
              precision: Some(
                  1.0,
@@ -46,6 +50,10 @@ mod tests {
              false_positives: 0,
              true_negatives: 2,
              false_negatives: 1,
+             weighted_true_positives: 2.0,
+             weighted_false_positives: 0.0,
+             weighted_true_negatives: 2.0,
+             weighted_false_negatives: 1.0,
              accuracy: 0.8,
              precision: Some(
                  1.0,
@@ -65,6 +73,10 @@ mod tests {
              false_positives: 0,
              true_negatives: 2,
              false_negatives: 2,
+             weighted_true_positives: 1.0,
+             weighted_false_positives: 0.0,
+             weighted_true_negatives: 2.0,
+             weighted_false_negatives: 2.0,
              accuracy: 0.6,
              precision: Some(
                  1.0,
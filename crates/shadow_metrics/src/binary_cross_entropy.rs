@@ -18,6 +18,10 @@ pub struct BinaryCrossEntropyInput {
 
 impl BinaryCrossEntropy {
 	pub fn update(&mut self, value: BinaryCrossEntropyInput) {
+		self.update_weighted(value, 1.0)
+	}
+
+	pub fn update_weighted(&mut self, value: BinaryCrossEntropyInput, weight: f32) {
 		let BinaryCrossEntropyInput { probability, label } = value;
 		let label = match label.map(|l| l.get()) {
 			Some(1) => 0.0,
@@ -28,7 +32,7 @@ impl BinaryCrossEntropy {
 		let probability_clamped = clamp(probability, std::f32::EPSILON, 1.0 - std::f32::EPSILON);
 		let binary_cross_entropy = -1.0 * label * probability_clamped.ln()
 			+ -1.0 * (1.0 - label) * (1.0 - probability_clamped).ln();
-		self.0.update(binary_cross_entropy);
+		self.0.update_weighted(binary_cross_entropy, weight);
 	}
 
 	pub fn merge(&mut self, other: BinaryCrossEntropy) {
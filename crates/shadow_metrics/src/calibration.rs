@@ -0,0 +1,101 @@
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_zip::zip;
+
+/// Measures how well predicted probabilities match observed frequencies, by splitting the probabilities into `n_bins` bins of equal width. For a binary classifier, pass the probability of the positive class. For a multiclass classifier, pass the probability of the predicted class as `probabilities` and whether the prediction was correct as `labels`.
+pub struct CalibrationMetrics {
+    /// For each bin, the sums of the weights, the weighted probabilities and the weighted labels, in that order.
+    bins: Array2<f64>,
+}
+
+pub struct CalibrationMetricsInput<'a> {
+    pub probabilities: ArrayView1<'a, f32>,
+    pub labels: ArrayView1<'a, bool>,
+}
+
+#[derive(Debug)]
+pub struct CalibrationMetricsOutput {
+    /// The bins that contain at least one example, in order of increasing probability.
+    pub reliability_curve: Vec<ReliabilityCurveBin>,
+    /// The mean over the examples of the absolute difference between the mean probability and the fraction of positive labels in their bin.
+    pub expected_calibration_error: f32,
+    /// The largest absolute difference between the mean probability and the fraction of positive labels of any bin.
+    pub maximum_calibration_error: f32,
+}
+
+#[derive(Debug)]
+pub struct ReliabilityCurveBin {
+    pub mean_predicted_probability: f32,
+    pub fraction_of_positives: f32,
+    /// The sum of the weights of the examples in the bin.
+    pub weight: f32,
+}
+
+impl CalibrationMetrics {
+    pub fn new(n_bins: usize) -> CalibrationMetrics {
+        assert!(n_bins > 0);
+        CalibrationMetrics {
+            bins: Array::zeros((n_bins, 3)),
+        }
+    }
+
+    pub fn update(&mut self, value: CalibrationMetricsInput) {
+        for (probability, label) in zip!(value.probabilities.iter(), value.labels.iter()) {
+            self.update_example(*probability, *label, 1.0);
+        }
+    }
+
+    /// Updates the metrics with each example counted `weights[i]` times.
+    pub fn update_weighted(&mut self, value: CalibrationMetricsInput, weights: ArrayView1<f32>) {
+        for (probability, label, weight) in zip!(
+            value.probabilities.iter(),
+            value.labels.iter(),
+            weights.iter()
+        ) {
+            self.update_example(*probability, *label, *weight);
+        }
+    }
+
+    fn update_example(&mut self, probability: f32, label: bool, weight: f32) {
+        let n_bins = self.bins.nrows();
+        let bin_index = (probability * n_bins.to_f32().unwrap())
+            .to_usize()
+            .unwrap_or(0)
+            .min(n_bins - 1);
+        let weight = weight.to_f64().unwrap();
+        let mut bin = self.bins.row_mut(bin_index);
+        bin[0] += weight;
+        bin[1] += weight * probability.to_f64().unwrap();
+        if label {
+            bin[2] += weight;
+        }
+    }
+
+    pub fn merge(&mut self, other: CalibrationMetrics) {
+        self.bins += &other.bins;
+    }
+
+    pub fn finalize(self) -> CalibrationMetricsOutput {
+        let total_weight = self.bins.column(0).sum();
+        let mut reliability_curve = Vec::new();
+        let mut expected_calibration_error = 0.0;
+        let mut maximum_calibration_error = 0.0f64;
+        for bin in self.bins.axis_iter(Axis(0)).filter(|bin| bin[0] > 0.0) {
+            let mean_predicted_probability = bin[1] / bin[0];
+            let fraction_of_positives = bin[2] / bin[0];
+            let calibration_error = (mean_predicted_probability - fraction_of_positives).abs();
+            expected_calibration_error += calibration_error * bin[0] / total_weight;
+            maximum_calibration_error = maximum_calibration_error.max(calibration_error);
+            reliability_curve.push(ReliabilityCurveBin {
+                mean_predicted_probability: mean_predicted_probability.to_f32().unwrap(),
+                fraction_of_positives: fraction_of_positives.to_f32().unwrap(),
+                weight: bin[0].to_f32().unwrap(),
+            });
+        }
+        CalibrationMetricsOutput {
+            reliability_curve,
+            expected_calibration_error: expected_calibration_error.to_f32().unwrap(),
+            maximum_calibration_error: maximum_calibration_error.to_f32().unwrap(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod test {
+    use crate::{CalibrationMetrics, CalibrationMetricsInput};
+    use ndarray::prelude::*;
+
+    #[test]
+    fn test_calibration_metrics() {
+        let probabilities = arr1(&[0.1, 0.1, 0.9, 0.9, 0.9, 0.9]);
+        let labels = arr1(&[false, false, true, true, true, false]);
+        let mut metrics = CalibrationMetrics::new(10);
+        metrics.update(CalibrationMetricsInput {
+            probabilities: probabilities.view(),
+            labels: labels.view(),
+        });
+        let output = metrics.finalize();
+        assert_eq!(output.reliability_curve.len(), 2);
+        assert_eq!(output.reliability_curve[0].fraction_of_positives, 0.0);
+        assert_eq!(output.reliability_curve[1].fraction_of_positives, 0.75);
+        assert_eq!(output.reliability_curve[1].weight, 4.0);
+        // The bins are off by 0.1 and 0.15, weighted by 2 and 4 examples.
+        assert!((output.expected_calibration_error - (0.1 * 2.0 + 0.15 * 4.0) / 6.0).abs() < 1e-6);
+        assert!((output.maximum_calibration_error - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_calibration_metrics_weighted() {
+        let probabilities = arr1(&[0.5, 0.5]);
+        let labels = arr1(&[true, false]);
+        let mut metrics = CalibrationMetrics::new(4);
+        metrics.update_weighted(
+            CalibrationMetricsInput {
+                probabilities: probabilities.view(),
+                labels: labels.view(),
+            },
+            arr1(&[3.0, 1.0]).view(),
+        );
+        let output = metrics.finalize();
+        assert_eq!(output.reliability_curve[0].fraction_of_positives, 0.75);
+        assert!((output.expected_calibration_error - 0.25).abs() < 1e-6);
+    }
+}
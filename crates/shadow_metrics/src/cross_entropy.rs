@@ -21,6 +21,10 @@ pub struct CrossEntropyOutput(pub Option<f32>);
 
 impl CrossEntropy {
     pub fn update(&mut self, value: CrossEntropyInput) {
+        self.update_weighted(value, 1.0)
+    }
+
+    pub fn update_weighted(&mut self, value: CrossEntropyInput, weight: f32) {
         let label = value.label.unwrap().get() - 1;
         let mut total = 0.0;
         for (index, &probability) in value.probabilities.indexed_iter() {
@@ -28,7 +32,7 @@ impl CrossEntropy {
                 total += -clamp(probability, std::f32::EPSILON, 1.0 - std::f32::EPSILON).ln();
             }
         }
-        self.0.update(total)
+        self.0.update_weighted(total, weight)
     }
 
     pub fn merge(&mut self, other: CrossEntropy) {
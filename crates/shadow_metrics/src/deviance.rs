@@ -0,0 +1,70 @@
+use super::mean::Mean;
+use num::clamp;
+
+/// The distribution whose unit deviance `MeanDeviance` computes.
+#[derive(Clone, Copy, Debug)]
+pub enum DevianceFamily {
+    Gaussian,
+    Poisson,
+    Gamma,
+    Bernoulli,
+}
+
+/// The mean unit deviance of the predictions, which is twice the difference between the log likelihood of a model that predicts every label exactly and the log likelihood of the predictions. For the gaussian family it is the mean squared error.
+#[derive(Debug)]
+pub struct MeanDeviance {
+    family: DevianceFamily,
+    mean: Mean,
+}
+
+pub struct MeanDevianceInput {
+    pub prediction: f32,
+    pub label: f32,
+}
+
+impl MeanDeviance {
+    pub fn new(family: DevianceFamily) -> MeanDeviance {
+        MeanDeviance {
+            family,
+            mean: Mean::new(),
+        }
+    }
+
+    pub fn update(&mut self, value: MeanDevianceInput) {
+        self.update_weighted(value, 1.0)
+    }
+
+    pub fn update_weighted(&mut self, value: MeanDevianceInput, weight: f32) {
+        let MeanDevianceInput { prediction, label } = value;
+        let deviance = match self.family {
+            DevianceFamily::Gaussian => (label - prediction) * (label - prediction),
+            DevianceFamily::Poisson => {
+                let prediction = prediction.max(f32::EPSILON);
+                let log_ratio = if label > 0.0 {
+                    label * (label / prediction).ln()
+                } else {
+                    0.0
+                };
+                2.0 * (log_ratio - (label - prediction))
+            }
+            DevianceFamily::Gamma => {
+                let prediction = prediction.max(f32::EPSILON);
+                let label = label.max(f32::EPSILON);
+                2.0 * (-(label / prediction).ln() + (label - prediction) / prediction)
+            }
+            DevianceFamily::Bernoulli => {
+                let prediction = clamp(prediction, f32::EPSILON, 1.0 - f32::EPSILON);
+                -2.0 * (label * prediction.ln() + (1.0 - label) * (1.0 - prediction).ln())
+            }
+        };
+        self.mean.update_weighted(deviance, weight);
+    }
+
+    pub fn merge(&mut self, other: MeanDeviance) {
+        self.mean.merge(other.mean)
+    }
+
+    pub fn finalize(self) -> Option<f32> {
+        self.mean.finalize()
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod test {
+    use crate::{DevianceFamily, MeanDeviance, MeanDevianceInput};
+
+    fn mean_deviance(family: DevianceFamily, examples: &[(f32, f32)]) -> f32 {
+        let mut metric = MeanDeviance::new(family);
+        for (prediction, label) in examples {
+            metric.update(MeanDevianceInput {
+                prediction: *prediction,
+                label: *label,
+            });
+        }
+        metric.finalize().unwrap()
+    }
+
+    #[test]
+    fn test_mean_deviance() {
+        // Predicting every label exactly has zero deviance.
+        let exact = [(1.0, 1.0), (3.0, 3.0)];
+        for family in [
+            DevianceFamily::Gaussian,
+            DevianceFamily::Poisson,
+            DevianceFamily::Gamma,
+        ] {
+            assert!(mean_deviance(family, &exact).abs() < 1e-6);
+        }
+        assert_eq!(mean_deviance(DevianceFamily::Gaussian, &[(1.0, 3.0)]), 4.0);
+        // For a label of zero, the Poisson deviance is twice the prediction.
+        assert_eq!(mean_deviance(DevianceFamily::Poisson, &[(1.5, 0.0)]), 3.0);
+        let bernoulli = mean_deviance(DevianceFamily::Bernoulli, &[(0.5, 1.0)]);
+        assert!((bernoulli - 2.0 * std::f32::consts::LN_2).abs() < 1e-6);
+    }
+}
@@ -6,15 +6,28 @@ pub use self::{
         BinaryClassificationMetricsOutput, BinaryClassificationMetricsOutputForThreshold,
     },
     binary_cross_entropy::{BinaryCrossEntropy, BinaryCrossEntropyInput},
+    calibration::{
+        CalibrationMetrics, CalibrationMetricsInput, CalibrationMetricsOutput,
+        ReliabilityCurveBin,
+    },
     cross_entropy::{CrossEntropy, CrossEntropyInput, CrossEntropyOutput},
+    deviance::{DevianceFamily, MeanDeviance, MeanDevianceInput},
     mean::Mean,
     mean_errors::MeanSquaredError,
     mean_variance::{m2_to_variance, merge_mean_m2, MeanVariance},
     mode::Mode,
+    multi_label_classification::{
+        MultiLabelClassificationMetrics, MultiLabelClassificationMetricsInput,
+        MultiLabelClassificationMetricsOutput,
+    },
     multiclass_classi::{
         ClassMetrics, MulticlassClassificationMetrics, MulticlassClassificationMetricsInput,
         MulticlassClassificationMetricsOutput,
     },
+    ordinal_classification::{
+        OrdinalClassificationMetrics, OrdinalClassificationMetricsInput,
+        OrdinalClassificationMetricsOutput,
+    },
     regression::{RegressionMetrics, RegressionMetricsInput, RegressionMetricsOutput},
 };
 
@@ -22,10 +35,26 @@ mod accuracy;
 mod auc_roc;
 mod binary_classification;
 mod binary_cross_entropy;
+mod calibration;
+#[cfg(test)]
+mod calibration_test;
 mod cross_entropy;
+mod deviance;
+#[cfg(test)]
+mod deviance_test;
 mod mean;
 mod mean_errors;
 mod mean_variance;
 mod mode;
+mod multi_label_classification;
+#[cfg(test)]
+mod multi_label_classification_test;
 mod multiclass_classi;
+mod ordinal_classification;
+#[cfg(test)]
+mod ordinal_classification_test;
 mod regression;
+#[cfg(test)]
+mod regression_test;
+#[cfg(test)]
+mod weighted_test;
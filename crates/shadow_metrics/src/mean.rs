@@ -2,12 +2,17 @@ use num::ToPrimitive;
 use std::num::NonZeroU64;
 
 #[derive(Debug, Clone, Default)]
-pub struct Mean(Option<(NonZeroU64, f64)>);
+pub struct Mean(Option<MeanInner>);
 
-fn merge(mean_a: f64, n_a: NonZeroU64, mean_b: f64, n_b: NonZeroU64) -> f64 {
-    let n_a = n_a.get().to_f64().unwrap();
-    let n_b = n_b.get().to_f64().unwrap();
-    ((n_a * mean_a) + (n_b * mean_b)) / (n_a + n_b)
+#[derive(Debug, Clone, Copy)]
+struct MeanInner {
+    n: NonZeroU64,
+    total_weight: f64,
+    mean: f64,
+}
+
+fn merge(mean_a: f64, weight_a: f64, mean_b: f64, weight_b: f64) -> f64 {
+    ((weight_a * mean_a) + (weight_b * mean_b)) / (weight_a + weight_b)
 }
 
 impl Mean {
@@ -28,31 +33,52 @@ impl Mean {
 
 impl Mean {
     pub fn update(&mut self, value: f32) {
-        let value = value as f64;
-        let one = NonZeroU64::new(1u64).unwrap();
-        self.0 = match self.0 {
-            None => Some((one, value)),
-            Some(n_mean) => {
-                let (n, mean) = n_mean;
-                let new_mean = merge(mean, n, value, one);
-                Some((NonZeroU64::new(n.get() + 1).unwrap(), new_mean))
-            }
-        }
+        self.update_weighted(value, 1.0)
+    }
+
+    /// Counts `value` `weight` times. A mean with only zero weights finalizes to the mean of the values with equal weights.
+    pub fn update_weighted(&mut self, value: f32, weight: f32) {
+        self.merge(Mean(Some(MeanInner {
+            n: NonZeroU64::new(1).unwrap(),
+            total_weight: weight.to_f64().unwrap(),
+            mean: value.to_f64().unwrap(),
+        })))
     }
 
     pub fn merge(&mut self, other: Mean) {
         self.0 = match (self.0, other.0) {
             (None, None) => None,
-            (None, Some((n, mean))) => Some((n, mean)),
-            (Some((n, mean)), None) => Some((n, mean)),
-            (Some((n_a, mean_a)), Some((n_b, mean_b))) => Some((
-                NonZeroU64::new(n_a.get() + n_b.get()).unwrap(),
-                merge(mean_a, n_a, mean_b, n_b),
-            )),
+            (None, Some(inner)) | (Some(inner), None) => Some(inner),
+            (Some(a), Some(b)) => {
+                let mean = if a.total_weight + b.total_weight == 0.0 {
+                    merge(
+                        a.mean,
+                        a.n.get().to_f64().unwrap(),
+                        b.mean,
+                        b.n.get().to_f64().unwrap(),
+                    )
+                } else {
+                    merge(a.mean, a.total_weight, b.mean, b.total_weight)
+                };
+                Some(MeanInner {
+                    n: NonZeroU64::new(a.n.get() + b.n.get()).unwrap(),
+                    total_weight: a.total_weight + b.total_weight,
+                    mean,
+                })
+            }
         };
     }
 
+    pub fn n(&self) -> u64 {
+        self.0.map(|inner| inner.n.get()).unwrap_or(0)
+    }
+
+    /// The sum of the weights of the values, which is `n` if every value was given weight 1.
+    pub fn total_weight(&self) -> f64 {
+        self.0.map(|inner| inner.total_weight).unwrap_or(0.0)
+    }
+
     pub fn finalize(self) -> Option<f32> {
-        self.0.map(|(_, mean)| mean.to_f32().unwrap())
+        self.0.map(|inner| inner.mean.to_f32().unwrap())
     }
 }
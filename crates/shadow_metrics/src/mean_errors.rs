@@ -11,7 +11,11 @@ impl MeanSquaredError {
 
 impl MeanSquaredError {
     pub fn update(&mut self, value: (f32, f32)) {
-        self.0.update((value.1 - value.0).powi(2))
+        self.update_weighted(value, 1.0)
+    }
+
+    pub fn update_weighted(&mut self, value: (f32, f32), weight: f32) {
+        self.0.update_weighted((value.1 - value.0).powi(2), weight)
     }
 
     pub fn merge(&mut self, other: MeanSquaredError) {
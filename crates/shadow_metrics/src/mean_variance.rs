@@ -4,6 +4,7 @@ use std::num::NonZeroU64;
 
 struct MeanVarianceInner {
     n: NonZeroU64,
+    total_weight: f64,
     m2: f64,
     mean: f64,
 }
@@ -29,39 +30,28 @@ impl MeanVariance {
 
 impl MeanVariance {
     pub fn update(&mut self, input: f32) {
-        match &mut self.0 {
-            Some(mean_variance) => {
-                let (mean, m2) = merge_mean_m2(
-                    mean_variance.n.get(),
-                    mean_variance.mean,
-                    mean_variance.m2,
-                    1,
-                    input as f64,
-                    0.0,
-                );
-                mean_variance.n = NonZeroU64::new(mean_variance.n.get() + 1).unwrap();
-                mean_variance.mean = mean;
-                mean_variance.m2 = m2;
-            }
-            None => {
-                self.0 = Some(MeanVarianceInner {
-                    n: NonZeroU64::new(1).unwrap(),
-                    mean: input as f64,
-                    m2: 0.0,
-                })
-            }
-        }
+        self.update_weighted(input, 1.0)
+    }
+
+    /// Counts `input` `weight` times. The weights must be positive.
+    pub fn update_weighted(&mut self, input: f32, weight: f32) {
+        self.merge(MeanVariance(Some(MeanVarianceInner {
+            n: NonZeroU64::new(1).unwrap(),
+            total_weight: weight as f64,
+            mean: input as f64,
+            m2: 0.0,
+        })))
     }
 
     pub fn merge(&mut self, other: MeanVariance) {
         match &mut self.0 {
             Some(mean_variance) => {
                 if let Some(other) = other.0 {
-                    let (mean, m2) = merge_mean_m2(
-                        mean_variance.n.get(),
+                    let (mean, m2) = merge_weighted_mean_m2(
+                        mean_variance.total_weight,
                         mean_variance.mean,
                         mean_variance.m2,
-                        other.n.get(),
+                        other.total_weight,
                         other.mean,
                         other.m2,
                     );
@@ -69,6 +59,7 @@ impl MeanVariance {
                     mean_variance.m2 = m2;
                     mean_variance.n =
                         NonZeroU64::new(mean_variance.n.get() + other.n.get()).unwrap();
+                    mean_variance.total_weight += other.total_weight;
                 }
             }
             None => {
@@ -81,7 +72,9 @@ impl MeanVariance {
         match self.0 {
             Some(mean_variance) => MeanVarianceOutput {
                 n: mean_variance.n.get(),
-                variance: m2_to_variance(mean_variance.m2, mean_variance.n),
+                variance: (mean_variance.m2 / mean_variance.total_weight)
+                    .to_f32()
+                    .unwrap(),
                 mean: mean_variance.mean.to_f32().unwrap(),
             },
             None => MeanVarianceOutput {
@@ -101,11 +94,28 @@ pub fn merge_mean_m2(
     mean_b: f64,
     m2_b: f64,
 ) -> (f64, f64) {
-    let n_a = n_a.to_f64().unwrap();
-    let n_b = n_b.to_f64().unwrap();
+    merge_weighted_mean_m2(
+        n_a.to_f64().unwrap(),
+        mean_a,
+        m2_a,
+        n_b.to_f64().unwrap(),
+        mean_b,
+        m2_b,
+    )
+}
+
+fn merge_weighted_mean_m2(
+    weight_a: f64,
+    mean_a: f64,
+    m2_a: f64,
+    weight_b: f64,
+    mean_b: f64,
+    m2_b: f64,
+) -> (f64, f64) {
     (
-        (((n_a * mean_a) + (n_b * mean_b)) / (n_a + n_b)),
-        m2_a + m2_b + (mean_b - mean_a) * (mean_b - mean_a) * (n_a * n_b / (n_a + n_b)),
+        (((weight_a * mean_a) + (weight_b * mean_b)) / (weight_a + weight_b)),
+        m2_a + m2_b
+            + (mean_b - mean_a) * (mean_b - mean_a) * (weight_a * weight_b / (weight_a + weight_b)),
     )
 }
 
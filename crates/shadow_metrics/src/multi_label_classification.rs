@@ -0,0 +1,160 @@
+use super::multiclass_classi::ClassMetrics;
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_zip::zip;
+
+/// Metrics for models that predict several binary labels for each example, with a probability threshold of 0.5.
+pub struct MultiLabelClassificationMetrics {
+    /// For each label, the numbers of true positives, false positives, true negatives and false negatives, in that order.
+    confusion_matrices: Array2<u64>,
+    /// The sums of the weights of the examples counted in `confusion_matrices`.
+    weighted_confusion_matrices: Array2<f64>,
+    /// The sum of the weights of the examples with every label predicted correctly.
+    exact_matches: f64,
+    total_weight: f64,
+}
+
+pub struct MultiLabelClassificationMetricsInput<'a> {
+    /// The probability that each label is true, with one row per example and one column per label.
+    pub probabilities: ArrayView2<'a, f32>,
+    pub labels: ArrayView2<'a, bool>,
+}
+
+#[derive(Debug)]
+pub struct MultiLabelClassificationMetricsOutput {
+    pub label_metrics: Vec<ClassMetrics>,
+    /// The fraction of labels predicted incorrectly.
+    pub hamming_loss: f32,
+    /// The fraction of examples with every label predicted correctly.
+    pub subset_accuracy: f32,
+    /// The F1 score of the true positives, false positives and false negatives summed over the labels.
+    pub micro_f1_score: f32,
+    /// The mean of the F1 scores of the labels, where labels with an undefined F1 score count as zero.
+    pub macro_f1_score: f32,
+}
+
+impl MultiLabelClassificationMetrics {
+    pub fn new(n_labels: usize) -> MultiLabelClassificationMetrics {
+        MultiLabelClassificationMetrics {
+            confusion_matrices: Array::zeros((n_labels, 4)),
+            weighted_confusion_matrices: Array::zeros((n_labels, 4)),
+            exact_matches: 0.0,
+            total_weight: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, value: MultiLabelClassificationMetricsInput) {
+        for (probabilities, labels) in zip!(
+            value.probabilities.axis_iter(Axis(0)),
+            value.labels.axis_iter(Axis(0))
+        ) {
+            self.update_example(probabilities, labels, 1.0);
+        }
+    }
+
+    /// Updates the metrics with each example counted `weights[i]` times.
+    pub fn update_weighted(
+        &mut self,
+        value: MultiLabelClassificationMetricsInput,
+        weights: ArrayView1<f32>,
+    ) {
+        for (probabilities, labels, weight) in zip!(
+            value.probabilities.axis_iter(Axis(0)),
+            value.labels.axis_iter(Axis(0)),
+            weights.iter()
+        ) {
+            self.update_example(probabilities, labels, *weight);
+        }
+    }
+
+    fn update_example(
+        &mut self,
+        probabilities: ArrayView1<f32>,
+        labels: ArrayView1<bool>,
+        weight: f32,
+    ) {
+        let weight = weight.to_f64().unwrap();
+        let mut exact_match = true;
+        for (mut confusion_matrix, mut weighted_confusion_matrix, probability, label) in zip!(
+            self.confusion_matrices.axis_iter_mut(Axis(0)),
+            self.weighted_confusion_matrices.axis_iter_mut(Axis(0)),
+            probabilities.iter(),
+            labels.iter()
+        ) {
+            let prediction = *probability >= 0.5;
+            let index = match (prediction, *label) {
+                (true, true) => 0,
+                (true, false) => 1,
+                (false, false) => 2,
+                (false, true) => 3,
+            };
+            confusion_matrix[index] += 1;
+            weighted_confusion_matrix[index] += weight;
+            exact_match &= prediction == *label;
+        }
+        if exact_match {
+            self.exact_matches += weight;
+        }
+        self.total_weight += weight;
+    }
+
+    pub fn merge(&mut self, other: MultiLabelClassificationMetrics) {
+        self.confusion_matrices += &other.confusion_matrices;
+        self.weighted_confusion_matrices += &other.weighted_confusion_matrices;
+        self.exact_matches += other.exact_matches;
+        self.total_weight += other.total_weight;
+    }
+
+    pub fn finalize(self) -> MultiLabelClassificationMetricsOutput {
+        let n_labels = self.confusion_matrices.nrows();
+        let label_metrics: Vec<_> = zip!(
+            self.confusion_matrices.axis_iter(Axis(0)),
+            self.weighted_confusion_matrices.axis_iter(Axis(0))
+        )
+        .map(|(confusion_matrix, weighted_confusion_matrix)| {
+            let true_positives = weighted_confusion_matrix[0].to_f32().unwrap();
+            let false_positives = weighted_confusion_matrix[1].to_f32().unwrap();
+            let true_negatives = weighted_confusion_matrix[2].to_f32().unwrap();
+            let false_negatives = weighted_confusion_matrix[3].to_f32().unwrap();
+            let accuracy = (true_positives + true_negatives)
+                / (true_positives + false_positives + true_negatives + false_negatives);
+            let precision = true_positives / (true_positives + false_positives);
+            let recall = true_positives / (true_positives + false_negatives);
+            let f1_score = 2.0 * (precision * recall) / (precision + recall);
+            ClassMetrics {
+                true_positives: confusion_matrix[0],
+                false_positives: confusion_matrix[1],
+                true_negatives: confusion_matrix[2],
+                false_negatives: confusion_matrix[3],
+                weighted_true_positives: true_positives,
+                weighted_false_positives: false_positives,
+                weighted_true_negatives: true_negatives,
+                weighted_false_negatives: false_negatives,
+                accuracy,
+                precision,
+                recall,
+                f1_score,
+            }
+        })
+        .collect();
+        let totals = self.weighted_confusion_matrices.sum_axis(Axis(0));
+        let hamming_loss = ((totals[1] + totals[3]) / totals.sum()).to_f32().unwrap();
+        let subset_accuracy = (self.exact_matches / self.total_weight).to_f32().unwrap();
+        let micro_f1_score = (2.0 * totals[0] / (2.0 * totals[0] + totals[1] + totals[3]))
+            .to_f32()
+            .unwrap();
+        let macro_f1_score = label_metrics
+            .iter()
+            .map(|label_metrics| label_metrics.f1_score)
+            .filter(|f1_score| f1_score.is_finite())
+            .sum::<f32>()
+            / n_labels.to_f32().unwrap();
+        MultiLabelClassificationMetricsOutput {
+            label_metrics,
+            hamming_loss,
+            subset_accuracy,
+            micro_f1_score,
+            macro_f1_score,
+        }
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod test {
+    use crate::{MultiLabelClassificationMetrics, MultiLabelClassificationMetricsInput};
+    use ndarray::prelude::*;
+
+    #[test]
+    fn test_multi_label_classification_metrics() {
+        let probabilities = arr2(&[[0.9, 0.1], [0.8, 0.7], [0.2, 0.6]]);
+        let labels = arr2(&[[true, false], [true, false], [false, true]]);
+        let mut metrics = MultiLabelClassificationMetrics::new(2);
+        metrics.update(MultiLabelClassificationMetricsInput {
+            probabilities: probabilities.view(),
+            labels: labels.view(),
+        });
+        let metrics = metrics.finalize();
+        // Only the second label of the second example is wrong.
+        assert!((metrics.hamming_loss - 1.0 / 6.0).abs() < 1e-6);
+        assert!((metrics.subset_accuracy - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(metrics.label_metrics[0].f1_score, 1.0);
+        assert!((metrics.label_metrics[1].f1_score - 2.0 / 3.0).abs() < 1e-6);
+        assert!((metrics.micro_f1_score - 0.8571429).abs() < 1e-6);
+        assert!((metrics.macro_f1_score - 5.0 / 6.0).abs() < 1e-6);
+    }
+}
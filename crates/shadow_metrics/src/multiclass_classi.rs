@@ -5,6 +5,8 @@ use std::num::NonZeroUsize;
 
 pub struct MulticlassClassificationMetrics {
     confusion_matrix: Array2<u64>,
+    /// The entry at (prediction, label) is the sum of the weights of the examples counted in `confusion_matrix` at the same position.
+    weighted_confusion_matrix: Array2<f64>,
 }
 
 pub struct MulticlassClassificationMetricsInput<'a> {
@@ -18,6 +20,11 @@ pub struct ClassMetrics {
     pub false_positives: u64,
     pub true_negatives: u64,
     pub false_negatives: u64,
+    /// The sums of the weights of the examples, which equal the counts when every weight is 1. The other metrics are computed from these.
+    pub weighted_true_positives: f32,
+    pub weighted_false_positives: f32,
+    pub weighted_true_negatives: f32,
+    pub weighted_false_negatives: f32,
     pub accuracy: f32,
     pub precision: f32,
     pub recall: f32,
@@ -38,43 +45,82 @@ pub struct MulticlassClassificationMetricsOutput {
 impl MulticlassClassificationMetrics {
     pub fn new(n_classes: usize) -> MulticlassClassificationMetrics {
         let confusion_matrix = Array::zeros((n_classes, n_classes));
-        MulticlassClassificationMetrics { confusion_matrix }
+        let weighted_confusion_matrix = Array::zeros((n_classes, n_classes));
+        MulticlassClassificationMetrics {
+            confusion_matrix,
+            weighted_confusion_matrix,
+        }
     }
 
     pub fn update(&mut self, value: MulticlassClassificationMetricsInput) {
         for (label, probabilities) in
         zip!(value.labels.iter(), value.probabilities.axis_iter(Axis(0)))
         {
-            let prediction = probabilities
-                .iter()
-                .enumerate()
-                .max_by(|(_, a), (_, b)| {
-                    if a.is_finite() && b.is_finite() {
-                        a.partial_cmp(b).unwrap()
-                    } else if a.is_finite() {
-                        std::cmp::Ordering::Greater
-                    } else {
-                        std::cmp::Ordering::Less
-                    }
-                })
-                .unwrap()
-                .0;
+            self.update_example(probabilities, *label, 1.0);
+        }
+    }
 
-            let label = label.unwrap().get() - 1;
-            self.confusion_matrix[(prediction, label)] += 1;
+    /// Updates the metrics with each example counted `weights[i]` times.
+    pub fn update_weighted(
+        &mut self,
+        value: MulticlassClassificationMetricsInput,
+        weights: ArrayView1<f32>,
+    ) {
+        for (label, probabilities, weight) in zip!(
+            value.labels.iter(),
+            value.probabilities.axis_iter(Axis(0)),
+            weights.iter()
+        ) {
+            self.update_example(probabilities, *label, *weight);
         }
     }
 
+    fn update_example(
+        &mut self,
+        probabilities: ArrayView1<f32>,
+        label: Option<NonZeroUsize>,
+        weight: f32,
+    ) {
+        let prediction = probabilities
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                if a.is_finite() && b.is_finite() {
+                    a.partial_cmp(b).unwrap()
+                } else if a.is_finite() {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Less
+                }
+            })
+            .unwrap()
+            .0;
+
+        let label = label.unwrap().get() - 1;
+        self.confusion_matrix[(prediction, label)] += 1;
+        self.weighted_confusion_matrix[(prediction, label)] += weight.to_f64().unwrap();
+    }
+
     pub fn merge(&mut self, other: MulticlassClassificationMetrics) {
         self.confusion_matrix += &other.confusion_matrix;
+        self.weighted_confusion_matrix += &other.weighted_confusion_matrix;
     }
 
     pub fn finalize(self) -> MulticlassClassificationMetricsOutput {
         let n_classes = self.confusion_matrix.nrows();
-        let n_examples = self.confusion_matrix.sum();
-        let confusion_matrix = self.confusion_matrix;
+        let counts = self.confusion_matrix;
+        let n_examples_count = counts.sum();
+        let confusion_matrix = self.weighted_confusion_matrix;
+        let n_examples = confusion_matrix.sum();
         let class_metrics: Vec<_> = (0..n_classes)
             .map(|class_index| {
+                let true_positives_count = counts[(class_index, class_index)];
+                let false_positives_count = counts.row(class_index).sum() - true_positives_count;
+                let false_negatives_count = counts.column(class_index).sum() - true_positives_count;
+                let true_negatives_count = n_examples_count
+                    - true_positives_count
+                    - false_positives_count
+                    - false_negatives_count;
                 let true_positives = confusion_matrix[(class_index, class_index)];
                 let false_positives = confusion_matrix.row(class_index).sum() - true_positives;
                 let false_negatives = confusion_matrix.column(class_index).sum() - true_positives;
@@ -88,10 +134,14 @@ impl MulticlassClassificationMetrics {
                     / (true_positives + false_negatives).to_f32().unwrap();
                 let f1_score = 2.0 * (precision * recall) / (precision + recall);
                 ClassMetrics {
-                    true_positives,
-                    false_positives,
-                    true_negatives,
-                    false_negatives,
+                    true_positives: true_positives_count,
+                    false_positives: false_positives_count,
+                    true_negatives: true_negatives_count,
+                    false_negatives: false_negatives_count,
+                    weighted_true_positives: true_positives.to_f32().unwrap(),
+                    weighted_false_positives: false_positives.to_f32().unwrap(),
+                    weighted_true_negatives: true_negatives.to_f32().unwrap(),
+                    weighted_false_negatives: false_negatives.to_f32().unwrap(),
                     accuracy,
                     precision,
                     recall,
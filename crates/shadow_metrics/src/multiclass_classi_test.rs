@@ -48,6 +48,10 @@ mod test {
              false_positives: 2,
              true_negatives: 3,
              false_negatives: 3,
+             weighted_true_positives: 5.0,
+             weighted_false_positives: 2.0,
+             weighted_true_negatives: 3.0,
+             weighted_false_negatives: 3.0,
              accuracy: 0.61538464,
              precision: 0.71428573,
              recall: 0.625,
@@ -58,6 +62,10 @@ mod test {
              false_positives: 3,
              true_negatives: 5,
              false_negatives: 2,
+             weighted_true_positives: 3.0,
+             weighted_false_positives: 3.0,
+             weighted_true_negatives: 5.0,
+             weighted_false_negatives: 2.0,
              accuracy: 0.61538464,
              precision: 0.5,
              recall: 0.6,
@@ -152,6 +160,10 @@ mod test {
              false_positives: 2,
              true_negatives: 17,
              false_negatives: 3,
+             weighted_true_positives: 5.0,
+             weighted_false_positives: 2.0,
+             weighted_true_negatives: 17.0,
+             weighted_false_negatives: 3.0,
              accuracy: 0.8148148,
              precision: 0.71428573,
              recall: 0.625,
@@ -162,6 +174,10 @@ mod test {
              false_positives: 5,
              true_negatives: 16,
              false_negatives: 3,
+             weighted_true_positives: 3.0,
+             weighted_false_positives: 5.0,
+             weighted_true_negatives: 16.0,
+             weighted_false_negatives: 3.0,
              accuracy: 0.7037037,
              precision: 0.375,
              recall: 0.5,
@@ -172,6 +188,10 @@ mod test {
              false_positives: 1,
              true_negatives: 13,
              false_negatives: 2,
+             weighted_true_positives: 11.0,
+             weighted_false_positives: 1.0,
+             weighted_true_negatives: 13.0,
+             weighted_false_negatives: 2.0,
              accuracy: 0.8888889,
              precision: 0.9166667,
              recall: 0.84615386,
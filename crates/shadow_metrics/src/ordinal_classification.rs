@@ -0,0 +1,107 @@
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use shadow_zip::zip;
+use std::num::NonZeroUsize;
+
+/// Metrics for classifiers whose classes are ordered, which take into account how far the predicted class is from the label. The predicted class is the one with the highest probability.
+pub struct OrdinalClassificationMetrics {
+    /// The entry at (prediction, label) is the sum of the weights of the examples.
+    confusion_matrix: Array2<f64>,
+}
+
+pub struct OrdinalClassificationMetricsInput<'a> {
+    pub probabilities: ArrayView2<'a, f32>,
+    pub labels: ArrayView1<'a, Option<NonZeroUsize>>,
+}
+
+#[derive(Debug)]
+pub struct OrdinalClassificationMetricsOutput {
+    pub accuracy: f32,
+    /// The mean absolute difference between the indexes of the predicted class and the label.
+    pub mae: f32,
+    /// Cohen's kappa with disagreements weighted by the squared difference between the indexes of the classes, which is 1 for perfect predictions and 0 for predictions no better than chance.
+    pub quadratic_weighted_kappa: f32,
+}
+
+impl OrdinalClassificationMetrics {
+    pub fn new(n_classes: usize) -> OrdinalClassificationMetrics {
+        OrdinalClassificationMetrics {
+            confusion_matrix: Array::zeros((n_classes, n_classes)),
+        }
+    }
+
+    pub fn update(&mut self, value: OrdinalClassificationMetricsInput) {
+        for (probabilities, label) in
+            zip!(value.probabilities.axis_iter(Axis(0)), value.labels.iter())
+        {
+            self.update_example(probabilities, *label, 1.0);
+        }
+    }
+
+    /// Updates the metrics with each example counted `weights[i]` times.
+    pub fn update_weighted(
+        &mut self,
+        value: OrdinalClassificationMetricsInput,
+        weights: ArrayView1<f32>,
+    ) {
+        for (probabilities, label, weight) in zip!(
+            value.probabilities.axis_iter(Axis(0)),
+            value.labels.iter(),
+            weights.iter()
+        ) {
+            self.update_example(probabilities, *label, *weight);
+        }
+    }
+
+    fn update_example(
+        &mut self,
+        probabilities: ArrayView1<f32>,
+        label: Option<NonZeroUsize>,
+        weight: f32,
+    ) {
+        let prediction = probabilities
+            .iter()
+            .enumerate()
+            .fold((0, f32::NEG_INFINITY), |best, (index, probability)| {
+                if *probability > best.1 {
+                    (index, *probability)
+                } else {
+                    best
+                }
+            })
+            .0;
+        let label = label.unwrap().get() - 1;
+        self.confusion_matrix[(prediction, label)] += weight.to_f64().unwrap();
+    }
+
+    pub fn merge(&mut self, other: OrdinalClassificationMetrics) {
+        self.confusion_matrix += &other.confusion_matrix;
+    }
+
+    pub fn finalize(self) -> OrdinalClassificationMetricsOutput {
+        let max_distance = (self.confusion_matrix.nrows() - 1).max(1).to_f64().unwrap();
+        let total_weight = self.confusion_matrix.sum();
+        let predictions_per_class = self.confusion_matrix.sum_axis(Axis(1));
+        let labels_per_class = self.confusion_matrix.sum_axis(Axis(0));
+        let mut absolute_error = 0.0;
+        let mut observed_disagreement = 0.0;
+        let mut expected_disagreement = 0.0;
+        for ((prediction, label), weight) in self.confusion_matrix.indexed_iter() {
+            let distance = prediction.abs_diff(label).to_f64().unwrap();
+            absolute_error += distance * weight;
+            let disagreement_weight = distance * distance / (max_distance * max_distance);
+            observed_disagreement += disagreement_weight * weight;
+            expected_disagreement +=
+                disagreement_weight * predictions_per_class[prediction] * labels_per_class[label]
+                    / total_weight;
+        }
+        let accuracy = self.confusion_matrix.diag().sum() / total_weight;
+        let mae = absolute_error / total_weight;
+        let quadratic_weighted_kappa = 1.0 - observed_disagreement / expected_disagreement;
+        OrdinalClassificationMetricsOutput {
+            accuracy: accuracy.to_f32().unwrap(),
+            mae: mae.to_f32().unwrap(),
+            quadratic_weighted_kappa: quadratic_weighted_kappa.to_f32().unwrap(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod test {
+    use crate::{OrdinalClassificationMetrics, OrdinalClassificationMetricsInput};
+    use ndarray::prelude::*;
+    use std::num::NonZeroUsize;
+
+    fn compute(
+        predictions: &[usize],
+        labels: &[usize],
+    ) -> crate::OrdinalClassificationMetricsOutput {
+        let mut probabilities = Array::zeros((predictions.len(), 3));
+        for (i, prediction) in predictions.iter().enumerate() {
+            probabilities[(i, *prediction)] = 1.0;
+        }
+        let labels: Array1<_> = labels
+            .iter()
+            .map(|label| NonZeroUsize::new(label + 1))
+            .collect();
+        let mut metrics = OrdinalClassificationMetrics::new(3);
+        metrics.update(OrdinalClassificationMetricsInput {
+            probabilities: probabilities.view(),
+            labels: labels.view(),
+        });
+        metrics.finalize()
+    }
+
+    #[test]
+    fn test_ordinal_classification_metrics() {
+        let perfect = compute(&[0, 1, 2, 1], &[0, 1, 2, 1]);
+        assert_eq!(perfect.accuracy, 1.0);
+        assert_eq!(perfect.mae, 0.0);
+        assert_eq!(perfect.quadratic_weighted_kappa, 1.0);
+        // Both predictions are wrong, but being off by one class is better than being off by two.
+        let near = compute(&[1, 1, 1, 2], &[0, 1, 2, 2]);
+        let far = compute(&[2, 1, 0, 2], &[0, 1, 2, 2]);
+        assert_eq!(near.accuracy, far.accuracy);
+        assert_eq!(near.mae, 0.5);
+        assert_eq!(far.mae, 1.0);
+        assert!(near.quadratic_weighted_kappa > far.quadratic_weighted_kappa);
+    }
+}
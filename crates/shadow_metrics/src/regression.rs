@@ -6,6 +6,7 @@ pub struct RegressionMetrics {
     mean_variance: MeanVariance,
     absolute_error: f64,
     squared_error: f64,
+    total_weight: f64,
 }
 
 pub struct RegressionMetricsInput<'a> {
@@ -27,6 +28,7 @@ impl Default for RegressionMetrics {
             mean_variance: MeanVariance::default(),
             absolute_error: 0.0,
             squared_error: 0.0,
+            total_weight: 0.0,
         }
     }
 }
@@ -38,26 +40,43 @@ impl RegressionMetrics {
 
     pub fn update(&mut self, input: RegressionMetricsInput) {
         for (prediction, label) in zip!(input.predictions.iter(), input.labels.iter()) {
-            self.mean_variance.update(*label);
-            let absolute_error = prediction - label;
-            let squared_error = absolute_error * absolute_error;
-            self.absolute_error += absolute_error as f64;
-            self.squared_error += squared_error as f64;
+            self.update_example(*prediction, *label, 1.0);
         }
     }
 
+    /// Updates the metrics with each example counted `weights[i]` times.
+    pub fn update_weighted(&mut self, input: RegressionMetricsInput, weights: &[f32]) {
+        for (prediction, label, weight) in zip!(
+            input.predictions.iter(),
+            input.labels.iter(),
+            weights.iter()
+        ) {
+            self.update_example(*prediction, *label, *weight);
+        }
+    }
+
+    fn update_example(&mut self, prediction: f32, label: f32, weight: f32) {
+        self.mean_variance.update_weighted(label, weight);
+        let absolute_error = (prediction - label).abs();
+        let squared_error = absolute_error * absolute_error;
+        self.absolute_error += weight as f64 * absolute_error as f64;
+        self.squared_error += weight as f64 * squared_error as f64;
+        self.total_weight += weight as f64;
+    }
+
     pub fn merge(&mut self, other: RegressionMetrics) {
         self.mean_variance.merge(other.mean_variance);
         self.absolute_error += other.absolute_error;
         self.squared_error += other.squared_error;
+        self.total_weight += other.total_weight;
     }
 
     pub fn finalize(self) -> RegressionMetricsOutput {
-        let MeanVarianceOutput { variance, n, .. } = self.mean_variance.finalize();
-        let mae = self.absolute_error / n.to_f64().unwrap();
-        let mse = self.squared_error / n.to_f64().unwrap();
+        let MeanVarianceOutput { variance, .. } = self.mean_variance.finalize();
+        let mae = self.absolute_error / self.total_weight;
+        let mse = self.squared_error / self.total_weight;
         let rmse = mse.sqrt();
-        let r2 = 1.0 - self.squared_error / (variance as f64 * n.to_f64().unwrap());
+        let r2 = 1.0 - self.squared_error / (variance as f64 * self.total_weight);
         RegressionMetricsOutput {
             mae: mae.to_f32().unwrap(),
             mse: mse.to_f32().unwrap(),
@@ -0,0 +1,18 @@
+#[cfg(test)]
+mod test {
+    use crate::{RegressionMetrics, RegressionMetricsInput};
+
+    #[test]
+    fn test_mae_of_opposite_signed_errors() {
+        let mut metrics = RegressionMetrics::new();
+        metrics.update(RegressionMetricsInput {
+            predictions: &[3.0, 1.0],
+            labels: &[1.0, 3.0],
+        });
+        let metrics = metrics.finalize();
+        // Errors of 2 and -2 do not cancel out.
+        assert_eq!(metrics.mae, 2.0);
+        assert_eq!(metrics.mse, 4.0);
+        assert_eq!(metrics.rmse, 2.0);
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        BinaryClassificationMetrics, BinaryClassificationMetricsInput, Mean, MeanVariance,
+        RegressionMetrics, RegressionMetricsInput,
+    };
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_weighted_mean_variance() {
+        // Weighting a value by 2 is the same as including it twice.
+        let mut weighted = MeanVariance::default();
+        weighted.update_weighted(1.0, 2.0);
+        weighted.update_weighted(4.0, 1.0);
+        let weighted = weighted.finalize();
+        let repeated = MeanVariance::compute([1.0, 1.0, 4.0]);
+        assert_eq!(weighted.mean, repeated.mean);
+        assert!((weighted.variance - repeated.variance).abs() < 1e-6);
+        let mut mean = Mean::new();
+        mean.update_weighted(1.0, 2.0);
+        mean.update_weighted(4.0, 1.0);
+        assert_eq!(mean.total_weight(), 3.0);
+        assert_eq!(mean.finalize(), Some(2.0));
+    }
+
+    #[test]
+    fn test_weighted_regression_metrics() {
+        let mut metrics = RegressionMetrics::new();
+        metrics.update_weighted(
+            RegressionMetricsInput {
+                predictions: &[1.0, 2.0, 10.0],
+                labels: &[2.0, 2.0, 0.0],
+            },
+            &[3.0, 1.0, 0.0],
+        );
+        let metrics = metrics.finalize();
+        assert_eq!(metrics.mae, 0.75);
+        assert_eq!(metrics.mse, 0.75);
+    }
+
+    #[test]
+    fn test_weighted_binary_classification_metrics() {
+        let mut metrics = BinaryClassificationMetrics::new(1);
+        metrics.update_weighted(
+            BinaryClassificationMetricsInput {
+                probabilities: &[0.9, 0.2, 0.7],
+                labels: &[
+                    NonZeroUsize::new(2),
+                    NonZeroUsize::new(2),
+                    NonZeroUsize::new(1),
+                ],
+            },
+            &[2.0, 0.5, 1.5],
+        );
+        let metrics = metrics.finalize();
+        let threshold = &metrics.thresholds[0];
+        assert_eq!(threshold.true_positives, 1);
+        assert_eq!(threshold.false_negatives, 1);
+        assert_eq!(threshold.false_positives, 1);
+        assert_eq!(threshold.weighted_true_positives, 2.0);
+        assert_eq!(threshold.weighted_false_negatives, 0.5);
+        assert_eq!(threshold.weighted_false_positives, 1.5);
+        assert_eq!(threshold.accuracy, 0.5);
+    }
+}
@@ -1 +1,6 @@
-pub mod model_train_options;
\ No newline at end of file
+pub mod model_train_options;
+#[cfg(test)]
+mod model_train_options_test;
+pub mod predict;
+#[cfg(test)]
+mod predict_test;
@@ -1,10 +1,13 @@
+use num::ToPrimitive;
+use shadow_linear::serialize::{Versioned, VersionedReader};
+
 #[derive(buffalo::Read, buffalo::Write)]
 #[buffalo(size = "static", value_size = 8)]
 pub enum ModelTrainOptions {
 	#[buffalo(id = 0, required)]
-	Linear(LinearModelTrainOptions),
+	Linear(Versioned<LinearModelTrainOptions>),
 	#[buffalo(id = 1, required)]
-	Tree(TreeModelTrainOptions),
+	Tree(Versioned<TreeModelTrainOptions>),
 }
 
 #[derive(buffalo::Read, buffalo::Write)]
@@ -21,7 +24,207 @@ pub struct LinearModelTrainOptions {
 	#[buffalo(id = 4, required)]
 	pub n_examples_per_batch: u64,
 	#[buffalo(id = 5, required)]
-	pub early_stopping_options: Option<LinearEarlyStoppingOptions>,
+	pub early_stopping_options: Option<Versioned<LinearEarlyStoppingOptions>>,
+	/// Options saved before the seed, shuffle and deterministic fields were added do not have them and are read with the defaults.
+	#[buffalo(id = 6)]
+	pub seed: u64,
+	#[buffalo(id = 7)]
+	pub shuffle: bool,
+	#[buffalo(id = 8)]
+	pub deterministic: bool,
+	/// Options saved before the optimizer and learning rate schedule were configurable were trained with SGD at a constant learning rate.
+	#[buffalo(id = 9)]
+	pub optimizer: LinearOptimizer,
+	#[buffalo(id = 10)]
+	pub learning_rate_schedule: LinearLearningRateSchedule,
+	/// Options saved before the L1 penalty was added were trained without it.
+	#[buffalo(id = 11)]
+	pub l1_regularization: f32,
+	/// Options saved before the loss was configurable were trained with the squared error.
+	#[buffalo(id = 12)]
+	pub regression_loss: LinearRegressionLoss,
+	/// Options saved before class weights were added weighted every class equally.
+	#[buffalo(id = 13)]
+	pub class_weights: LinearClassWeights,
+	/// Options saved before GLMs were added have no GLM options and are read with the Gaussian family and its canonical link.
+	#[buffalo(id = 14)]
+	pub glm_options: LinearGlmOptions,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 8)]
+pub enum LinearOptimizer {
+	#[buffalo(id = 0)]
+	Sgd,
+	#[buffalo(id = 1, required)]
+	Momentum(LinearMomentumOptions),
+	#[buffalo(id = 2, required)]
+	Adam(LinearAdamOptions),
+	#[buffalo(id = 3, required)]
+	AdaGrad(LinearAdaGradOptions),
+	#[buffalo(id = 4, required)]
+	Ftrl(LinearFtrlOptions),
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearMomentumOptions {
+	#[buffalo(id = 0, required)]
+	pub momentum: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearAdamOptions {
+	#[buffalo(id = 0, required)]
+	pub beta_1: f32,
+	#[buffalo(id = 1, required)]
+	pub beta_2: f32,
+	#[buffalo(id = 2, required)]
+	pub epsilon: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearAdaGradOptions {
+	#[buffalo(id = 0, required)]
+	pub epsilon: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearFtrlOptions {
+	#[buffalo(id = 0, required)]
+	pub beta: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 8)]
+pub enum LinearLearningRateSchedule {
+	#[buffalo(id = 0)]
+	Constant,
+	#[buffalo(id = 1, required)]
+	Step(LinearStepLearningRateScheduleOptions),
+	#[buffalo(id = 2, required)]
+	Exponential(LinearExponentialLearningRateScheduleOptions),
+	#[buffalo(id = 3, required)]
+	Cosine(LinearCosineLearningRateScheduleOptions),
+	#[buffalo(id = 4, required)]
+	Warmup(LinearWarmupLearningRateScheduleOptions),
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearStepLearningRateScheduleOptions {
+	#[buffalo(id = 0, required)]
+	pub step_size: u64,
+	#[buffalo(id = 1, required)]
+	pub gamma: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearExponentialLearningRateScheduleOptions {
+	#[buffalo(id = 0, required)]
+	pub gamma: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearCosineLearningRateScheduleOptions {
+	#[buffalo(id = 0, required)]
+	pub min_learning_rate: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearWarmupLearningRateScheduleOptions {
+	#[buffalo(id = 0, required)]
+	pub n_warmup_epochs: u64,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 8)]
+pub enum LinearClassWeights {
+	#[buffalo(id = 0)]
+	Uniform,
+	#[buffalo(id = 1)]
+	Balanced,
+	#[buffalo(id = 2, required)]
+	Custom(Vec<f32>),
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 8)]
+pub enum LinearRegressionLoss {
+	#[buffalo(id = 0)]
+	SquaredError,
+	#[buffalo(id = 1, required)]
+	Huber(LinearHuberLossOptions),
+	#[buffalo(id = 2, required)]
+	Quantile(LinearQuantileLossOptions),
+	#[buffalo(id = 3)]
+	Poisson,
+	#[buffalo(id = 4, required)]
+	Tweedie(LinearTweedieLossOptions),
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearHuberLossOptions {
+	#[buffalo(id = 0, required)]
+	pub delta: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearQuantileLossOptions {
+	#[buffalo(id = 0, required)]
+	pub quantile: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearTweedieLossOptions {
+	#[buffalo(id = 0, required)]
+	pub variance_power: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct LinearGlmOptions {
+	#[buffalo(id = 0, required)]
+	pub family: LinearGlmFamily,
+	#[buffalo(id = 1, required)]
+	pub link: Option<LinearLinkFunction>,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 0)]
+pub enum LinearGlmFamily {
+	#[buffalo(id = 0)]
+	Gaussian,
+	#[buffalo(id = 1)]
+	Poisson,
+	#[buffalo(id = 2)]
+	Gamma,
+	#[buffalo(id = 3)]
+	Bernoulli,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 0)]
+pub enum LinearLinkFunction {
+	#[buffalo(id = 0)]
+	Identity,
+	#[buffalo(id = 1)]
+	Log,
+	#[buffalo(id = 2)]
+	Logit,
+	#[buffalo(id = 3)]
+	Inverse,
+	#[buffalo(id = 4)]
+	Sqrt,
 }
 
 #[derive(buffalo::Read, buffalo::Write)]
@@ -33,6 +236,29 @@ pub struct LinearEarlyStoppingOptions {
 	pub n_rounds_without_improvement_to_stop: u64,
 	#[buffalo(id = 2, required)]
 	pub min_decrease_in_loss_for_significant_change: f32,
+	/// Options saved before the metric was configurable stopped on the loss.
+	#[buffalo(id = 3)]
+	pub metric: LinearEarlyStoppingMetric,
+}
+
+/// A custom metric is a function, so only the fact that one was used is recorded.
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 0)]
+pub enum LinearEarlyStoppingMetric {
+	#[buffalo(id = 0)]
+	Loss,
+	#[buffalo(id = 1)]
+	MeanSquaredError,
+	#[buffalo(id = 2)]
+	MeanAbsoluteError,
+	#[buffalo(id = 3)]
+	Accuracy,
+	#[buffalo(id = 4)]
+	F1Score,
+	#[buffalo(id = 5)]
+	AucRoc,
+	#[buffalo(id = 6)]
+	Custom,
 }
 
 #[derive(buffalo::Read, buffalo::Write)]
@@ -68,6 +294,27 @@ pub struct TreeModelTrainOptions {
 	pub min_sum_hessians_per_node: f32,
 	#[buffalo(id = 14, required)]
 	pub smoothing_factor_for_discrete_bin_sorting: f32,
+	/// Options saved before sampling and DART were added trained every round on all of the examples and features.
+	#[buffalo(id = 15)]
+	pub seed: u64,
+	#[buffalo(id = 16)]
+	pub bagging_fraction: f32,
+	#[buffalo(id = 17)]
+	pub goss_options: Option<TreeGossOptions>,
+	#[buffalo(id = 18)]
+	pub feature_fraction_per_tree: f32,
+	#[buffalo(id = 19)]
+	pub feature_fraction_per_node: f32,
+	#[buffalo(id = 20)]
+	pub dart_options: Option<TreeDartOptions>,
+	/// Options saved before one-vs-rest splits were added are read with the default limit of 4 variants.
+	#[buffalo(id = 21)]
+	pub max_variants_for_one_vs_rest_splits: u64,
+	/// Options saved before the growth policy and pruning were added grew trees leaf-wise without pruning.
+	#[buffalo(id = 22)]
+	pub growth_policy: TreeGrowthPolicy,
+	#[buffalo(id = 23)]
+	pub cost_complexity_pruning_alpha: f32,
 }
 
 #[derive(buffalo::Read, buffalo::Write)]
@@ -79,6 +326,15 @@ pub enum BinnedFeaturesLayout {
 	ColumnMajor,
 }
 
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "static", value_size = 0)]
+pub enum TreeGrowthPolicy {
+	#[buffalo(id = 0)]
+	LeafWise,
+	#[buffalo(id = 1)]
+	DepthWise,
+}
+
 #[derive(buffalo::Read, buffalo::Write)]
 #[buffalo(size = "dynamic")]
 pub struct TreeEarlyStoppingOptions {
@@ -88,4 +344,334 @@ pub struct TreeEarlyStoppingOptions {
 	pub n_rounds_without_improvement_to_stop: u64,
 	#[buffalo(id = 2, required)]
 	pub min_decrease_in_loss_for_significant_change: f32,
-}
\ No newline at end of file
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct TreeGossOptions {
+	#[buffalo(id = 0, required)]
+	pub top_fraction: f32,
+	#[buffalo(id = 1, required)]
+	pub other_fraction: f32,
+}
+
+#[derive(buffalo::Read, buffalo::Write)]
+#[buffalo(size = "dynamic")]
+pub struct TreeDartOptions {
+	#[buffalo(id = 0, required)]
+	pub drop_rate: f32,
+	#[buffalo(id = 1, required)]
+	pub max_dropped_rounds: Option<u64>,
+	#[buffalo(id = 2, required)]
+	pub skip_drop_rate: f32,
+}
+/// Reads linear train options. Fields added after the options were first saved fall back to the values in `shadow_linear::TrainOptions::default()`.
+pub fn deserialize_linear_train_options(
+	options: VersionedReader<LinearModelTrainOptionsReader>,
+) -> shadow_linear::TrainOptions {
+	let defaults = shadow_linear::TrainOptions::default();
+	let early_stopping_options = options
+		.early_stopping_options()
+		.map(deserialize_linear_early_stopping_options);
+	shadow_linear::TrainOptions {
+		compute_losses: options.compute_loss(),
+		early_stopping_options,
+		l2_regularization: options.l2_regularization(),
+		learning_rate: options.learning_rate(),
+		max_epochs: options.max_epochs().to_usize().unwrap(),
+		n_examples_per_batch: options.n_examples_per_batch().to_usize().unwrap(),
+		seed: options
+			.get(6, LinearModelTrainOptionsReader::seed)
+			.unwrap_or(defaults.seed),
+		shuffle: options
+			.get(7, LinearModelTrainOptionsReader::shuffle)
+			.unwrap_or(defaults.shuffle),
+		deterministic: options
+			.get(8, LinearModelTrainOptionsReader::deterministic)
+			.unwrap_or(defaults.deterministic),
+		optimizer: options
+			.get(9, LinearModelTrainOptionsReader::optimizer)
+			.map(deserialize_linear_optimizer)
+			.unwrap_or(defaults.optimizer),
+		learning_rate_schedule: options
+			.get(10, LinearModelTrainOptionsReader::learning_rate_schedule)
+			.map(deserialize_linear_learning_rate_schedule)
+			.unwrap_or(defaults.learning_rate_schedule),
+		l1_regularization: options
+			.get(11, LinearModelTrainOptionsReader::l1_regularization)
+			.unwrap_or(defaults.l1_regularization),
+		regression_loss: options
+			.get(12, LinearModelTrainOptionsReader::regression_loss)
+			.map(deserialize_linear_regression_loss)
+			.unwrap_or(defaults.regression_loss),
+		class_weights: options
+			.get(13, LinearModelTrainOptionsReader::class_weights)
+			.map(deserialize_linear_class_weights)
+			.unwrap_or(defaults.class_weights),
+		glm_options: options
+			.get(14, LinearModelTrainOptionsReader::glm_options)
+			.map(deserialize_linear_glm_options)
+			.unwrap_or(defaults.glm_options),
+	}
+}
+
+fn deserialize_linear_optimizer(optimizer: LinearOptimizerReader) -> shadow_linear::Optimizer {
+	match optimizer {
+		LinearOptimizerReader::Sgd(_) => shadow_linear::Optimizer::Sgd,
+		LinearOptimizerReader::Momentum(options) => {
+			shadow_linear::Optimizer::Momentum(shadow_linear::MomentumOptions {
+				momentum: options.read().momentum(),
+			})
+		}
+		LinearOptimizerReader::Adam(options) => {
+			let options = options.read();
+			shadow_linear::Optimizer::Adam(shadow_linear::AdamOptions {
+				beta_1: options.beta_1(),
+				beta_2: options.beta_2(),
+				epsilon: options.epsilon(),
+			})
+		}
+		LinearOptimizerReader::AdaGrad(options) => {
+			shadow_linear::Optimizer::AdaGrad(shadow_linear::AdaGradOptions {
+				epsilon: options.read().epsilon(),
+			})
+		}
+		LinearOptimizerReader::Ftrl(options) => {
+			shadow_linear::Optimizer::Ftrl(shadow_linear::FtrlOptions {
+				beta: options.read().beta(),
+			})
+		}
+	}
+}
+
+fn deserialize_linear_learning_rate_schedule(
+	schedule: LinearLearningRateScheduleReader,
+) -> shadow_linear::LearningRateSchedule {
+	match schedule {
+		LinearLearningRateScheduleReader::Constant(_) => {
+			shadow_linear::LearningRateSchedule::Constant
+		}
+		LinearLearningRateScheduleReader::Step(options) => {
+			let options = options.read();
+			shadow_linear::LearningRateSchedule::Step(
+				shadow_linear::StepLearningRateScheduleOptions {
+					step_size: options.step_size().to_usize().unwrap(),
+					gamma: options.gamma(),
+				},
+			)
+		}
+		LinearLearningRateScheduleReader::Exponential(options) => {
+			shadow_linear::LearningRateSchedule::Exponential(
+				shadow_linear::ExponentialLearningRateScheduleOptions {
+					gamma: options.read().gamma(),
+				},
+			)
+		}
+		LinearLearningRateScheduleReader::Cosine(options) => {
+			shadow_linear::LearningRateSchedule::Cosine(
+				shadow_linear::CosineLearningRateScheduleOptions {
+					min_learning_rate: options.read().min_learning_rate(),
+				},
+			)
+		}
+		LinearLearningRateScheduleReader::Warmup(options) => {
+			shadow_linear::LearningRateSchedule::Warmup(
+				shadow_linear::WarmupLearningRateScheduleOptions {
+					n_warmup_epochs: options.read().n_warmup_epochs().to_usize().unwrap(),
+				},
+			)
+		}
+	}
+}
+
+fn deserialize_linear_class_weights(
+	class_weights: LinearClassWeightsReader,
+) -> shadow_linear::ClassWeights {
+	match class_weights {
+		LinearClassWeightsReader::Uniform(_) => shadow_linear::ClassWeights::Uniform,
+		LinearClassWeightsReader::Balanced(_) => shadow_linear::ClassWeights::Balanced,
+		LinearClassWeightsReader::Custom(class_weights) => {
+			shadow_linear::ClassWeights::Custom(class_weights.read().iter().collect())
+		}
+	}
+}
+
+fn deserialize_linear_regression_loss(
+	loss: LinearRegressionLossReader,
+) -> shadow_linear::RegressionLoss {
+	match loss {
+		LinearRegressionLossReader::SquaredError(_) => shadow_linear::RegressionLoss::SquaredError,
+		LinearRegressionLossReader::Huber(options) => {
+			shadow_linear::RegressionLoss::Huber(shadow_linear::HuberLossOptions {
+				delta: options.read().delta(),
+			})
+		}
+		LinearRegressionLossReader::Quantile(options) => {
+			shadow_linear::RegressionLoss::Quantile(shadow_linear::QuantileLossOptions {
+				quantile: options.read().quantile(),
+			})
+		}
+		LinearRegressionLossReader::Poisson(_) => shadow_linear::RegressionLoss::Poisson,
+		LinearRegressionLossReader::Tweedie(options) => {
+			shadow_linear::RegressionLoss::Tweedie(shadow_linear::TweedieLossOptions {
+				variance_power: options.read().variance_power(),
+			})
+		}
+	}
+}
+
+fn deserialize_linear_glm_options(options: LinearGlmOptionsReader) -> shadow_linear::GlmOptions {
+	let family = match options.family() {
+		LinearGlmFamilyReader::Gaussian(_) => shadow_linear::GlmFamily::Gaussian,
+		LinearGlmFamilyReader::Poisson(_) => shadow_linear::GlmFamily::Poisson,
+		LinearGlmFamilyReader::Gamma(_) => shadow_linear::GlmFamily::Gamma,
+		LinearGlmFamilyReader::Bernoulli(_) => shadow_linear::GlmFamily::Bernoulli,
+	};
+	let link = options.link().map(|link| match link {
+		LinearLinkFunctionReader::Identity(_) => shadow_linear::LinkFunction::Identity,
+		LinearLinkFunctionReader::Log(_) => shadow_linear::LinkFunction::Log,
+		LinearLinkFunctionReader::Logit(_) => shadow_linear::LinkFunction::Logit,
+		LinearLinkFunctionReader::Inverse(_) => shadow_linear::LinkFunction::Inverse,
+		LinearLinkFunctionReader::Sqrt(_) => shadow_linear::LinkFunction::Sqrt,
+	});
+	shadow_linear::GlmOptions { family, link }
+}
+
+fn deserialize_linear_early_stopping_options(
+	options: VersionedReader<LinearEarlyStoppingOptionsReader>,
+) -> shadow_linear::EarlyStoppingOptions {
+	shadow_linear::EarlyStoppingOptions {
+		early_stopping_fraction: options.early_stopping_fraction(),
+		n_rounds_without_improvement_to_stop: options
+			.n_rounds_without_improvement_to_stop()
+			.to_usize()
+			.unwrap(),
+		min_decrease_in_loss_for_significant_change: options
+			.min_decrease_in_loss_for_significant_change(),
+		metric: options
+			.get(3, LinearEarlyStoppingOptionsReader::metric)
+			.map(deserialize_linear_early_stopping_metric)
+			.unwrap_or(shadow_linear::EarlyStoppingMetric::Loss),
+	}
+}
+
+/// The function behind a custom metric is not saved, so options that used one are read with the loss.
+fn deserialize_linear_early_stopping_metric(
+	metric: LinearEarlyStoppingMetricReader,
+) -> shadow_linear::EarlyStoppingMetric {
+	match metric {
+		LinearEarlyStoppingMetricReader::Loss(_) | LinearEarlyStoppingMetricReader::Custom(_) => {
+			shadow_linear::EarlyStoppingMetric::Loss
+		}
+		LinearEarlyStoppingMetricReader::MeanSquaredError(_) => {
+			shadow_linear::EarlyStoppingMetric::MeanSquaredError
+		}
+		LinearEarlyStoppingMetricReader::MeanAbsoluteError(_) => {
+			shadow_linear::EarlyStoppingMetric::MeanAbsoluteError
+		}
+		LinearEarlyStoppingMetricReader::Accuracy(_) => {
+			shadow_linear::EarlyStoppingMetric::Accuracy
+		}
+		LinearEarlyStoppingMetricReader::F1Score(_) => shadow_linear::EarlyStoppingMetric::F1Score,
+		LinearEarlyStoppingMetricReader::AucRoc(_) => shadow_linear::EarlyStoppingMetric::AucRoc,
+	}
+}
+
+/// Reads tree train options. Fields added after the options were first saved fall back to the values in `shadow_tree::TrainOptions::default()`. The monotone and interaction constraints and the random forest options are not saved and are read with their defaults.
+pub fn deserialize_tree_train_options(
+	options: VersionedReader<TreeModelTrainOptionsReader>,
+) -> shadow_tree::TrainOptions {
+	let defaults = shadow_tree::TrainOptions::default();
+	let binned_features_layout = match options.binned_features_layout() {
+		BinnedFeaturesLayoutReader::RowMajor(_) => shadow_tree::BinnedFeaturesLayout::RowMajor,
+		BinnedFeaturesLayoutReader::ColumnMajor(_) => {
+			shadow_tree::BinnedFeaturesLayout::ColumnMajor
+		}
+	};
+	let early_stopping_options =
+		options
+			.early_stopping_options()
+			.map(|options| shadow_tree::EarlyStoppingOptions {
+				early_stopping_fraction: options.early_stopping_fraction(),
+				n_rounds_without_improvement_to_stop: options
+					.n_rounds_without_improvement_to_stop()
+					.to_usize()
+					.unwrap(),
+				min_decrease_in_loss_for_significant_change: options
+					.min_decrease_in_loss_for_significant_change(),
+			});
+	let growth_policy = options
+		.get(22, TreeModelTrainOptionsReader::growth_policy)
+		.map(|growth_policy| match growth_policy {
+			TreeGrowthPolicyReader::LeafWise(_) => shadow_tree::GrowthPolicy::LeafWise,
+			TreeGrowthPolicyReader::DepthWise(_) => shadow_tree::GrowthPolicy::DepthWise,
+		})
+		.unwrap_or(defaults.growth_policy);
+	shadow_tree::TrainOptions {
+		binned_features_layout,
+		compute_losses: options.compute_loss(),
+		early_stopping_options,
+		l2_regularization_for_continuous_splits: options.l2_regularization_for_continuous_splits(),
+		l2_regularization_for_discrete_splits: options.l2_regularization_for_discrete_splits(),
+		learning_rate: options.learning_rate(),
+		max_depth: options
+			.max_depth()
+			.map(|max_depth| max_depth.to_usize().unwrap()),
+		max_examples_for_computing_bin_thresholds: options
+			.max_examples_for_computing_bin_thresholds()
+			.to_usize()
+			.unwrap(),
+		max_leaf_nodes: options.max_leaf_nodes().to_usize().unwrap(),
+		max_rounds: options.max_rounds().to_usize().unwrap(),
+		max_valid_bins_for_number_features: options.max_valid_bins_for_number_features(),
+		min_examples_per_node: options.min_examples_per_node().to_usize().unwrap(),
+		min_gain_to_split: options.min_gain_to_split(),
+		min_sum_hessians_per_node: options.min_sum_hessians_per_node(),
+		smoothing_factor_for_discrete_bin_sorting: options
+			.smoothing_factor_for_discrete_bin_sorting(),
+		seed: options
+			.get(15, TreeModelTrainOptionsReader::seed)
+			.unwrap_or(defaults.seed),
+		bagging_fraction: options
+			.get(16, TreeModelTrainOptionsReader::bagging_fraction)
+			.unwrap_or(defaults.bagging_fraction),
+		goss_options: options
+			.get(17, TreeModelTrainOptionsReader::goss_options)
+			.flatten()
+			.map(|options| shadow_tree::GossOptions {
+				top_fraction: options.top_fraction(),
+				other_fraction: options.other_fraction(),
+			}),
+		feature_fraction_per_tree: options
+			.get(18, TreeModelTrainOptionsReader::feature_fraction_per_tree)
+			.unwrap_or(defaults.feature_fraction_per_tree),
+		feature_fraction_per_node: options
+			.get(19, TreeModelTrainOptionsReader::feature_fraction_per_node)
+			.unwrap_or(defaults.feature_fraction_per_node),
+		dart_options: options
+			.get(20, TreeModelTrainOptionsReader::dart_options)
+			.flatten()
+			.map(|options| shadow_tree::DartOptions {
+				drop_rate: options.drop_rate(),
+				max_dropped_rounds: options
+					.max_dropped_rounds()
+					.map(|max_dropped_rounds| max_dropped_rounds.to_usize().unwrap()),
+				skip_drop_rate: options.skip_drop_rate(),
+			}),
+		max_variants_for_one_vs_rest_splits: options
+			.get(
+				21,
+				TreeModelTrainOptionsReader::max_variants_for_one_vs_rest_splits,
+			)
+			.map(|max_variants| max_variants.to_usize().unwrap())
+			.unwrap_or(defaults.max_variants_for_one_vs_rest_splits),
+		growth_policy,
+		cost_complexity_pruning_alpha: options
+			.get(
+				23,
+				TreeModelTrainOptionsReader::cost_complexity_pruning_alpha,
+			)
+			.unwrap_or(defaults.cost_complexity_pruning_alpha),
+		..defaults
+	}
+}
@@ -0,0 +1,320 @@
+#[cfg(test)]
+mod test {
+	use crate::model_train_options::*;
+	use shadow_linear::serialize::Versioned;
+
+	/// The linear train options as they were saved before this series of fields was added.
+	#[allow(unused)]
+	#[derive(buffalo::Write)]
+	#[buffalo(size = "dynamic")]
+	struct OldLinearModelTrainOptions {
+		#[buffalo(id = 0, required)]
+		compute_loss: bool,
+		#[buffalo(id = 1, required)]
+		l2_regularization: f32,
+		#[buffalo(id = 2, required)]
+		learning_rate: f32,
+		#[buffalo(id = 3, required)]
+		max_epochs: u64,
+		#[buffalo(id = 4, required)]
+		n_examples_per_batch: u64,
+		#[buffalo(id = 5, required)]
+		early_stopping_options: Option<OldLinearEarlyStoppingOptions>,
+	}
+
+	/// The early stopping options as they were saved before the metric was added.
+	#[allow(unused)]
+	#[derive(buffalo::Write)]
+	#[buffalo(size = "dynamic")]
+	struct OldLinearEarlyStoppingOptions {
+		#[buffalo(id = 0, required)]
+		early_stopping_fraction: f32,
+		#[buffalo(id = 1, required)]
+		n_rounds_without_improvement_to_stop: u64,
+		#[buffalo(id = 2, required)]
+		min_decrease_in_loss_for_significant_change: f32,
+	}
+
+	#[test]
+	fn test_read_old_linear_train_options() {
+		let mut writer = buffalo::Writer::new();
+		let early_stopping_options = writer.write(&OldLinearEarlyStoppingOptionsWriter {
+			early_stopping_fraction: 0.2,
+			n_rounds_without_improvement_to_stop: 3,
+			min_decrease_in_loss_for_significant_change: 0.001,
+		});
+		let options = writer.write(&OldLinearModelTrainOptionsWriter {
+			compute_loss: true,
+			l2_regularization: 0.5,
+			learning_rate: 0.01,
+			max_epochs: 7,
+			n_examples_per_batch: 16,
+			early_stopping_options: Some(early_stopping_options),
+		});
+		writer.write(&options);
+		let bytes = writer.into_bytes();
+		let options = buffalo::read::<Versioned<LinearModelTrainOptionsReader>>(&bytes);
+		let options = deserialize_linear_train_options(options);
+		let defaults = shadow_linear::TrainOptions::default();
+		assert!(options.compute_losses);
+		assert_eq!(options.l2_regularization, 0.5);
+		assert_eq!(options.learning_rate, 0.01);
+		assert_eq!(options.max_epochs, 7);
+		assert_eq!(options.n_examples_per_batch, 16);
+		let early_stopping_options = options.early_stopping_options.unwrap();
+		assert_eq!(early_stopping_options.early_stopping_fraction, 0.2);
+		assert_eq!(
+			early_stopping_options.n_rounds_without_improvement_to_stop,
+			3
+		);
+		assert!(matches!(
+			early_stopping_options.metric,
+			shadow_linear::EarlyStoppingMetric::Loss
+		));
+		assert_eq!(options.seed, defaults.seed);
+		assert_eq!(options.shuffle, defaults.shuffle);
+		assert_eq!(options.deterministic, defaults.deterministic);
+		assert_eq!(options.l1_regularization, defaults.l1_regularization);
+		assert!(matches!(
+			options.class_weights,
+			shadow_linear::ClassWeights::Uniform
+		));
+		assert!(matches!(
+			options.regression_loss,
+			shadow_linear::RegressionLoss::SquaredError
+		));
+		assert!(matches!(options.optimizer, shadow_linear::Optimizer::Sgd));
+		assert!(matches!(
+			options.glm_options.family,
+			shadow_linear::GlmFamily::Gaussian
+		));
+		assert!(options.glm_options.link.is_none());
+		assert!(matches!(
+			options.learning_rate_schedule,
+			shadow_linear::LearningRateSchedule::Constant
+		));
+	}
+
+	#[test]
+	fn test_read_linear_train_options() {
+		let mut writer = buffalo::Writer::new();
+		let glm_options = writer.write(&LinearGlmOptionsWriter {
+			family: LinearGlmFamilyWriter::Poisson,
+			link: Some(LinearLinkFunctionWriter::Sqrt),
+		});
+		let optimizer = writer.write(&LinearAdamOptionsWriter {
+			beta_1: 0.8,
+			beta_2: 0.99,
+			epsilon: 1e-6,
+		});
+		let class_weights = writer.write([1.0f32, 3.0].as_slice());
+		let early_stopping_options = writer.write(&LinearEarlyStoppingOptionsWriter {
+			early_stopping_fraction: 0.1,
+			n_rounds_without_improvement_to_stop: 5,
+			min_decrease_in_loss_for_significant_change: 0.0,
+			metric: LinearEarlyStoppingMetricWriter::AucRoc,
+		});
+		let options = writer.write(&LinearModelTrainOptionsWriter {
+			compute_loss: false,
+			l2_regularization: 0.0,
+			learning_rate: 0.1,
+			max_epochs: 100,
+			n_examples_per_batch: 32,
+			early_stopping_options: Some(early_stopping_options),
+			seed: 42,
+			shuffle: true,
+			deterministic: true,
+			optimizer: LinearOptimizerWriter::Adam(optimizer),
+			learning_rate_schedule: LinearLearningRateScheduleWriter::Constant,
+			l1_regularization: 0.25,
+			regression_loss: LinearRegressionLossWriter::Poisson,
+			class_weights: LinearClassWeightsWriter::Custom(class_weights),
+			glm_options,
+		});
+		writer.write(&options);
+		let bytes = writer.into_bytes();
+		let options = buffalo::read::<Versioned<LinearModelTrainOptionsReader>>(&bytes);
+		let options = deserialize_linear_train_options(options);
+		assert!(matches!(
+			options.early_stopping_options.unwrap().metric,
+			shadow_linear::EarlyStoppingMetric::AucRoc
+		));
+		assert_eq!(options.seed, 42);
+		assert!(options.shuffle);
+		assert!(options.deterministic);
+		assert_eq!(options.l1_regularization, 0.25);
+		match options.class_weights {
+			shadow_linear::ClassWeights::Custom(class_weights) => {
+				assert_eq!(class_weights, vec![1.0, 3.0])
+			}
+			_ => panic!(),
+		}
+		assert!(matches!(
+			options.regression_loss,
+			shadow_linear::RegressionLoss::Poisson
+		));
+		assert!(matches!(
+			options.glm_options.family,
+			shadow_linear::GlmFamily::Poisson
+		));
+		assert!(matches!(
+			options.glm_options.link,
+			Some(shadow_linear::LinkFunction::Sqrt)
+		));
+		match options.optimizer {
+			shadow_linear::Optimizer::Adam(options) => assert_eq!(options.beta_1, 0.8),
+			_ => panic!(),
+		}
+	}
+
+	/// The tree train options as they were saved before sampling, DART, one-vs-rest splits, the growth policy and pruning were added.
+	#[allow(unused)]
+	#[derive(buffalo::Write)]
+	#[buffalo(size = "dynamic")]
+	struct OldTreeModelTrainOptions {
+		#[buffalo(id = 0, required)]
+		binned_features_layout: BinnedFeaturesLayout,
+		#[buffalo(id = 1, required)]
+		compute_loss: bool,
+		#[buffalo(id = 2, required)]
+		early_stopping_options: Option<TreeEarlyStoppingOptions>,
+		#[buffalo(id = 3, required)]
+		l2_regularization_for_continuous_splits: f32,
+		#[buffalo(id = 4, required)]
+		l2_regularization_for_discrete_splits: f32,
+		#[buffalo(id = 5, required)]
+		learning_rate: f32,
+		#[buffalo(id = 6, required)]
+		max_depth: Option<u64>,
+		#[buffalo(id = 7, required)]
+		max_examples_for_computing_bin_thresholds: u64,
+		#[buffalo(id = 8, required)]
+		max_leaf_nodes: u64,
+		#[buffalo(id = 9, required)]
+		max_rounds: u64,
+		#[buffalo(id = 10, required)]
+		max_valid_bins_for_number_features: u8,
+		#[buffalo(id = 11, required)]
+		min_examples_per_node: u64,
+		#[buffalo(id = 12, required)]
+		min_gain_to_split: f32,
+		#[buffalo(id = 13, required)]
+		min_sum_hessians_per_node: f32,
+		#[buffalo(id = 14, required)]
+		smoothing_factor_for_discrete_bin_sorting: f32,
+	}
+
+	#[test]
+	fn test_read_old_tree_train_options() {
+		let mut writer = buffalo::Writer::new();
+		let options = writer.write(&OldTreeModelTrainOptionsWriter {
+			binned_features_layout: BinnedFeaturesLayoutWriter::ColumnMajor,
+			compute_loss: false,
+			early_stopping_options: None,
+			l2_regularization_for_continuous_splits: 0.0,
+			l2_regularization_for_discrete_splits: 10.0,
+			learning_rate: 0.2,
+			max_depth: None,
+			max_examples_for_computing_bin_thresholds: 200_000,
+			max_leaf_nodes: 15,
+			max_rounds: 50,
+			max_valid_bins_for_number_features: 255,
+			min_examples_per_node: 20,
+			min_gain_to_split: 0.0,
+			min_sum_hessians_per_node: 1e-3,
+			smoothing_factor_for_discrete_bin_sorting: 10.0,
+		});
+		writer.write(&options);
+		let bytes = writer.into_bytes();
+		let options = buffalo::read::<Versioned<TreeModelTrainOptionsReader>>(&bytes);
+		let options = deserialize_tree_train_options(options);
+		assert_eq!(options.learning_rate, 0.2);
+		assert_eq!(options.max_leaf_nodes, 15);
+		assert_eq!(options.max_rounds, 50);
+		assert!(options.early_stopping_options.is_none());
+		assert_eq!(options.seed, 0);
+		assert_eq!(options.bagging_fraction, 1.0);
+		assert!(options.goss_options.is_none());
+		assert_eq!(options.feature_fraction_per_tree, 1.0);
+		assert_eq!(options.feature_fraction_per_node, 1.0);
+		assert!(options.dart_options.is_none());
+		assert_eq!(options.max_variants_for_one_vs_rest_splits, 4);
+		assert_eq!(options.growth_policy, shadow_tree::GrowthPolicy::LeafWise);
+		assert_eq!(options.cost_complexity_pruning_alpha, 0.0);
+	}
+
+	#[test]
+	fn test_read_tree_train_options() {
+		let mut writer = buffalo::Writer::new();
+		let early_stopping_options = writer.write(&TreeEarlyStoppingOptionsWriter {
+			early_stopping_fraction: 0.1,
+			n_rounds_without_improvement_to_stop: 5,
+			min_decrease_in_loss_for_significant_change: 1e-3,
+		});
+		let goss_options = writer.write(&TreeGossOptionsWriter {
+			top_fraction: 0.3,
+			other_fraction: 0.2,
+		});
+		let dart_options = writer.write(&TreeDartOptionsWriter {
+			drop_rate: 0.2,
+			max_dropped_rounds: None,
+			skip_drop_rate: 0.0,
+		});
+		let options = writer.write(&TreeModelTrainOptionsWriter {
+			binned_features_layout: BinnedFeaturesLayoutWriter::RowMajor,
+			compute_loss: true,
+			early_stopping_options: Some(early_stopping_options),
+			l2_regularization_for_continuous_splits: 1.0,
+			l2_regularization_for_discrete_splits: 10.0,
+			learning_rate: 0.05,
+			max_depth: Some(6),
+			max_examples_for_computing_bin_thresholds: 1000,
+			max_leaf_nodes: 63,
+			max_rounds: 200,
+			max_valid_bins_for_number_features: 127,
+			min_examples_per_node: 10,
+			min_gain_to_split: 0.0,
+			min_sum_hessians_per_node: 1.0,
+			smoothing_factor_for_discrete_bin_sorting: 10.0,
+			seed: 42,
+			bagging_fraction: 0.8,
+			goss_options: Some(goss_options),
+			feature_fraction_per_tree: 0.5,
+			feature_fraction_per_node: 0.7,
+			dart_options: Some(dart_options),
+			max_variants_for_one_vs_rest_splits: 8,
+			growth_policy: TreeGrowthPolicyWriter::DepthWise,
+			cost_complexity_pruning_alpha: 0.01,
+		});
+		writer.write(&options);
+		let bytes = writer.into_bytes();
+		let options = buffalo::read::<Versioned<TreeModelTrainOptionsReader>>(&bytes);
+		let options = deserialize_tree_train_options(options);
+		assert!(matches!(
+			options.binned_features_layout,
+			shadow_tree::BinnedFeaturesLayout::RowMajor
+		));
+		assert!(options.compute_losses);
+		assert_eq!(
+			options
+				.early_stopping_options
+				.unwrap()
+				.n_rounds_without_improvement_to_stop,
+			5
+		);
+		assert_eq!(options.max_depth, Some(6));
+		assert_eq!(options.max_leaf_nodes, 63);
+		assert_eq!(options.max_valid_bins_for_number_features, 127);
+		assert_eq!(options.seed, 42);
+		assert_eq!(options.bagging_fraction, 0.8);
+		assert_eq!(options.goss_options.unwrap().top_fraction, 0.3);
+		assert_eq!(options.feature_fraction_per_tree, 0.5);
+		assert_eq!(options.feature_fraction_per_node, 0.7);
+		let dart_options = options.dart_options.unwrap();
+		assert_eq!(dart_options.drop_rate, 0.2);
+		assert_eq!(dart_options.max_dropped_rounds, None);
+		assert_eq!(options.max_variants_for_one_vs_rest_splits, 8);
+		assert_eq!(options.growth_policy, shadow_tree::GrowthPolicy::DepthWise);
+		assert_eq!(options.cost_complexity_pruning_alpha, 0.01);
+	}
+}
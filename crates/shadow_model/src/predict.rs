@@ -0,0 +1,256 @@
+use anyhow::{bail, Result};
+use ndarray::prelude::*;
+use shadow_features::FeatureGroup;
+use shadow_linear::ComputeShapValuesForExampleOutput;
+use shadow_table::prelude::*;
+use shadow_zip::zip;
+use std::collections::BTreeMap;
+
+/// A trained model together with what `predict` needs to go from a table to typed outputs: the feature groups the training features were computed with and the names of the classes.
+#[derive(Clone, Debug)]
+pub enum PredictModel {
+	Regressor(RegressorPredictModel),
+	BinaryClassifier(BinaryClassifierPredictModel),
+	MulticlassClassifier(MulticlassClassifierPredictModel),
+}
+
+#[derive(Clone, Debug)]
+pub enum RegressorModel {
+	Linear(shadow_linear::Regressor),
+	Tree(shadow_tree::Regressor),
+}
+
+#[derive(Clone, Debug)]
+pub enum BinaryClassifierModel {
+	Linear(shadow_linear::BinaryClassifier),
+	Tree(shadow_tree::BinaryClassifier),
+}
+
+#[derive(Clone, Debug)]
+pub enum MulticlassClassifierModel {
+	Linear(shadow_linear::MulticlassClassifier),
+	Tree(shadow_tree::MulticlassClassifier),
+}
+
+#[derive(Clone, Debug)]
+pub struct RegressorPredictModel {
+	pub model: RegressorModel,
+	pub feature_groups: Vec<FeatureGroup>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BinaryClassifierPredictModel {
+	pub model: BinaryClassifierModel,
+	pub feature_groups: Vec<FeatureGroup>,
+	pub negative_class: String,
+	pub positive_class: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct MulticlassClassifierPredictModel {
+	pub model: MulticlassClassifierModel,
+	pub feature_groups: Vec<FeatureGroup>,
+	/// The names of the classes, in the order of the columns of the model's probabilities.
+	pub classes: Vec<String>,
+}
+
+/// The fields match the JSON written by the language libraries' `PredictionOptions`.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct PredictOptions {
+	/// The probability of the positive class at or above which a binary classifier predicts it. Defaults to 0.5. Ignored by the other models.
+	pub threshold: Option<f32>,
+	/// Only linear models compute feature contributions.
+	#[serde(default)]
+	pub compute_feature_contributions: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PredictOutput {
+	Regression {
+		value: f32,
+		feature_contributions: Option<ComputeShapValuesForExampleOutput>,
+	},
+	BinaryClassification {
+		class_name: String,
+		/// The probability of the predicted class.
+		probability: f32,
+		/// The contributions to the logit of the positive class.
+		feature_contributions: Option<ComputeShapValuesForExampleOutput>,
+	},
+	MulticlassClassification {
+		class_name: String,
+		/// The probability of the predicted class.
+		probability: f32,
+		probabilities: BTreeMap<String, f32>,
+		/// The contributions to the logit of each class.
+		feature_contributions: Option<BTreeMap<String, ComputeShapValuesForExampleOutput>>,
+	},
+}
+
+/// Computes the features of each row of `table` with the model's feature groups and predicts one output per row. Returns an error if feature contributions are requested from a tree model.
+pub fn predict(
+	model: &PredictModel,
+	table: &TableView,
+	options: &PredictOptions,
+) -> Result<Vec<PredictOutput>> {
+	match model {
+		PredictModel::Regressor(model) => predict_regressor(model, table, options),
+		PredictModel::BinaryClassifier(model) => predict_binary_classifier(model, table, options),
+		PredictModel::MulticlassClassifier(model) => {
+			predict_multiclass_classifier(model, table, options)
+		}
+	}
+}
+
+fn predict_regressor(
+	model: &RegressorPredictModel,
+	table: &TableView,
+	options: &PredictOptions,
+) -> Result<Vec<PredictOutput>> {
+	let mut predictions = Array::zeros(table.nrows());
+	let mut feature_contributions = match &model.model {
+		RegressorModel::Linear(linear_model) => {
+			let features =
+				shadow_features::compute_features_array_f32(table, &model.feature_groups, &|| {});
+			linear_model.predict(features.view(), predictions.view_mut());
+			compute_feature_contributions(options, || {
+				linear_model.compute_feature_contributions(features.view())
+			})
+		}
+		RegressorModel::Tree(tree_model) => {
+			check_tree_options(options)?;
+			let features =
+				shadow_features::compute_features_array_value(table, &model.feature_groups, &|| {});
+			tree_model.predict(features.view(), predictions.view_mut());
+			None
+		}
+	};
+	Ok(predictions
+		.iter()
+		.map(|value| PredictOutput::Regression {
+			value: *value,
+			feature_contributions: feature_contributions
+				.as_mut()
+				.map(|feature_contributions| feature_contributions.next().unwrap()),
+		})
+		.collect())
+}
+
+fn predict_binary_classifier(
+	model: &BinaryClassifierPredictModel,
+	table: &TableView,
+	options: &PredictOptions,
+) -> Result<Vec<PredictOutput>> {
+	let threshold = options.threshold.unwrap_or(0.5);
+	let mut probabilities = Array::zeros(table.nrows());
+	let mut feature_contributions = match &model.model {
+		BinaryClassifierModel::Linear(linear_model) => {
+			let features =
+				shadow_features::compute_features_array_f32(table, &model.feature_groups, &|| {});
+			linear_model.predict(features.view(), probabilities.view_mut());
+			compute_feature_contributions(options, || {
+				linear_model.compute_feature_contributions(features.view())
+			})
+		}
+		BinaryClassifierModel::Tree(tree_model) => {
+			check_tree_options(options)?;
+			let features =
+				shadow_features::compute_features_array_value(table, &model.feature_groups, &|| {});
+			tree_model.predict(features.view(), probabilities.view_mut());
+			None
+		}
+	};
+	Ok(probabilities
+		.iter()
+		.map(|probability| {
+			let (class_name, probability) = if *probability >= threshold {
+				(&model.positive_class, *probability)
+			} else {
+				(&model.negative_class, 1.0 - *probability)
+			};
+			PredictOutput::BinaryClassification {
+				class_name: class_name.clone(),
+				probability,
+				feature_contributions: feature_contributions
+					.as_mut()
+					.map(|feature_contributions| feature_contributions.next().unwrap()),
+			}
+		})
+		.collect())
+}
+
+fn predict_multiclass_classifier(
+	model: &MulticlassClassifierPredictModel,
+	table: &TableView,
+	options: &PredictOptions,
+) -> Result<Vec<PredictOutput>> {
+	let mut probabilities = Array::zeros((table.nrows(), model.classes.len()));
+	let mut feature_contributions = match &model.model {
+		MulticlassClassifierModel::Linear(linear_model) => {
+			assert_eq!(model.classes.len(), linear_model.biases.len());
+			let features =
+				shadow_features::compute_features_array_f32(table, &model.feature_groups, &|| {});
+			linear_model.predict(features.view(), probabilities.view_mut());
+			compute_feature_contributions(options, || {
+				linear_model.compute_feature_contributions(features.view())
+			})
+		}
+		MulticlassClassifierModel::Tree(tree_model) => {
+			assert_eq!(model.classes.len(), tree_model.biases.len());
+			check_tree_options(options)?;
+			let features =
+				shadow_features::compute_features_array_value(table, &model.feature_groups, &|| {});
+			tree_model.predict(features.view(), probabilities.view_mut());
+			None
+		}
+	};
+	Ok(probabilities
+		.axis_iter(Axis(0))
+		.map(|probabilities| {
+			let (class_index, probability) = probabilities.iter().enumerate().fold(
+				(0, f32::NEG_INFINITY),
+				|best, (class_index, probability)| {
+					if *probability > best.1 {
+						(class_index, *probability)
+					} else {
+						best
+					}
+				},
+			);
+			PredictOutput::MulticlassClassification {
+				class_name: model.classes[class_index].clone(),
+				probability,
+				probabilities: zip!(model.classes.iter(), probabilities.iter())
+					.map(|(class, probability)| (class.clone(), *probability))
+					.collect(),
+				feature_contributions: feature_contributions.as_mut().map(
+					|feature_contributions| {
+						zip!(model.classes.iter(), feature_contributions.next().unwrap())
+							.map(|(class, feature_contributions)| {
+								(class.clone(), feature_contributions)
+							})
+							.collect()
+					},
+				),
+			}
+		})
+		.collect())
+}
+
+fn check_tree_options(options: &PredictOptions) -> Result<()> {
+	if options.compute_feature_contributions {
+		bail!("tree models do not compute feature contributions");
+	}
+	Ok(())
+}
+
+/// Computes the feature contributions of every example if they were requested, to be moved into the outputs in order.
+fn compute_feature_contributions<T>(
+	options: &PredictOptions,
+	compute: impl FnOnce() -> Vec<T>,
+) -> Option<std::vec::IntoIter<T>> {
+	options
+		.compute_feature_contributions
+		.then(|| compute().into_iter())
+}
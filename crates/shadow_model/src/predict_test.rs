@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod test {
+	use crate::predict::*;
+	use ndarray::prelude::*;
+	use shadow_features::{FeatureGroup, IdentityFeatureGroup};
+	use shadow_linear::RegressionLoss;
+	use shadow_table::{NumberTableColumn, Table, TableColumn};
+	use shadow_tree::{
+		BranchNode, BranchSplit, BranchSplitContinuous, LeafNode, Node, SplitDirection, Tree,
+	};
+
+	fn table_and_feature_groups() -> (Table, Vec<FeatureGroup>) {
+		let mut table = Table::new(Vec::new(), Vec::new());
+		*table.columns_mut() = vec![TableColumn::Number(NumberTableColumn::new(
+			Some("x".to_owned()),
+			vec![-1.0, 0.0, 2.0],
+		))];
+		let feature_groups = vec![FeatureGroup::Identity(IdentityFeatureGroup {
+			source_column_name: "x".to_owned(),
+		})];
+		(table, feature_groups)
+	}
+
+	#[test]
+	fn test_predict_regressor() {
+		let (table, feature_groups) = table_and_feature_groups();
+		let model = PredictModel::Regressor(RegressorPredictModel {
+			model: RegressorModel::Linear(shadow_linear::Regressor {
+				bias: 1.0,
+				weights: arr1(&[2.0]),
+				means: vec![0.0],
+				loss: RegressionLoss::SquaredError,
+			}),
+			feature_groups,
+		});
+		let outputs = predict(&model, &table.view(), &PredictOptions::default()).unwrap();
+		let values: Vec<f32> = outputs
+			.iter()
+			.map(|output| match output {
+				PredictOutput::Regression {
+					value,
+					feature_contributions,
+				} => {
+					assert!(feature_contributions.is_none());
+					*value
+				}
+				_ => unreachable!(),
+			})
+			.collect();
+		assert_eq!(values, vec![-1.0, 1.0, 5.0]);
+	}
+
+	#[test]
+	fn test_predict_binary_classifier_with_threshold() {
+		let (table, feature_groups) = table_and_feature_groups();
+		let model = PredictModel::BinaryClassifier(BinaryClassifierPredictModel {
+			model: BinaryClassifierModel::Linear(shadow_linear::BinaryClassifier {
+				bias: 0.0,
+				weights: arr1(&[1.0]),
+				means: vec![0.0],
+				calibrator: None,
+			}),
+			feature_groups,
+			negative_class: "no".to_owned(),
+			positive_class: "yes".to_owned(),
+		});
+		let class_names = |threshold| {
+			predict(
+				&model,
+				&table.view(),
+				&PredictOptions {
+					threshold: Some(threshold),
+					compute_feature_contributions: true,
+				},
+			)
+			.unwrap()
+			.into_iter()
+			.map(|output| match output {
+				PredictOutput::BinaryClassification {
+					class_name,
+					feature_contributions,
+					..
+				} => {
+					assert_eq!(
+						feature_contributions
+							.unwrap()
+							.feature_contribution_values
+							.len(),
+						1
+					);
+					class_name
+				}
+				_ => unreachable!(),
+			})
+			.collect::<Vec<_>>()
+		};
+		// The probabilities are about 0.27, 0.5 and 0.88.
+		assert_eq!(class_names(0.5), vec!["no", "yes", "yes"]);
+		assert_eq!(class_names(0.9), vec!["no", "no", "no"]);
+		assert_eq!(class_names(0.1), vec!["yes", "yes", "yes"]);
+	}
+
+	#[test]
+	fn test_predict_multiclass_classifier() {
+		let (table, feature_groups) = table_and_feature_groups();
+		let model = PredictModel::MulticlassClassifier(MulticlassClassifierPredictModel {
+			model: MulticlassClassifierModel::Linear(shadow_linear::MulticlassClassifier {
+				biases: arr1(&[0.0, 0.5, 0.0]),
+				weights: arr2(&[[-1.0, 0.0, 1.0]]),
+				means: vec![0.0],
+				calibrator: None,
+			}),
+			feature_groups,
+			classes: vec!["low".to_owned(), "mid".to_owned(), "high".to_owned()],
+		});
+		let outputs = predict(
+			&model,
+			&table.view(),
+			&PredictOptions {
+				threshold: None,
+				compute_feature_contributions: true,
+			},
+		)
+		.unwrap();
+		let class_names: Vec<&str> = outputs
+			.iter()
+			.map(|output| match output {
+				PredictOutput::MulticlassClassification {
+					class_name,
+					probability,
+					probabilities,
+					feature_contributions,
+				} => {
+					assert_eq!(probabilities[class_name], *probability);
+					assert!((probabilities.values().sum::<f32>() - 1.0).abs() < 1e-6);
+					assert_eq!(feature_contributions.as_ref().unwrap().len(), 3);
+					class_name.as_str()
+				}
+				_ => unreachable!(),
+			})
+			.collect();
+		assert_eq!(class_names, vec!["low", "mid", "high"]);
+	}
+
+	/// A tree that predicts `left` for examples whose feature is at most 0.5 and `right` for the others.
+	fn stump(left: f64, right: f64) -> Tree {
+		let leaf = |value| {
+			Node::Leaf(LeafNode {
+				value,
+				examples_fraction: 0.5,
+			})
+		};
+		Tree {
+			nodes: vec![
+				Node::Branch(BranchNode {
+					left_child_index: 1,
+					right_child_index: 2,
+					split: BranchSplit::Continuous(BranchSplitContinuous {
+						feature_index: 0,
+						split_value: 0.5,
+						invalid_values_direction: SplitDirection::Left,
+					}),
+					examples_fraction: 1.0,
+					gain: 1.0,
+				}),
+				leaf(left),
+				leaf(right),
+			],
+		}
+	}
+
+	#[test]
+	fn test_predict_tree_models() {
+		let (table, feature_groups) = table_and_feature_groups();
+		let model = PredictModel::Regressor(RegressorPredictModel {
+			model: RegressorModel::Tree(shadow_tree::Regressor {
+				bias: 1.0,
+				trees: vec![stump(-1.0, 2.0)],
+			}),
+			feature_groups: feature_groups.clone(),
+		});
+		let values: Vec<f32> = predict(&model, &table.view(), &PredictOptions::default())
+			.unwrap()
+			.iter()
+			.map(|output| match output {
+				PredictOutput::Regression { value, .. } => *value,
+				_ => unreachable!(),
+			})
+			.collect();
+		assert_eq!(values, vec![0.0, 0.0, 3.0]);
+		// Tree models do not compute feature contributions.
+		assert!(predict(
+			&model,
+			&table.view(),
+			&PredictOptions {
+				threshold: None,
+				compute_feature_contributions: true,
+			},
+		)
+		.is_err());
+		let model = PredictModel::BinaryClassifier(BinaryClassifierPredictModel {
+			model: BinaryClassifierModel::Tree(shadow_tree::BinaryClassifier {
+				bias: 0.0,
+				trees: vec![stump(-2.0, 2.0)],
+			}),
+			feature_groups: feature_groups.clone(),
+			negative_class: "no".to_owned(),
+			positive_class: "yes".to_owned(),
+		});
+		let class_names: Vec<String> = predict(&model, &table.view(), &PredictOptions::default())
+			.unwrap()
+			.into_iter()
+			.map(|output| match output {
+				PredictOutput::BinaryClassification { class_name, .. } => class_name,
+				_ => unreachable!(),
+			})
+			.collect();
+		assert_eq!(class_names, vec!["no", "no", "yes"]);
+		let model = PredictModel::MulticlassClassifier(MulticlassClassifierPredictModel {
+			model: MulticlassClassifierModel::Tree(shadow_tree::MulticlassClassifier {
+				biases: arr1(&[0.0, 0.0]),
+				trees: Array2::from_shape_vec((1, 2), vec![stump(1.0, -1.0), stump(-1.0, 1.0)])
+					.unwrap(),
+			}),
+			feature_groups,
+			classes: vec!["low".to_owned(), "high".to_owned()],
+		});
+		let class_names: Vec<String> = predict(&model, &table.view(), &PredictOptions::default())
+			.unwrap()
+			.into_iter()
+			.map(|output| match output {
+				PredictOutput::MulticlassClassification { class_name, .. } => class_name,
+				_ => unreachable!(),
+			})
+			.collect();
+		assert_eq!(class_names, vec!["low", "low", "high"]);
+	}
+
+	#[test]
+	fn test_predict_options_from_json() {
+		let options: PredictOptions =
+			serde_json::from_str(r#"{"threshold":null,"compute_feature_contributions":true}"#)
+				.unwrap();
+		assert_eq!(options.threshold, None);
+		assert!(options.compute_feature_contributions);
+	}
+}
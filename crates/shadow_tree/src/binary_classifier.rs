@@ -0,0 +1,79 @@
+use crate::{
+    train::{sigmoid, train, Task},
+    Progress, TrainOptions, Tree, TreeStats,
+};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use rayon::prelude::*;
+use shadow_table::prelude::*;
+use shadow_zip::pzip;
+
+/// A gradient boosted binary classifier, whose bias and trees sum to the log odds of the positive class.
+#[derive(Clone, Debug)]
+pub struct BinaryClassifier {
+    pub bias: f32,
+    pub trees: Vec<Tree>,
+}
+
+pub struct BinaryClassifierTrainOutput {
+    pub model: BinaryClassifier,
+    pub losses: Option<Vec<f32>>,
+    pub feature_importances: Option<Vec<f32>>,
+    #[cfg(feature = "timing")]
+    pub timing: crate::Timing,
+}
+
+impl BinaryClassifier {
+    /// `labels` must have two variants, where the second is the positive class.
+    pub fn train(
+        features: &TableView,
+        labels: EnumTableColumnView,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> BinaryClassifierTrainOutput {
+        assert_eq!(labels.variants().len(), 2);
+        let labels = labels
+            .iter()
+            .map(|label| (label.unwrap().get() - 1).to_f32().unwrap())
+            .collect::<Array1<f32>>();
+        let output = train(
+            Task::BinaryClassification,
+            features,
+            labels.view(),
+            train_options,
+            progress,
+        );
+        BinaryClassifierTrainOutput {
+            model: BinaryClassifier {
+                bias: output.biases[0],
+                trees: output.trees.into_iter().collect(),
+            },
+            losses: output.losses,
+            feature_importances: Some(output.feature_importances),
+            #[cfg(feature = "timing")]
+            timing: output.timing,
+        }
+    }
+
+    /// Computes the probability of the positive class.
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut probabilities: ArrayViewMut1<f32>) {
+        pzip!(
+            features.axis_iter(Axis(0)),
+            probabilities.axis_iter_mut(Axis(0))
+        )
+        .for_each(|(example, mut probability)| {
+            let example = example.as_slice().unwrap();
+            let logit = self.bias
+                + self
+                    .trees
+                    .iter()
+                    .map(|tree| tree.predict(example))
+                    .sum::<f32>();
+            probability[()] = sigmoid(logit);
+        });
+    }
+
+    pub fn tree_stats(&self) -> TreeStats {
+        TreeStats::compute(self.trees.iter())
+    }
+}
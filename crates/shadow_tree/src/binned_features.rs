@@ -0,0 +1,64 @@
+use crate::{compute_binning_instructions::BinningInstruction, BinnedFeaturesLayout};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use rayon::prelude::*;
+use shadow_table::prelude::*;
+use shadow_zip::pzip;
+
+/// The bin of every feature value, computed with the `BinningInstruction` of its column. Bin 0 holds the invalid values, which are non-finite numbers and missing enum variants.
+pub(crate) struct BinnedFeatures {
+    /// One row per example and one column per feature, stored in the memory order given by `BinnedFeaturesLayout`.
+    pub bins: Array2<u16>,
+}
+
+impl BinnedFeatures {
+    pub fn compute(
+        features: &TableView,
+        binning_instructions: &[BinningInstruction],
+        layout: BinnedFeaturesLayout,
+    ) -> BinnedFeatures {
+        let shape = (features.nrows(), features.ncols());
+        let mut bins = match layout {
+            BinnedFeaturesLayout::RowMajor => Array::zeros(shape),
+            BinnedFeaturesLayout::ColumnMajor => Array::zeros(shape.f()),
+        };
+        pzip!(
+            bins.axis_iter_mut(Axis(1)),
+            features.columns().par_iter(),
+            binning_instructions.par_iter()
+        )
+        .for_each(|(mut bins, column, binning_instruction)| {
+            assert!(binning_instruction.n_bins() <= u16::MAX.to_usize().unwrap());
+            match (column, binning_instruction) {
+                (TableColumnView::Number(column), BinningInstruction::Number { thresholds }) => {
+                    for (bin, value) in bins.iter_mut().zip(column.iter()) {
+                        *bin = compute_bin_for_number(*value, thresholds);
+                    }
+                }
+                (TableColumnView::Enum(column), BinningInstruction::Enum { .. }) => {
+                    for (bin, value) in bins.iter_mut().zip(column.iter()) {
+                        *bin = value
+                            .map(|value| value.get().to_u16().unwrap())
+                            .unwrap_or(0);
+                    }
+                }
+                _ => unreachable!(),
+            }
+        });
+        BinnedFeatures { bins }
+    }
+
+    pub fn n_features(&self) -> usize {
+        self.bins.ncols()
+    }
+}
+
+/// Values at or below `thresholds[i]` and above `thresholds[i - 1]` go to bin `i + 1`, so a continuous split after bin `b` has the split value `thresholds[b - 1]`.
+pub(crate) fn compute_bin_for_number(value: f32, thresholds: &[f32]) -> u16 {
+    if !value.is_finite() {
+        return 0;
+    }
+    (1 + thresholds.partition_point(|threshold| *threshold < value))
+        .to_u16()
+        .unwrap()
+}
@@ -0,0 +1,282 @@
+use crate::{
+    train::{sigmoid, softmax},
+    BinaryClassifier, BranchSplit, MulticlassClassifier, Node, Regressor, SplitDirection, Tree,
+};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use rayon::prelude::*;
+use shadow_table::prelude::*;
+use shadow_zip::{pzip, zip};
+
+/// The number of examples each tree is evaluated on before moving to the next tree, so a tree's nodes stay in cache while they are reused.
+const N_EXAMPLES_PER_BLOCK: usize = 64;
+
+/// Trees flattened into one struct of arrays, indexed by node. The nodes of each tree are stored in depth first order, so a branch's left child usually follows it.
+#[derive(Clone, Debug)]
+pub struct CompiledTrees {
+    n_outputs: usize,
+    /// The index of each tree's root node.
+    roots: Vec<u32>,
+    /// The output each tree's predictions are added to.
+    outputs: Vec<u32>,
+    kinds: Vec<NodeKind>,
+    feature_indexes: Vec<u32>,
+    thresholds: Vec<f32>,
+    left_children: Vec<u32>,
+    right_children: Vec<u32>,
+    leaf_values: Vec<f32>,
+    /// For discrete splits, the start of the node's directions in `discrete_directions`, which has one entry per bin, where true means right.
+    discrete_offsets: Vec<u32>,
+    discrete_lengths: Vec<u32>,
+    discrete_directions: Vec<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeKind {
+    Leaf,
+    ContinuousInvalidValuesLeft,
+    ContinuousInvalidValuesRight,
+    Discrete,
+}
+
+impl CompiledTrees {
+    /// Compiles each tree along with the index of the output it predicts, which must be less than `n_outputs`.
+    pub fn compile<'a>(
+        n_outputs: usize,
+        trees: impl IntoIterator<Item = (usize, &'a Tree)>,
+    ) -> CompiledTrees {
+        let mut compiled = CompiledTrees {
+            n_outputs,
+            roots: Vec::new(),
+            outputs: Vec::new(),
+            kinds: Vec::new(),
+            feature_indexes: Vec::new(),
+            thresholds: Vec::new(),
+            left_children: Vec::new(),
+            right_children: Vec::new(),
+            leaf_values: Vec::new(),
+            discrete_offsets: Vec::new(),
+            discrete_lengths: Vec::new(),
+            discrete_directions: Vec::new(),
+        };
+        for (output, tree) in trees {
+            assert!(output < n_outputs);
+            compiled.outputs.push(output.to_u32().unwrap());
+            compiled.roots.push(compiled.kinds.len().to_u32().unwrap());
+            compiled.push_tree(tree);
+        }
+        compiled
+    }
+
+    fn push_tree(&mut self, tree: &Tree) {
+        let offset = self.kinds.len();
+        // Number the nodes in depth first order, then fill in each node at its new index.
+        let mut new_indexes = vec![0; tree.nodes.len()];
+        let mut order = Vec::with_capacity(tree.nodes.len());
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            new_indexes[node_index] = offset + order.len();
+            order.push(node_index);
+            if let Node::Branch(branch) = &tree.nodes[node_index] {
+                stack.push(branch.right_child_index);
+                stack.push(branch.left_child_index);
+            }
+        }
+        for node_index in order {
+            let mut kind = NodeKind::Leaf;
+            let mut feature_index = 0;
+            let mut threshold = 0.0;
+            let mut left_child = 0;
+            let mut right_child = 0;
+            let mut leaf_value = 0.0;
+            let mut discrete_offset = 0;
+            let mut discrete_length = 0;
+            match &tree.nodes[node_index] {
+                Node::Leaf(leaf) => {
+                    leaf_value = leaf.value as f32;
+                }
+                Node::Branch(branch) => {
+                    feature_index = branch.split.feature_index().to_u32().unwrap();
+                    left_child = new_indexes[branch.left_child_index].to_u32().unwrap();
+                    right_child = new_indexes[branch.right_child_index].to_u32().unwrap();
+                    match &branch.split {
+                        BranchSplit::Continuous(split) => {
+                            kind = match split.invalid_values_direction {
+                                SplitDirection::Left => NodeKind::ContinuousInvalidValuesLeft,
+                                SplitDirection::Right => NodeKind::ContinuousInvalidValuesRight,
+                            };
+                            threshold = split.split_value;
+                        }
+                        BranchSplit::Discrete(split) => {
+                            kind = NodeKind::Discrete;
+                            discrete_offset = self.discrete_directions.len().to_u32().unwrap();
+                            discrete_length = split.directions.len().to_u32().unwrap();
+                            self.discrete_directions
+                                .extend(split.directions.iter().by_vals());
+                        }
+                    }
+                }
+            }
+            self.kinds.push(kind);
+            self.feature_indexes.push(feature_index);
+            self.thresholds.push(threshold);
+            self.left_children.push(left_child);
+            self.right_children.push(right_child);
+            self.leaf_values.push(leaf_value);
+            self.discrete_offsets.push(discrete_offset);
+            self.discrete_lengths.push(discrete_length);
+        }
+    }
+
+    pub fn n_outputs(&self) -> usize {
+        self.n_outputs
+    }
+
+    pub fn n_trees(&self) -> usize {
+        self.roots.len()
+    }
+
+    /// Sets each example's outputs to the sum of the predictions of the trees for that output, added in the order the trees were compiled. This matches summing `Tree::predict` over the trees exactly.
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut outputs: ArrayViewMut2<f32>) {
+        pzip!(
+            features.axis_chunks_iter(Axis(0), N_EXAMPLES_PER_BLOCK),
+            outputs.axis_chunks_iter_mut(Axis(0), N_EXAMPLES_PER_BLOCK)
+        )
+        .for_each(|(features, mut outputs)| {
+            outputs.fill(0.0);
+            for (root, output) in zip!(self.roots.iter(), self.outputs.iter()) {
+                let output = output.to_usize().unwrap();
+                for (example, mut outputs) in
+                    zip!(features.axis_iter(Axis(0)), outputs.axis_iter_mut(Axis(0)))
+                {
+                    outputs[output] += self.predict_tree(*root, example.as_slice().unwrap());
+                }
+            }
+        });
+    }
+
+    fn predict_tree(&self, root: u32, example: &[TableValue]) -> f32 {
+        let mut node_index = root.to_usize().unwrap();
+        loop {
+            let kind = self.kinds[node_index];
+            if kind == NodeKind::Leaf {
+                return self.leaf_values[node_index];
+            }
+            let value = &example[self.feature_indexes[node_index].to_usize().unwrap()];
+            let goes_right = match kind {
+                NodeKind::Discrete => {
+                    let bin = value
+                        .as_enum()
+                        .unwrap()
+                        .map(|value| value.get())
+                        .unwrap_or(0);
+                    // Variants beyond the ones seen in training go the same way as missing values.
+                    let length = self.discrete_lengths[node_index].to_usize().unwrap();
+                    let bin = if bin < length { bin } else { 0 };
+                    let offset = self.discrete_offsets[node_index].to_usize().unwrap();
+                    self.discrete_directions[offset + bin]
+                }
+                _ => {
+                    let value = *value.as_number().unwrap();
+                    if !value.is_finite() {
+                        kind == NodeKind::ContinuousInvalidValuesRight
+                    } else {
+                        value > self.thresholds[node_index]
+                    }
+                }
+            };
+            node_index = if goes_right {
+                self.right_children[node_index]
+            } else {
+                self.left_children[node_index]
+            }
+            .to_usize()
+            .unwrap();
+        }
+    }
+}
+
+/// A `Regressor` compiled for fast inference.
+#[derive(Clone, Debug)]
+pub struct CompiledRegressor {
+    pub bias: f32,
+    pub trees: CompiledTrees,
+}
+
+/// A `BinaryClassifier` compiled for fast inference.
+#[derive(Clone, Debug)]
+pub struct CompiledBinaryClassifier {
+    pub bias: f32,
+    pub trees: CompiledTrees,
+}
+
+/// A `MulticlassClassifier` compiled for fast inference.
+#[derive(Clone, Debug)]
+pub struct CompiledMulticlassClassifier {
+    pub biases: Array1<f32>,
+    pub trees: CompiledTrees,
+}
+
+impl Regressor {
+    pub fn compile(&self) -> CompiledRegressor {
+        CompiledRegressor {
+            bias: self.bias,
+            trees: CompiledTrees::compile(1, self.trees.iter().map(|tree| (0, tree))),
+        }
+    }
+}
+
+impl BinaryClassifier {
+    pub fn compile(&self) -> CompiledBinaryClassifier {
+        CompiledBinaryClassifier {
+            bias: self.bias,
+            trees: CompiledTrees::compile(1, self.trees.iter().map(|tree| (0, tree))),
+        }
+    }
+}
+
+impl MulticlassClassifier {
+    pub fn compile(&self) -> CompiledMulticlassClassifier {
+        let trees = self
+            .trees
+            .axis_iter(Axis(0))
+            .flat_map(|trees| trees.into_iter().enumerate());
+        CompiledMulticlassClassifier {
+            biases: self.biases.clone(),
+            trees: CompiledTrees::compile(self.biases.len(), trees),
+        }
+    }
+}
+
+impl CompiledRegressor {
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut predictions: ArrayViewMut1<f32>) {
+        let mut outputs = Array::zeros((features.nrows(), 1));
+        self.trees.predict(features, outputs.view_mut());
+        for (prediction, output) in zip!(predictions.iter_mut(), outputs.iter()) {
+            *prediction = self.bias + output;
+        }
+    }
+}
+
+impl CompiledBinaryClassifier {
+    /// Computes the probability of the positive class.
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut probabilities: ArrayViewMut1<f32>) {
+        let mut outputs = Array::zeros((features.nrows(), 1));
+        self.trees.predict(features, outputs.view_mut());
+        for (probability, output) in zip!(probabilities.iter_mut(), outputs.iter()) {
+            *probability = sigmoid(self.bias + output);
+        }
+    }
+}
+
+impl CompiledMulticlassClassifier {
+    /// Computes the probability of each class, with one column per class.
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut probabilities: ArrayViewMut2<f32>) {
+        self.trees.predict(features, probabilities.view_mut());
+        for mut probabilities in probabilities.axis_iter_mut(Axis(0)) {
+            probabilities += &self.biases;
+            let softmax = softmax(probabilities.view());
+            probabilities.assign(&softmax);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod test {
+    use crate::{BinaryClassifier, MulticlassClassifier, Progress, Regressor, TrainOptions};
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::{EnumTableColumn, NumberTableColumn, Table, TableColumn, TableValue};
+    use std::num::NonZeroUsize;
+
+    /// Generates examples with a number feature that is sometimes NaN and an enum feature with four variants that is sometimes missing, along with a number label computed from both.
+    fn features_and_labels() -> (Table, Vec<f32>) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let numbers: Vec<f32> = (0..n_examples)
+            .map(|_| {
+                if rng.gen::<f32>() < 0.1 {
+                    f32::NAN
+                } else {
+                    rng.gen::<f32>()
+                }
+            })
+            .collect();
+        let variants: Vec<Option<NonZeroUsize>> = (0..n_examples)
+            .map(|_| NonZeroUsize::new(rng.gen_range(0..5)))
+            .collect();
+        let labels = numbers
+            .iter()
+            .zip(variants.iter())
+            .map(|(number, variant)| {
+                let number = if number.is_finite() { *number } else { 0.5 };
+                let variant = variant.map(|variant| variant.get()).unwrap_or(0);
+                2.0 * number + [0.3, 1.0, -0.5, 0.0, 0.8][variant]
+            })
+            .collect();
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = vec![
+            TableColumn::Number(NumberTableColumn::new(Some("x".to_owned()), numbers)),
+            TableColumn::Enum(EnumTableColumn::new(
+                Some("c".to_owned()),
+                (1..=4).map(|variant| variant.to_string()).collect(),
+                variants,
+            )),
+        ];
+        (table, labels)
+    }
+
+    /// Returns the training rows followed by rows with values the trees never saw in training.
+    fn rows(features: &Table) -> Array2<TableValue<'_>> {
+        let mut rows = features.to_rows();
+        let unseen = arr2(&[
+            [
+                TableValue::Number(f32::INFINITY),
+                TableValue::Enum(NonZeroUsize::new(9)),
+            ],
+            [TableValue::Number(-3.0), TableValue::Enum(None)],
+            [
+                TableValue::Number(7.0),
+                TableValue::Enum(NonZeroUsize::new(2)),
+            ],
+        ]);
+        rows.append(Axis(0), unseen.view()).unwrap();
+        rows
+    }
+
+    fn train_options() -> TrainOptions {
+        TrainOptions {
+            max_rounds: 20,
+            max_leaf_nodes: 8,
+            min_examples_per_node: 5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compiled_regressor() {
+        let (features, labels) = features_and_labels();
+        let labels = NumberTableColumn::new(None, labels);
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let model = Regressor::train(
+            &features.view(),
+            labels.view(),
+            &train_options(),
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .model;
+        let compiled = model.compile();
+        assert_eq!(compiled.trees.n_trees(), model.trees.len());
+        let rows = rows(&features);
+        let mut expected = Array::zeros(rows.nrows());
+        model.predict(rows.view(), expected.view_mut());
+        let mut actual = Array::zeros(rows.nrows());
+        compiled.predict(rows.view(), actual.view_mut());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_compiled_binary_classifier() {
+        let (features, labels) = features_and_labels();
+        let labels = EnumTableColumn::new(
+            None,
+            vec!["false".to_owned(), "true".to_owned()],
+            labels
+                .iter()
+                .map(|label| NonZeroUsize::new(if *label > 1.0 { 2 } else { 1 }))
+                .collect(),
+        );
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let model = BinaryClassifier::train(
+            &features.view(),
+            labels.view(),
+            &train_options(),
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .model;
+        let compiled = model.compile();
+        let rows = rows(&features);
+        let mut expected = Array::zeros(rows.nrows());
+        model.predict(rows.view(), expected.view_mut());
+        let mut actual = Array::zeros(rows.nrows());
+        compiled.predict(rows.view(), actual.view_mut());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_compiled_multiclass_classifier() {
+        let (features, labels) = features_and_labels();
+        let labels = EnumTableColumn::new(
+            None,
+            vec!["low".to_owned(), "mid".to_owned(), "high".to_owned()],
+            labels
+                .iter()
+                .map(|label| {
+                    NonZeroUsize::new(if *label < 0.5 {
+                        1
+                    } else if *label < 1.5 {
+                        2
+                    } else {
+                        3
+                    })
+                })
+                .collect(),
+        );
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let model = MulticlassClassifier::train(
+            &features.view(),
+            labels.view(),
+            &train_options(),
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+        .model;
+        let compiled = model.compile();
+        assert_eq!(compiled.trees.n_outputs(), 3);
+        assert_eq!(compiled.trees.n_trees(), model.trees.len());
+        let rows = rows(&features);
+        let mut expected = Array::zeros((rows.nrows(), 3));
+        model.predict(rows.view(), expected.view_mut());
+        let mut actual = Array::zeros((rows.nrows(), 3));
+        compiled.predict(rows.view(), actual.view_mut());
+        assert_eq!(actual, expected);
+    }
+}
@@ -48,25 +48,35 @@ fn compute_binning_instructions_for_number_feature(
     column: NumberTableColumnView,
     train_options: &TrainOptions,
 ) -> BinningInstruction {
-    let mut histogram: BTreeMap<Finite<f32>, usize> = BTreeMap::new();
-    let mut n_finite_values = 0;
-    let max = usize::min(
-        column.len(),
+    let thresholds = compute_thresholds_for_number_feature(
+        column.as_slice(),
+        train_options
+            .max_valid_bins_for_number_features
+            .to_usize()
+            .unwrap(),
         train_options.max_examples_for_computing_bin_thresholds,
     );
-    for value in column.iter().take(max) {
+    BinningInstruction::Number { thresholds }
+}
+
+pub fn compute_thresholds_for_number_feature(
+    values: &[f32],
+    max_valid_bins: usize,
+    max_examples_for_computing_bin_thresholds: usize,
+) -> Vec<f32> {
+    let mut histogram: BTreeMap<Finite<f32>, usize> = BTreeMap::new();
+    let mut n_finite_values = 0;
+    let max = usize::min(values.len(), max_examples_for_computing_bin_thresholds);
+    for value in values.iter().take(max) {
         if let Ok(value) = Finite::new(*value) {
             *histogram.entry(value).or_insert(0) += 1;
             n_finite_values += 1;
         }
     }
-
-    let thresholds = if histogram.len()
-        < train_options
-            .max_valid_bins_for_number_features
-            .to_usize()
-            .unwrap()
-    {
+    if histogram.is_empty() {
+        return Vec::new();
+    }
+    if histogram.len() < max_valid_bins {
         histogram
             .keys()
             .tuple_windows()
@@ -76,16 +86,15 @@ fn compute_binning_instructions_for_number_feature(
         compute_binning_instruction_thresholds_for_number_feature_as_quantiles_from_histogram(
             histogram,
             n_finite_values,
-            train_options,
+            max_valid_bins,
         )
-    };
-    BinningInstruction::Number { thresholds }
+    }
 }
 
 fn compute_binning_instruction_thresholds_for_number_feature_as_quantiles_from_histogram(
     histogram: BTreeMap<Finite<f32>, usize>,
     histogram_values_count: usize,
-    train_options: &TrainOptions,
+    max_valid_bins: usize,
 ) -> Vec<f32> {
     let first_hist_entry = histogram.iter().next().unwrap();
     let num_zeros = if first_hist_entry.0.get() == 0.0 {
@@ -95,17 +104,8 @@ fn compute_binning_instruction_thresholds_for_number_feature_as_quantiles_from_h
     };
     let total_non_zero_values_count = histogram_values_count - num_zeros;
     let total_non_zero_values_count = total_non_zero_values_count.to_f32().unwrap();
-    let quantiles: Vec<f32> = (1..train_options
-        .max_valid_bins_for_number_features
-        .to_usize()
-        .unwrap())
-        .map(|i| {
-            i.to_f32().unwrap()
-                / train_options
-                    .max_valid_bins_for_number_features
-                    .to_f32()
-                    .unwrap()
-        })
+    let quantiles: Vec<f32> = (1..max_valid_bins)
+        .map(|i| i.to_f32().unwrap() / max_valid_bins.to_f32().unwrap())
         .collect();
     let quantile_indexes: Vec<usize> = quantiles
         .iter()
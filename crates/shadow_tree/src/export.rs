@@ -0,0 +1,355 @@
+use crate::{
+    BinaryClassifier, BranchSplit, MulticlassClassifier, Node, RandomForestBinaryClassifier,
+    RandomForestMulticlassClassifier, RandomForestRegressor, Regressor, SplitDirection, Tree,
+};
+use ndarray::prelude::*;
+use shadow_table::prelude::*;
+use std::fmt::Write;
+
+/// Bump this whenever the JSON written by `to_json` changes in a way that could break a reader.
+const JSON_VERSION: u32 = 1;
+
+/// Describes a feature the trees were trained on, so exports can show names instead of indexes. Exports fall back to `feature {index}` and `variant {index}` for anything not described.
+#[derive(Clone, Debug)]
+pub struct ExportFeature {
+    pub name: String,
+    /// The variants of an enum feature, where the first variant is bin 1.
+    pub variants: Option<Vec<String>>,
+}
+
+impl ExportFeature {
+    /// Describes each column of the table the trees were trained on.
+    pub fn from_table(table: &TableView) -> Vec<ExportFeature> {
+        table
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(index, column)| ExportFeature {
+                name: column
+                    .name()
+                    .map(|name| name.to_owned())
+                    .unwrap_or_else(|| format!("feature {}", index)),
+                variants: match column {
+                    TableColumnView::Enum(column) => Some(column.variants().to_owned()),
+                    _ => None,
+                },
+            })
+            .collect()
+    }
+}
+
+fn feature_name(features: &[ExportFeature], feature_index: usize) -> String {
+    features
+        .get(feature_index)
+        .map(|feature| feature.name.clone())
+        .unwrap_or_else(|| format!("feature {}", feature_index))
+}
+
+/// Names a bin of a discrete split. Bin 0 holds missing values and variants not seen in training.
+fn bin_name(features: &[ExportFeature], feature_index: usize, bin_index: usize) -> String {
+    if bin_index == 0 {
+        return "missing".to_owned();
+    }
+    features
+        .get(feature_index)
+        .and_then(|feature| feature.variants.as_ref())
+        .and_then(|variants| variants.get(bin_index - 1))
+        .cloned()
+        .unwrap_or_else(|| format!("variant {}", bin_index))
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Tree {
+    /// Renders the tree as a Graphviz DOT digraph. Each branch's left edge is labeled with the condition that sends an example left.
+    pub fn to_dot(&self, features: &[ExportFeature]) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph tree {{").unwrap();
+        writeln!(dot, "  node [shape=box, fontname=\"Helvetica\"];").unwrap();
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            let examples = format!("examples: {:.1}%", 100.0 * node.examples_fraction());
+            match node {
+                Node::Leaf(leaf) => {
+                    writeln!(
+                        dot,
+                        "  {} [label=\"value: {}\\n{}\", style=rounded];",
+                        node_index, leaf.value, examples,
+                    )
+                    .unwrap();
+                }
+                Node::Branch(branch) => {
+                    let feature_index = branch.split.feature_index();
+                    let name = escape_dot(&feature_name(features, feature_index));
+                    let (condition, detail) = match &branch.split {
+                        BranchSplit::Continuous(split) => {
+                            let invalid_values = match split.invalid_values_direction {
+                                SplitDirection::Left => "left",
+                                SplitDirection::Right => "right",
+                            };
+                            (
+                                format!("{} <= {}", name, split.split_value),
+                                format!("invalid values go {}", invalid_values),
+                            )
+                        }
+                        BranchSplit::Discrete(split) => {
+                            let left_bins = split
+                                .directions
+                                .iter()
+                                .by_vals()
+                                .enumerate()
+                                .filter(|(_, direction)| !direction)
+                                .map(|(bin_index, _)| {
+                                    escape_dot(&bin_name(features, feature_index, bin_index))
+                                })
+                                .collect::<Vec<_>>();
+                            (
+                                format!("{} in {{{}}}", name, left_bins.join(", ")),
+                                "unseen variants go with missing".to_owned(),
+                            )
+                        }
+                    };
+                    writeln!(
+                        dot,
+                        "  {} [label=\"{}\\n{}\\ngain: {}\\n{}\"];",
+                        node_index, condition, detail, branch.gain, examples,
+                    )
+                    .unwrap();
+                    writeln!(
+                        dot,
+                        "  {} -> {} [label=\"yes\"];",
+                        node_index, branch.left_child_index
+                    )
+                    .unwrap();
+                    writeln!(
+                        dot,
+                        "  {} -> {} [label=\"no\"];",
+                        node_index, branch.right_child_index
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    fn to_json_tree(&self, features: &[ExportFeature]) -> JsonTree {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| match node {
+                Node::Leaf(leaf) => JsonNode::Leaf {
+                    value: leaf.value,
+                    examples_fraction: leaf.examples_fraction,
+                },
+                Node::Branch(branch) => {
+                    let feature_index = branch.split.feature_index();
+                    let split = match &branch.split {
+                        BranchSplit::Continuous(split) => JsonSplit::Continuous {
+                            feature_index,
+                            feature_name: feature_name(features, feature_index),
+                            split_value: split.split_value,
+                            invalid_values_direction: split.invalid_values_direction.into(),
+                        },
+                        BranchSplit::Discrete(split) => JsonSplit::Discrete {
+                            feature_index,
+                            feature_name: feature_name(features, feature_index),
+                            bins: split
+                                .directions
+                                .iter()
+                                .by_vals()
+                                .enumerate()
+                                .map(|(bin_index, direction)| JsonBin {
+                                    variant: if bin_index == 0 {
+                                        None
+                                    } else {
+                                        Some(bin_name(features, feature_index, bin_index))
+                                    },
+                                    direction: SplitDirection::from(direction).into(),
+                                })
+                                .collect(),
+                        },
+                    };
+                    JsonNode::Branch {
+                        left_child_index: branch.left_child_index,
+                        right_child_index: branch.right_child_index,
+                        split,
+                        examples_fraction: branch.examples_fraction,
+                        gain: branch.gain,
+                    }
+                }
+            })
+            .collect();
+        JsonTree { nodes }
+    }
+}
+
+/// The JSON dump of an ensemble. Fields are only added, removed or changed along with `version`. Trees are grouped by round for boosted models and by bootstrap sample for random forests, with one tree per output in each group.
+#[derive(serde::Serialize)]
+struct JsonEnsemble {
+    version: u32,
+    model_type: &'static str,
+    /// One bias per output. Random forests have none.
+    biases: Vec<f32>,
+    trees: Vec<Vec<JsonTree>>,
+}
+
+#[derive(serde::Serialize)]
+struct JsonTree {
+    nodes: Vec<JsonNode>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonNode {
+    Branch {
+        left_child_index: usize,
+        right_child_index: usize,
+        split: JsonSplit,
+        examples_fraction: f32,
+        gain: f32,
+    },
+    Leaf {
+        value: f64,
+        examples_fraction: f32,
+    },
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonSplit {
+    Continuous {
+        feature_index: usize,
+        feature_name: String,
+        split_value: f32,
+        invalid_values_direction: JsonSplitDirection,
+    },
+    Discrete {
+        feature_index: usize,
+        feature_name: String,
+        /// One entry per bin. The first bin holds missing values and variants not seen in training.
+        bins: Vec<JsonBin>,
+    },
+}
+
+#[derive(serde::Serialize)]
+struct JsonBin {
+    variant: Option<String>,
+    direction: JsonSplitDirection,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum JsonSplitDirection {
+    Left,
+    Right,
+}
+
+impl From<SplitDirection> for JsonSplitDirection {
+    fn from(value: SplitDirection) -> Self {
+        match value {
+            SplitDirection::Left => JsonSplitDirection::Left,
+            SplitDirection::Right => JsonSplitDirection::Right,
+        }
+    }
+}
+
+fn to_json<'a>(
+    model_type: &'static str,
+    biases: Vec<f32>,
+    trees: impl Iterator<Item = ArrayView1<'a, Tree>>,
+    features: &[ExportFeature],
+) -> String {
+    let ensemble = JsonEnsemble {
+        version: JSON_VERSION,
+        model_type,
+        biases,
+        trees: trees
+            .map(|trees| {
+                trees
+                    .iter()
+                    .map(|tree| tree.to_json_tree(features))
+                    .collect()
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&ensemble).unwrap()
+}
+
+fn single_output_trees(trees: &[Tree]) -> impl Iterator<Item = ArrayView1<'_, Tree>> {
+    trees
+        .iter()
+        .map(|tree| ArrayView1::from(std::slice::from_ref(tree)))
+}
+
+impl Regressor {
+    /// Dumps the model as pretty printed JSON.
+    pub fn to_json(&self, features: &[ExportFeature]) -> String {
+        to_json(
+            "regressor",
+            vec![self.bias],
+            single_output_trees(&self.trees),
+            features,
+        )
+    }
+}
+
+impl BinaryClassifier {
+    /// Dumps the model as pretty printed JSON. The trees predict the logit of the positive class.
+    pub fn to_json(&self, features: &[ExportFeature]) -> String {
+        to_json(
+            "binary_classifier",
+            vec![self.bias],
+            single_output_trees(&self.trees),
+            features,
+        )
+    }
+}
+
+impl MulticlassClassifier {
+    /// Like `BinaryClassifier::to_json`, with one tree per class in each round.
+    pub fn to_json(&self, features: &[ExportFeature]) -> String {
+        to_json(
+            "multiclass_classifier",
+            self.biases.to_vec(),
+            self.trees.axis_iter(Axis(0)),
+            features,
+        )
+    }
+}
+
+impl RandomForestRegressor {
+    pub fn to_json(&self, features: &[ExportFeature]) -> String {
+        to_json(
+            "random_forest_regressor",
+            Vec::new(),
+            single_output_trees(&self.trees),
+            features,
+        )
+    }
+}
+
+impl RandomForestBinaryClassifier {
+    /// The trees of a random forest classifier predict probabilities rather than logits.
+    pub fn to_json(&self, features: &[ExportFeature]) -> String {
+        to_json(
+            "random_forest_binary_classifier",
+            Vec::new(),
+            single_output_trees(&self.trees),
+            features,
+        )
+    }
+}
+
+impl RandomForestMulticlassClassifier {
+    pub fn to_json(&self, features: &[ExportFeature]) -> String {
+        to_json(
+            "random_forest_multiclass_classifier",
+            Vec::new(),
+            self.trees.axis_iter(Axis(0)),
+            features,
+        )
+    }
+}
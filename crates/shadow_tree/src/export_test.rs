@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        BranchNode, BranchSplit, BranchSplitContinuous, BranchSplitDiscrete, ExportFeature,
+        LeafNode, Node, Regressor, SplitDirection, Tree,
+    };
+    use bitvec::prelude::*;
+
+    /// Splits on a number feature `x`, then sends the `b` variant of an enum feature `c` right.
+    fn tree() -> Tree {
+        Tree {
+            nodes: vec![
+                Node::Branch(BranchNode {
+                    left_child_index: 1,
+                    right_child_index: 2,
+                    split: BranchSplit::Continuous(BranchSplitContinuous {
+                        feature_index: 0,
+                        split_value: 0.5,
+                        invalid_values_direction: SplitDirection::Right,
+                    }),
+                    examples_fraction: 1.0,
+                    gain: 4.0,
+                }),
+                Node::Leaf(LeafNode {
+                    value: -1.0,
+                    examples_fraction: 0.25,
+                }),
+                Node::Branch(BranchNode {
+                    left_child_index: 3,
+                    right_child_index: 4,
+                    split: BranchSplit::Discrete(BranchSplitDiscrete {
+                        feature_index: 1,
+                        directions: bitvec![u8, Lsb0; 0, 0, 1],
+                    }),
+                    examples_fraction: 0.75,
+                    gain: 2.5,
+                }),
+                Node::Leaf(LeafNode {
+                    value: 0.5,
+                    examples_fraction: 0.5,
+                }),
+                Node::Leaf(LeafNode {
+                    value: 2.0,
+                    examples_fraction: 0.25,
+                }),
+            ],
+        }
+    }
+
+    fn features() -> Vec<ExportFeature> {
+        vec![
+            ExportFeature {
+                name: "x".to_owned(),
+                variants: None,
+            },
+            ExportFeature {
+                name: "c".to_owned(),
+                variants: Some(vec!["a".to_owned(), "b".to_owned()]),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_dot() {
+        let expected = r#"digraph tree {
+  node [shape=box, fontname="Helvetica"];
+  0 [label="x <= 0.5\ninvalid values go right\ngain: 4\nexamples: 100.0%"];
+  0 -> 1 [label="yes"];
+  0 -> 2 [label="no"];
+  1 [label="value: -1\nexamples: 25.0%", style=rounded];
+  2 [label="c in {missing, a}\nunseen variants go with missing\ngain: 2.5\nexamples: 75.0%"];
+  2 -> 3 [label="yes"];
+  2 -> 4 [label="no"];
+  3 [label="value: 0.5\nexamples: 50.0%", style=rounded];
+  4 [label="value: 2\nexamples: 25.0%", style=rounded];
+}
+"#;
+        assert_eq!(tree().to_dot(&features()), expected);
+        // Without feature descriptions the indexes are shown.
+        assert!(tree()
+            .to_dot(&[])
+            .contains("feature 1 in {missing, variant 1}"));
+    }
+
+    #[test]
+    fn test_json() {
+        let model = Regressor {
+            bias: 1.5,
+            trees: vec![tree()],
+        };
+        let json = model.to_json(&features());
+        assert_eq!(json, model.to_json(&features()));
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["model_type"], "regressor");
+        assert_eq!(json["biases"], serde_json::json!([1.5]));
+        let nodes = &json["trees"][0][0]["nodes"];
+        assert_eq!(nodes.as_array().unwrap().len(), 5);
+        assert_eq!(
+            nodes[0]["split"],
+            serde_json::json!({
+                "type": "continuous",
+                "feature_index": 0,
+                "feature_name": "x",
+                "split_value": 0.5,
+                "invalid_values_direction": "right",
+            })
+        );
+        assert_eq!(
+            nodes[2]["split"]["bins"],
+            serde_json::json!([
+                { "variant": null, "direction": "left" },
+                { "variant": "a", "direction": "left" },
+                { "variant": "b", "direction": "right" },
+            ])
+        );
+        assert_eq!(
+            nodes[4],
+            serde_json::json!({ "type": "leaf", "value": 2.0, "examples_fraction": 0.25 })
+        );
+    }
+}
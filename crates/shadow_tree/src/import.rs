@@ -0,0 +1,53 @@
+use crate::{BinaryClassifier, MulticlassClassifier, Node, Regressor, Tree};
+use anyhow::{bail, Result};
+
+/// A model converted from another library. Number features are read from number columns. Categorical features are read from enum columns, where the first variant is category 0. Unlike in the original libraries, infinite numbers go the same way as missing values.
+#[derive(Clone, Debug)]
+pub enum ImportedModel {
+    Regressor(Regressor),
+    BinaryClassifier(BinaryClassifier),
+    MulticlassClassifier(MulticlassClassifier),
+}
+
+/// Returns the largest `f32` threshold `t` such that `value <= t` exactly when `value < threshold`, to convert strict comparisons to the `<=` used by `BranchSplitContinuous`.
+pub(crate) fn threshold_for_less_than(threshold: f32) -> f32 {
+    threshold.next_down()
+}
+
+/// Returns the largest `f32` threshold `t` such that `value <= t` exactly when `value as f64 <= threshold`.
+pub(crate) fn threshold_for_less_than_or_equal(threshold: f64) -> f32 {
+    let rounded = threshold as f32;
+    if rounded as f64 > threshold {
+        rounded.next_down()
+    } else {
+        rounded
+    }
+}
+
+/// `Tree::predict` does not check its indexes, so every imported tree is checked first. Each branch must split on one of the model's `n_features` features and point to children after it, which also rules out cycles.
+pub(crate) fn check_tree(tree: &Tree, n_features: usize) -> Result<()> {
+    if tree.nodes.is_empty() {
+        bail!("tree has no nodes");
+    }
+    for (node_index, node) in tree.nodes.iter().enumerate() {
+        let branch = match node {
+            Node::Branch(branch) => branch,
+            Node::Leaf(_) => continue,
+        };
+        let feature_index = branch.split.feature_index();
+        if feature_index >= n_features {
+            bail!(
+                "node {} splits on feature {} but the model has {} features",
+                node_index,
+                feature_index,
+                n_features
+            );
+        }
+        for child_index in [branch.left_child_index, branch.right_child_index] {
+            if child_index <= node_index || child_index >= tree.nodes.len() {
+                bail!("node {} has invalid child {}", node_index, child_index);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use crate::{
+    import::{check_tree, threshold_for_less_than_or_equal, ImportedModel},
+    BinaryClassifier, BranchNode, BranchSplit, BranchSplitContinuous, BranchSplitDiscrete,
+    LeafNode, MulticlassClassifier, Node, Regressor, SplitDirection, Tree,
+};
+use anyhow::{anyhow, bail, Result};
+use bitvec::prelude::*;
+use ndarray::prelude::*;
+use std::{collections::HashMap, str::FromStr};
+
+const CATEGORICAL_MASK: u8 = 1;
+const DEFAULT_LEFT_MASK: u8 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MissingType {
+    None,
+    Zero,
+    NaN,
+}
+
+/// Imports a model saved by LightGBM's `save_model` in text format. Only regression, binary and multiclass objectives without linear trees are supported.
+///
+/// Numerical splits that treat zero as missing are only supported when zero already goes the same way as missing values.
+pub fn import_lightgbm_text(text: &str) -> Result<ImportedModel> {
+    let mut sections = text.split("\nTree=");
+    let header = parse_key_values(sections.next().unwrap());
+    if header.contains_key("average_output") {
+        bail!("random forest models are not supported");
+    }
+    let objective = header
+        .get("objective")
+        .ok_or_else(|| anyhow!("model is missing its objective"))?;
+    let mut objective_parts = objective.split_whitespace();
+    let objective_name = objective_parts.next().unwrap_or("");
+    let objective_parameters: HashMap<&str, &str> = objective_parts
+        .filter_map(|part| part.split_once(':'))
+        .collect();
+    let n_trees_per_iteration: usize = parse_value(&header, "num_tree_per_iteration")?;
+    let max_feature_index: usize = parse_value(&header, "max_feature_idx")?;
+    let mut trees = Vec::new();
+    for section in sections {
+        let section = section.split("end of trees").next().unwrap();
+        let tree = parse_key_values(section);
+        let tree = convert_tree(&tree)?;
+        check_tree(&tree, max_feature_index + 1)?;
+        trees.push(tree);
+    }
+    match objective_name {
+        "regression" | "regression_l1" | "huber" | "fair" | "quantile" | "mape" => {
+            Ok(ImportedModel::Regressor(Regressor { bias: 0.0, trees }))
+        }
+        "binary" => {
+            // LightGBM computes probabilities as sigmoid(sigmoid_parameter * score), so the parameter is folded into the leaf values.
+            let sigmoid: f64 = objective_parameters
+                .get("sigmoid")
+                .map(|value| value.parse())
+                .transpose()?
+                .unwrap_or(1.0);
+            if sigmoid != 1.0 {
+                for tree in trees.iter_mut() {
+                    for node in tree.nodes.iter_mut() {
+                        if let Node::Leaf(leaf) = node {
+                            leaf.value *= sigmoid;
+                        }
+                    }
+                }
+            }
+            Ok(ImportedModel::BinaryClassifier(BinaryClassifier {
+                bias: 0.0,
+                trees,
+            }))
+        }
+        "multiclass" => {
+            let n_classes: usize = parse_value(&header, "num_class")?;
+            if n_classes != n_trees_per_iteration || trees.len() % n_classes != 0 {
+                bail!("trees do not divide evenly among {} classes", n_classes);
+            }
+            let n_rounds = trees.len() / n_classes;
+            Ok(ImportedModel::MulticlassClassifier(MulticlassClassifier {
+                biases: Array1::zeros(n_classes),
+                trees: Array2::from_shape_vec((n_rounds, n_classes), trees).unwrap(),
+            }))
+        }
+        name => bail!("unsupported objective \"{}\"", name),
+    }
+}
+
+fn parse_key_values(section: &str) -> HashMap<&str, &str> {
+    section
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect()
+}
+
+fn parse_value<T>(values: &HashMap<&str, &str>, key: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let value = values
+        .get(key)
+        .ok_or_else(|| anyhow!("model is missing {}", key))?;
+    Ok(value.trim().parse()?)
+}
+
+/// Parses a space separated list. Missing keys are treated as empty lists.
+fn parse_values<T>(values: &HashMap<&str, &str>, key: &str) -> Result<Vec<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    values
+        .get(key)
+        .map(|value| {
+            value
+                .split_whitespace()
+                .map(|value| Ok(value.parse()?))
+                .collect()
+        })
+        .unwrap_or_else(|| Ok(Vec::new()))
+}
+
+/// Converts a tree, placing its internal nodes first followed by its leaves, so the root stays at index 0.
+fn convert_tree(tree: &HashMap<&str, &str>) -> Result<Tree> {
+    if tree
+        .get("is_linear")
+        .is_some_and(|value| value.trim() != "0")
+    {
+        bail!("linear trees are not supported");
+    }
+    let n_leaves: usize = parse_value(tree, "num_leaves")?;
+    let leaf_values: Vec<f64> = parse_values(tree, "leaf_value")?;
+    let leaf_counts: Vec<f32> = parse_values(tree, "leaf_count")?;
+    if n_leaves == 0 || leaf_values.len() != n_leaves {
+        bail!(
+            "tree has {} leaves but {} leaf values",
+            n_leaves,
+            leaf_values.len()
+        );
+    }
+    if n_leaves == 1 {
+        return Ok(Tree {
+            nodes: vec![Node::Leaf(LeafNode {
+                value: leaf_values[0],
+                examples_fraction: 1.0,
+            })],
+        });
+    }
+    let n_branches = n_leaves - 1;
+    let split_features: Vec<usize> = parse_values(tree, "split_feature")?;
+    let split_gains: Vec<f32> = parse_values(tree, "split_gain")?;
+    let thresholds: Vec<f64> = parse_values(tree, "threshold")?;
+    let decision_types: Vec<u8> = parse_values(tree, "decision_type")?;
+    let left_children: Vec<i64> = parse_values(tree, "left_child")?;
+    let right_children: Vec<i64> = parse_values(tree, "right_child")?;
+    let internal_counts: Vec<f32> = parse_values(tree, "internal_count")?;
+    let cat_boundaries: Vec<usize> = parse_values(tree, "cat_boundaries")?;
+    let cat_thresholds: Vec<u32> = parse_values(tree, "cat_threshold")?;
+    if [
+        split_features.len(),
+        thresholds.len(),
+        decision_types.len(),
+        left_children.len(),
+        right_children.len(),
+    ]
+    .iter()
+    .any(|len| *len != n_branches)
+    {
+        bail!("tree arrays do not match its {} leaves", n_leaves);
+    }
+    let total_count = internal_counts.first().copied().unwrap_or(0.0);
+    let examples_fraction = |count: Option<&f32>| match count {
+        Some(count) if total_count > 0.0 => count / total_count,
+        _ => 0.0,
+    };
+    // Non-negative children are internal nodes and negative children are leaves, where -1 is leaf 0.
+    let child_index = |child: i64| -> Result<usize> {
+        let index = if child >= 0 {
+            usize::try_from(child)
+                .ok()
+                .filter(|index| *index < n_branches)
+        } else {
+            usize::try_from(-child - 1)
+                .ok()
+                .filter(|index| *index < n_leaves)
+                .map(|index| n_branches + index)
+        };
+        index.ok_or_else(|| anyhow!("invalid child index {}", child))
+    };
+    let mut nodes = Vec::with_capacity(n_branches + n_leaves);
+    for branch_index in 0..n_branches {
+        let feature_index = split_features[branch_index];
+        let decision_type = decision_types[branch_index];
+        let threshold = thresholds[branch_index];
+        let split = if decision_type & CATEGORICAL_MASK != 0 {
+            // The threshold indexes the bitset of categories that go left. Missing values, negative values and categories outside the bitset go right.
+            let cat_index = threshold as usize;
+            let words = cat_boundaries
+                .get(cat_index)
+                .zip(cat_boundaries.get(cat_index + 1))
+                .and_then(|(start, end)| cat_thresholds.get(*start..*end))
+                .ok_or_else(|| anyhow!("invalid categorical split {}", cat_index))?;
+            let mut directions = bitvec![u8, Lsb0; 1; 1 + 32 * words.len()];
+            for (word_index, word) in words.iter().enumerate() {
+                for bit in 0..32 {
+                    if word & (1 << bit) != 0 {
+                        directions.set(1 + 32 * word_index + bit, false);
+                    }
+                }
+            }
+            BranchSplit::Discrete(BranchSplitDiscrete {
+                feature_index,
+                directions,
+            })
+        } else {
+            let default_left = decision_type & DEFAULT_LEFT_MASK != 0;
+            let missing_type = match (decision_type >> 2) & 3 {
+                0 => MissingType::None,
+                1 => MissingType::Zero,
+                2 => MissingType::NaN,
+                _ => bail!("invalid decision type {}", decision_type),
+            };
+            // Without a missing type, LightGBM replaces NaN with zero before comparing it to the threshold.
+            let zero_goes_left = 0.0 <= threshold;
+            let invalid_values_left = match missing_type {
+                MissingType::NaN => default_left,
+                MissingType::None => zero_goes_left,
+                MissingType::Zero => {
+                    if default_left != zero_goes_left {
+                        bail!("splits that send zero and missing values apart are not supported");
+                    }
+                    default_left
+                }
+            };
+            BranchSplit::Continuous(BranchSplitContinuous {
+                feature_index,
+                split_value: threshold_for_less_than_or_equal(threshold),
+                invalid_values_direction: if invalid_values_left {
+                    SplitDirection::Left
+                } else {
+                    SplitDirection::Right
+                },
+            })
+        };
+        nodes.push(Node::Branch(BranchNode {
+            left_child_index: child_index(left_children[branch_index])?,
+            right_child_index: child_index(right_children[branch_index])?,
+            split,
+            examples_fraction: examples_fraction(internal_counts.get(branch_index)),
+            gain: split_gains.get(branch_index).copied().unwrap_or(0.0),
+        }));
+    }
+    for (leaf_index, value) in leaf_values.iter().enumerate() {
+        nodes.push(Node::Leaf(LeafNode {
+            value: *value,
+            examples_fraction: examples_fraction(leaf_counts.get(leaf_index)),
+        }));
+    }
+    Ok(Tree { nodes })
+}
@@ -0,0 +1,252 @@
+// The fixtures are written by hand in the formats saved by XGBoost 2.0 and LightGBM 4, and the expected predictions are worked out by walking their trees. They are not output from the libraries themselves.
+#[cfg(test)]
+mod test {
+    use crate::{
+        import_lightgbm_text, import_xgboost_json,
+        train::{sigmoid, softmax},
+        ImportedModel,
+    };
+    use ndarray::prelude::*;
+    use shadow_table::TableValue;
+    use std::num::NonZeroUsize;
+
+    fn number(value: f32) -> TableValue<'static> {
+        TableValue::Number(value)
+    }
+
+    /// Variant `category + 1` holds the category.
+    fn category(category: Option<usize>) -> TableValue<'static> {
+        TableValue::Enum(category.and_then(|category| NonZeroUsize::new(category + 1)))
+    }
+
+    fn assert_close(actual: ArrayView1<f32>, expected: ArrayView1<f32>) {
+        for (actual, expected) in actual.iter().zip(expected.iter()) {
+            assert!(
+                (actual - expected).abs() < 1e-6,
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    /// Checks a regressor's predictions, a binary classifier's logits or a multiclass classifier's logits, with one row of logits per example.
+    fn assert_predictions(
+        model: &ImportedModel,
+        rows: ArrayView2<TableValue>,
+        expected: &[&[f32]],
+    ) {
+        match model {
+            ImportedModel::Regressor(model) => {
+                let mut predictions = Array::zeros(rows.nrows());
+                model.predict(rows, predictions.view_mut());
+                let expected: Array1<f32> = expected.iter().map(|row| row[0]).collect();
+                assert_close(predictions.view(), expected.view());
+            }
+            ImportedModel::BinaryClassifier(model) => {
+                let mut probabilities = Array::zeros(rows.nrows());
+                model.predict(rows, probabilities.view_mut());
+                let expected: Array1<f32> = expected.iter().map(|row| sigmoid(row[0])).collect();
+                assert_close(probabilities.view(), expected.view());
+            }
+            ImportedModel::MulticlassClassifier(model) => {
+                let mut probabilities = Array::zeros((rows.nrows(), model.biases.len()));
+                model.predict(rows, probabilities.view_mut());
+                for (probabilities, logits) in probabilities.axis_iter(Axis(0)).zip(expected) {
+                    let expected = softmax(ArrayView1::from(*logits));
+                    assert_close(probabilities, expected.view());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_xgboost_regression() {
+        let model =
+            import_xgboost_json(include_str!("../fixtures/xgboost_regression.json")).unwrap();
+        let rows = arr2(&[
+            [number(0.2), number(1.0)],
+            // XGBoost sends values less than the split condition left, so ties go right.
+            [number(0.5), number(1.0)],
+            [number(0.5f32.next_down()), number(1.0)],
+            [number(f32::NAN), number(2.0)],
+            [number(0.9), number(f32::NAN)],
+            [number(0.7), number(0.1)],
+        ]);
+        assert_predictions(
+            &model,
+            rows.view(),
+            &[&[0.35], &[0.85], &[0.35], &[0.35], &[1.15], &[0.75]],
+        );
+    }
+
+    #[test]
+    fn test_xgboost_binary_categorical() {
+        let model =
+            import_xgboost_json(include_str!("../fixtures/xgboost_binary_categorical.json"))
+                .unwrap();
+        let rows = arr2(&[
+            [category(Some(1)), number(5.0)],
+            [category(Some(3)), number(-1.0)],
+            [category(Some(0)), number(-1.0)],
+            [category(Some(2)), number(0.0)],
+            [category(None), number(f32::NAN)],
+            [category(Some(4)), number(1.0)],
+        ]);
+        assert_predictions(
+            &model,
+            rows.view(),
+            &[&[1.0], &[1.0], &[-1.0], &[-0.5], &[-0.5], &[-0.5]],
+        );
+    }
+
+    #[test]
+    fn test_xgboost_multiclass() {
+        let model =
+            import_xgboost_json(include_str!("../fixtures/xgboost_multiclass.json")).unwrap();
+        match &model {
+            ImportedModel::MulticlassClassifier(model) => assert_eq!(model.trees.dim(), (2, 3)),
+            _ => panic!("expected a multiclass classifier"),
+        }
+        let rows = arr2(&[
+            [number(0.1)],
+            [number(0.5)],
+            [number(0.9)],
+            [number(f32::NAN)],
+        ]);
+        assert_predictions(
+            &model,
+            rows.view(),
+            &[
+                &[1.0, 0.3, 0.5],
+                &[0.2, 0.9, 0.5],
+                &[0.2, 1.0, 0.8],
+                &[1.0, 0.3, 0.5],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_lightgbm_regression() {
+        let model =
+            import_lightgbm_text(include_str!("../fixtures/lightgbm_regression.txt")).unwrap();
+        let rows = arr2(&[
+            [number(0.2), number(1.0)],
+            // LightGBM sends values less than or equal to the threshold left, so ties go left.
+            [number(0.5), number(1.0)],
+            [number(f32::NAN), number(2.0)],
+            // The second split has no missing type, so NaN is compared as zero.
+            [number(0.9), number(f32::NAN)],
+            [number(0.7), number(3.0)],
+            // The threshold 0.30000000000000004 lies between these two floats.
+            [number(0.7), number(0.3)],
+            [number(0.7), number(0.3f32.next_down())],
+        ]);
+        assert_predictions(
+            &model,
+            rows.view(),
+            &[
+                &[-0.05],
+                &[-0.05],
+                &[-0.05],
+                &[0.45],
+                &[0.75],
+                &[0.45],
+                &[0.35],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_lightgbm_binary_categorical() {
+        let model =
+            import_lightgbm_text(include_str!("../fixtures/lightgbm_binary_categorical.txt"))
+                .unwrap();
+        let rows = arr2(&[
+            [category(Some(0)), number(5.0)],
+            [category(Some(2)), number(-1.0)],
+            [category(Some(33)), number(f32::NAN)],
+            // Zero is treated as missing by the second split.
+            [category(Some(2)), number(0.0)],
+            [category(Some(1)), number(0.0)],
+            [category(None), number(1.0)],
+            [category(Some(99)), number(1.0)],
+        ]);
+        assert_predictions(
+            &model,
+            rows.view(),
+            &[&[0.1], &[-0.5], &[-0.5], &[-0.5], &[0.7], &[0.7], &[0.7]],
+        );
+    }
+
+    #[test]
+    fn test_lightgbm_multiclass() {
+        let model =
+            import_lightgbm_text(include_str!("../fixtures/lightgbm_multiclass.txt")).unwrap();
+        let rows = arr2(&[
+            [number(0.1)],
+            [number(0.5)],
+            [number(0.9)],
+            [number(f32::NAN)],
+        ]);
+        assert_predictions(
+            &model,
+            rows.view(),
+            &[
+                &[0.5, -0.2, 0.0],
+                &[0.1, -0.2, 0.0],
+                &[-0.3, 0.5, 0.3],
+                &[0.5, -0.2, 0.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_unsupported_models() {
+        let json = include_str!("../fixtures/xgboost_regression.json")
+            .replace("reg:squarederror", "count:poisson");
+        assert!(import_xgboost_json(&json).is_err());
+        let text = include_str!("../fixtures/lightgbm_regression.txt")
+            .replace("objective=regression", "objective=poisson");
+        assert!(import_lightgbm_text(&text).is_err());
+        // Zero is missing and goes right, but a zero compared to the threshold goes left.
+        let text = include_str!("../fixtures/lightgbm_binary_categorical.txt")
+            .replace("decision_type=9 6", "decision_type=9 4");
+        assert!(import_lightgbm_text(&text).is_err());
+    }
+
+    #[test]
+    fn test_malformed_models() {
+        let json = include_str!("../fixtures/xgboost_regression.json");
+        // A feature beyond num_feature.
+        let malformed = json.replace(
+            "\"split_indices\": [0, 0, 1, 0, 0]",
+            "\"split_indices\": [0, 0, 2, 0, 0]",
+        );
+        assert!(import_xgboost_json(&malformed).is_err());
+        // Node 2 points back to the root.
+        let malformed = json.replace(
+            "\"right_children\": [2, -1, 4, -1, -1]",
+            "\"right_children\": [2, -1, 0, -1, -1]",
+        );
+        assert!(import_xgboost_json(&malformed).is_err());
+        // A categorical split without a segment for its categories.
+        let json = include_str!("../fixtures/xgboost_binary_categorical.json");
+        let malformed = json.replace(
+            "\"categories_segments\": [0], \"categories_sizes\": [2]",
+            "\"categories_segments\": [], \"categories_sizes\": []",
+        );
+        assert!(import_xgboost_json(&malformed).is_err());
+        let malformed = json.replace(
+            "\"categories_segments\": [0]",
+            &format!("\"categories_segments\": [{}]", usize::MAX),
+        );
+        assert!(import_xgboost_json(&malformed).is_err());
+        let text = include_str!("../fixtures/lightgbm_regression.txt");
+        let malformed = text.replace("split_feature=0 1", "split_feature=0 2");
+        assert!(import_lightgbm_text(&malformed).is_err());
+        // The internal node 1 points back to itself.
+        let malformed = text.replace("right_child=1 -3", "right_child=1 1");
+        assert!(import_lightgbm_text(&malformed).is_err());
+    }
+}
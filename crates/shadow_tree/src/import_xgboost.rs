@@ -0,0 +1,280 @@
+use crate::{
+    import::{check_tree, threshold_for_less_than, ImportedModel},
+    BinaryClassifier, BranchNode, BranchSplit, BranchSplitContinuous, BranchSplitDiscrete,
+    LeafNode, MulticlassClassifier, Node, Regressor, SplitDirection, Tree,
+};
+use anyhow::{anyhow, bail, Result};
+use bitvec::prelude::*;
+use ndarray::prelude::*;
+
+#[derive(serde::Deserialize)]
+struct Model {
+    learner: Learner,
+}
+
+#[derive(serde::Deserialize)]
+struct Learner {
+    gradient_booster: GradientBooster,
+    learner_model_param: LearnerModelParam,
+    objective: Objective,
+}
+
+#[derive(serde::Deserialize)]
+struct GradientBooster {
+    name: String,
+    model: Option<GbtreeModel>,
+    /// DART boosters nest a gbtree booster and scale each tree by its weight.
+    gbtree: Option<Box<GradientBooster>>,
+    weight_drop: Option<Vec<f32>>,
+}
+
+#[derive(serde::Deserialize)]
+struct GbtreeModel {
+    trees: Vec<XgboostTree>,
+    /// The class each tree predicts.
+    tree_info: Vec<usize>,
+}
+
+#[derive(serde::Deserialize)]
+struct LearnerModelParam {
+    base_score: String,
+    num_class: String,
+    num_feature: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Objective {
+    name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct XgboostTree {
+    left_children: Vec<i64>,
+    right_children: Vec<i64>,
+    split_indices: Vec<usize>,
+    /// The threshold of each branch and the value of each leaf.
+    split_conditions: Vec<f32>,
+    default_left: Vec<Flag>,
+    loss_changes: Vec<f32>,
+    sum_hessian: Vec<f32>,
+    /// 0 for numerical splits and 1 for categorical splits. Missing in models without categorical features.
+    #[serde(default)]
+    split_type: Vec<u8>,
+    #[serde(default)]
+    categories: Vec<usize>,
+    #[serde(default)]
+    categories_nodes: Vec<usize>,
+    #[serde(default)]
+    categories_segments: Vec<usize>,
+    #[serde(default)]
+    categories_sizes: Vec<usize>,
+}
+
+/// Older versions write `default_left` as integers and newer ones as booleans.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Flag {
+    Bool(bool),
+    Int(u8),
+}
+
+impl Flag {
+    fn get(&self) -> bool {
+        match self {
+            Flag::Bool(value) => *value,
+            Flag::Int(value) => *value != 0,
+        }
+    }
+}
+
+/// Imports a model saved by XGBoost's `save_model` in JSON format. Only tree boosters with squared error, logistic and softmax objectives are supported.
+///
+/// XGBoost sends categories outside a categorical split's set left, while the imported split can only send categories greater than any in the set in the same direction as missing values. Predictions for those categories differ when missing values go right.
+pub fn import_xgboost_json(json: &str) -> Result<ImportedModel> {
+    let model: Model = serde_json::from_str(json)?;
+    let learner = model.learner;
+    let (model, weights) = match learner.gradient_booster.name.as_str() {
+        "gbtree" => (learner.gradient_booster.model, None),
+        "dart" => {
+            let gbtree = learner
+                .gradient_booster
+                .gbtree
+                .ok_or_else(|| anyhow!("dart booster is missing its gbtree"))?;
+            (gbtree.model, learner.gradient_booster.weight_drop)
+        }
+        name => bail!("unsupported booster \"{}\"", name),
+    };
+    let model = model.ok_or_else(|| anyhow!("booster is missing its model"))?;
+    if model.tree_info.len() != model.trees.len() {
+        bail!("tree_info does not match the number of trees");
+    }
+    if let Some(weights) = &weights {
+        if weights.len() != model.trees.len() {
+            bail!("weight_drop does not match the number of trees");
+        }
+    }
+    let n_classes: usize = learner.learner_model_param.num_class.parse()?;
+    let base_scores = parse_base_score(&learner.learner_model_param.base_score)?;
+    let n_features: usize = learner.learner_model_param.num_feature.parse()?;
+    let mut trees_per_class: Vec<Vec<Tree>> = vec![Vec::new(); n_classes.max(1)];
+    for (tree_index, (tree, class)) in model.trees.iter().zip(model.tree_info.iter()).enumerate() {
+        let weight = weights
+            .as_ref()
+            .map(|weights| weights[tree_index])
+            .unwrap_or(1.0);
+        let tree = convert_tree(tree, weight)?;
+        check_tree(&tree, n_features)?;
+        trees_per_class
+            .get_mut(*class)
+            .ok_or_else(|| anyhow!("tree {} predicts class {}", tree_index, class))?
+            .push(tree);
+    }
+    match learner.objective.name.as_str() {
+        "reg:squarederror" | "reg:linear" | "reg:absoluteerror" | "reg:pseudohubererror" => {
+            Ok(ImportedModel::Regressor(Regressor {
+                bias: base_scores[0],
+                trees: trees_per_class.remove(0),
+            }))
+        }
+        "binary:logistic" | "binary:logitraw" => {
+            // The base score of binary:logistic is a probability, which is converted to a logit before it is added to the trees' outputs.
+            let bias = if learner.objective.name == "binary:logistic" {
+                let probability = base_scores[0];
+                (probability / (1.0 - probability)).ln()
+            } else {
+                base_scores[0]
+            };
+            Ok(ImportedModel::BinaryClassifier(BinaryClassifier {
+                bias,
+                trees: trees_per_class.remove(0),
+            }))
+        }
+        "multi:softprob" | "multi:softmax" => {
+            if n_classes < 2 {
+                bail!("multiclass model has {} classes", n_classes);
+            }
+            let n_rounds = trees_per_class[0].len();
+            if trees_per_class.iter().any(|trees| trees.len() != n_rounds) {
+                bail!("classes have different numbers of trees");
+            }
+            let mut trees = Vec::with_capacity(n_rounds * n_classes);
+            for round in 0..n_rounds {
+                for class_trees in trees_per_class.iter() {
+                    trees.push(class_trees[round].clone());
+                }
+            }
+            let biases = if base_scores.len() == n_classes {
+                Array1::from(base_scores)
+            } else {
+                Array1::from_elem(n_classes, base_scores[0])
+            };
+            Ok(ImportedModel::MulticlassClassifier(MulticlassClassifier {
+                biases,
+                trees: Array2::from_shape_vec((n_rounds, n_classes), trees).unwrap(),
+            }))
+        }
+        name => bail!("unsupported objective \"{}\"", name),
+    }
+}
+
+/// Newer versions write the base score as a list, such as `[5E-1]`.
+fn parse_base_score(base_score: &str) -> Result<Vec<f32>> {
+    let base_scores = base_score
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|value| value.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if base_scores.is_empty() {
+        bail!("base_score is empty");
+    }
+    Ok(base_scores)
+}
+
+fn convert_tree(tree: &XgboostTree, weight: f32) -> Result<Tree> {
+    let n_nodes = tree.left_children.len();
+    if [
+        tree.right_children.len(),
+        tree.split_indices.len(),
+        tree.split_conditions.len(),
+        tree.default_left.len(),
+        tree.loss_changes.len(),
+        tree.sum_hessian.len(),
+    ]
+    .iter()
+    .any(|len| *len != n_nodes)
+    {
+        bail!("tree arrays have different lengths");
+    }
+    if n_nodes == 0 {
+        bail!("tree has no nodes");
+    }
+    let total_hessian = tree.sum_hessian[0];
+    let child_index = |child: i64| -> Result<usize> {
+        usize::try_from(child)
+            .ok()
+            .filter(|child| *child < n_nodes)
+            .ok_or_else(|| anyhow!("invalid child index {}", child))
+    };
+    let nodes = (0..n_nodes)
+        .map(|node_index| {
+            let examples_fraction = if total_hessian > 0.0 {
+                tree.sum_hessian[node_index] / total_hessian
+            } else {
+                0.0
+            };
+            if tree.left_children[node_index] == -1 {
+                return Ok(Node::Leaf(LeafNode {
+                    value: (weight * tree.split_conditions[node_index]) as f64,
+                    examples_fraction,
+                }));
+            }
+            let feature_index = tree.split_indices[node_index];
+            let default_left = tree.default_left[node_index].get();
+            let is_categorical = tree.split_type.get(node_index).copied().unwrap_or(0) == 1;
+            let split = if is_categorical {
+                let segment = tree
+                    .categories_nodes
+                    .iter()
+                    .position(|node| *node == node_index)
+                    .ok_or_else(|| anyhow!("node {} is missing its categories", node_index))?;
+                let categories = tree
+                    .categories_segments
+                    .get(segment)
+                    .zip(tree.categories_sizes.get(segment))
+                    .and_then(|(start, size)| Some(*start..start.checked_add(*size)?))
+                    .and_then(|range| tree.categories.get(range))
+                    .ok_or_else(|| anyhow!("node {} has invalid categories", node_index))?;
+                // Bin 0 holds missing values, and each category's bin is one more than the category. Categories in the set go right.
+                let n_bins = categories.iter().max().map(|max| max + 2).unwrap_or(1);
+                let mut directions = bitvec![u8, Lsb0; 0; n_bins];
+                directions.set(0, !default_left);
+                for category in categories {
+                    directions.set(category + 1, true);
+                }
+                BranchSplit::Discrete(BranchSplitDiscrete {
+                    feature_index,
+                    directions,
+                })
+            } else {
+                BranchSplit::Continuous(BranchSplitContinuous {
+                    feature_index,
+                    split_value: threshold_for_less_than(tree.split_conditions[node_index]),
+                    invalid_values_direction: if default_left {
+                        SplitDirection::Left
+                    } else {
+                        SplitDirection::Right
+                    },
+                })
+            };
+            Ok(Node::Branch(BranchNode {
+                left_child_index: child_index(tree.left_children[node_index])?,
+                right_child_index: child_index(tree.right_children[node_index])?,
+                split,
+                examples_fraction,
+                gain: tree.loss_changes[node_index],
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Tree { nodes })
+}
@@ -1,9 +1,52 @@
+pub use self::binary_classifier::{BinaryClassifier, BinaryClassifierTrainOutput};
+pub use self::compiled::{
+    CompiledBinaryClassifier, CompiledMulticlassClassifier, CompiledRegressor, CompiledTrees,
+};
+pub use self::export::ExportFeature;
+pub use self::import::ImportedModel;
+pub use self::import_lightgbm::import_lightgbm_text;
+pub use self::import_xgboost::import_xgboost_json;
+pub use self::multiclass_classifier::{MulticlassClassifier, MulticlassClassifierTrainOutput};
+pub use self::random_forest::{
+    RandomForestBinaryClassifier, RandomForestBinaryClassifierTrainOutput,
+    RandomForestMulticlassClassifier, RandomForestMulticlassClassifierTrainOutput,
+    RandomForestOptions, RandomForestRegressor, RandomForestRegressorTrainOutput,
+};
+pub use self::regressor::{Regressor, RegressorTrainOutput};
+#[cfg(feature = "timing")]
+pub use self::timing::{RoundTiming, Timing, TimingDuration};
+pub use self::tree_stats::TreeStats;
 use bitvec::prelude::*;
 use shadow_progress_counter::ProgressCounter;
 
+mod binary_classifier;
+mod binned_features;
+mod compiled;
+#[cfg(test)]
+mod compiled_test;
 pub mod compute_binning_instructions;
-#[cfg(feature = "timing")]
+mod export;
+#[cfg(test)]
+mod export_test;
+mod import;
+mod import_lightgbm;
+#[cfg(test)]
+mod import_test;
+mod import_xgboost;
+mod multiclass_classifier;
+pub mod pool;
+#[cfg(test)]
+mod pool_test;
+mod random_forest;
+#[cfg(test)]
+mod random_forest_test;
+mod regressor;
 mod timing;
+mod train;
+#[cfg(test)]
+mod train_test;
+mod train_tree;
+mod tree_stats;
 
 pub struct Progress<'a> {
     pub kill_chip: &'a shadow_kill_chip::KillChip,
@@ -25,8 +68,32 @@ pub struct TrainOptions {
     pub max_valid_bins_for_number_features: u8,
     pub min_examples_per_node: usize,
     pub min_gain_to_split: f32,
+    /// The minimum sum of the hessians of the examples in each child of a split, which XGBoost calls `min_child_weight`. For regression every hessian is one, so it is also a minimum number of examples.
     pub min_sum_hessians_per_node: f32,
+    /// The number of trees and how they are randomized, for the random forest models.
+    pub random_forest_options: RandomForestOptions,
+    /// Seeds the random number generators, so training with the same seed is reproducible.
+    pub seed: u64,
     pub smoothing_factor_for_discrete_bin_sorting: f32,
+    /// Enum features with at most this many occupied bins at a node, counting the bin of missing values, are split one variant against the rest instead of by sorting the bins.
+    pub max_variants_for_one_vs_rest_splits: usize,
+    /// The fraction of the training examples, drawn without replacement, that each boosting round trains on.
+    pub bagging_fraction: f32,
+    /// If set, each boosting round samples examples by the size of their gradients instead of uniformly, and `bagging_fraction` is ignored.
+    pub goss_options: Option<GossOptions>,
+    /// The fraction of the features each boosted tree may split on.
+    pub feature_fraction_per_tree: f32,
+    /// The fraction of a boosted tree's features considered when choosing each split.
+    pub feature_fraction_per_node: f32,
+    /// If set, each boosting round drops a random subset of the previous rounds while fitting the new trees.
+    pub dart_options: Option<DartOptions>,
+    /// One per feature, or empty to leave every feature unconstrained. Only number features may be constrained.
+    pub monotone_constraints: Vec<MonotoneConstraint>,
+    /// Groups of feature indexes. If any are given, the features split on along each path from the root to a leaf must all belong to one group, and a feature in no group may only be split on alone.
+    pub interaction_constraints: Vec<Vec<usize>>,
+    pub growth_policy: GrowthPolicy,
+    /// After growing each tree, its subtrees are pruned while the gain of their splits per leaf they add is below this. Zero disables pruning.
+    pub cost_complexity_pruning_alpha: f32,
 }
 
 impl Default for TrainOptions {
@@ -46,17 +113,83 @@ impl Default for TrainOptions {
             min_gain_to_split: 0.0,
             min_sum_hessians_per_node: 1e-3,
             max_examples_for_computing_bin_thresholds: 200_000,
+            random_forest_options: RandomForestOptions::default(),
+            seed: 0,
             smoothing_factor_for_discrete_bin_sorting: 10.0,
+            max_variants_for_one_vs_rest_splits: 4,
+            bagging_fraction: 1.0,
+            goss_options: None,
+            feature_fraction_per_tree: 1.0,
+            feature_fraction_per_node: 1.0,
+            dart_options: None,
+            monotone_constraints: Vec::new(),
+            interaction_constraints: Vec::new(),
+            growth_policy: GrowthPolicy::LeafWise,
+            cost_complexity_pruning_alpha: 0.0,
         }
     }
 }
 
+/// The order trees are grown in, until `max_leaf_nodes` is reached or no leaf can be split.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrowthPolicy {
+    /// Split the leaf with the largest gain first.
+    LeafWise,
+    /// Split every leaf at one depth before any leaf at the next.
+    DepthWise,
+}
+
+/// How the predictions must change as a number feature increases and the other features stay the same.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MonotoneConstraint {
+    #[default]
+    None,
+    Increasing,
+    Decreasing,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum BinnedFeaturesLayout {
     RowMajor,
     ColumnMajor,
 }
 
+/// Gradient-based one-side sampling keeps the examples with the largest gradients and a uniform sample of the rest, whose gradients and hessians are scaled up by `(1 - top_fraction) / other_fraction` to keep the sums unbiased.
+#[derive(Clone, Debug)]
+pub struct GossOptions {
+    pub top_fraction: f32,
+    pub other_fraction: f32,
+}
+
+impl Default for GossOptions {
+    fn default() -> GossOptions {
+        GossOptions {
+            top_fraction: 0.2,
+            other_fraction: 0.1,
+        }
+    }
+}
+
+/// Dropouts meet Multiple Additive Regression Trees. With `k` rounds dropped, the new round's trees are scaled by `learning_rate / (k + learning_rate)` and the dropped rounds by `k / (k + learning_rate)`, so the predictions keep their scale.
+#[derive(Clone, Debug)]
+pub struct DartOptions {
+    /// The probability of dropping each previous round.
+    pub drop_rate: f32,
+    pub max_dropped_rounds: Option<usize>,
+    /// The probability of skipping the dropout in a round, which then trains like plain boosting.
+    pub skip_drop_rate: f32,
+}
+
+impl Default for DartOptions {
+    fn default() -> DartOptions {
+        DartOptions {
+            drop_rate: 0.1,
+            max_dropped_rounds: Some(50),
+            skip_drop_rate: 0.5,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct EarlyStoppingOptions {
     pub early_stopping_fraction: f32,
@@ -91,16 +224,21 @@ impl Tree {
                             BranchSplit::Continuous(BranchSplitContinuous {
                                 feature_index,
                                 split_value,
-                                ..
+                                invalid_values_direction,
                             }),
                         ..
                     }) => {
-                        node_index = if example.get_unchecked(*feature_index).as_number().unwrap()
-                            <= split_value
-                        {
-                            *left_child_index
+                        let value = *example.get_unchecked(*feature_index).as_number().unwrap();
+                        let direction = if !value.is_finite() {
+                            *invalid_values_direction
+                        } else if value <= *split_value {
+                            SplitDirection::Left
                         } else {
-                            *right_child_index
+                            SplitDirection::Right
+                        };
+                        node_index = match direction {
+                            SplitDirection::Left => *left_child_index,
+                            SplitDirection::Right => *right_child_index,
                         };
                     }
 
@@ -122,6 +260,12 @@ impl Tree {
                         } else {
                             0
                         };
+                        // Variants beyond the ones seen in training go the same way as missing values.
+                        let bin_index = if bin_index < directions.len() {
+                            bin_index
+                        } else {
+                            0
+                        };
                         let direction = (*directions.get(bin_index).unwrap()).into();
                         node_index = match direction {
                             SplitDirection::Left => *left_child_index,
@@ -173,6 +317,8 @@ pub struct BranchNode {
     pub right_child_index: usize,
     pub split: BranchSplit,
     pub examples_fraction: f32,
+    /// The gain of the split, which is twice the decrease in the second order approximation of the training loss.
+    pub gain: f32,
 }
 
 #[derive(Clone, Debug)]
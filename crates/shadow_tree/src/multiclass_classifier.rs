@@ -0,0 +1,80 @@
+use crate::{
+    train::{softmax, train, Task},
+    Progress, TrainOptions, Tree, TreeStats,
+};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use rayon::prelude::*;
+use shadow_table::prelude::*;
+use shadow_zip::{pzip, zip};
+
+/// A gradient boosted multiclass classifier, which trains one tree per class in each round and applies the softmax to each class's bias plus the sum of its trees' predictions.
+#[derive(Clone, Debug)]
+pub struct MulticlassClassifier {
+    pub biases: Array1<f32>,
+    /// One row per round and one column per class.
+    pub trees: Array2<Tree>,
+}
+
+pub struct MulticlassClassifierTrainOutput {
+    pub model: MulticlassClassifier,
+    pub losses: Option<Vec<f32>>,
+    pub feature_importances: Option<Vec<f32>>,
+    #[cfg(feature = "timing")]
+    pub timing: crate::Timing,
+}
+
+impl MulticlassClassifier {
+    pub fn train(
+        features: &TableView,
+        labels: EnumTableColumnView,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> MulticlassClassifierTrainOutput {
+        let n_classes = labels.variants().len();
+        let labels = labels
+            .iter()
+            .map(|label| (label.unwrap().get() - 1).to_f32().unwrap())
+            .collect::<Array1<f32>>();
+        let output = train(
+            Task::MulticlassClassification { n_classes },
+            features,
+            labels.view(),
+            train_options,
+            progress,
+        );
+        MulticlassClassifierTrainOutput {
+            model: MulticlassClassifier {
+                biases: output.biases,
+                trees: output.trees,
+            },
+            losses: output.losses,
+            feature_importances: Some(output.feature_importances),
+            #[cfg(feature = "timing")]
+            timing: output.timing,
+        }
+    }
+
+    /// Computes the probability of each class, with one column per class.
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut probabilities: ArrayViewMut2<f32>) {
+        pzip!(
+            features.axis_iter(Axis(0)),
+            probabilities.axis_iter_mut(Axis(0))
+        )
+        .for_each(|(example, mut probabilities)| {
+            let example = example.as_slice().unwrap();
+            let mut logits = Array1::<f32>::zeros(self.biases.len());
+            for trees in self.trees.axis_iter(Axis(0)) {
+                for (logit, tree) in zip!(logits.iter_mut(), trees.iter()) {
+                    *logit += tree.predict(example);
+                }
+            }
+            logits += &self.biases;
+            probabilities.assign(&softmax(logits.view()));
+        });
+    }
+
+    pub fn tree_stats(&self) -> TreeStats {
+        TreeStats::compute(self.trees.iter())
+    }
+}
@@ -1,52 +1,177 @@
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
+/// A pool of at most `max_items` items, which are created on demand and returned to the pool when the `PoolItem` holding them is dropped.
 pub struct Pool<T> {
-	create_item: Box<dyn Fn() -> T>,
+	create_item: Box<dyn Fn() -> T + Send + Sync>,
 	max_items: usize,
-	state: Arc<Mutex<State<T>>>,
+	shared: Arc<Shared<T>>,
+}
+
+struct Shared<T> {
+	state: Mutex<State<T>>,
+	item_returned: Condvar,
 }
 
 struct State<T> {
 	n_items_outstanding: usize,
+	n_items_created: usize,
+	high_water_mark: usize,
+	n_waits: usize,
 	available_items: Vec<T>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolMetrics {
+	pub max_items: usize,
+	pub n_items_created: usize,
+	pub n_items_outstanding: usize,
+	/// The most items that were outstanding at once.
+	pub high_water_mark: usize,
+	/// The number of times `get` or `get_timeout` found the pool exhausted and had to wait.
+	pub n_waits: usize,
+}
+
+enum Acquired<T> {
+	Available(T),
+	/// A slot was reserved for a new item, which is created after the lock is released.
+	Create,
+}
+
 impl<T> Pool<T> {
-	pub fn new(max_items: usize, create_item: Box<dyn Fn() -> T>) -> Pool<T> {
+	pub fn new(max_items: usize, create_item: Box<dyn Fn() -> T + Send + Sync>) -> Pool<T> {
 		Pool {
 			create_item,
 			max_items,
-			state: Arc::new(Mutex::new(State {
-				n_items_outstanding: 0,
-				available_items: Vec::new(),
-			})),
+			shared: Arc::new(Shared {
+				state: Mutex::new(State {
+					n_items_outstanding: 0,
+					n_items_created: 0,
+					high_water_mark: 0,
+					n_waits: 0,
+					available_items: Vec::new(),
+				}),
+				item_returned: Condvar::new(),
+			}),
 		}
 	}
 
-	pub fn get(&self) -> Option<PoolItem<T>> {
-		let mut state = self.state.lock().unwrap();
-		if let Some(item) = state.available_items.pop() {
-			state.n_items_outstanding += 1;
-			Some(PoolItem {
-				item: Some(item),
-				state: Arc::clone(&self.state),
-			})
+	/// Returns an item if one is available or can be created, without waiting.
+	pub fn try_get(&self) -> Option<PoolItem<T>> {
+		let mut state = self.shared.state.lock().unwrap();
+		let acquired = self.acquire(&mut state);
+		drop(state);
+		acquired.map(|acquired| self.finish(acquired))
+	}
+
+	/// Waits until an item is available.
+	pub fn get(&self) -> PoolItem<T> {
+		let mut state = self.shared.state.lock().unwrap();
+		let mut waited = false;
+		let acquired = loop {
+			if let Some(acquired) = self.acquire(&mut state) {
+				break acquired;
+			}
+			if !waited {
+				state.n_waits += 1;
+				waited = true;
+			}
+			state = self.shared.item_returned.wait(state).unwrap();
+		};
+		drop(state);
+		self.finish(acquired)
+	}
+
+	/// Waits up to `timeout` for an item to be available, and returns `None` if none was.
+	pub fn get_timeout(&self, timeout: Duration) -> Option<PoolItem<T>> {
+		let deadline = Instant::now() + timeout;
+		let mut state = self.shared.state.lock().unwrap();
+		let mut waited = false;
+		let acquired = loop {
+			if let Some(acquired) = self.acquire(&mut state) {
+				break acquired;
+			}
+			let now = Instant::now();
+			if now >= deadline {
+				return None;
+			}
+			if !waited {
+				state.n_waits += 1;
+				waited = true;
+			}
+			state = self
+				.shared
+				.item_returned
+				.wait_timeout(state, deadline - now)
+				.unwrap()
+				.0;
+		};
+		drop(state);
+		Some(self.finish(acquired))
+	}
+
+	pub fn metrics(&self) -> PoolMetrics {
+		let state = self.shared.state.lock().unwrap();
+		PoolMetrics {
+			max_items: self.max_items,
+			n_items_created: state.n_items_created,
+			n_items_outstanding: state.n_items_outstanding,
+			high_water_mark: state.high_water_mark,
+			n_waits: state.n_waits,
+		}
+	}
+
+	fn acquire(&self, state: &mut State<T>) -> Option<Acquired<T>> {
+		let acquired = if let Some(item) = state.available_items.pop() {
+			Acquired::Available(item)
 		} else if state.n_items_outstanding < self.max_items {
-			state.n_items_outstanding += 1;
-			let item = (self.create_item)();
-			Some(PoolItem {
-				item: Some(item),
-				state: Arc::clone(&self.state),
-			})
+			state.n_items_created += 1;
+			Acquired::Create
 		} else {
-			None
+			return None;
+		};
+		state.n_items_outstanding += 1;
+		state.high_water_mark = state.high_water_mark.max(state.n_items_outstanding);
+		Some(acquired)
+	}
+
+	fn finish(&self, acquired: Acquired<T>) -> PoolItem<T> {
+		let item = match acquired {
+			Acquired::Available(item) => item,
+			Acquired::Create => {
+				let reserved_slot = ReservedSlot {
+					shared: &self.shared,
+				};
+				let item = (self.create_item)();
+				std::mem::forget(reserved_slot);
+				item
+			}
+		};
+		PoolItem {
+			item: Some(item),
+			shared: Arc::clone(&self.shared),
+		}
+	}
+}
+
+/// Releases a slot reserved for a new item if `create_item` panics, so the pool does not lose capacity and a waiting `get` can take the slot.
+struct ReservedSlot<'a, T> {
+	shared: &'a Shared<T>,
+}
+
+impl<T> Drop for ReservedSlot<'_, T> {
+	fn drop(&mut self) {
+		if let Ok(mut state) = self.shared.state.lock() {
+			state.n_items_outstanding -= 1;
+			state.n_items_created -= 1;
 		}
+		self.shared.item_returned.notify_one();
 	}
 }
 
 pub struct PoolItem<T> {
 	item: Option<T>,
-	state: Arc<Mutex<State<T>>>,
+	shared: Arc<Shared<T>>,
 }
 
 impl<T> std::ops::Deref for PoolItem<T> {
@@ -64,8 +189,10 @@ impl<T> std::ops::DerefMut for PoolItem<T> {
 
 impl<T> Drop for PoolItem<T> {
 	fn drop(&mut self) {
-		let mut state = self.state.lock().unwrap();
+		let mut state = self.shared.state.lock().unwrap();
 		state.available_items.push(self.item.take().unwrap());
 		state.n_items_outstanding -= 1;
+		drop(state);
+		self.shared.item_returned.notify_one();
 	}
-}
\ No newline at end of file
+}
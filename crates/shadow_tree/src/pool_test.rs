@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod test {
+    use crate::pool::{Pool, PoolMetrics};
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn test_try_get() {
+        let pool = Pool::new(2, Box::new(|| vec![0; 4]));
+        let mut first = pool.try_get().unwrap();
+        first[0] = 1;
+        let second = pool.try_get().unwrap();
+        assert!(pool.try_get().is_none());
+        drop(first);
+        // The returned item is reused as it was left.
+        let third = pool.try_get().unwrap();
+        assert_eq!(*third, vec![1, 0, 0, 0]);
+        drop(second);
+        drop(third);
+        assert_eq!(
+            pool.metrics(),
+            PoolMetrics {
+                max_items: 2,
+                n_items_created: 2,
+                n_items_outstanding: 0,
+                high_water_mark: 2,
+                n_waits: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_waits_for_returned_item() {
+        let pool = Arc::new(Pool::new(1, Box::new(|| 0)));
+        let item = pool.get();
+        let waiter = {
+            let pool = Arc::clone(&pool);
+            thread::spawn(move || *pool.get() + 1)
+        };
+        while pool.metrics().n_waits == 0 {
+            thread::yield_now();
+        }
+        drop(item);
+        assert_eq!(waiter.join().unwrap(), 1);
+        let metrics = pool.metrics();
+        assert_eq!(metrics.n_items_created, 1);
+        assert_eq!(metrics.high_water_mark, 1);
+        assert_eq!(metrics.n_waits, 1);
+    }
+
+    #[test]
+    fn test_get_timeout() {
+        let pool = Pool::new(1, Box::new(|| 0));
+        let item = pool.get_timeout(Duration::from_millis(10)).unwrap();
+        assert!(pool.get_timeout(Duration::from_millis(10)).is_none());
+        assert_eq!(pool.metrics().n_waits, 1);
+        drop(item);
+        assert!(pool.get_timeout(Duration::from_millis(10)).is_some());
+    }
+
+    #[test]
+    fn test_create_item_panics() {
+        let fail = AtomicBool::new(true);
+        let pool = Pool::new(
+            1,
+            Box::new(move || {
+                if fail.swap(false, Ordering::SeqCst) {
+                    panic!("failed to create the item");
+                }
+                0
+            }),
+        );
+        let result = panic::catch_unwind(AssertUnwindSafe(|| pool.try_get().map(|_| ())));
+        assert!(result.is_err());
+        // The slot reserved for the item that failed is released.
+        assert_eq!(pool.metrics().n_items_outstanding, 0);
+        assert!(pool.get_timeout(Duration::from_millis(10)).is_some());
+        assert_eq!(pool.metrics().n_items_created, 1);
+    }
+}
@@ -0,0 +1,460 @@
+use crate::{
+    binned_features::BinnedFeatures,
+    compute_binning_instructions::compute_binning_instructions,
+    timing::time,
+    train_tree::{
+        compute_feature_importances, create_histogram_pool, train_tree, TrainTreeOptions,
+    },
+    Progress, TrainOptions, TrainProgressEvent, Tree, TreeStats,
+};
+use bitvec::prelude::*;
+use ndarray::prelude::*;
+use num::{clamp, ToPrimitive};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256Plus;
+use rayon::prelude::*;
+use shadow_metrics::{Accuracy, MeanSquaredError};
+use shadow_progress_counter::ProgressCounter;
+use shadow_table::prelude::*;
+use shadow_zip::{pzip, zip};
+use std::num::NonZeroUsize;
+
+#[derive(Clone, Debug)]
+pub struct RandomForestOptions {
+    pub n_trees: usize,
+    /// Whether each tree is trained on a sample of the examples drawn with replacement, the size of the training set. Without it, every tree sees every example and there is no out of bag error.
+    pub bootstrap: bool,
+    /// The fraction of the features considered when choosing each split. If `None`, the square root of the number of features is considered.
+    pub feature_fraction_per_node: Option<f32>,
+    /// Extremely randomized trees consider a single random split per feature, drawn from the bins of its `BinningInstruction`, instead of the best one. They train faster and vary more from tree to tree.
+    pub extremely_randomized: bool,
+}
+
+impl Default for RandomForestOptions {
+    fn default() -> RandomForestOptions {
+        RandomForestOptions {
+            n_trees: 100,
+            bootstrap: true,
+            feature_fraction_per_node: None,
+            extremely_randomized: false,
+        }
+    }
+}
+
+/// Predicts the mean of the trees' predictions.
+#[derive(Clone, Debug)]
+pub struct RandomForestRegressor {
+    pub trees: Vec<Tree>,
+}
+
+/// Predicts the mean of the trees' probabilities of the positive class.
+#[derive(Clone, Debug)]
+pub struct RandomForestBinaryClassifier {
+    pub trees: Vec<Tree>,
+}
+
+/// Predicts the mean of the trees' probabilities of each class, normalized to sum to one.
+#[derive(Clone, Debug)]
+pub struct RandomForestMulticlassClassifier {
+    /// One row per bootstrap sample and one column per class. Each tree predicts the probability of its class.
+    pub trees: Array2<Tree>,
+}
+
+pub struct RandomForestRegressorTrainOutput {
+    pub model: RandomForestRegressor,
+    pub feature_importances: Option<Vec<f32>>,
+    /// The mean squared error of the predictions of each example by the trees whose bootstrap sample did not include it.
+    pub oob_error: Option<f32>,
+    #[cfg(feature = "timing")]
+    pub timing: crate::Timing,
+}
+
+pub struct RandomForestBinaryClassifierTrainOutput {
+    pub model: RandomForestBinaryClassifier,
+    pub feature_importances: Option<Vec<f32>>,
+    /// The fraction of examples misclassified by the trees whose bootstrap sample did not include them.
+    pub oob_error: Option<f32>,
+    #[cfg(feature = "timing")]
+    pub timing: crate::Timing,
+}
+
+pub struct RandomForestMulticlassClassifierTrainOutput {
+    pub model: RandomForestMulticlassClassifier,
+    pub feature_importances: Option<Vec<f32>>,
+    /// The fraction of examples misclassified by the trees whose bootstrap sample did not include them.
+    pub oob_error: Option<f32>,
+    #[cfg(feature = "timing")]
+    pub timing: crate::Timing,
+}
+
+struct TrainRandomForestOutput {
+    /// One row per bootstrap sample and one column per target.
+    trees: Array2<Tree>,
+    feature_importances: Vec<f32>,
+    /// The mean out of bag prediction of each target for each example, or `None` for examples that were in every bootstrap sample.
+    oob_predictions: Option<Vec<Option<Array1<f32>>>>,
+    #[cfg(feature = "timing")]
+    timing: crate::Timing,
+}
+
+impl RandomForestRegressor {
+    /// `train_options.random_forest_options` configures the ensemble, and the other options configure the trees.
+    pub fn train(
+        features: &TableView,
+        labels: NumberTableColumnView,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> RandomForestRegressorTrainOutput {
+        let targets = Array::from_shape_vec((labels.len(), 1), labels.as_slice().to_vec()).unwrap();
+        let output = train_random_forest(features, targets.view(), train_options, progress);
+        let oob_error = output.oob_predictions.and_then(|oob_predictions| {
+            let mut mean_squared_error = MeanSquaredError::new();
+            for (prediction, label) in zip!(oob_predictions.iter(), labels.iter()) {
+                if let Some(prediction) = prediction {
+                    mean_squared_error.update((prediction[0], *label));
+                }
+            }
+            mean_squared_error.finalize()
+        });
+        RandomForestRegressorTrainOutput {
+            model: RandomForestRegressor {
+                trees: output.trees.into_iter().collect(),
+            },
+            feature_importances: Some(output.feature_importances),
+            oob_error,
+            #[cfg(feature = "timing")]
+            timing: output.timing,
+        }
+    }
+
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut predictions: ArrayViewMut1<f32>) {
+        let n_trees = self.trees.len().to_f32().unwrap();
+        pzip!(
+            features.axis_iter(Axis(0)),
+            predictions.axis_iter_mut(Axis(0))
+        )
+        .for_each(|(example, mut prediction)| {
+            let example = example.as_slice().unwrap();
+            prediction[()] = self
+                .trees
+                .iter()
+                .map(|tree| tree.predict(example))
+                .sum::<f32>()
+                / n_trees;
+        });
+    }
+
+    pub fn tree_stats(&self) -> TreeStats {
+        TreeStats::compute(self.trees.iter())
+    }
+}
+
+impl RandomForestBinaryClassifier {
+    /// `labels` must have two variants, where the second is the positive class. `train_options.random_forest_options` configures the ensemble, and the other options configure the trees.
+    pub fn train(
+        features: &TableView,
+        labels: EnumTableColumnView,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> RandomForestBinaryClassifierTrainOutput {
+        assert_eq!(labels.variants().len(), 2);
+        let targets = compute_class_indicators(labels.as_slice(), 2);
+        let targets = targets.slice(s![.., 1..2]);
+        let output = train_random_forest(features, targets, train_options, progress);
+        let oob_error = output.oob_predictions.and_then(|oob_predictions| {
+            let mut accuracy = Accuracy::new();
+            for (prediction, label) in zip!(oob_predictions.iter(), labels.iter()) {
+                if let Some(prediction) = prediction {
+                    let prediction = if prediction[0] >= 0.5 { 2 } else { 1 };
+                    accuracy.update((prediction, label.unwrap().get()));
+                }
+            }
+            accuracy.finalize().map(|accuracy| 1.0 - accuracy)
+        });
+        RandomForestBinaryClassifierTrainOutput {
+            model: RandomForestBinaryClassifier {
+                trees: output.trees.into_iter().collect(),
+            },
+            feature_importances: Some(output.feature_importances),
+            oob_error,
+            #[cfg(feature = "timing")]
+            timing: output.timing,
+        }
+    }
+
+    /// Computes the probability of the positive class.
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut probabilities: ArrayViewMut1<f32>) {
+        let n_trees = self.trees.len().to_f32().unwrap();
+        pzip!(
+            features.axis_iter(Axis(0)),
+            probabilities.axis_iter_mut(Axis(0))
+        )
+        .for_each(|(example, mut probability)| {
+            let example = example.as_slice().unwrap();
+            let mean = self
+                .trees
+                .iter()
+                .map(|tree| tree.predict(example))
+                .sum::<f32>()
+                / n_trees;
+            probability[()] = clamp(mean, 0.0, 1.0);
+        });
+    }
+
+    pub fn tree_stats(&self) -> TreeStats {
+        TreeStats::compute(self.trees.iter())
+    }
+}
+
+impl RandomForestMulticlassClassifier {
+    /// `train_options.random_forest_options` configures the ensemble, and the other options configure the trees. Each bootstrap sample trains one tree per class.
+    pub fn train(
+        features: &TableView,
+        labels: EnumTableColumnView,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> RandomForestMulticlassClassifierTrainOutput {
+        let n_classes = labels.variants().len();
+        let targets = compute_class_indicators(labels.as_slice(), n_classes);
+        let output = train_random_forest(features, targets.view(), train_options, progress);
+        let oob_error = output.oob_predictions.and_then(|oob_predictions| {
+            let mut accuracy = Accuracy::new();
+            for (prediction, label) in zip!(oob_predictions.iter(), labels.iter()) {
+                if let Some(prediction) = prediction {
+                    accuracy.update((argmax(prediction.view()) + 1, label.unwrap().get()));
+                }
+            }
+            accuracy.finalize().map(|accuracy| 1.0 - accuracy)
+        });
+        RandomForestMulticlassClassifierTrainOutput {
+            model: RandomForestMulticlassClassifier {
+                trees: output.trees,
+            },
+            feature_importances: Some(output.feature_importances),
+            oob_error,
+            #[cfg(feature = "timing")]
+            timing: output.timing,
+        }
+    }
+
+    /// Computes the probability of each class, with one column per class.
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut probabilities: ArrayViewMut2<f32>) {
+        pzip!(
+            features.axis_iter(Axis(0)),
+            probabilities.axis_iter_mut(Axis(0))
+        )
+        .for_each(|(example, mut probabilities)| {
+            let example = example.as_slice().unwrap();
+            probabilities.fill(0.0);
+            for trees in self.trees.axis_iter(Axis(0)) {
+                for (probability, tree) in zip!(probabilities.iter_mut(), trees.iter()) {
+                    *probability += tree.predict(example).max(0.0);
+                }
+            }
+            normalize_probabilities(probabilities);
+        });
+    }
+
+    pub fn tree_stats(&self) -> TreeStats {
+        TreeStats::compute(self.trees.iter())
+    }
+}
+
+fn compute_class_indicators(labels: &[Option<NonZeroUsize>], n_classes: usize) -> Array2<f32> {
+    let mut targets = Array::zeros((labels.len(), n_classes));
+    for (mut targets, label) in zip!(targets.axis_iter_mut(Axis(0)), labels.iter()) {
+        targets[label.unwrap().get() - 1] = 1.0;
+    }
+    targets
+}
+
+fn normalize_probabilities(mut probabilities: ArrayViewMut1<f32>) {
+    let sum = probabilities.sum();
+    if sum > 0.0 {
+        probabilities /= sum;
+    } else {
+        probabilities.fill(1.0 / probabilities.len().to_f32().unwrap());
+    }
+}
+
+fn argmax(values: ArrayView1<f32>) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (index, value)| {
+            if *value > best.1 {
+                (index, *value)
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+/// Trains a tree for each target on each bootstrap sample. The trees fit the targets with the squared error, so each leaf predicts the mean target of its examples. The l2 regularization options are ignored, because they shrink toward zero, which only makes sense for the residuals fit by boosting.
+fn train_random_forest(
+    features: &TableView,
+    targets: ArrayView2<f32>,
+    train_options: &TrainOptions,
+    progress: Progress,
+) -> TrainRandomForestOutput {
+    #[cfg(feature = "timing")]
+    let start = std::time::Instant::now();
+    #[cfg(feature = "timing")]
+    let timing = crate::Timing::new();
+    let random_forest_options = &train_options.random_forest_options;
+    let n_examples = features.nrows();
+    let n_features = features.ncols();
+    let n_targets = targets.ncols();
+    let progress_counter = ProgressCounter::new(n_features.to_u64().unwrap());
+    (progress.handle_progress_event)(TrainProgressEvent::Initialize(progress_counter.clone()));
+    let binning_instructions = time!(
+        timing.compute_binning_instructions,
+        compute_binning_instructions(features, train_options)
+    );
+    let binned_features = time!(
+        timing.compute_binned_features,
+        BinnedFeatures::compute(
+            features,
+            &binning_instructions,
+            train_options.binned_features_layout,
+        )
+    );
+    progress_counter.inc(n_features.to_u64().unwrap());
+    (progress.handle_progress_event)(TrainProgressEvent::InitializeDone);
+    // With a hessian of one, the leaf values are the means of the targets and the gains are the reductions in squared error.
+    let gradients = targets
+        .axis_iter(Axis(1))
+        .map(|targets| targets.iter().map(|target| -target).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let hessians = vec![1.0; n_examples];
+    let feature_fraction_per_node = random_forest_options
+        .feature_fraction_per_node
+        .unwrap_or_else(|| {
+            let n_features = n_features.max(1).to_f32().unwrap();
+            n_features.sqrt() / n_features
+        });
+    let train_options = &TrainOptions {
+        l2_regularization_for_continuous_splits: 0.0,
+        l2_regularization_for_discrete_splits: 0.0,
+        ..train_options.clone()
+    };
+    let feature_indexes: Vec<usize> = (0..n_features).collect();
+    let progress_counter = ProgressCounter::new(random_forest_options.n_trees.to_u64().unwrap());
+    (progress.handle_progress_event)(TrainProgressEvent::Train(progress_counter.clone()));
+    let kill_chip = progress.kill_chip;
+    let rounds: Vec<(Vec<Tree>, Vec<f64>, BitVec)> = (0..random_forest_options.n_trees)
+        .into_par_iter()
+        // Each of rayon's jobs grows its trees one at a time, so it gets its own histogram pool instead of waiting on another job's.
+        .map_init(
+            || create_histogram_pool(&binning_instructions, train_options.max_leaf_nodes),
+            |histogram_pool, round| {
+                if kill_chip.is_activate() {
+                    return None;
+                }
+                let train_tree_options = TrainTreeOptions {
+                    train_options,
+                    feature_indexes: &feature_indexes,
+                    feature_fraction_per_node,
+                    random_splits: random_forest_options.extremely_randomized,
+                    histogram_pool,
+                    #[cfg(feature = "timing")]
+                    timing: &timing,
+                };
+                let mut rng = Xoshiro256Plus::seed_from_u64(
+                    train_options.seed.wrapping_add(round.to_u64().unwrap()),
+                );
+                let examples_index: Vec<usize> = if random_forest_options.bootstrap {
+                    (0..n_examples)
+                        .map(|_| rng.gen_range(0..n_examples))
+                        .collect()
+                } else {
+                    (0..n_examples).collect()
+                };
+                let mut in_bag = bitvec![0; n_examples];
+                for example in examples_index.iter() {
+                    in_bag.set(*example, true);
+                }
+                let mut feature_gains = vec![0.0; n_features];
+                let trees = gradients
+                    .iter()
+                    .map(|gradients| {
+                        let output = train_tree(
+                            &binned_features,
+                            &binning_instructions,
+                            gradients,
+                            &hessians,
+                            &mut examples_index.clone(),
+                            &train_tree_options,
+                            &mut rng,
+                        );
+                        for (feature_gain, gain) in
+                            zip!(feature_gains.iter_mut(), output.feature_gains.iter())
+                        {
+                            *feature_gain += gain;
+                        }
+                        output.tree
+                    })
+                    .collect();
+                progress_counter.inc(1);
+                Some((trees, feature_gains, in_bag))
+            },
+        )
+        .flatten()
+        .collect();
+    (progress.handle_progress_event)(TrainProgressEvent::TrainDone);
+    let mut feature_gains = vec![0.0; n_features];
+    for (_, round_feature_gains, _) in rounds.iter() {
+        for (feature_gain, gain) in zip!(feature_gains.iter_mut(), round_feature_gains.iter()) {
+            *feature_gain += gain;
+        }
+    }
+    let feature_importances = compute_feature_importances(&feature_gains);
+    let oob_predictions = if random_forest_options.bootstrap {
+        let rows = features.to_rows();
+        Some(
+            rows.axis_iter(Axis(0))
+                .into_par_iter()
+                .enumerate()
+                .map(|(example_index, example)| {
+                    let example = example.as_slice().unwrap();
+                    let mut prediction = Array1::<f32>::zeros(n_targets);
+                    let mut n_trees = 0;
+                    for (trees, _, in_bag) in rounds.iter() {
+                        if in_bag[example_index] {
+                            continue;
+                        }
+                        for (prediction, tree) in zip!(prediction.iter_mut(), trees.iter()) {
+                            *prediction += tree.predict(example);
+                        }
+                        n_trees += 1;
+                    }
+                    if n_trees > 0 {
+                        prediction /= n_trees.to_f32().unwrap();
+                        Some(prediction)
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+    let n_rounds = rounds.len();
+    let trees = Array::from_shape_vec(
+        (n_rounds, n_targets),
+        rounds.into_iter().flat_map(|(trees, _, _)| trees).collect(),
+    )
+    .unwrap();
+    TrainRandomForestOutput {
+        trees,
+        feature_importances,
+        oob_predictions,
+        #[cfg(feature = "timing")]
+        timing: {
+            timing.total.inc(start.elapsed());
+            timing
+        },
+    }
+}
@@ -0,0 +1,206 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        Progress, RandomForestBinaryClassifier, RandomForestMulticlassClassifier,
+        RandomForestOptions, RandomForestRegressor, TrainOptions,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::{EnumTableColumn, NumberTableColumn, Table, TableColumn};
+    use std::num::NonZeroUsize;
+
+    /// Generates 1000 examples with two number features drawn uniformly from [0, 1), where the second is sometimes missing, and an enum feature with three variants.
+    fn features() -> Table {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let x0 = (0..n_examples).map(|_| rng.gen::<f32>()).collect();
+        let x1 = (0..n_examples)
+            .map(|_| {
+                if rng.gen::<f32>() < 0.1 {
+                    f32::NAN
+                } else {
+                    rng.gen::<f32>()
+                }
+            })
+            .collect();
+        let c = (0..n_examples)
+            .map(|_| NonZeroUsize::new(rng.gen_range(1..4)))
+            .collect();
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = vec![
+            TableColumn::Number(NumberTableColumn::new(Some("x0".to_owned()), x0)),
+            TableColumn::Number(NumberTableColumn::new(Some("x1".to_owned()), x1)),
+            TableColumn::Enum(EnumTableColumn::new(
+                Some("c".to_owned()),
+                vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+                c,
+            )),
+        ];
+        table
+    }
+
+    fn train_options(random_forest_options: RandomForestOptions) -> TrainOptions {
+        TrainOptions {
+            min_examples_per_node: 5,
+            random_forest_options: RandomForestOptions {
+                n_trees: 20,
+                feature_fraction_per_node: Some(1.0),
+                ..random_forest_options
+            },
+            ..Default::default()
+        }
+    }
+
+    fn train_regressor(
+        features: &Table,
+        labels: &NumberTableColumn,
+        train_options: &TrainOptions,
+    ) -> crate::RandomForestRegressorTrainOutput {
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        RandomForestRegressor::train(
+            &features.view(),
+            labels.view(),
+            train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+    }
+
+    fn regression_labels(features: &Table) -> NumberTableColumn {
+        let x0 = features.columns()[0].as_number().unwrap();
+        let c = features.columns()[2].as_enum().unwrap();
+        let labels = x0
+            .iter()
+            .zip(c.iter())
+            .map(|(x0, c)| {
+                let step = if *x0 > 0.5 { 3.0 } else { 0.0 };
+                let offset = if c.unwrap().get() == 2 { 1.0 } else { 0.0 };
+                step + offset
+            })
+            .collect();
+        NumberTableColumn::new(None, labels)
+    }
+
+    #[test]
+    fn test_random_forest_regressor() {
+        let features = features();
+        let labels = regression_labels(&features);
+        for extremely_randomized in [false, true] {
+            let output = train_regressor(
+                &features,
+                &labels,
+                &train_options(RandomForestOptions {
+                    extremely_randomized,
+                    ..Default::default()
+                }),
+            );
+            let oob_error = output.oob_error.unwrap();
+            assert!(oob_error < 0.1, "{}", oob_error);
+            // The label depends on x0 and c but not x1.
+            let feature_importances = output.feature_importances.unwrap();
+            assert!((feature_importances.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+            assert!(feature_importances[0] > feature_importances[2]);
+            assert!(feature_importances[2] > feature_importances[1]);
+            let rows = features.to_rows();
+            let mut predictions = Array::zeros(features.nrows());
+            output.model.predict(rows.view(), predictions.view_mut());
+            let mean_squared_error = predictions
+                .iter()
+                .zip(labels.iter())
+                .map(|(prediction, label)| (prediction - label).powi(2))
+                .sum::<f32>()
+                / features.nrows() as f32;
+            assert!(mean_squared_error < oob_error);
+        }
+    }
+
+    #[test]
+    fn test_random_forest_is_reproducible() {
+        let features = features();
+        let labels = regression_labels(&features);
+        let train_options = TrainOptions {
+            seed: 7,
+            ..train_options(RandomForestOptions {
+                feature_fraction_per_node: Some(0.5),
+                ..Default::default()
+            })
+        };
+        let a = train_regressor(&features, &labels, &train_options);
+        let b = train_regressor(&features, &labels, &train_options);
+        assert_eq!(a.oob_error, b.oob_error);
+        assert_eq!(a.feature_importances, b.feature_importances);
+    }
+
+    #[test]
+    fn test_random_forest_without_bootstrap_has_no_oob_error() {
+        let features = features();
+        let labels = regression_labels(&features);
+        let output = train_regressor(
+            &features,
+            &labels,
+            &train_options(RandomForestOptions {
+                bootstrap: false,
+                ..Default::default()
+            }),
+        );
+        assert!(output.oob_error.is_none());
+    }
+
+    #[test]
+    fn test_random_forest_classifiers() {
+        let features = features();
+        let x0 = features.columns()[0].as_number().unwrap();
+        let x1 = features.columns()[1].as_number().unwrap();
+        let binary_labels = x0
+            .iter()
+            .zip(x1.iter())
+            .map(|(x0, x1)| NonZeroUsize::new(if *x0 > 0.3 && !x1.is_nan() { 2 } else { 1 }))
+            .collect();
+        let binary_labels = EnumTableColumn::new(
+            None,
+            vec!["false".to_owned(), "true".to_owned()],
+            binary_labels,
+        );
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let output = RandomForestBinaryClassifier::train(
+            &features.view(),
+            binary_labels.view(),
+            &train_options(RandomForestOptions::default()),
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        );
+        // Missing values of x1 are routed by the splits' invalid values direction.
+        assert!(output.oob_error.unwrap() < 0.02, "{:?}", output.oob_error);
+        let multiclass_labels = x0
+            .iter()
+            .map(|x0| NonZeroUsize::new(1 + (*x0 * 3.0) as usize))
+            .collect();
+        let multiclass_labels = EnumTableColumn::new(
+            None,
+            vec!["low".to_owned(), "mid".to_owned(), "high".to_owned()],
+            multiclass_labels,
+        );
+        let output = RandomForestMulticlassClassifier::train(
+            &features.view(),
+            multiclass_labels.view(),
+            &train_options(RandomForestOptions::default()),
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        );
+        assert!(output.oob_error.unwrap() < 0.05, "{:?}", output.oob_error);
+        assert_eq!(output.model.trees.ncols(), 3);
+        let rows = features.to_rows();
+        let mut probabilities = Array::zeros((features.nrows(), 3));
+        output.model.predict(rows.view(), probabilities.view_mut());
+        for probabilities in probabilities.axis_iter(Axis(0)) {
+            assert!((probabilities.sum() - 1.0).abs() < 1e-5);
+        }
+    }
+}
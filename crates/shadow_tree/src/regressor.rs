@@ -0,0 +1,65 @@
+use crate::{
+    train::{train, Task},
+    Progress, TrainOptions, Tree, TreeStats,
+};
+use ndarray::prelude::*;
+use rayon::prelude::*;
+use shadow_table::prelude::*;
+use shadow_zip::pzip;
+
+/// A gradient boosted regressor, which predicts the bias plus the sum of the trees' predictions.
+#[derive(Clone, Debug)]
+pub struct Regressor {
+    pub bias: f32,
+    pub trees: Vec<Tree>,
+}
+
+pub struct RegressorTrainOutput {
+    pub model: Regressor,
+    pub losses: Option<Vec<f32>>,
+    pub feature_importances: Option<Vec<f32>>,
+    #[cfg(feature = "timing")]
+    pub timing: crate::Timing,
+}
+
+impl Regressor {
+    pub fn train(
+        features: &TableView,
+        labels: NumberTableColumnView,
+        train_options: &TrainOptions,
+        progress: Progress,
+    ) -> RegressorTrainOutput {
+        let labels = ArrayView1::from(labels.as_slice());
+        let output = train(Task::Regression, features, labels, train_options, progress);
+        RegressorTrainOutput {
+            model: Regressor {
+                bias: output.biases[0],
+                trees: output.trees.into_iter().collect(),
+            },
+            losses: output.losses,
+            feature_importances: Some(output.feature_importances),
+            #[cfg(feature = "timing")]
+            timing: output.timing,
+        }
+    }
+
+    pub fn predict(&self, features: ArrayView2<TableValue>, mut predictions: ArrayViewMut1<f32>) {
+        pzip!(
+            features.axis_iter(Axis(0)),
+            predictions.axis_iter_mut(Axis(0))
+        )
+        .for_each(|(example, mut prediction)| {
+            let example = example.as_slice().unwrap();
+            prediction[()] = self.bias
+                + self
+                    .trees
+                    .iter()
+                    .map(|tree| tree.predict(example))
+                    .sum::<f32>();
+        });
+    }
+
+    pub fn tree_stats(&self) -> TreeStats {
+        TreeStats::compute(self.trees.iter())
+    }
+}
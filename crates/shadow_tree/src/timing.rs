@@ -1,39 +1,140 @@
+#[cfg(feature = "timing")]
 use num::ToPrimitive;
+#[cfg(feature = "timing")]
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+#[cfg(feature = "timing")]
+use std::time::{Duration, Instant};
 
-#[derive(Debug)]
+/// Evaluates `$body`. With the `timing` feature, the time it took is added to `$duration`, which is a `TimingDuration`. Without it, `$duration` is never compiled.
+macro_rules! time {
+    ($duration:expr, $body:expr) => {{
+        #[cfg(feature = "timing")]
+        let start = std::time::Instant::now();
+        let output = $body;
+        #[cfg(feature = "timing")]
+        $duration.inc(start.elapsed());
+        output
+    }};
+}
+pub(crate) use time;
+
+/// Where the time went while training. Each phase sums its durations over the whole run. Phases that run on several threads at once, such as the trees of a random forest, add up the time on every thread, so their sum can exceed `total`.
+#[cfg(feature = "timing")]
+#[derive(Debug, Default)]
 pub struct Timing {
-    pub choose_best_split_not_root: TimingDuration,
-    pub choose_best_split_root: TimingDuration,
-    pub compute_binned_features: TimingDuration,
+    pub total: TimingDuration,
     pub compute_binning_instructions: TimingDuration,
+    pub compute_binned_features: TimingDuration,
     pub compute_gradients_and_hessians: TimingDuration,
-    pub rearrange_examples_index: TimingDuration,
+    /// Bagging or GOSS.
+    pub sample_examples: TimingDuration,
     pub sum_gradients_and_hessians_root: TimingDuration,
+    pub compute_histograms_root: TimingDuration,
+    /// Includes subtracting the smaller child's histogram from its parent's.
+    pub compute_histograms_not_root: TimingDuration,
+    pub choose_best_split_root: TimingDuration,
+    pub choose_best_split_not_root: TimingDuration,
+    pub rearrange_examples_index: TimingDuration,
+    /// Includes removing and restoring the predictions of rounds dropped by DART.
     pub update_predictions: TimingDuration,
+    /// The training losses and the early stopping losses.
+    pub compute_losses: TimingDuration,
+    /// The phases of each boosting round. Random forests train their trees in parallel and record no rounds.
+    pub rounds: Vec<RoundTiming>,
 }
 
+#[cfg(feature = "timing")]
+#[derive(Default)]
 pub struct TimingDuration(AtomicU64);
 
+#[cfg(feature = "timing")]
+#[derive(Clone, Debug)]
+pub struct RoundTiming {
+    pub duration: Duration,
+    /// The time spent in each phase during the round, in the order of `Timing::phases`.
+    pub phases: Vec<(&'static str, Duration)>,
+}
+
+/// Marks the start of a round, to be passed to `Timing::record_round` at its end.
+#[cfg(feature = "timing")]
+pub(crate) struct RoundStart {
+    start: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+#[cfg(feature = "timing")]
 impl Timing {
     pub fn new() -> Timing {
-        Timing {
-            compute_binning_instructions: TimingDuration::new(),
-            compute_binned_features: TimingDuration::new(),
-            compute_gradients_and_hessians: TimingDuration::new(),
-            sum_gradients_and_hessians_root: TimingDuration::new(),
-            rearrange_examples_index: TimingDuration::new(),
-            choose_best_split_not_root: TimingDuration::new(),
-            choose_best_split_root: TimingDuration::new(),
-            update_predictions: TimingDuration::new(),
+        Timing::default()
+    }
+
+    /// The total duration of each phase, excluding `total`.
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        vec![
+            (
+                "compute_binning_instructions",
+                self.compute_binning_instructions.get(),
+            ),
+            (
+                "compute_binned_features",
+                self.compute_binned_features.get(),
+            ),
+            (
+                "compute_gradients_and_hessians",
+                self.compute_gradients_and_hessians.get(),
+            ),
+            ("sample_examples", self.sample_examples.get()),
+            (
+                "sum_gradients_and_hessians_root",
+                self.sum_gradients_and_hessians_root.get(),
+            ),
+            (
+                "compute_histograms_root",
+                self.compute_histograms_root.get(),
+            ),
+            (
+                "compute_histograms_not_root",
+                self.compute_histograms_not_root.get(),
+            ),
+            ("choose_best_split_root", self.choose_best_split_root.get()),
+            (
+                "choose_best_split_not_root",
+                self.choose_best_split_not_root.get(),
+            ),
+            (
+                "rearrange_examples_index",
+                self.rearrange_examples_index.get(),
+            ),
+            ("update_predictions", self.update_predictions.get()),
+            ("compute_losses", self.compute_losses.get()),
+        ]
+    }
+
+    pub(crate) fn start_round(&self) -> RoundStart {
+        RoundStart {
+            start: Instant::now(),
+            phases: self.phases(),
         }
     }
+
+    pub(crate) fn record_round(&mut self, round_start: RoundStart) {
+        let phases = self
+            .phases()
+            .into_iter()
+            .zip(round_start.phases)
+            .map(|((name, after), (_, before))| (name, after - before))
+            .collect();
+        self.rounds.push(RoundTiming {
+            duration: round_start.start.elapsed(),
+            phases,
+        });
+    }
 }
 
+#[cfg(feature = "timing")]
 impl TimingDuration {
     pub fn new() -> TimingDuration {
-        TimingDuration(AtomicU64::new(0))
+        TimingDuration::default()
     }
     pub fn get(&self) -> Duration {
         Duration::from_nanos(self.0.load(Ordering::Relaxed))
@@ -44,8 +145,73 @@ impl TimingDuration {
     }
 }
 
+#[cfg(feature = "timing")]
 impl std::fmt::Debug for TimingDuration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.get())
     }
 }
+
+/// Writes a table of the phases with their share of the total, followed by a table with a row per round and a column per phase that ran during the rounds.
+#[cfg(feature = "timing")]
+impl std::fmt::Display for Timing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let total = self.total.get();
+        let phases = self.phases();
+        let name_width = phases.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        writeln!(
+            f,
+            "{:<name_width$}  {:>12}  {:>6}",
+            "phase", "duration", "share"
+        )?;
+        for (name, duration) in phases.iter().chain([("total", total)].iter()) {
+            let share = if total.is_zero() {
+                0.0
+            } else {
+                100.0 * duration.as_secs_f64() / total.as_secs_f64()
+            };
+            writeln!(
+                f,
+                "{:<name_width$}  {:>12}  {:>5.1}%",
+                name,
+                format!("{:.3?}", duration),
+                share
+            )?;
+        }
+        if self.rounds.is_empty() {
+            return Ok(());
+        }
+        let round_phases: Vec<usize> = (0..phases.len())
+            .filter(|index| {
+                self.rounds
+                    .iter()
+                    .any(|round| !round.phases[*index].1.is_zero())
+            })
+            .collect();
+        writeln!(f)?;
+        write!(f, "{:>5}  {:>12}", "round", "duration")?;
+        for index in round_phases.iter() {
+            write!(f, "  {:>12}", phases[*index].0)?;
+        }
+        writeln!(f)?;
+        for (round_index, round) in self.rounds.iter().enumerate() {
+            write!(
+                f,
+                "{:>5}  {:>12}",
+                round_index,
+                format!("{:.3?}", round.duration)
+            )?;
+            for index in round_phases.iter() {
+                let (name, duration) = round.phases[*index];
+                write!(
+                    f,
+                    "  {:>width$}",
+                    format!("{:.3?}", duration),
+                    width = name.len().max(12)
+                )?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,638 @@
+use crate::{
+    binned_features::BinnedFeatures,
+    compute_binning_instructions::{compute_binning_instructions, BinningInstruction},
+    timing::time,
+    train_tree::{
+        compute_feature_importances, create_histogram_pool, sample_features, train_tree,
+        TrainTreeOptions,
+    },
+    BinnedFeaturesLayout, BranchSplit, DartOptions, EarlyStoppingOptions, Node, Progress,
+    SplitDirection, TrainOptions, TrainProgressEvent, Tree,
+};
+use ndarray::prelude::*;
+use num::ToPrimitive;
+use rand::{seq::index::sample, Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256Plus;
+use rayon::prelude::*;
+use shadow_progress_counter::ProgressCounter;
+use shadow_table::prelude::*;
+use shadow_zip::{pzip, zip};
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Task {
+    Regression,
+    BinaryClassification,
+    MulticlassClassification { n_classes: usize },
+}
+
+impl Task {
+    fn n_trees_per_round(&self) -> usize {
+        match self {
+            Task::Regression | Task::BinaryClassification => 1,
+            Task::MulticlassClassification { n_classes } => *n_classes,
+        }
+    }
+}
+
+pub(crate) struct TrainOutput {
+    /// The initial prediction of each tree in a round, before any trees are added.
+    pub biases: Array1<f32>,
+    /// One row per round and one column per tree in each round.
+    pub trees: Array2<Tree>,
+    /// The loss on the training examples after each round, if `compute_losses` is set.
+    pub losses: Option<Vec<f32>>,
+    pub feature_importances: Vec<f32>,
+    #[cfg(feature = "timing")]
+    pub timing: crate::Timing,
+}
+
+/// Trains a gradient boosted ensemble. `labels` holds the targets for regression and the zero-based class indexes for classification. If early stopping is enabled, the last `early_stopping_fraction` of the examples are held out to decide when to stop, and the rounds after the one with the lowest loss on them are discarded.
+pub(crate) fn train(
+    task: Task,
+    features: &TableView,
+    labels: ArrayView1<f32>,
+    train_options: &TrainOptions,
+    progress: Progress,
+) -> TrainOutput {
+    #[cfg(feature = "timing")]
+    let start = std::time::Instant::now();
+    #[cfg(feature = "timing")]
+    let mut timing = crate::Timing::new();
+    let n_examples = features.nrows();
+    let n_features = features.ncols();
+    let n_trees_per_round = task.n_trees_per_round();
+    let n_train_examples = match &train_options.early_stopping_options {
+        Some(early_stopping_options) => ((1.0 - early_stopping_options.early_stopping_fraction)
+            * n_examples.to_f32().unwrap())
+        .to_usize()
+        .unwrap(),
+        None => n_examples,
+    };
+    let (features_train, features_early_stopping) = features.split_at_row(n_train_examples);
+    let (labels_train, labels_early_stopping) = labels.split_at(Axis(0), n_train_examples);
+
+    let progress_counter = ProgressCounter::new(n_features.to_u64().unwrap());
+    (progress.handle_progress_event)(TrainProgressEvent::Initialize(progress_counter.clone()));
+    let binning_instructions = time!(
+        timing.compute_binning_instructions,
+        compute_binning_instructions(&features_train, train_options)
+    );
+    // The held out examples are binned with the thresholds of the training examples, so the trees can be evaluated on the bins of every example.
+    let (binned_features, binned_features_early_stopping) = time!(
+        timing.compute_binned_features,
+        (
+            BinnedFeatures::compute(
+                &features_train,
+                &binning_instructions,
+                train_options.binned_features_layout,
+            ),
+            BinnedFeatures::compute(
+                &features_early_stopping,
+                &binning_instructions,
+                BinnedFeaturesLayout::RowMajor,
+            ),
+        )
+    );
+    progress_counter.inc(n_features.to_u64().unwrap());
+    (progress.handle_progress_event)(TrainProgressEvent::InitializeDone);
+
+    let biases = compute_biases(task, labels_train);
+    let mut predictions =
+        Array::from_shape_fn((n_examples, n_trees_per_round), |(_, index)| biases[index]);
+    let mut gradients = Array::zeros((n_trees_per_round, n_train_examples));
+    let mut hessians = Array::zeros((n_trees_per_round, n_train_examples));
+    let mut rng = Xoshiro256Plus::seed_from_u64(train_options.seed);
+    let all_feature_indexes: Vec<usize> = (0..n_features).collect();
+    let histogram_pool = create_histogram_pool(&binning_instructions, train_options.max_leaf_nodes);
+    let mut rounds: Vec<Vec<Tree>> = Vec::new();
+    // DART scales the trees of the dropped rounds after each round. The factors are kept here and applied to the trees once training is done, so the rounds after the best one can be discarded.
+    let mut round_scales: Vec<f32> = Vec::new();
+    let mut best_round_scales: Option<Vec<f32>> = None;
+    let mut losses = if train_options.compute_losses {
+        Some(Vec::new())
+    } else {
+        None
+    };
+    let mut early_stopping_monitor = train_options
+        .early_stopping_options
+        .as_ref()
+        .filter(|_| n_train_examples < n_examples)
+        .map(EarlyStoppingMonitor::new);
+
+    let progress_counter = ProgressCounter::new(train_options.max_rounds.to_u64().unwrap());
+    (progress.handle_progress_event)(TrainProgressEvent::Train(progress_counter.clone()));
+    for _ in 0..train_options.max_rounds {
+        if progress.kill_chip.is_activate() {
+            break;
+        }
+        #[cfg(feature = "timing")]
+        let round_start = timing.start_round();
+        let dropped_rounds = match &train_options.dart_options {
+            Some(dart_options) => choose_dropped_rounds(&mut rng, rounds.len(), dart_options),
+            None => Vec::new(),
+        };
+        // The new trees fit the gradients of the ensemble without the dropped rounds.
+        let dropped_predictions = time!(
+            timing.update_predictions,
+            if dropped_rounds.is_empty() {
+                None
+            } else {
+                let mut dropped_predictions = Array::zeros((n_examples, n_trees_per_round));
+                let (dropped_predictions_train, dropped_predictions_early_stopping) =
+                    dropped_predictions
+                        .view_mut()
+                        .split_at(Axis(0), n_train_examples);
+                for (binned_features, mut dropped_predictions) in [
+                    (&binned_features, dropped_predictions_train),
+                    (
+                        &binned_features_early_stopping,
+                        dropped_predictions_early_stopping,
+                    ),
+                ] {
+                    dropped_predictions
+                        .axis_iter_mut(Axis(0))
+                        .into_par_iter()
+                        .enumerate()
+                        .for_each(|(example, mut dropped_predictions)| {
+                            for round in dropped_rounds.iter() {
+                                for (prediction, tree) in
+                                    zip!(dropped_predictions.iter_mut(), rounds[*round].iter())
+                                {
+                                    *prediction += round_scales[*round]
+                                        * predict_binned(
+                                            tree,
+                                            binned_features,
+                                            &binning_instructions,
+                                            example,
+                                        );
+                                }
+                            }
+                        });
+                }
+                predictions -= &dropped_predictions;
+                Some(dropped_predictions)
+            }
+        );
+        time!(
+            timing.compute_gradients_and_hessians,
+            compute_gradients_and_hessians(
+                task,
+                predictions.slice(s![..n_train_examples, ..]),
+                labels_train,
+                gradients.view_mut(),
+                hessians.view_mut(),
+            )
+        );
+        let examples_index = time!(
+            timing.sample_examples,
+            sample_examples(
+                &mut rng,
+                gradients.view_mut(),
+                hessians.view_mut(),
+                train_options,
+            )
+        );
+        let learning_rate = train_options.learning_rate;
+        let n_dropped_rounds = dropped_rounds.len().to_f32().unwrap();
+        let shrinkage = if dropped_rounds.is_empty() {
+            learning_rate
+        } else {
+            learning_rate / (n_dropped_rounds + learning_rate)
+        };
+        let mut round_trees = Vec::with_capacity(n_trees_per_round);
+        let mut round_examples = Vec::with_capacity(n_trees_per_round);
+        for (gradients, hessians) in zip!(gradients.axis_iter(Axis(0)), hessians.axis_iter(Axis(0)))
+        {
+            let feature_indexes = sample_features(
+                &mut rng,
+                &all_feature_indexes,
+                train_options.feature_fraction_per_tree,
+            );
+            let train_tree_options = TrainTreeOptions {
+                train_options,
+                feature_indexes: &feature_indexes,
+                feature_fraction_per_node: train_options.feature_fraction_per_node,
+                random_splits: false,
+                histogram_pool: &histogram_pool,
+                #[cfg(feature = "timing")]
+                timing: &timing,
+            };
+            let mut tree_examples_index = examples_index.clone();
+            let output = train_tree(
+                &binned_features,
+                &binning_instructions,
+                gradients.as_slice().unwrap(),
+                hessians.as_slice().unwrap(),
+                &mut tree_examples_index,
+                &train_tree_options,
+                &mut rng,
+            );
+            let mut tree = output.tree;
+            scale_tree(&mut tree, shrinkage);
+            round_trees.push(tree);
+            round_examples.push((tree_examples_index, output.node_examples_ranges));
+        }
+        time!(timing.update_predictions, {
+            let (mut predictions_train, mut predictions_early_stopping) =
+                predictions.view_mut().split_at(Axis(0), n_train_examples);
+            // The sampled training examples get the value of the leaf they were partitioned into while the tree was grown.
+            for (mut predictions, tree, (examples_index, node_examples_ranges)) in zip!(
+                predictions_train.axis_iter_mut(Axis(1)),
+                round_trees.iter(),
+                round_examples.iter()
+            ) {
+                for (node, examples_range) in zip!(tree.nodes.iter(), node_examples_ranges.iter()) {
+                    if let Node::Leaf(leaf) = node {
+                        let value = leaf.value.to_f32().unwrap();
+                        for example in examples_index[examples_range.clone()].iter() {
+                            predictions[*example] += value;
+                        }
+                    }
+                }
+            }
+            // The other examples are evaluated on their bins.
+            if examples_index.len() < n_train_examples {
+                let mut is_sampled = vec![false; n_train_examples];
+                for example in examples_index.iter() {
+                    is_sampled[*example] = true;
+                }
+                predictions_train
+                    .axis_iter_mut(Axis(0))
+                    .into_par_iter()
+                    .enumerate()
+                    .filter(|(example, _)| !is_sampled[*example])
+                    .for_each(|(example, mut predictions)| {
+                        for (prediction, tree) in zip!(predictions.iter_mut(), round_trees.iter()) {
+                            *prediction += predict_binned(
+                                tree,
+                                &binned_features,
+                                &binning_instructions,
+                                example,
+                            );
+                        }
+                    });
+            }
+            predictions_early_stopping
+                .axis_iter_mut(Axis(0))
+                .into_par_iter()
+                .enumerate()
+                .for_each(|(example, mut predictions)| {
+                    for (prediction, tree) in zip!(predictions.iter_mut(), round_trees.iter()) {
+                        *prediction += predict_binned(
+                            tree,
+                            &binned_features_early_stopping,
+                            &binning_instructions,
+                            example,
+                        );
+                    }
+                });
+            if let Some(dropped_predictions) = dropped_predictions {
+                let factor = n_dropped_rounds / (n_dropped_rounds + learning_rate);
+                predictions.scaled_add(factor, &dropped_predictions);
+                for round in dropped_rounds {
+                    round_scales[round] *= factor;
+                }
+            }
+        });
+        rounds.push(round_trees);
+        round_scales.push(1.0);
+        progress_counter.inc(1);
+        let should_stop = time!(timing.compute_losses, {
+            if let Some(losses) = losses.as_mut() {
+                losses.push(compute_loss(
+                    task,
+                    predictions.slice(s![..n_train_examples, ..]),
+                    labels_train,
+                ));
+            }
+            early_stopping_monitor
+                .as_mut()
+                .is_some_and(|early_stopping_monitor| {
+                    let loss = compute_loss(
+                        task,
+                        predictions.slice(s![n_train_examples.., ..]),
+                        labels_early_stopping,
+                    );
+                    let should_stop = early_stopping_monitor.update(loss);
+                    if early_stopping_monitor.improved() {
+                        best_round_scales = Some(round_scales.clone());
+                    }
+                    should_stop
+                })
+        });
+        #[cfg(feature = "timing")]
+        timing.record_round(round_start);
+        if should_stop {
+            break;
+        }
+    }
+    (progress.handle_progress_event)(TrainProgressEvent::TrainDone);
+
+    let round_scales = best_round_scales.unwrap_or(round_scales);
+    rounds.truncate(round_scales.len());
+    for (round, scale) in zip!(rounds.iter_mut(), round_scales.iter()) {
+        if *scale != 1.0 {
+            for tree in round.iter_mut() {
+                scale_tree(tree, *scale);
+            }
+        }
+    }
+    let mut feature_gains = vec![0.0; n_features];
+    for branch in rounds
+        .iter()
+        .flatten()
+        .flat_map(|tree| tree.nodes.iter().filter_map(Node::as_branch))
+    {
+        feature_gains[branch.split.feature_index()] += branch.gain.to_f64().unwrap();
+    }
+    let n_rounds = rounds.len();
+    let trees = Array::from_shape_vec(
+        (n_rounds, n_trees_per_round),
+        rounds.into_iter().flatten().collect(),
+    )
+    .unwrap();
+    TrainOutput {
+        biases,
+        trees,
+        losses,
+        feature_importances: compute_feature_importances(&feature_gains),
+        #[cfg(feature = "timing")]
+        timing: {
+            timing.total.inc(start.elapsed());
+            timing
+        },
+    }
+}
+
+/// Evaluates `tree` on the bins of an example, which gives the same value as `Tree::predict` on the feature values the bins were computed from.
+fn predict_binned(
+    tree: &Tree,
+    binned_features: &BinnedFeatures,
+    binning_instructions: &[BinningInstruction],
+    example: usize,
+) -> f32 {
+    let mut node_index = 0;
+    loop {
+        let branch = match &tree.nodes[node_index] {
+            Node::Leaf(leaf) => return leaf.value.to_f32().unwrap(),
+            Node::Branch(branch) => branch,
+        };
+        let feature_index = branch.split.feature_index();
+        let bin = binned_features.bins[(example, feature_index)]
+            .to_usize()
+            .unwrap();
+        let direction = match &branch.split {
+            BranchSplit::Continuous(split) => {
+                let thresholds = match &binning_instructions[feature_index] {
+                    BinningInstruction::Number { thresholds } => thresholds,
+                    _ => unreachable!(),
+                };
+                // Bin `bin` holds the values at or below `thresholds[bin - 1]`, and the last bin the values above every threshold.
+                if bin == 0 {
+                    split.invalid_values_direction
+                } else if thresholds
+                    .get(bin - 1)
+                    .is_some_and(|threshold| *threshold <= split.split_value)
+                {
+                    SplitDirection::Left
+                } else {
+                    SplitDirection::Right
+                }
+            }
+            BranchSplit::Discrete(split) => {
+                let bin = if bin < split.directions.len() { bin } else { 0 };
+                (*split.directions.get(bin).unwrap()).into()
+            }
+        };
+        node_index = match direction {
+            SplitDirection::Left => branch.left_child_index,
+            SplitDirection::Right => branch.right_child_index,
+        };
+    }
+}
+
+/// The mean for regression, and the log odds of the class frequencies for classification.
+fn compute_biases(task: Task, labels: ArrayView1<f32>) -> Array1<f32> {
+    let n_examples = labels.len().max(1).to_f32().unwrap();
+    match task {
+        Task::Regression => arr1(&[labels.sum() / n_examples]),
+        Task::BinaryClassification => {
+            let positive_fraction = (labels.sum() / n_examples).clamp(1e-6, 1.0 - 1e-6);
+            arr1(&[(positive_fraction / (1.0 - positive_fraction)).ln()])
+        }
+        Task::MulticlassClassification { n_classes } => {
+            let mut class_counts = Array::zeros(n_classes);
+            for label in labels.iter() {
+                class_counts[label.to_usize().unwrap()] += 1.0;
+            }
+            class_counts.mapv(|count: f32| (count / n_examples).max(1e-6).ln())
+        }
+    }
+}
+
+/// `predictions` has one row per example, and `gradients` and `hessians` have one row per tree in the round.
+fn compute_gradients_and_hessians(
+    task: Task,
+    predictions: ArrayView2<f32>,
+    labels: ArrayView1<f32>,
+    mut gradients: ArrayViewMut2<f32>,
+    mut hessians: ArrayViewMut2<f32>,
+) {
+    pzip!(
+        predictions.axis_iter(Axis(0)),
+        labels.axis_iter(Axis(0)),
+        gradients.axis_iter_mut(Axis(1)),
+        hessians.axis_iter_mut(Axis(1))
+    )
+    .for_each(|(predictions, label, mut gradients, mut hessians)| {
+        let label = label[()];
+        match task {
+            Task::Regression => {
+                gradients[0] = predictions[0] - label;
+                hessians[0] = 1.0;
+            }
+            Task::BinaryClassification => {
+                let probability = sigmoid(predictions[0]);
+                gradients[0] = probability - label;
+                hessians[0] = probability * (1.0 - probability);
+            }
+            Task::MulticlassClassification { .. } => {
+                let probabilities = softmax(predictions);
+                let label = label.to_usize().unwrap();
+                for (class_index, (gradient, hessian, probability)) in zip!(
+                    gradients.iter_mut(),
+                    hessians.iter_mut(),
+                    probabilities.iter()
+                )
+                .enumerate()
+                {
+                    let indicator = if class_index == label { 1.0 } else { 0.0 };
+                    *gradient = probability - indicator;
+                    *hessian = probability * (1.0 - probability);
+                }
+            }
+        }
+    });
+}
+
+/// The mean squared error for regression and the mean cross entropy for classification.
+fn compute_loss(task: Task, predictions: ArrayView2<f32>, labels: ArrayView1<f32>) -> f32 {
+    let n_examples = labels.len().max(1).to_f32().unwrap();
+    let total: f32 = zip!(predictions.axis_iter(Axis(0)), labels.iter())
+        .map(|(predictions, label)| match task {
+            Task::Regression => (predictions[0] - label).powi(2),
+            Task::BinaryClassification => {
+                // The cross entropy in terms of the logit, which does not overflow.
+                let logit = predictions[0];
+                logit.max(0.0) - logit * label + (-logit.abs()).exp().ln_1p()
+            }
+            Task::MulticlassClassification { .. } => {
+                let probability = softmax(predictions)[label.to_usize().unwrap()];
+                -probability.max(f32::EPSILON).ln()
+            }
+        })
+        .sum();
+    total / n_examples
+}
+
+/// Uniform bagging, or GOSS if `goss_options` is set. GOSS scales the gradients and hessians of the sampled small-gradient examples in place.
+fn sample_examples(
+    rng: &mut Xoshiro256Plus,
+    mut gradients: ArrayViewMut2<f32>,
+    mut hessians: ArrayViewMut2<f32>,
+    train_options: &TrainOptions,
+) -> Vec<usize> {
+    let n_examples = gradients.ncols();
+    let n_examples_f32 = n_examples.to_f32().unwrap();
+    if let Some(goss_options) = &train_options.goss_options {
+        let n_top_examples = (goss_options.top_fraction * n_examples_f32)
+            .ceil()
+            .to_usize()
+            .unwrap()
+            .min(n_examples);
+        if n_top_examples == n_examples {
+            return (0..n_examples).collect();
+        }
+        let magnitudes: Vec<f32> = gradients
+            .axis_iter(Axis(1))
+            .map(|gradients| gradients.iter().map(|gradient| gradient.abs()).sum())
+            .collect();
+        let mut examples: Vec<usize> = (0..n_examples).collect();
+        examples.select_nth_unstable_by(n_top_examples, |a, b| {
+            magnitudes[*b].total_cmp(&magnitudes[*a])
+        });
+        let (top_examples, other_examples) = examples.split_at(n_top_examples);
+        let n_sampled_other_examples = (goss_options.other_fraction * n_examples_f32)
+            .ceil()
+            .to_usize()
+            .unwrap()
+            .min(other_examples.len());
+        let amplification = (1.0 - goss_options.top_fraction) / goss_options.other_fraction;
+        let mut sampled_examples = top_examples.to_vec();
+        for index in sample(rng, other_examples.len(), n_sampled_other_examples) {
+            let example = other_examples[index];
+            gradients
+                .column_mut(example)
+                .mapv_inplace(|g| g * amplification);
+            hessians
+                .column_mut(example)
+                .mapv_inplace(|h| h * amplification);
+            sampled_examples.push(example);
+        }
+        sampled_examples
+    } else if train_options.bagging_fraction < 1.0 {
+        let n_sampled_examples = (train_options.bagging_fraction * n_examples_f32)
+            .ceil()
+            .to_usize()
+            .unwrap()
+            .max(1)
+            .min(n_examples);
+        sample(rng, n_examples, n_sampled_examples).into_vec()
+    } else {
+        (0..n_examples).collect()
+    }
+}
+
+/// Drops each round with probability `drop_rate`, unless the whole round's dropout is skipped.
+fn choose_dropped_rounds(
+    rng: &mut Xoshiro256Plus,
+    n_rounds: usize,
+    dart_options: &DartOptions,
+) -> Vec<usize> {
+    if n_rounds == 0 || rng.gen::<f32>() < dart_options.skip_drop_rate {
+        return Vec::new();
+    }
+    let dropped_rounds: Vec<usize> = (0..n_rounds)
+        .filter(|_| rng.gen::<f32>() < dart_options.drop_rate)
+        .collect();
+    match dart_options.max_dropped_rounds {
+        Some(max_dropped_rounds) if dropped_rounds.len() > max_dropped_rounds => {
+            let mut kept_rounds: Vec<usize> = sample(rng, dropped_rounds.len(), max_dropped_rounds)
+                .into_iter()
+                .map(|index| dropped_rounds[index])
+                .collect();
+            kept_rounds.sort_unstable();
+            kept_rounds
+        }
+        _ => dropped_rounds,
+    }
+}
+
+fn scale_tree(tree: &mut Tree, factor: f32) {
+    let factor = factor.to_f64().unwrap();
+    for node in tree.nodes.iter_mut() {
+        if let Node::Leaf(leaf) = node {
+            leaf.value *= factor;
+        }
+    }
+}
+
+struct EarlyStoppingMonitor {
+    min_decrease_in_loss_for_significant_change: f32,
+    n_rounds_without_improvement_to_stop: usize,
+    n_rounds_without_improvement: usize,
+    best_loss: Option<f32>,
+}
+
+impl EarlyStoppingMonitor {
+    fn new(options: &EarlyStoppingOptions) -> EarlyStoppingMonitor {
+        EarlyStoppingMonitor {
+            min_decrease_in_loss_for_significant_change: options
+                .min_decrease_in_loss_for_significant_change,
+            n_rounds_without_improvement_to_stop: options.n_rounds_without_improvement_to_stop,
+            n_rounds_without_improvement: 0,
+            best_loss: None,
+        }
+    }
+
+    /// Whether the last loss passed to `update` was the best so far.
+    fn improved(&self) -> bool {
+        self.n_rounds_without_improvement == 0
+    }
+
+    /// Returns true if training should stop.
+    fn update(&mut self, loss: f32) -> bool {
+        let improved = self.best_loss.is_none_or(|best_loss| {
+            best_loss - loss > self.min_decrease_in_loss_for_significant_change
+        });
+        if improved {
+            self.best_loss = Some(loss);
+            self.n_rounds_without_improvement = 0;
+        } else {
+            self.n_rounds_without_improvement += 1;
+        }
+        self.n_rounds_without_improvement >= self.n_rounds_without_improvement_to_stop
+    }
+}
+
+pub(crate) fn sigmoid(logit: f32) -> f32 {
+    1.0 / (1.0 + (-logit).exp())
+}
+
+pub(crate) fn softmax(logits: ArrayView1<f32>) -> Array1<f32> {
+    let max = logits.fold(f32::NEG_INFINITY, |max, logit| max.max(*logit));
+    let mut probabilities = logits.mapv(|logit| (logit - max).exp());
+    let sum = probabilities.sum();
+    probabilities /= sum;
+    probabilities
+}
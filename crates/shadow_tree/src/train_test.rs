@@ -0,0 +1,682 @@
+#[cfg(test)]
+mod test {
+    use crate::train_tree::satisfies_constraints;
+    use crate::{
+        BinaryClassifier, BranchNode, BranchSplit, BranchSplitContinuous, DartOptions,
+        EarlyStoppingOptions, GossOptions, GrowthPolicy, LeafNode, MonotoneConstraint,
+        MulticlassClassifier, Node, Progress, Regressor, RegressorTrainOutput, SplitDirection,
+        TrainOptions, Tree,
+    };
+    use ndarray::prelude::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use shadow_table::{EnumTableColumn, NumberTableColumn, Table, TableColumn, TableValue};
+    use std::collections::BTreeSet;
+    use std::num::NonZeroUsize;
+
+    /// Generates 1000 examples with three number features drawn uniformly from [0, 1) and a label of `3 * x0 + x1 * x1`, which ignores `x2`.
+    fn features_and_labels() -> (Table, NumberTableColumn) {
+        let n_examples = 1000;
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let columns: Vec<Vec<f32>> = (0..3)
+            .map(|_| (0..n_examples).map(|_| rng.gen::<f32>()).collect())
+            .collect();
+        let labels = columns[0]
+            .iter()
+            .zip(columns[1].iter())
+            .map(|(x0, x1)| 3.0 * x0 + x1 * x1)
+            .collect();
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = columns
+            .into_iter()
+            .enumerate()
+            .map(|(index, values)| {
+                TableColumn::Number(NumberTableColumn::new(Some(format!("x{}", index)), values))
+            })
+            .collect();
+        (table, NumberTableColumn::new(None, labels))
+    }
+
+    fn train_regressor(
+        features: &Table,
+        labels: &NumberTableColumn,
+        train_options: &TrainOptions,
+    ) -> RegressorTrainOutput {
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        Regressor::train(
+            &features.view(),
+            labels.view(),
+            train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        )
+    }
+
+    fn predict(model: &Regressor, features: &Table) -> Array1<f32> {
+        let rows = features.to_rows();
+        let mut predictions = Array::zeros(features.nrows());
+        model.predict(rows.view(), predictions.view_mut());
+        predictions
+    }
+
+    fn mean_squared_error(predictions: &Array1<f32>, labels: &NumberTableColumn) -> f32 {
+        predictions
+            .iter()
+            .zip(labels.iter())
+            .map(|(prediction, label)| (prediction - label).powi(2))
+            .sum::<f32>()
+            / predictions.len() as f32
+    }
+
+    #[test]
+    fn test_regressor() {
+        let (features, labels) = features_and_labels();
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                compute_losses: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(output.model.trees.len(), 100);
+        let losses = output.losses.unwrap();
+        assert!(losses.windows(2).all(|losses| losses[1] <= losses[0]));
+        let predictions = predict(&output.model, &features);
+        let mean_squared_error = mean_squared_error(&predictions, &labels);
+        assert!(mean_squared_error < 0.01, "{}", mean_squared_error);
+        assert!((losses.last().unwrap() - mean_squared_error).abs() < 1e-4);
+        let feature_importances = output.feature_importances.unwrap();
+        assert!(feature_importances[0] > feature_importances[1]);
+        assert!(feature_importances[1] > feature_importances[2]);
+    }
+
+    #[test]
+    fn test_subsampling_is_seeded() {
+        let (features, labels) = features_and_labels();
+        let train_options = |seed| TrainOptions {
+            max_rounds: 20,
+            bagging_fraction: 0.5,
+            feature_fraction_per_tree: 0.7,
+            feature_fraction_per_node: 0.5,
+            seed,
+            ..Default::default()
+        };
+        let a = predict(
+            &train_regressor(&features, &labels, &train_options(1)).model,
+            &features,
+        );
+        let b = predict(
+            &train_regressor(&features, &labels, &train_options(1)).model,
+            &features,
+        );
+        let c = predict(
+            &train_regressor(&features, &labels, &train_options(2)).model,
+            &features,
+        );
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_feature_fraction_per_tree() {
+        let (features, labels) = features_and_labels();
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                max_rounds: 20,
+                feature_fraction_per_tree: 0.3,
+                ..Default::default()
+            },
+        );
+        // A third of three features rounds up to one feature per tree.
+        for tree in output.model.trees.iter() {
+            let feature_indexes: BTreeSet<usize> = tree
+                .nodes
+                .iter()
+                .filter_map(Node::as_branch)
+                .map(|branch| branch.split.feature_index())
+                .collect();
+            assert!(feature_indexes.len() <= 1);
+        }
+    }
+
+    #[test]
+    fn test_goss() {
+        let (features, labels) = features_and_labels();
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                compute_losses: true,
+                goss_options: Some(GossOptions::default()),
+                ..Default::default()
+            },
+        );
+        let predictions = predict(&output.model, &features);
+        let mean_squared_error = mean_squared_error(&predictions, &labels);
+        assert!(mean_squared_error < 0.02, "{}", mean_squared_error);
+        // The predictions of the examples left out of each round's sample are updated from their bins, and match the model's.
+        let last_loss = *output.losses.unwrap().last().unwrap();
+        assert!((last_loss - mean_squared_error).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_dart() {
+        let (features, labels) = features_and_labels();
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                compute_losses: true,
+                learning_rate: 0.3,
+                dart_options: Some(DartOptions {
+                    drop_rate: 0.2,
+                    max_dropped_rounds: Some(5),
+                    skip_drop_rate: 0.0,
+                }),
+                ..Default::default()
+            },
+        );
+        // The dropped rounds are rescaled in the model, so its predictions match the losses tracked during training.
+        let predictions = predict(&output.model, &features);
+        let mean_squared_error = mean_squared_error(&predictions, &labels);
+        let last_loss = *output.losses.unwrap().last().unwrap();
+        assert!((last_loss - mean_squared_error).abs() < 1e-4);
+        assert!(mean_squared_error < 0.05, "{}", mean_squared_error);
+    }
+
+    #[test]
+    fn test_early_stopping_keeps_the_best_round() {
+        let (features, labels) = features_and_labels();
+        let n_rounds_without_improvement_to_stop = 5;
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                compute_losses: true,
+                early_stopping_options: Some(EarlyStoppingOptions {
+                    early_stopping_fraction: 0.2,
+                    n_rounds_without_improvement_to_stop,
+                    min_decrease_in_loss_for_significant_change: 1e-3,
+                }),
+                learning_rate: 0.3,
+                max_rounds: 1000,
+                dart_options: Some(DartOptions {
+                    drop_rate: 0.2,
+                    max_dropped_rounds: Some(5),
+                    skip_drop_rate: 0.0,
+                }),
+                ..Default::default()
+            },
+        );
+        let losses = output.losses.unwrap();
+        assert!(losses.len() < 1000);
+        let n_rounds = output.model.trees.len();
+        assert_eq!(
+            n_rounds,
+            losses.len() - n_rounds_without_improvement_to_stop
+        );
+        // The trees of the best round are scaled back to their values after that round, so the model's training loss is the loss recorded after it.
+        let n_train_examples = 800;
+        let mut train_features = Table::new(Vec::new(), Vec::new());
+        *train_features.columns_mut() = features
+            .columns()
+            .iter()
+            .map(|column| {
+                let values =
+                    column.as_number().unwrap().view().as_slice()[..n_train_examples].to_vec();
+                TableColumn::Number(NumberTableColumn::new(None, values))
+            })
+            .collect();
+        let train_labels =
+            NumberTableColumn::new(None, labels.view().as_slice()[..n_train_examples].to_vec());
+        let mean_squared_error =
+            mean_squared_error(&predict(&output.model, &train_features), &train_labels);
+        assert!(
+            (losses[n_rounds - 1] - mean_squared_error).abs() < 1e-4,
+            "{} != {}",
+            losses[n_rounds - 1],
+            mean_squared_error
+        );
+    }
+
+    #[test]
+    fn test_classifiers() {
+        let (features, _) = features_and_labels();
+        let x0 = features.columns()[0].as_number().unwrap();
+        let kill_chip = shadow_kill_chip::KillChip::new();
+        let train_options = TrainOptions {
+            bagging_fraction: 0.8,
+            feature_fraction_per_node: 0.7,
+            ..Default::default()
+        };
+        let rows = features.to_rows();
+        let binary_labels = EnumTableColumn::new(
+            None,
+            vec!["false".to_owned(), "true".to_owned()],
+            x0.iter()
+                .map(|x0| NonZeroUsize::new(if *x0 > 0.4 { 2 } else { 1 }))
+                .collect(),
+        );
+        let output = BinaryClassifier::train(
+            &features.view(),
+            binary_labels.view(),
+            &train_options,
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        );
+        let mut probabilities = Array::zeros(features.nrows());
+        output.model.predict(rows.view(), probabilities.view_mut());
+        let n_correct = probabilities
+            .iter()
+            .zip(binary_labels.iter())
+            .filter(|(probability, label)| (**probability > 0.5) == (label.unwrap().get() == 2))
+            .count();
+        assert!(n_correct > 990, "{}", n_correct);
+
+        let multiclass_labels = EnumTableColumn::new(
+            None,
+            vec!["low".to_owned(), "mid".to_owned(), "high".to_owned()],
+            x0.iter()
+                .map(|x0| NonZeroUsize::new(1 + (*x0 * 3.0) as usize))
+                .collect(),
+        );
+        let output = MulticlassClassifier::train(
+            &features.view(),
+            multiclass_labels.view(),
+            &TrainOptions {
+                goss_options: Some(GossOptions::default()),
+                ..train_options
+            },
+            Progress {
+                kill_chip: &kill_chip,
+                handle_progress_event: &mut |_| {},
+            },
+        );
+        assert_eq!(output.model.trees.ncols(), 3);
+        let mut probabilities = Array::zeros((features.nrows(), 3));
+        output.model.predict(rows.view(), probabilities.view_mut());
+        let n_correct = probabilities
+            .axis_iter(Axis(0))
+            .zip(multiclass_labels.iter())
+            .filter(|(probabilities, label)| {
+                let class = probabilities
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+                    .unwrap()
+                    .0;
+                class + 1 == label.unwrap().get()
+            })
+            .count();
+        assert!(n_correct > 980, "{}", n_correct);
+    }
+
+    #[test]
+    fn test_monotone_constraints() {
+        let (features, _) = features_and_labels();
+        let x0 = features.columns()[0].as_number().unwrap();
+        let x1 = features.columns()[1].as_number().unwrap();
+        // The label wiggles in x0 and increases with x1, against the constraints.
+        let labels = NumberTableColumn::new(
+            None,
+            x0.iter()
+                .zip(x1.iter())
+                .map(|(x0, x1)| x0 + 0.3 * (12.0 * x0).sin() + x1)
+                .collect(),
+        );
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                monotone_constraints: vec![
+                    MonotoneConstraint::Increasing,
+                    MonotoneConstraint::Decreasing,
+                    MonotoneConstraint::None,
+                ],
+                ..Default::default()
+            },
+        );
+        let grid: Vec<f32> = (0..=50).map(|index| index as f32 / 50.0).collect();
+        for other in [0.1, 0.5, 0.9] {
+            let constant = vec![other; grid.len()];
+            let increasing = predict_grid(&output.model, [&grid, &constant, &constant]);
+            assert!(increasing.windows(2).all(|p| p[0] <= p[1]));
+            let decreasing = predict_grid(&output.model, [&constant, &grid, &constant]);
+            assert!(decreasing.windows(2).all(|p| p[0] >= p[1]));
+        }
+        // The model still follows the overall trend in x0.
+        let constant = vec![0.5; grid.len()];
+        let predictions = predict_grid(&output.model, [&grid, &constant, &constant]);
+        assert!(predictions[50] - predictions[0] > 0.3);
+    }
+
+    #[test]
+    fn test_satisfies_constraints() {
+        let branch = |feature_index, left_child_index, right_child_index| {
+            Node::Branch(BranchNode {
+                left_child_index,
+                right_child_index,
+                split: BranchSplit::Continuous(BranchSplitContinuous {
+                    feature_index,
+                    split_value: 0.5,
+                    invalid_values_direction: SplitDirection::Left,
+                }),
+                examples_fraction: 1.0,
+                gain: 1.0,
+            })
+        };
+        let leaf = |value| {
+            Node::Leaf(LeafNode {
+                value,
+                examples_fraction: 0.5,
+            })
+        };
+        // Splits on x0, then on x1 on the left.
+        let nodes = vec![
+            branch(0, 1, 2),
+            branch(1, 3, 4),
+            leaf(0.5),
+            leaf(0.0),
+            leaf(1.0),
+        ];
+        let with_monotone_constraints = |monotone_constraints| TrainOptions {
+            monotone_constraints,
+            ..Default::default()
+        };
+        use MonotoneConstraint::{Decreasing, Increasing, None};
+        assert!(satisfies_constraints(&nodes, &TrainOptions::default()));
+        assert!(satisfies_constraints(
+            &nodes,
+            &with_monotone_constraints(vec![None, Increasing])
+        ));
+        // A leaf on the left of the split on x0 is above the leaf on the right.
+        assert!(!satisfies_constraints(
+            &nodes,
+            &with_monotone_constraints(vec![Increasing, None])
+        ));
+        assert!(!satisfies_constraints(
+            &nodes,
+            &with_monotone_constraints(vec![Decreasing, None])
+        ));
+        assert!(!satisfies_constraints(
+            &nodes,
+            &with_monotone_constraints(vec![None, Decreasing])
+        ));
+        assert!(!satisfies_constraints(
+            &nodes,
+            &TrainOptions {
+                interaction_constraints: vec![vec![0], vec![1]],
+                ..Default::default()
+            }
+        ));
+        assert!(satisfies_constraints(
+            &nodes,
+            &TrainOptions {
+                interaction_constraints: vec![vec![0, 1]],
+                ..Default::default()
+            }
+        ));
+    }
+
+    fn predict_grid(model: &Regressor, columns: [&Vec<f32>; 3]) -> Vec<f32> {
+        let mut table = Table::new(Vec::new(), Vec::new());
+        *table.columns_mut() = columns
+            .iter()
+            .map(|values| TableColumn::Number(NumberTableColumn::new(None, values.to_vec())))
+            .collect();
+        predict(model, &table).to_vec()
+    }
+
+    #[test]
+    fn test_interaction_constraints() {
+        let (features, labels) = features_and_labels();
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                max_rounds: 20,
+                interaction_constraints: vec![vec![0, 2]],
+                ..Default::default()
+            },
+        );
+        // Feature 1 is in no group, so it may only appear on paths without the other features.
+        for tree in output.model.trees.iter() {
+            for path in path_feature_indexes(tree, 0, BTreeSet::new()) {
+                assert!(
+                    path.iter()
+                        .all(|feature_index| [0, 2].contains(feature_index))
+                        || path.iter().all(|feature_index| *feature_index == 1),
+                    "{:?}",
+                    path
+                );
+            }
+        }
+    }
+
+    /// Returns the set of features split on along each path from `node_index` to a leaf.
+    fn path_feature_indexes(
+        tree: &Tree,
+        node_index: usize,
+        path: BTreeSet<usize>,
+    ) -> Vec<BTreeSet<usize>> {
+        match &tree.nodes[node_index] {
+            Node::Leaf(_) => vec![path],
+            Node::Branch(branch) => {
+                let mut path = path;
+                path.insert(branch.split.feature_index());
+                let mut paths = path_feature_indexes(tree, branch.left_child_index, path.clone());
+                paths.extend(path_feature_indexes(tree, branch.right_child_index, path));
+                paths
+            }
+        }
+    }
+
+    /// Trains a single tree with one split on a table with one enum feature.
+    fn train_stump(
+        variants: usize,
+        values: Vec<Option<NonZeroUsize>>,
+        labels: Vec<f32>,
+    ) -> Regressor {
+        let mut features = Table::new(Vec::new(), Vec::new());
+        *features.columns_mut() = vec![TableColumn::Enum(EnumTableColumn::new(
+            Some("c".to_owned()),
+            (1..=variants).map(|variant| variant.to_string()).collect(),
+            values,
+        ))];
+        let labels = NumberTableColumn::new(None, labels);
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                learning_rate: 1.0,
+                max_rounds: 1,
+                max_leaf_nodes: 2,
+                min_examples_per_node: 1,
+                ..Default::default()
+            },
+        );
+        output.model
+    }
+
+    fn predict_enum(model: &Regressor, value: Option<usize>) -> f32 {
+        let rows = arr2(&[[TableValue::Enum(value.and_then(NonZeroUsize::new))]]);
+        let mut predictions = Array::zeros(1);
+        model.predict(rows.view(), predictions.view_mut());
+        predictions[0]
+    }
+
+    #[test]
+    fn test_many_vs_many_discrete_split() {
+        let values: Vec<usize> = (0..800).map(|index| 1 + index % 8).collect();
+        let labels = values
+            .iter()
+            .map(|value| if [2, 5, 7].contains(value) { 1.0 } else { 0.0 })
+            .collect();
+        let model = train_stump(
+            8,
+            values
+                .iter()
+                .map(|value| NonZeroUsize::new(*value))
+                .collect(),
+            labels,
+        );
+        // A single split separates the variants 2, 5 and 7 from the rest, which no split in the order of the variants can.
+        let high = predict_enum(&model, Some(2));
+        let low = predict_enum(&model, Some(1));
+        assert!(high - low > 0.9);
+        for variant in 1..=8 {
+            let expected = if [2, 5, 7].contains(&variant) {
+                high
+            } else {
+                low
+            };
+            assert_eq!(predict_enum(&model, Some(variant)), expected);
+        }
+    }
+
+    #[test]
+    fn test_one_vs_rest_discrete_split() {
+        // Variant 3 never appears, and every fifth value is missing.
+        let values: Vec<Option<usize>> = (0..500)
+            .map(|index| match index % 5 {
+                0 => None,
+                1 | 2 => Some(1),
+                _ => Some(2),
+            })
+            .collect();
+        let labels = values
+            .iter()
+            .map(|value| if *value == Some(1) { 1.0 } else { 0.0 })
+            .collect();
+        let model = train_stump(
+            3,
+            values
+                .iter()
+                .map(|value| value.and_then(NonZeroUsize::new))
+                .collect(),
+            labels,
+        );
+        let directions = match &model.trees[0].nodes[0] {
+            Node::Branch(branch) => match &branch.split {
+                crate::BranchSplit::Discrete(split) => split.directions.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        // Variant 1 is split from the rest, and the unseen variant 3 goes with the missing values.
+        assert_ne!(directions[1], directions[0]);
+        assert_eq!(directions[2], directions[0]);
+        assert_eq!(directions[3], directions[0]);
+        let missing = predict_enum(&model, None);
+        assert_eq!(predict_enum(&model, Some(2)), missing);
+        assert_eq!(predict_enum(&model, Some(3)), missing);
+        // Variants the model has never heard of also go with the missing values.
+        assert_eq!(predict_enum(&model, Some(9)), missing);
+        assert!(predict_enum(&model, Some(1)) - missing > 0.9);
+    }
+
+    #[test]
+    fn test_growth_policies() {
+        let (features, labels) = features_and_labels();
+        let train_options = |growth_policy| TrainOptions {
+            max_rounds: 10,
+            max_leaf_nodes: 8,
+            growth_policy,
+            ..Default::default()
+        };
+        let depth_wise =
+            train_regressor(&features, &labels, &train_options(GrowthPolicy::DepthWise))
+                .model
+                .tree_stats();
+        // Eight leaves grown level by level make a complete tree of depth three.
+        assert_eq!(depth_wise.leaf_counts, vec![8; 10]);
+        assert_eq!(depth_wise.depth_histogram, vec![0, 0, 0, 80]);
+        assert_eq!(depth_wise.split_gains.len(), 70);
+        let leaf_wise = train_regressor(&features, &labels, &train_options(GrowthPolicy::LeafWise))
+            .model
+            .tree_stats();
+        assert_eq!(leaf_wise.leaf_counts, vec![8; 10]);
+        assert!(leaf_wise.max_depth() > depth_wise.max_depth());
+    }
+
+    #[test]
+    fn test_cost_complexity_pruning() {
+        let (features, labels) = features_and_labels();
+        let train_options = |cost_complexity_pruning_alpha| TrainOptions {
+            max_rounds: 10,
+            cost_complexity_pruning_alpha,
+            ..Default::default()
+        };
+        let unpruned = train_regressor(&features, &labels, &train_options(0.0)).model;
+        let alpha = 5.0;
+        let pruned = train_regressor(&features, &labels, &train_options(alpha)).model;
+        let n_unpruned_leaves: usize = unpruned.tree_stats().leaf_counts.iter().sum();
+        let n_pruned_leaves: usize = pruned.tree_stats().leaf_counts.iter().sum();
+        assert!(n_pruned_leaves < n_unpruned_leaves);
+        // Every split left with two leaves pays for its extra leaf.
+        for tree in pruned.trees.iter() {
+            for branch in tree.nodes.iter().filter_map(Node::as_branch) {
+                let left = &tree.nodes[branch.left_child_index];
+                let right = &tree.nodes[branch.right_child_index];
+                if left.as_leaf().is_some() && right.as_leaf().is_some() {
+                    assert!(branch.gain >= alpha);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_sum_hessians_per_node() {
+        let (features, labels) = features_and_labels();
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                max_rounds: 5,
+                min_examples_per_node: 1,
+                min_sum_hessians_per_node: 100.0,
+                ..Default::default()
+            },
+        );
+        // The hessians of the squared error are one, so every leaf has at least 100 of the 1000 examples.
+        for tree in output.model.trees.iter() {
+            for leaf in tree.nodes.iter().filter_map(Node::as_leaf) {
+                assert!(leaf.examples_fraction >= 0.1);
+            }
+        }
+    }
+
+    #[cfg(feature = "timing")]
+    #[test]
+    fn test_timing() {
+        let (features, labels) = features_and_labels();
+        let output = train_regressor(
+            &features,
+            &labels,
+            &TrainOptions {
+                max_rounds: 3,
+                ..Default::default()
+            },
+        );
+        let timing = output.timing;
+        assert_eq!(timing.rounds.len(), 3);
+        let phases = timing.phases();
+        let total = timing.total.get();
+        assert!(phases.iter().all(|(_, duration)| *duration <= total));
+        for round in timing.rounds.iter() {
+            let round_total: std::time::Duration = round.phases.iter().map(|(_, d)| *d).sum();
+            assert!(round_total <= round.duration);
+        }
+        let table = timing.to_string();
+        assert!(table.contains("compute_histograms_root"));
+        // The header, the phases, the total, a blank line, the rounds' header and the rounds.
+        assert_eq!(table.lines().count(), 1 + phases.len() + 1 + 1 + 1 + 3);
+    }
+}
@@ -0,0 +1,1048 @@
+use crate::{
+    binned_features::BinnedFeatures,
+    compute_binning_instructions::BinningInstruction,
+    pool::{Pool, PoolItem},
+    timing::time,
+    BranchNode, BranchSplit, BranchSplitContinuous, BranchSplitDiscrete, GrowthPolicy, LeafNode,
+    MonotoneConstraint, Node, SplitDirection, TrainOptions, Tree,
+};
+use bitvec::prelude::*;
+use num::ToPrimitive;
+use rand::{seq::index::sample, Rng};
+use rand_xoshiro::Xoshiro256Plus;
+use rayon::prelude::*;
+use shadow_zip::zip;
+use std::ops::{AddAssign, Range, SubAssign};
+
+pub(crate) struct TrainTreeOptions<'a> {
+    pub train_options: &'a TrainOptions,
+    /// The features this tree may split on, in increasing order.
+    pub feature_indexes: &'a [usize],
+    /// The fraction of `feature_indexes` considered when choosing each split.
+    pub feature_fraction_per_node: f32,
+    /// Whether to consider a single random split per feature instead of every split, as in extremely randomized trees.
+    pub random_splits: bool,
+    /// Holds the histograms of the leaves being grown. See `create_histogram_pool`.
+    pub histogram_pool: &'a Pool<Histogram>,
+    #[cfg(feature = "timing")]
+    pub timing: &'a crate::Timing,
+}
+
+pub(crate) struct TrainTreeOutput {
+    pub tree: Tree,
+    /// The sum of the gains of the splits on each feature.
+    pub feature_gains: Vec<f64>,
+    /// The range of the reordered `examples_index` holding the examples of each node of `tree`.
+    pub node_examples_ranges: Vec<Range<usize>>,
+}
+
+/// The sums of the gradients and hessians and the number of examples in a bin, or in a node when summed over the bins of any feature.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct BinStats {
+    pub sum_gradients: f64,
+    pub sum_hessians: f64,
+    pub count: usize,
+}
+
+impl AddAssign for BinStats {
+    fn add_assign(&mut self, other: BinStats) {
+        self.sum_gradients += other.sum_gradients;
+        self.sum_hessians += other.sum_hessians;
+        self.count += other.count;
+    }
+}
+
+impl SubAssign for BinStats {
+    fn sub_assign(&mut self, other: BinStats) {
+        self.sum_gradients -= other.sum_gradients;
+        self.sum_hessians -= other.sum_hessians;
+        self.count -= other.count;
+    }
+}
+
+/// The stats of every bin of every feature for the examples in a node.
+pub(crate) type Histogram = Vec<Vec<BinStats>>;
+
+/// Creates a pool of `max_leaf_nodes` histograms. A tree holds at most one histogram per leaf, so reusing them across nodes and trees keeps memory bounded by `max_leaf_nodes` however deep the tree grows.
+pub(crate) fn create_histogram_pool(
+    binning_instructions: &[BinningInstruction],
+    max_leaf_nodes: usize,
+) -> Pool<Histogram> {
+    let n_bins: Vec<usize> = binning_instructions
+        .iter()
+        .map(|binning_instruction| binning_instruction.n_bins())
+        .collect();
+    Pool::new(
+        max_leaf_nodes.max(1),
+        Box::new(move || {
+            n_bins
+                .iter()
+                .map(|n_bins| vec![BinStats::default(); *n_bins])
+                .collect()
+        }),
+    )
+}
+
+struct SplitCandidate {
+    feature_index: usize,
+    gain: f64,
+    bin_split: BinSplit,
+    left_stats: BinStats,
+    right_stats: BinStats,
+}
+
+enum BinSplit {
+    /// The valid bins up to and including `bin_index` go left.
+    Continuous {
+        bin_index: usize,
+        invalid_values_direction: SplitDirection,
+    },
+    /// The bins whose bit is set go right.
+    Discrete { directions: BitVec<u8, Lsb0> },
+}
+
+impl BinSplit {
+    fn direction(&self, bin: usize) -> SplitDirection {
+        match self {
+            BinSplit::Continuous {
+                bin_index,
+                invalid_values_direction,
+            } => {
+                if bin == 0 {
+                    *invalid_values_direction
+                } else if bin <= *bin_index {
+                    SplitDirection::Left
+                } else {
+                    SplitDirection::Right
+                }
+            }
+            BinSplit::Discrete { directions } => (*directions.get(bin).unwrap()).into(),
+        }
+    }
+}
+
+/// A leaf of the tree being grown, which may still be split.
+struct GrowingLeaf {
+    node_index: usize,
+    examples_range: Range<usize>,
+    depth: usize,
+    stats: BinStats,
+    /// Returned to the pool once the leaf is known not to split.
+    histogram: Option<PoolItem<Histogram>>,
+    constraints: NodeConstraints,
+    best_split: Option<SplitCandidate>,
+}
+
+#[derive(Clone, Debug)]
+struct NodeConstraints {
+    value_bounds: ValueBounds,
+    /// The features split on along the path from the root, which the interaction constraints restrict.
+    path_feature_indexes: Vec<usize>,
+}
+
+/// The range the values of a node's leaves must stay in. Splits on monotone features narrow the range of each child to its side of the midpoint of the children's values, so no leaf on the left can exceed a leaf on the right, or the reverse.
+#[derive(Clone, Copy, Debug)]
+struct ValueBounds {
+    lower: f64,
+    upper: f64,
+}
+
+impl ValueBounds {
+    fn unbounded() -> ValueBounds {
+        ValueBounds {
+            lower: f64::NEG_INFINITY,
+            upper: f64::INFINITY,
+        }
+    }
+
+    fn clamp(&self, value: f64) -> f64 {
+        value.clamp(self.lower, self.upper)
+    }
+}
+
+/// Grows a tree best first on the examples in `examples_index`, which may repeat examples and is reordered so the examples of each node are contiguous. Each leaf's value is the regularized Newton step `-sum_gradients / (sum_hessians + l2_regularization)`.
+pub(crate) fn train_tree(
+    binned_features: &BinnedFeatures,
+    binning_instructions: &[BinningInstruction],
+    gradients: &[f32],
+    hessians: &[f32],
+    examples_index: &mut [usize],
+    options: &TrainTreeOptions,
+    rng: &mut Xoshiro256Plus,
+) -> TrainTreeOutput {
+    let train_options = options.train_options;
+    assert!(
+        train_options.monotone_constraints.is_empty()
+            || train_options.monotone_constraints.len() == binning_instructions.len()
+    );
+    for (monotone_constraint, binning_instruction) in train_options
+        .monotone_constraints
+        .iter()
+        .zip(binning_instructions.iter())
+    {
+        assert!(
+            *monotone_constraint == MonotoneConstraint::None
+                || matches!(binning_instruction, BinningInstruction::Number { .. }),
+            "only number features may have monotone constraints"
+        );
+    }
+    let n_root_examples = examples_index.len().to_f32().unwrap();
+    let root_stats = time!(options.timing.sum_gradients_and_hessians_root, {
+        let mut root_stats = BinStats::default();
+        for example in examples_index.iter() {
+            root_stats += BinStats {
+                sum_gradients: gradients[*example].to_f64().unwrap(),
+                sum_hessians: hessians[*example].to_f64().unwrap(),
+                count: 1,
+            };
+        }
+        root_stats
+    });
+    let mut root_histogram = get_histogram(options.histogram_pool);
+    time!(
+        options.timing.compute_histograms_root,
+        compute_histogram(
+            &mut root_histogram,
+            binned_features,
+            binning_instructions,
+            options.feature_indexes,
+            gradients,
+            hessians,
+            examples_index,
+        )
+    );
+    let root_constraints = NodeConstraints {
+        value_bounds: ValueBounds::unbounded(),
+        path_feature_indexes: Vec::new(),
+    };
+    let mut nodes = vec![placeholder_node()];
+    // The stats and value bounds of every node, which pruning needs to turn a branch back into a leaf.
+    let mut node_stats = vec![(root_stats, root_constraints.value_bounds)];
+    #[allow(clippy::single_range_in_vec_init)]
+    let mut node_examples_ranges = vec![0..examples_index.len()];
+    let root_best_split = time!(
+        options.timing.choose_best_split_root,
+        choose_best_split(
+            &root_histogram,
+            root_stats,
+            0,
+            &root_constraints,
+            binning_instructions,
+            options,
+            rng,
+        )
+    );
+    let mut leaves = vec![GrowingLeaf {
+        node_index: 0,
+        examples_range: 0..examples_index.len(),
+        depth: 0,
+        histogram: root_best_split.as_ref().map(|_| root_histogram),
+        best_split: root_best_split,
+        stats: root_stats,
+        constraints: root_constraints,
+    }];
+    let mut n_leaves = 1;
+    while n_leaves < train_options.max_leaf_nodes {
+        let splittable_leaves = leaves
+            .iter()
+            .enumerate()
+            .filter_map(|(index, leaf)| leaf.best_split.as_ref().map(|split| (index, leaf, split)));
+        let best_leaf_index = match train_options.growth_policy {
+            GrowthPolicy::LeafWise => splittable_leaves
+                .max_by(|a, b| a.2.gain.partial_cmp(&b.2.gain).unwrap())
+                .map(|(index, _, _)| index),
+            // Nodes are numbered in the order they were created, so this splits each level from left to right.
+            GrowthPolicy::DepthWise => splittable_leaves
+                .min_by_key(|(_, leaf, _)| (leaf.depth, leaf.node_index))
+                .map(|(index, _, _)| index),
+        };
+        let best_leaf_index = match best_leaf_index {
+            Some(best_leaf_index) => best_leaf_index,
+            None => break,
+        };
+        let leaf = leaves.swap_remove(best_leaf_index);
+        let split = leaf.best_split.unwrap();
+        let feature_bins = binned_features.bins.column(split.feature_index);
+        let n_left_examples = time!(
+            options.timing.rearrange_examples_index,
+            partition(
+                &mut examples_index[leaf.examples_range.clone()],
+                |example| {
+                    split
+                        .bin_split
+                        .direction(feature_bins[example].to_usize().unwrap())
+                        == SplitDirection::Left
+                }
+            )
+        );
+        let left_range = leaf.examples_range.start..leaf.examples_range.start + n_left_examples;
+        let right_range = left_range.end..leaf.examples_range.end;
+        // Only the smaller child's histogram is computed from its examples. The larger child's is the parent's minus the smaller child's.
+        let mut larger_histogram = leaf.histogram.unwrap();
+        let smaller_range = if left_range.len() <= right_range.len() {
+            left_range.clone()
+        } else {
+            right_range.clone()
+        };
+        let mut smaller_histogram = get_histogram(options.histogram_pool);
+        time!(options.timing.compute_histograms_not_root, {
+            compute_histogram(
+                &mut smaller_histogram,
+                binned_features,
+                binning_instructions,
+                options.feature_indexes,
+                gradients,
+                hessians,
+                &examples_index[smaller_range],
+            );
+            for feature_index in options.feature_indexes.iter() {
+                let larger = &mut larger_histogram[*feature_index];
+                let smaller = &smaller_histogram[*feature_index];
+                for (larger, smaller) in larger.iter_mut().zip(smaller.iter()) {
+                    *larger -= *smaller;
+                }
+            }
+        });
+        let (left_histogram, right_histogram) = if left_range.len() <= right_range.len() {
+            (smaller_histogram, larger_histogram)
+        } else {
+            (larger_histogram, smaller_histogram)
+        };
+        let left_child_index = nodes.len();
+        let right_child_index = left_child_index + 1;
+        nodes.push(placeholder_node());
+        nodes.push(placeholder_node());
+        nodes[leaf.node_index] = Node::Branch(BranchNode {
+            left_child_index,
+            right_child_index,
+            split: compute_branch_split(&split, binning_instructions),
+            examples_fraction: leaf.stats.count.to_f32().unwrap() / n_root_examples,
+            gain: split.gain.to_f32().unwrap(),
+        });
+        let (left_constraints, right_constraints) =
+            compute_children_constraints(&leaf.constraints, &split, train_options);
+        node_stats.push((split.left_stats, left_constraints.value_bounds));
+        node_stats.push((split.right_stats, right_constraints.value_bounds));
+        node_examples_ranges.push(left_range.clone());
+        node_examples_ranges.push(right_range.clone());
+        for (node_index, examples_range, stats, histogram, constraints) in [
+            (
+                left_child_index,
+                left_range,
+                split.left_stats,
+                left_histogram,
+                left_constraints,
+            ),
+            (
+                right_child_index,
+                right_range,
+                split.right_stats,
+                right_histogram,
+                right_constraints,
+            ),
+        ] {
+            let best_split = time!(
+                options.timing.choose_best_split_not_root,
+                choose_best_split(
+                    &histogram,
+                    stats,
+                    leaf.depth + 1,
+                    &constraints,
+                    binning_instructions,
+                    options,
+                    rng,
+                )
+            );
+            leaves.push(GrowingLeaf {
+                node_index,
+                examples_range,
+                depth: leaf.depth + 1,
+                histogram: best_split.as_ref().map(|_| histogram),
+                best_split,
+                stats,
+                constraints,
+            });
+        }
+        n_leaves += 1;
+    }
+    for leaf in leaves {
+        nodes[leaf.node_index] = make_leaf(
+            leaf.stats,
+            leaf.constraints.value_bounds,
+            n_root_examples,
+            train_options,
+        );
+    }
+    if train_options.cost_complexity_pruning_alpha > 0.0 {
+        (nodes, node_examples_ranges) = prune(
+            nodes,
+            &node_stats,
+            node_examples_ranges,
+            n_root_examples,
+            train_options,
+        );
+    }
+    debug_assert!(
+        satisfies_constraints(&nodes, train_options),
+        "the tree violates its monotone or interaction constraints"
+    );
+    let mut feature_gains = vec![0.0; binned_features.n_features()];
+    for branch in nodes.iter().filter_map(Node::as_branch) {
+        feature_gains[branch.split.feature_index()] += branch.gain.to_f64().unwrap();
+    }
+    TrainTreeOutput {
+        tree: Tree { nodes },
+        feature_gains,
+        node_examples_ranges,
+    }
+}
+
+fn make_leaf(
+    stats: BinStats,
+    value_bounds: ValueBounds,
+    n_root_examples: f32,
+    train_options: &TrainOptions,
+) -> Node {
+    Node::Leaf(LeafNode {
+        value: value_bounds.clamp(compute_leaf_value(
+            stats,
+            train_options.l2_regularization_for_continuous_splits,
+        )),
+        examples_fraction: stats.count.to_f32().unwrap() / n_root_examples,
+    })
+}
+
+/// Weakest link pruning. While some branch's subtree has a total gain per leaf it adds, `gain / (n_leaves - 1)`, below `cost_complexity_pruning_alpha`, the branch with the smallest one is turned into a leaf. The remaining nodes and their examples ranges are then renumbered, keeping their order.
+fn prune(
+    mut nodes: Vec<Node>,
+    node_stats: &[(BinStats, ValueBounds)],
+    node_examples_ranges: Vec<Range<usize>>,
+    n_root_examples: f32,
+    train_options: &TrainOptions,
+) -> (Vec<Node>, Vec<Range<usize>>) {
+    let alpha = train_options
+        .cost_complexity_pruning_alpha
+        .to_f64()
+        .unwrap();
+    loop {
+        let mut weakest_link: Option<(usize, f64)> = None;
+        find_weakest_link(&nodes, 0, &mut weakest_link);
+        match weakest_link {
+            Some((node_index, gain_per_leaf)) if gain_per_leaf < alpha => {
+                let (stats, value_bounds) = node_stats[node_index];
+                nodes[node_index] = make_leaf(stats, value_bounds, n_root_examples, train_options);
+            }
+            _ => break,
+        }
+    }
+    let mut reachable = vec![false; nodes.len()];
+    let mut stack = vec![0];
+    while let Some(node_index) = stack.pop() {
+        reachable[node_index] = true;
+        if let Node::Branch(branch) = &nodes[node_index] {
+            stack.push(branch.left_child_index);
+            stack.push(branch.right_child_index);
+        }
+    }
+    let mut new_indexes = vec![0; nodes.len()];
+    let mut n_reachable = 0;
+    for (node_index, reachable) in reachable.iter().enumerate() {
+        if *reachable {
+            new_indexes[node_index] = n_reachable;
+            n_reachable += 1;
+        }
+    }
+    zip!(nodes, node_examples_ranges, reachable)
+        .filter(|(_, _, reachable)| *reachable)
+        .map(|(node, examples_range, _)| {
+            let node = match node {
+                Node::Branch(mut branch) => {
+                    branch.left_child_index = new_indexes[branch.left_child_index];
+                    branch.right_child_index = new_indexes[branch.right_child_index];
+                    Node::Branch(branch)
+                }
+                leaf => leaf,
+            };
+            (node, examples_range)
+        })
+        .unzip()
+}
+
+/// Returns the total gain and the number of leaves of the subtree at `node_index`, and updates `weakest_link` with the branch that adds the least gain per leaf.
+fn find_weakest_link(
+    nodes: &[Node],
+    node_index: usize,
+    weakest_link: &mut Option<(usize, f64)>,
+) -> (f64, usize) {
+    match &nodes[node_index] {
+        Node::Leaf(_) => (0.0, 1),
+        Node::Branch(branch) => {
+            let (left_gain, left_n_leaves) =
+                find_weakest_link(nodes, branch.left_child_index, weakest_link);
+            let (right_gain, right_n_leaves) =
+                find_weakest_link(nodes, branch.right_child_index, weakest_link);
+            let gain = branch.gain.to_f64().unwrap() + left_gain + right_gain;
+            let n_leaves = left_n_leaves + right_n_leaves;
+            let gain_per_leaf = gain / (n_leaves - 1).to_f64().unwrap();
+            if weakest_link.is_none_or(|(_, weakest)| gain_per_leaf < weakest) {
+                *weakest_link = Some((node_index, gain_per_leaf));
+            }
+            (gain, n_leaves)
+        }
+    }
+}
+
+fn placeholder_node() -> Node {
+    Node::Leaf(LeafNode {
+        value: 0.0,
+        examples_fraction: 0.0,
+    })
+}
+
+/// Takes a histogram from the pool. The pool holds `max_leaf_nodes` histograms and a tree never holds more than one per leaf, so one is always available. Waiting for one would deadlock, because a tree is grown on a single thread.
+fn get_histogram(histogram_pool: &Pool<Histogram>) -> PoolItem<Histogram> {
+    histogram_pool
+        .try_get()
+        .expect("the histogram pool should hold a histogram for every leaf")
+}
+
+/// Fills the histograms of the features in `feature_indexes`. The histograms of the other features are left as they were, because the buffer may have been used by another node.
+fn compute_histogram(
+    histogram: &mut Histogram,
+    binned_features: &BinnedFeatures,
+    binning_instructions: &[BinningInstruction],
+    feature_indexes: &[usize],
+    gradients: &[f32],
+    hessians: &[f32],
+    examples: &[usize],
+) {
+    histogram
+        .par_iter_mut()
+        .zip(binning_instructions.par_iter())
+        .enumerate()
+        .for_each(|(feature_index, (histogram, binning_instruction))| {
+            if feature_indexes.binary_search(&feature_index).is_err() {
+                return;
+            }
+            debug_assert_eq!(histogram.len(), binning_instruction.n_bins());
+            histogram.fill(BinStats::default());
+            let feature_bins = binned_features.bins.column(feature_index);
+            for example in examples.iter() {
+                let stats = &mut histogram[feature_bins[*example].to_usize().unwrap()];
+                stats.sum_gradients += gradients[*example].to_f64().unwrap();
+                stats.sum_hessians += hessians[*example].to_f64().unwrap();
+                stats.count += 1;
+            }
+        });
+}
+
+/// Moves the examples for which `goes_left` is true to the front and returns how many there are.
+fn partition(examples: &mut [usize], goes_left: impl Fn(usize) -> bool) -> usize {
+    let mut n_left = 0;
+    for index in 0..examples.len() {
+        if goes_left(examples[index]) {
+            examples.swap(index, n_left);
+            n_left += 1;
+        }
+    }
+    n_left
+}
+
+pub(crate) fn compute_leaf_value(stats: BinStats, l2_regularization: f32) -> f64 {
+    let denominator = stats.sum_hessians + l2_regularization.to_f64().unwrap();
+    if denominator > 0.0 {
+        -stats.sum_gradients / denominator
+    } else {
+        0.0
+    }
+}
+
+/// The decrease in the second order approximation of the loss from giving the examples the best value within `value_bounds`. Without bounds this is `sum_gradients^2 / (sum_hessians + l2_regularization)`.
+fn compute_score(stats: BinStats, l2_regularization: f64, value_bounds: ValueBounds) -> f64 {
+    let denominator = stats.sum_hessians + l2_regularization;
+    if denominator > 0.0 {
+        let value = value_bounds.clamp(-stats.sum_gradients / denominator);
+        -(2.0 * stats.sum_gradients * value + denominator * value * value)
+    } else {
+        0.0
+    }
+}
+
+fn compute_bounded_leaf_value(
+    stats: BinStats,
+    l2_regularization: f64,
+    value_bounds: ValueBounds,
+) -> f64 {
+    let denominator = stats.sum_hessians + l2_regularization;
+    if denominator > 0.0 {
+        value_bounds.clamp(-stats.sum_gradients / denominator)
+    } else {
+        value_bounds.clamp(0.0)
+    }
+}
+
+/// The children's constraints add the split's feature to the path, and after a split on a monotone feature, bound each child's values by the midpoint of their values.
+fn compute_children_constraints(
+    constraints: &NodeConstraints,
+    split: &SplitCandidate,
+    train_options: &TrainOptions,
+) -> (NodeConstraints, NodeConstraints) {
+    let mut path_feature_indexes = constraints.path_feature_indexes.clone();
+    if !path_feature_indexes.contains(&split.feature_index) {
+        path_feature_indexes.push(split.feature_index);
+    }
+    let mut left_bounds = constraints.value_bounds;
+    let mut right_bounds = constraints.value_bounds;
+    let monotone_constraint = monotone_constraint(train_options, split.feature_index);
+    if monotone_constraint != MonotoneConstraint::None {
+        let l2_regularization = train_options
+            .l2_regularization_for_continuous_splits
+            .to_f64()
+            .unwrap();
+        let left_value = compute_bounded_leaf_value(
+            split.left_stats,
+            l2_regularization,
+            constraints.value_bounds,
+        );
+        let right_value = compute_bounded_leaf_value(
+            split.right_stats,
+            l2_regularization,
+            constraints.value_bounds,
+        );
+        let midpoint = (left_value + right_value) / 2.0;
+        if monotone_constraint == MonotoneConstraint::Increasing {
+            left_bounds.upper = left_bounds.upper.min(midpoint);
+            right_bounds.lower = right_bounds.lower.max(midpoint);
+        } else {
+            left_bounds.lower = left_bounds.lower.max(midpoint);
+            right_bounds.upper = right_bounds.upper.min(midpoint);
+        }
+    }
+    (
+        NodeConstraints {
+            value_bounds: left_bounds,
+            path_feature_indexes: path_feature_indexes.clone(),
+        },
+        NodeConstraints {
+            value_bounds: right_bounds,
+            path_feature_indexes,
+        },
+    )
+}
+
+fn monotone_constraint(train_options: &TrainOptions, feature_index: usize) -> MonotoneConstraint {
+    train_options
+        .monotone_constraints
+        .get(feature_index)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Whether splitting on `feature_index` keeps the features on the path within one of the interaction constraints' groups.
+fn is_interaction_allowed(
+    feature_index: usize,
+    path_feature_indexes: &[usize],
+    interaction_constraints: &[Vec<usize>],
+) -> bool {
+    if interaction_constraints.is_empty()
+        || path_feature_indexes
+            .iter()
+            .all(|path_feature_index| *path_feature_index == feature_index)
+    {
+        return true;
+    }
+    interaction_constraints.iter().any(|group| {
+        group.contains(&feature_index)
+            && path_feature_indexes
+                .iter()
+                .all(|path_feature_index| group.contains(path_feature_index))
+    })
+}
+
+/// The split search only keeps splits that satisfy the constraints, and debug builds check the finished tree against them again. Each split on a monotone feature must leave every leaf on one side at most every leaf on the other, and the features on each path from the root must be allowed together. Scaling the tree by a positive factor, as the learning rate and DART do, keeps both.
+pub(crate) fn satisfies_constraints(nodes: &[Node], train_options: &TrainOptions) -> bool {
+    leaf_values_range(nodes, 0, &mut Vec::new(), train_options).is_some()
+}
+
+/// The smallest and largest leaf values in the subtree at `node_index`, or `None` if the subtree violates a constraint.
+fn leaf_values_range(
+    nodes: &[Node],
+    node_index: usize,
+    path_feature_indexes: &mut Vec<usize>,
+    train_options: &TrainOptions,
+) -> Option<(f64, f64)> {
+    let branch = match &nodes[node_index] {
+        Node::Leaf(leaf) => return Some((leaf.value, leaf.value)),
+        Node::Branch(branch) => branch,
+    };
+    let feature_index = branch.split.feature_index();
+    if !is_interaction_allowed(
+        feature_index,
+        path_feature_indexes,
+        &train_options.interaction_constraints,
+    ) {
+        return None;
+    }
+    path_feature_indexes.push(feature_index);
+    let left = leaf_values_range(
+        nodes,
+        branch.left_child_index,
+        path_feature_indexes,
+        train_options,
+    );
+    let right = leaf_values_range(
+        nodes,
+        branch.right_child_index,
+        path_feature_indexes,
+        train_options,
+    );
+    path_feature_indexes.pop();
+    let ((left_min, left_max), (right_min, right_max)) = (left?, right?);
+    let is_monotone = match monotone_constraint(train_options, feature_index) {
+        MonotoneConstraint::None => true,
+        MonotoneConstraint::Increasing => left_max <= right_min,
+        MonotoneConstraint::Decreasing => left_min >= right_max,
+    };
+    is_monotone.then(|| (left_min.min(right_min), left_max.max(right_max)))
+}
+
+fn choose_best_split(
+    histogram: &Histogram,
+    stats: BinStats,
+    depth: usize,
+    constraints: &NodeConstraints,
+    binning_instructions: &[BinningInstruction],
+    options: &TrainTreeOptions,
+    rng: &mut Xoshiro256Plus,
+) -> Option<SplitCandidate> {
+    let train_options = options.train_options;
+    if let Some(max_depth) = train_options.max_depth {
+        if depth >= max_depth {
+            return None;
+        }
+    }
+    if stats.count < 2 * train_options.min_examples_per_node {
+        return None;
+    }
+    let allowed_feature_indexes: Vec<usize> = options
+        .feature_indexes
+        .iter()
+        .copied()
+        .filter(|feature_index| {
+            is_interaction_allowed(
+                *feature_index,
+                &constraints.path_feature_indexes,
+                &train_options.interaction_constraints,
+            )
+        })
+        .collect();
+    let feature_indexes = sample_features(
+        rng,
+        &allowed_feature_indexes,
+        options.feature_fraction_per_node,
+    );
+    let mut best_split: Option<SplitCandidate> = None;
+    for feature_index in feature_indexes {
+        let split = match &binning_instructions[feature_index] {
+            BinningInstruction::Number { .. } => choose_best_continuous_split(
+                feature_index,
+                &histogram[feature_index],
+                stats,
+                constraints.value_bounds,
+                options,
+                rng,
+            ),
+            BinningInstruction::Enum { .. } => choose_best_discrete_split(
+                feature_index,
+                &histogram[feature_index],
+                stats,
+                constraints.value_bounds,
+                options,
+                rng,
+            ),
+        };
+        if let Some(split) = split {
+            if best_split
+                .as_ref()
+                .map(|best_split| split.gain > best_split.gain)
+                .unwrap_or(true)
+            {
+                best_split = Some(split);
+            }
+        }
+    }
+    best_split.filter(|split| {
+        split.gain > 0.0 && split.gain > train_options.min_gain_to_split.to_f64().unwrap()
+    })
+}
+
+/// Samples `fraction` of `feature_indexes`, rounded up so at least one feature is kept, and returns them in increasing order.
+pub(crate) fn sample_features(
+    rng: &mut Xoshiro256Plus,
+    feature_indexes: &[usize],
+    fraction: f32,
+) -> Vec<usize> {
+    let n_features = feature_indexes.len();
+    if fraction >= 1.0 || n_features == 0 {
+        return feature_indexes.to_vec();
+    }
+    let n_sampled_features = (fraction * n_features.to_f32().unwrap())
+        .ceil()
+        .to_usize()
+        .unwrap()
+        .clamp(1, n_features);
+    let mut sampled_features: Vec<usize> = sample(rng, n_features, n_sampled_features)
+        .into_iter()
+        .map(|index| feature_indexes[index])
+        .collect();
+    sampled_features.sort_unstable();
+    sampled_features
+}
+
+/// Normalizes the total gain of the splits on each feature to sum to one.
+pub(crate) fn compute_feature_importances(feature_gains: &[f64]) -> Vec<f32> {
+    let feature_gains_sum = feature_gains.iter().sum::<f64>();
+    feature_gains
+        .iter()
+        .map(|feature_gain| {
+            if feature_gains_sum > 0.0 {
+                (feature_gain / feature_gains_sum).to_f32().unwrap()
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+fn is_valid_split(
+    left_stats: BinStats,
+    right_stats: BinStats,
+    train_options: &TrainOptions,
+) -> bool {
+    let min_sum_hessians = train_options.min_sum_hessians_per_node.to_f64().unwrap();
+    left_stats.count >= train_options.min_examples_per_node
+        && right_stats.count >= train_options.min_examples_per_node
+        && left_stats.sum_hessians >= min_sum_hessians
+        && right_stats.sum_hessians >= min_sum_hessians
+}
+
+/// Returns the first and last bins in `bins` that have examples, if they differ.
+fn occupied_bins_range(histogram: &[BinStats], bins: Range<usize>) -> Option<(usize, usize)> {
+    let first = bins.clone().find(|bin| histogram[*bin].count > 0)?;
+    let last = bins.rev().find(|bin| histogram[*bin].count > 0)?;
+    if first < last {
+        Some((first, last))
+    } else {
+        None
+    }
+}
+
+fn choose_best_continuous_split(
+    feature_index: usize,
+    histogram: &[BinStats],
+    stats: BinStats,
+    value_bounds: ValueBounds,
+    options: &TrainTreeOptions,
+    rng: &mut Xoshiro256Plus,
+) -> Option<SplitCandidate> {
+    let train_options = options.train_options;
+    let l2_regularization = train_options
+        .l2_regularization_for_continuous_splits
+        .to_f64()
+        .unwrap();
+    let monotone_constraint = monotone_constraint(train_options, feature_index);
+    let parent_score = compute_score(stats, l2_regularization, value_bounds);
+    let invalid_stats = histogram[0];
+    // Splitting after bin `b` sends the valid bins `1..=b` left, so the candidates are the occupied bins except the last.
+    let (first_bin, last_bin) = occupied_bins_range(histogram, 1..histogram.len())?;
+    let bin_indexes = if options.random_splits {
+        let bin_index = rng.gen_range(first_bin..last_bin);
+        bin_index..bin_index + 1
+    } else {
+        first_bin..last_bin
+    };
+    let mut left_valid_stats = BinStats::default();
+    for stats in &histogram[1..bin_indexes.start] {
+        left_valid_stats += *stats;
+    }
+    let mut best_split: Option<SplitCandidate> = None;
+    for bin_index in bin_indexes {
+        left_valid_stats += histogram[bin_index];
+        for invalid_values_direction in [SplitDirection::Left, SplitDirection::Right] {
+            let mut left_stats = left_valid_stats;
+            if invalid_values_direction == SplitDirection::Left {
+                left_stats += invalid_stats;
+            }
+            let mut right_stats = stats;
+            right_stats -= left_stats;
+            if !is_valid_split(left_stats, right_stats, train_options) {
+                continue;
+            }
+            let left_value =
+                compute_bounded_leaf_value(left_stats, l2_regularization, value_bounds);
+            let right_value =
+                compute_bounded_leaf_value(right_stats, l2_regularization, value_bounds);
+            let violates_monotone_constraint = match monotone_constraint {
+                MonotoneConstraint::None => false,
+                MonotoneConstraint::Increasing => left_value > right_value,
+                MonotoneConstraint::Decreasing => left_value < right_value,
+            };
+            if violates_monotone_constraint {
+                continue;
+            }
+            let gain = compute_score(left_stats, l2_regularization, value_bounds)
+                + compute_score(right_stats, l2_regularization, value_bounds)
+                - parent_score;
+            if best_split
+                .as_ref()
+                .map(|best_split| gain > best_split.gain)
+                .unwrap_or(true)
+            {
+                best_split = Some(SplitCandidate {
+                    feature_index,
+                    gain,
+                    bin_split: BinSplit::Continuous {
+                        bin_index,
+                        invalid_values_direction,
+                    },
+                    left_stats,
+                    right_stats,
+                });
+            }
+            // Without invalid values, both directions give the same split.
+            if invalid_stats.count == 0 {
+                break;
+            }
+        }
+    }
+    best_split
+}
+
+/// Splits the bins of an enum feature in two. With at most `max_variants_for_one_vs_rest_splits` occupied bins, each occupied bin is tried alone against the rest. Otherwise the occupied bins are sorted by their smoothed mean gradient, `sum_gradients / (sum_hessians + smoothing_factor_for_discrete_bin_sorting)`, and every split of that order into a prefix and a suffix is tried, which includes the best of all partitions of the bins. The invalid bin 0 is sorted like the others, and unoccupied bins, which hold variants unseen at this node, go the same way as bin 0.
+fn choose_best_discrete_split(
+    feature_index: usize,
+    histogram: &[BinStats],
+    stats: BinStats,
+    value_bounds: ValueBounds,
+    options: &TrainTreeOptions,
+    rng: &mut Xoshiro256Plus,
+) -> Option<SplitCandidate> {
+    let train_options = options.train_options;
+    let l2_regularization = train_options
+        .l2_regularization_for_discrete_splits
+        .to_f64()
+        .unwrap();
+    let parent_score = compute_score(stats, l2_regularization, value_bounds);
+    let mut occupied_bins: Vec<usize> = (0..histogram.len())
+        .filter(|bin| histogram[*bin].count > 0)
+        .collect();
+    if occupied_bins.len() < 2 {
+        return None;
+    }
+    let one_vs_rest = occupied_bins.len() <= train_options.max_variants_for_one_vs_rest_splits;
+    if !one_vs_rest {
+        let smoothing_factor = train_options
+            .smoothing_factor_for_discrete_bin_sorting
+            .to_f64()
+            .unwrap();
+        let sort_key = |bin: &usize| {
+            let stats = histogram[*bin];
+            stats.sum_gradients / (stats.sum_hessians + smoothing_factor)
+        };
+        occupied_bins.sort_by(|a, b| sort_key(a).total_cmp(&sort_key(b)));
+    }
+    // With one vs rest, candidate `i` sends `occupied_bins[i]` right. Otherwise, it sends `occupied_bins[i + 1..]` right.
+    let n_candidates = if one_vs_rest {
+        occupied_bins.len()
+    } else {
+        occupied_bins.len() - 1
+    };
+    let candidates = if options.random_splits {
+        let candidate = rng.gen_range(0..n_candidates);
+        candidate..candidate + 1
+    } else {
+        0..n_candidates
+    };
+    let mut prefix_stats = BinStats::default();
+    for bin in &occupied_bins[0..candidates.start] {
+        prefix_stats += histogram[*bin];
+    }
+    let mut best: Option<(usize, f64, BinStats, BinStats)> = None;
+    for candidate in candidates {
+        let (left_stats, right_stats) = if one_vs_rest {
+            let right_stats = histogram[occupied_bins[candidate]];
+            let mut left_stats = stats;
+            left_stats -= right_stats;
+            (left_stats, right_stats)
+        } else {
+            prefix_stats += histogram[occupied_bins[candidate]];
+            let mut right_stats = stats;
+            right_stats -= prefix_stats;
+            (prefix_stats, right_stats)
+        };
+        if !is_valid_split(left_stats, right_stats, train_options) {
+            continue;
+        }
+        let gain = compute_score(left_stats, l2_regularization, value_bounds)
+            + compute_score(right_stats, l2_regularization, value_bounds)
+            - parent_score;
+        if best.map(|best| gain > best.1).unwrap_or(true) {
+            best = Some((candidate, gain, left_stats, right_stats));
+        }
+    }
+    best.map(|(candidate, gain, left_stats, right_stats)| {
+        let right_bins = if one_vs_rest {
+            &occupied_bins[candidate..candidate + 1]
+        } else {
+            &occupied_bins[candidate + 1..]
+        };
+        let mut directions = bitvec![u8, Lsb0; 0; histogram.len()];
+        for bin in right_bins {
+            directions.set(*bin, true);
+        }
+        // If bin 0 is unoccupied, it and the other unoccupied bins go to the child with more examples.
+        let unoccupied_bins_go_right = if histogram[0].count > 0 {
+            directions[0]
+        } else {
+            right_stats.count > left_stats.count
+        };
+        for (bin, bin_stats) in histogram.iter().enumerate() {
+            if bin_stats.count == 0 {
+                directions.set(bin, unoccupied_bins_go_right);
+            }
+        }
+        SplitCandidate {
+            feature_index,
+            gain,
+            bin_split: BinSplit::Discrete { directions },
+            left_stats,
+            right_stats,
+        }
+    })
+}
+
+fn compute_branch_split(
+    split: &SplitCandidate,
+    binning_instructions: &[BinningInstruction],
+) -> BranchSplit {
+    match &split.bin_split {
+        BinSplit::Continuous {
+            bin_index,
+            invalid_values_direction,
+        } => {
+            let thresholds = match &binning_instructions[split.feature_index] {
+                BinningInstruction::Number { thresholds } => thresholds,
+                _ => unreachable!(),
+            };
+            BranchSplit::Continuous(BranchSplitContinuous {
+                feature_index: split.feature_index,
+                split_value: thresholds[bin_index - 1],
+                invalid_values_direction: *invalid_values_direction,
+            })
+        }
+        BinSplit::Discrete { directions } => BranchSplit::Discrete(BranchSplitDiscrete {
+            feature_index: split.feature_index,
+            directions: directions.clone(),
+        }),
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{Node, Tree};
+
+/// Summarizes the shape of an ensemble's trees, to weigh their accuracy against the cost of evaluating them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeStats {
+    /// The number of leaves at each depth, summed over the trees, where the root is at depth zero.
+    pub depth_histogram: Vec<usize>,
+    /// The number of leaves in each tree.
+    pub leaf_counts: Vec<usize>,
+    /// The gain of every split, in the order of the trees and of their nodes.
+    pub split_gains: Vec<f32>,
+}
+
+impl TreeStats {
+    pub fn compute<'a>(trees: impl IntoIterator<Item = &'a Tree>) -> TreeStats {
+        let mut stats = TreeStats::default();
+        for tree in trees {
+            let mut n_leaves = 0;
+            let mut stack = vec![(0, 0)];
+            while let Some((node_index, depth)) = stack.pop() {
+                match &tree.nodes[node_index] {
+                    Node::Leaf(_) => {
+                        if stats.depth_histogram.len() <= depth {
+                            stats.depth_histogram.resize(depth + 1, 0);
+                        }
+                        stats.depth_histogram[depth] += 1;
+                        n_leaves += 1;
+                    }
+                    Node::Branch(branch) => {
+                        stack.push((branch.right_child_index, depth + 1));
+                        stack.push((branch.left_child_index, depth + 1));
+                    }
+                }
+            }
+            stats.leaf_counts.push(n_leaves);
+            stats.split_gains.extend(
+                tree.nodes
+                    .iter()
+                    .filter_map(Node::as_branch)
+                    .map(|branch| branch.gain),
+            );
+        }
+        stats
+    }
+
+    /// The depth of the deepest leaf.
+    pub fn max_depth(&self) -> usize {
+        self.depth_histogram.len().saturating_sub(1)
+    }
+}